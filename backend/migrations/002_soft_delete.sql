@@ -0,0 +1,28 @@
+-- 软删除与归档
+-- persons / classes / departments 删除时只标记 deleted_at，保留历史考勤、积分与小组记录
+
+ALTER TABLE persons ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+ALTER TABLE persons ADD COLUMN IF NOT EXISTS deleted_by UUID;
+
+ALTER TABLE classes ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+ALTER TABLE classes ADD COLUMN IF NOT EXISTS deleted_by UUID;
+
+ALTER TABLE departments ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+ALTER TABLE departments ADD COLUMN IF NOT EXISTS deleted_by UUID;
+
+-- 默认列表只查询未归档记录，使用部分索引
+CREATE INDEX IF NOT EXISTS idx_persons_active ON persons(created_at) WHERE deleted_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_persons_deleted_at ON persons(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_classes_deleted_at ON classes(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_departments_deleted_at ON departments(deleted_at) WHERE deleted_at IS NOT NULL;
+
+-- 归档相关权限
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'person.restore', true, 10),
+    ('admin', 'person.purge', true, 10),
+    ('admin', 'class.restore', true, 10),
+    ('admin', 'class.purge', true, 10),
+    ('admin', 'department.restore', true, 10),
+    ('admin', 'department.purge', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
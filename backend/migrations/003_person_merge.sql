@@ -0,0 +1,14 @@
+-- 重复人员检测与合并
+
+-- 被合并的人员记录其合并去向，便于追溯
+ALTER TABLE persons ADD COLUMN IF NOT EXISTS merged_into UUID;
+
+-- 姓名三元组索引，加速相似度查询
+CREATE INDEX IF NOT EXISTS idx_persons_name_trgm ON persons USING gin (name gin_trgm_ops);
+
+-- 合并相关权限
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'person.duplicates', true, 10),
+    ('admin', 'person.merge', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
@@ -0,0 +1,14 @@
+-- 家长-学生关系管理
+
+ALTER TABLE student_parent ADD COLUMN IF NOT EXISTS is_primary_contact BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE student_parent ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ DEFAULT NOW();
+
+-- 每个学生最多一位首要联系人
+CREATE UNIQUE INDEX IF NOT EXISTS idx_student_parent_primary
+    ON student_parent(student_id) WHERE is_primary_contact;
+
+-- 家长关联权限
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'person.guardian.update', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
@@ -0,0 +1,21 @@
+-- 人员变更历史（字段级）
+CREATE TABLE IF NOT EXISTS person_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    person_id UUID NOT NULL REFERENCES persons(id) ON DELETE CASCADE,
+    -- 同一次保存产生的多条字段变更共用一个 change_id
+    change_id UUID NOT NULL,
+    field VARCHAR(50) NOT NULL,
+    old_value TEXT,
+    new_value TEXT,
+    changed_by UUID REFERENCES persons(id) ON DELETE SET NULL,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_person_history_person ON person_history(person_id, changed_at DESC);
+CREATE INDEX IF NOT EXISTS idx_person_history_field ON person_history(person_id, field, changed_at);
+
+-- 历史查看权限
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'person.history.view', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
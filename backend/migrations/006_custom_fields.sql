@@ -0,0 +1,33 @@
+-- 自定义字段（可扩展属性）
+
+CREATE TABLE IF NOT EXISTS custom_field_definitions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    entity VARCHAR(20) NOT NULL CHECK (entity IN ('person', 'class')),
+    key VARCHAR(50) NOT NULL,
+    label VARCHAR(100) NOT NULL,
+    field_type VARCHAR(20) NOT NULL CHECK (field_type IN ('text', 'number', 'boolean', 'date', 'enum')),
+    required BOOLEAN NOT NULL DEFAULT false,
+    options JSONB NOT NULL DEFAULT '[]',
+    person_type VARCHAR(20) CHECK (person_type IN ('student', 'teacher', 'parent')),
+    visibility_permission VARCHAR(255),
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(entity, key)
+);
+
+CREATE TRIGGER update_custom_field_definitions_updated_at BEFORE UPDATE ON custom_field_definitions
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE persons ADD COLUMN IF NOT EXISTS custom_fields JSONB NOT NULL DEFAULT '{}';
+ALTER TABLE classes ADD COLUMN IF NOT EXISTS custom_fields JSONB NOT NULL DEFAULT '{}';
+
+-- 支持按自定义字段过滤（@> 包含查询）
+CREATE INDEX IF NOT EXISTS idx_persons_custom_fields ON persons USING gin (custom_fields);
+CREATE INDEX IF NOT EXISTS idx_classes_custom_fields ON classes USING gin (custom_fields);
+
+-- 自定义字段管理权限
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'custom_field.manage', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
@@ -0,0 +1,24 @@
+-- 人员头像与附件
+
+CREATE TABLE IF NOT EXISTS attachments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    person_id UUID NOT NULL REFERENCES persons(id) ON DELETE CASCADE,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('avatar', 'document')),
+    file_name VARCHAR(255) NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    storage_key VARCHAR(500) NOT NULL,
+    thumbnail_key VARCHAR(500),
+    uploaded_by UUID REFERENCES persons(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_attachments_person ON attachments(person_id, kind);
+
+-- 附件权限
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'attachment.upload', true, 10),
+    ('admin', 'attachment.view', true, 10),
+    ('admin', 'attachment.delete', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
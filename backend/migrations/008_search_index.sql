@@ -0,0 +1,17 @@
+-- 拼音检索索引：全拼与首字母由应用层在写入时计算，存量数据在启动时补齐
+
+ALTER TABLE persons ADD COLUMN IF NOT EXISTS name_pinyin VARCHAR(500);
+ALTER TABLE persons ADD COLUMN IF NOT EXISTS name_initials VARCHAR(100);
+ALTER TABLE classes ADD COLUMN IF NOT EXISTS name_pinyin VARCHAR(500);
+ALTER TABLE classes ADD COLUMN IF NOT EXISTS name_initials VARCHAR(100);
+ALTER TABLE class_groups ADD COLUMN IF NOT EXISTS name_pinyin VARCHAR(500);
+ALTER TABLE class_groups ADD COLUMN IF NOT EXISTS name_initials VARCHAR(100);
+
+CREATE INDEX IF NOT EXISTS idx_persons_name_pinyin_trgm ON persons USING gin (name_pinyin gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_persons_name_initials_trgm ON persons USING gin (name_initials gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_classes_name_trgm ON classes USING gin (name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_classes_name_pinyin_trgm ON classes USING gin (name_pinyin gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_classes_name_initials_trgm ON classes USING gin (name_initials gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_class_groups_name_trgm ON class_groups USING gin (name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_class_groups_name_pinyin_trgm ON class_groups USING gin (name_pinyin gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_class_groups_name_initials_trgm ON class_groups USING gin (name_initials gin_trgm_ops);
@@ -0,0 +1,11 @@
+-- 部门树：禁止自引用，删除上级部门不再级联删除下级部门
+
+ALTER TABLE departments DROP CONSTRAINT IF EXISTS fk_departments_parent_id;
+ALTER TABLE departments
+ADD CONSTRAINT fk_departments_parent_id
+FOREIGN KEY (parent_id) REFERENCES departments(id) ON DELETE RESTRICT;
+
+UPDATE departments SET parent_id = NULL WHERE parent_id = id;
+ALTER TABLE departments DROP CONSTRAINT IF EXISTS chk_departments_not_self_parent;
+ALTER TABLE departments
+ADD CONSTRAINT chk_departments_not_self_parent CHECK (parent_id IS NULL OR parent_id <> id);
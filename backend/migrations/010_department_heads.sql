@@ -0,0 +1,10 @@
+-- 部门负责人（如教研组长），负责人对本部门及所有下级部门拥有部门范围权限
+
+ALTER TABLE departments ADD COLUMN IF NOT EXISTS head_id UUID REFERENCES persons(id) ON DELETE SET NULL;
+CREATE INDEX IF NOT EXISTS idx_departments_head_id ON departments(head_id);
+
+-- 设置部门负责人权限
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'department.update.head', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
@@ -0,0 +1,34 @@
+-- 学科与任课安排（教师-学科-班级，按学年）
+
+CREATE TABLE IF NOT EXISTS subjects (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(50) NOT NULL UNIQUE,
+    code VARCHAR(20) UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER update_subjects_updated_at BEFORE UPDATE ON subjects
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS teaching_assignments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    teacher_id UUID NOT NULL REFERENCES teachers(person_id) ON DELETE CASCADE,
+    subject_id UUID NOT NULL REFERENCES subjects(id) ON DELETE CASCADE,
+    class_id UUID NOT NULL REFERENCES classes(id) ON DELETE CASCADE,
+    academic_year VARCHAR(20) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (teacher_id, subject_id, class_id, academic_year)
+);
+
+CREATE INDEX IF NOT EXISTS idx_teaching_assignments_class ON teaching_assignments(class_id, academic_year);
+CREATE INDEX IF NOT EXISTS idx_teaching_assignments_teacher ON teaching_assignments(teacher_id, academic_year);
+
+-- 学科与任课权限
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'subject.create', true, 10),
+    ('admin', 'subject.update', true, 10),
+    ('admin', 'subject.delete', true, 10),
+    ('admin', 'subject.assign', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
@@ -0,0 +1,39 @@
+-- 课程表：作息节次 + 班级 × 星期 × 节次 → 学科、任课教师、教室
+
+CREATE TABLE IF NOT EXISTS periods (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    period_no SMALLINT NOT NULL UNIQUE,
+    name VARCHAR(20) NOT NULL,
+    start_time TIME NOT NULL,
+    end_time TIME NOT NULL,
+    CHECK (end_time > start_time)
+);
+
+CREATE TABLE IF NOT EXISTS timetable_entries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    class_id UUID NOT NULL REFERENCES classes(id) ON DELETE CASCADE,
+    academic_year VARCHAR(20) NOT NULL,
+    day_of_week SMALLINT NOT NULL CHECK (day_of_week BETWEEN 1 AND 7), -- 1 表示星期一
+    period_id UUID NOT NULL REFERENCES periods(id) ON DELETE CASCADE,
+    subject_id UUID NOT NULL REFERENCES subjects(id) ON DELETE CASCADE,
+    teacher_id UUID REFERENCES teachers(person_id) ON DELETE SET NULL,
+    room VARCHAR(50),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (class_id, academic_year, day_of_week, period_id)
+);
+
+CREATE TRIGGER update_timetable_entries_updated_at BEFORE UPDATE ON timetable_entries
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- 同一时段教师、教室不能重复排课
+CREATE UNIQUE INDEX IF NOT EXISTS uq_timetable_teacher_slot
+    ON timetable_entries(teacher_id, academic_year, day_of_week, period_id) WHERE teacher_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS uq_timetable_room_slot
+    ON timetable_entries(room, academic_year, day_of_week, period_id) WHERE room IS NOT NULL;
+
+-- 课程表权限
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'timetable.manage', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
@@ -0,0 +1,33 @@
+-- 校历：学期（起止日期、周次）+ 节假日 / 调休上课日
+
+CREATE TABLE IF NOT EXISTS terms (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(50) NOT NULL,
+    academic_year VARCHAR(20) NOT NULL,
+    start_date DATE NOT NULL,
+    end_date DATE NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (academic_year, name),
+    CHECK (end_date >= start_date)
+);
+
+CREATE TRIGGER update_terms_updated_at BEFORE UPDATE ON terms
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- 特殊日期：holiday 为工作日放假，workday 为周末调休上课
+CREATE TABLE IF NOT EXISTS calendar_days (
+    date DATE PRIMARY KEY,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('holiday', 'workday')),
+    name VARCHAR(50) NOT NULL,
+    -- 调休上课日按星期几的课表上课（1 表示星期一），不填按当天星期
+    follows_day_of_week SMALLINT CHECK (follows_day_of_week BETWEEN 1 AND 7),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (kind = 'workday' OR follows_day_of_week IS NULL)
+);
+
+-- 校历权限
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'calendar.manage', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
@@ -0,0 +1,29 @@
+-- 班级合并 / 拆分
+
+-- 小组积分记录保存记录时小组所属的班级，小组随班级合并迁移后历史记录仍归属原班级
+ALTER TABLE group_score_records ADD COLUMN IF NOT EXISTS class_id UUID;
+
+UPDATE group_score_records r SET class_id = g.class_id
+FROM class_groups g
+WHERE g.id = r.group_id AND r.class_id IS NULL;
+
+CREATE OR REPLACE FUNCTION set_group_score_record_class()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.class_id IS NULL THEN
+        SELECT class_id INTO NEW.class_id FROM class_groups WHERE id = NEW.group_id;
+    END IF;
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+DROP TRIGGER IF EXISTS set_group_score_records_class ON group_score_records;
+CREATE TRIGGER set_group_score_records_class BEFORE INSERT ON group_score_records
+    FOR EACH ROW EXECUTE FUNCTION set_group_score_record_class();
+
+-- 合并、拆分权限
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'class.merge', true, 10),
+    ('admin', 'class.split', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
@@ -0,0 +1,29 @@
+-- 座位表：每个班级一张 行 × 列 的座位布局，以及学生所在座位
+
+CREATE TABLE IF NOT EXISTS seating_charts (
+    class_id UUID PRIMARY KEY REFERENCES classes(id) ON DELETE CASCADE,
+    row_count SMALLINT NOT NULL CHECK (row_count BETWEEN 1 AND 20),
+    col_count SMALLINT NOT NULL CHECK (col_count BETWEEN 1 AND 20),
+    updated_by UUID REFERENCES persons(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER update_seating_charts_updated_at BEFORE UPDATE ON seating_charts
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- 第 1 排靠近讲台，第 1 列在讲台视角的左侧
+CREATE TABLE IF NOT EXISTS seat_assignments (
+    class_id UUID NOT NULL REFERENCES seating_charts(class_id) ON DELETE CASCADE,
+    seat_row SMALLINT NOT NULL CHECK (seat_row >= 1),
+    seat_col SMALLINT NOT NULL CHECK (seat_col >= 1),
+    person_id UUID NOT NULL REFERENCES persons(id) ON DELETE CASCADE,
+    PRIMARY KEY (class_id, seat_row, seat_col),
+    UNIQUE (class_id, person_id)
+);
+
+-- 座位表权限
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'class.seating.update', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
@@ -0,0 +1,5 @@
+-- 考勤按节次记录：period_id 为空表示全天考勤
+
+ALTER TABLE attendances ADD COLUMN IF NOT EXISTS period_id UUID REFERENCES periods(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_attendances_person_date ON attendances(person_id, date);
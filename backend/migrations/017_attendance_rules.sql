@@ -0,0 +1,80 @@
+-- 考勤规则：每人每天每个时段（全天或某一节）只有一条记录；签到时间窗口；更正保留原值
+
+-- 考勤更正记录，保存每次修改前后的值
+CREATE TABLE IF NOT EXISTS attendance_corrections (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    attendance_id UUID NOT NULL REFERENCES attendances(id) ON DELETE CASCADE,
+    old_status VARCHAR(20),
+    new_status VARCHAR(20),
+    old_time TIME,
+    new_time TIME,
+    old_remark TEXT,
+    new_remark TEXT,
+    reason TEXT,
+    corrected_by UUID REFERENCES persons(id) ON DELETE SET NULL,
+    corrected_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_attendance_corrections_attendance ON attendance_corrections(attendance_id);
+
+-- 合并已有的重复记录：保留最近更新的一条，其余作为更正记录保存
+CREATE TEMP TABLE attendance_duplicates AS
+SELECT id, keep_id, status, time, remark, created_by, created_at
+FROM (
+    SELECT id, status, time, remark, created_by, created_at,
+           FIRST_VALUE(id) OVER w AS keep_id,
+           ROW_NUMBER() OVER w AS rn
+    FROM attendances
+    WHERE person_id IS NOT NULL
+    WINDOW w AS (
+        PARTITION BY person_id, date, COALESCE(period_id, '00000000-0000-0000-0000-000000000000'::uuid)
+        ORDER BY updated_at DESC NULLS LAST, created_at DESC NULLS LAST, id
+    )
+) ranked
+WHERE rn > 1;
+
+INSERT INTO attendance_corrections
+    (attendance_id, old_status, new_status, old_time, new_time, old_remark, new_remark, reason, corrected_by, corrected_at)
+SELECT d.keep_id, d.status, k.status, d.time, k.time, d.remark, k.remark, '合并重复考勤记录', d.created_by,
+       COALESCE(d.created_at, NOW())
+FROM attendance_duplicates d
+JOIN attendances k ON k.id = d.keep_id;
+
+DELETE FROM attendances WHERE id IN (SELECT id FROM attendance_duplicates);
+DROP TABLE attendance_duplicates;
+
+DROP INDEX IF EXISTS idx_attendances_person_date;
+CREATE UNIQUE INDEX IF NOT EXISTS uq_attendances_person_date_session
+    ON attendances (person_id, date, COALESCE(period_id, '00000000-0000-0000-0000-000000000000'::uuid));
+
+-- 新记录的状态只能取固定值；历史数据不做校验
+ALTER TABLE attendances DROP CONSTRAINT IF EXISTS chk_attendances_status;
+ALTER TABLE attendances
+ADD CONSTRAINT chk_attendances_status CHECK (status IN ('present', 'absent', 'late', 'early_leave', 'excused')) NOT VALID;
+
+-- 签到时间窗口：on_time_until 之前为出勤，late_until 之前为迟到，之后为缺勤
+-- period_id 为空的一行是全天（到校）考勤的窗口；节次没有单独配置时按节次的上下课时间判断
+CREATE TABLE IF NOT EXISTS attendance_windows (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    period_id UUID UNIQUE REFERENCES periods(id) ON DELETE CASCADE,
+    on_time_until TIME NOT NULL,
+    late_until TIME NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (late_until >= on_time_until)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS uq_attendance_windows_day ON attendance_windows ((period_id IS NULL)) WHERE period_id IS NULL;
+
+CREATE TRIGGER update_attendance_windows_updated_at BEFORE UPDATE ON attendance_windows
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+INSERT INTO attendance_windows (period_id, on_time_until, late_until)
+SELECT NULL, '07:50', '08:30'
+WHERE NOT EXISTS (SELECT 1 FROM attendance_windows WHERE period_id IS NULL);
+
+-- 考勤设置权限
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'attendance.settings', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
@@ -0,0 +1,48 @@
+-- 请假申请：家长或学生提交，班主任审批，较长的请假再由有审批权限的人员复核；批准后自动记为请假考勤
+
+CREATE TABLE IF NOT EXISTS leave_requests (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    student_id UUID NOT NULL REFERENCES persons(id) ON DELETE CASCADE,
+    -- 提交时学生所在班级，由该班班主任审批
+    class_id UUID REFERENCES classes(id) ON DELETE SET NULL,
+    leave_type VARCHAR(20) NOT NULL CHECK (leave_type IN ('sick', 'personal', 'other')),
+    start_date DATE NOT NULL,
+    end_date DATE NOT NULL,
+    -- 请假期间的上学日天数，超过规定天数需要复核
+    school_days INTEGER NOT NULL,
+    reason TEXT NOT NULL,
+    attachment_id UUID REFERENCES attachments(id) ON DELETE SET NULL,
+    -- pending：待班主任审批；escalated：待复核；approved / rejected / cancelled
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'escalated', 'approved', 'rejected', 'cancelled')),
+    submitted_by UUID REFERENCES persons(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (end_date >= start_date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_leave_requests_student ON leave_requests(student_id, start_date);
+CREATE INDEX IF NOT EXISTS idx_leave_requests_class_status ON leave_requests(class_id, status);
+
+CREATE TRIGGER update_leave_requests_updated_at BEFORE UPDATE ON leave_requests
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- 审批记录，step 1 为班主任审批，2 为复核
+CREATE TABLE IF NOT EXISTS leave_approvals (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    leave_request_id UUID NOT NULL REFERENCES leave_requests(id) ON DELETE CASCADE,
+    step SMALLINT NOT NULL,
+    decision VARCHAR(20) NOT NULL CHECK (decision IN ('approve', 'reject')),
+    approver_id UUID REFERENCES persons(id) ON DELETE SET NULL,
+    comment TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_leave_approvals_request ON leave_approvals(leave_request_id);
+
+-- 请假权限
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'leave.view', true, 10),
+    ('admin', 'leave.approve', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
@@ -0,0 +1,48 @@
+-- 一体机：登记的设备使用各自的凭据，在屏幕上展示定时更换的签到二维码，学生扫码自助签到
+
+CREATE TABLE IF NOT EXISTS kiosk_devices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    -- 设备所在班级，只为该班学生生成签到码
+    class_id UUID NOT NULL REFERENCES classes(id) ON DELETE CASCADE,
+    -- 设备位置；配置后签到时需提交定位且在 radius_m 米范围内
+    latitude DOUBLE PRECISION,
+    longitude DOUBLE PRECISION,
+    radius_m INTEGER NOT NULL DEFAULT 100 CHECK (radius_m > 0),
+    secret_hash VARCHAR(255) NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    last_seen_at TIMESTAMPTZ,
+    created_by UUID REFERENCES persons(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((latitude IS NULL) = (longitude IS NULL))
+);
+
+CREATE TRIGGER update_kiosk_devices_updated_at BEFORE UPDATE ON kiosk_devices
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- 已使用的签到码，同一个码每名学生只能使用一次
+CREATE TABLE IF NOT EXISTS kiosk_token_uses (
+    nonce UUID NOT NULL,
+    person_id UUID NOT NULL REFERENCES persons(id) ON DELETE CASCADE,
+    used_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (nonce, person_id)
+);
+
+-- 设备事件日志：认证、生成签到码、签到成功与被拒绝
+CREATE TABLE IF NOT EXISTS kiosk_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    device_id UUID REFERENCES kiosk_devices(id) ON DELETE CASCADE,
+    event VARCHAR(30) NOT NULL,
+    person_id UUID REFERENCES persons(id) ON DELETE SET NULL,
+    detail TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_kiosk_events_device ON kiosk_events(device_id, created_at DESC);
+
+-- 一体机管理权限
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'kiosk.manage', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
@@ -0,0 +1,54 @@
+-- 缺勤提醒：按规则通知家长、班主任，多次缺勤上报年级组长；通知以个人通知（target_type = 'person'）投递
+
+-- 提醒规则：window_days 天内出现 threshold 次 status 时通知所选的接收人，同一窗口内只提醒一次
+CREATE TABLE IF NOT EXISTS attendance_alert_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    status VARCHAR(20) NOT NULL CHECK (status IN ('absent', 'late')),
+    threshold INTEGER NOT NULL DEFAULT 1 CHECK (threshold >= 1),
+    window_days INTEGER NOT NULL DEFAULT 1 CHECK (window_days >= 1),
+    notify_parents BOOLEAN NOT NULL DEFAULT false,
+    notify_head_teacher BOOLEAN NOT NULL DEFAULT false,
+    notify_grade_leader BOOLEAN NOT NULL DEFAULT false,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER update_attendance_alert_rules_updated_at BEFORE UPDATE ON attendance_alert_rules
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+INSERT INTO attendance_alert_rules (name, status, threshold, window_days, notify_parents, notify_head_teacher, notify_grade_leader)
+SELECT * FROM (VALUES
+    ('缺勤通知', 'absent', 1, 1, true, true, false),
+    ('迟到通知', 'late', 1, 1, true, false, false),
+    ('多次缺勤上报年级组长', 'absent', 3, 30, false, false, true)
+) AS defaults(name, status, threshold, window_days, notify_parents, notify_head_teacher, notify_grade_leader)
+WHERE NOT EXISTS (SELECT 1 FROM attendance_alert_rules);
+
+-- 年级组长
+CREATE TABLE IF NOT EXISTS grade_leaders (
+    grade SMALLINT NOT NULL,
+    teacher_id UUID NOT NULL REFERENCES persons(id) ON DELETE CASCADE,
+    PRIMARY KEY (grade, teacher_id)
+);
+
+-- 已发出的提醒，用于去重和查询
+CREATE TABLE IF NOT EXISTS attendance_alerts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    rule_id UUID NOT NULL REFERENCES attendance_alert_rules(id) ON DELETE CASCADE,
+    student_id UUID NOT NULL REFERENCES persons(id) ON DELETE CASCADE,
+    attendance_id UUID REFERENCES attendances(id) ON DELETE SET NULL,
+    date DATE NOT NULL,
+    occurrences INTEGER NOT NULL,
+    recipients UUID[] NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_attendance_alerts_rule_student ON attendance_alerts(rule_id, student_id, date);
+
+-- 提醒规则权限
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'attendance.alerts', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
@@ -0,0 +1,73 @@
+-- 积分按天汇总（排行榜和班级看板使用，不再每次汇总 scores 全表）
+-- group_key 为积分所属小组，没有小组时为全零 UUID（便于做唯一键）
+CREATE TABLE IF NOT EXISTS score_daily_totals (
+    score_date DATE NOT NULL,
+    person_id UUID NOT NULL,
+    group_key UUID NOT NULL DEFAULT '00000000-0000-0000-0000-000000000000',
+    score_type VARCHAR(20) NOT NULL,
+    total BIGINT NOT NULL DEFAULT 0,
+    record_count INTEGER NOT NULL DEFAULT 0,
+    positive_count INTEGER NOT NULL DEFAULT 0,
+    negative_count INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (score_date, person_id, group_key, score_type)
+);
+
+CREATE INDEX IF NOT EXISTS idx_score_daily_totals_person ON score_daily_totals(person_id, score_date);
+CREATE INDEX IF NOT EXISTS idx_score_daily_totals_group ON score_daily_totals(group_key, score_date);
+
+-- 把一条积分记录计入（sign = 1）或移出（sign = -1）汇总
+CREATE OR REPLACE FUNCTION apply_score_daily_total(
+    p_created_at TIMESTAMPTZ, p_person_id UUID, p_group_id UUID, p_score_type VARCHAR, p_value INTEGER, sign INTEGER
+) RETURNS VOID AS $$
+BEGIN
+    IF p_person_id IS NULL THEN
+        RETURN;
+    END IF;
+    INSERT INTO score_daily_totals AS t
+        (score_date, person_id, group_key, score_type, total, record_count, positive_count, negative_count)
+    VALUES (
+        COALESCE(p_created_at, NOW())::date, p_person_id,
+        COALESCE(p_group_id, '00000000-0000-0000-0000-000000000000'), p_score_type,
+        sign * p_value, sign,
+        CASE WHEN p_value > 0 THEN sign ELSE 0 END,
+        CASE WHEN p_value < 0 THEN sign ELSE 0 END
+    )
+    ON CONFLICT (score_date, person_id, group_key, score_type) DO UPDATE SET
+        total = t.total + EXCLUDED.total,
+        record_count = t.record_count + EXCLUDED.record_count,
+        positive_count = t.positive_count + EXCLUDED.positive_count,
+        negative_count = t.negative_count + EXCLUDED.negative_count;
+
+    DELETE FROM score_daily_totals
+    WHERE score_date = COALESCE(p_created_at, NOW())::date AND person_id = p_person_id
+      AND group_key = COALESCE(p_group_id, '00000000-0000-0000-0000-000000000000')
+      AND score_type = p_score_type AND record_count <= 0;
+END;
+$$ language 'plpgsql';
+
+CREATE OR REPLACE FUNCTION sync_score_daily_totals()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        PERFORM apply_score_daily_total(OLD.created_at, OLD.person_id, OLD.group_id, OLD.score_type, OLD.value, -1);
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        PERFORM apply_score_daily_total(NEW.created_at, NEW.person_id, NEW.group_id, NEW.score_type, NEW.value, 1);
+    END IF;
+    RETURN NULL;
+END;
+$$ language 'plpgsql';
+
+DROP TRIGGER IF EXISTS sync_scores_daily_totals ON scores;
+CREATE TRIGGER sync_scores_daily_totals AFTER INSERT OR UPDATE OR DELETE ON scores
+    FOR EACH ROW EXECUTE FUNCTION sync_score_daily_totals();
+
+-- 已有积分记录重新汇总
+DELETE FROM score_daily_totals;
+INSERT INTO score_daily_totals
+    (score_date, person_id, group_key, score_type, total, record_count, positive_count, negative_count)
+SELECT created_at::date, person_id, COALESCE(group_id, '00000000-0000-0000-0000-000000000000'), score_type,
+       SUM(value), COUNT(*), COUNT(*) FILTER (WHERE value > 0), COUNT(*) FILTER (WHERE value < 0)
+FROM scores
+WHERE person_id IS NOT NULL AND created_at IS NOT NULL
+GROUP BY 1, 2, 3, 4;
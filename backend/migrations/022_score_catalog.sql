@@ -0,0 +1,79 @@
+-- 积分类别（纪律、卫生、学业、宿舍等）
+CREATE TABLE IF NOT EXISTS score_categories (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    code VARCHAR(30) NOT NULL UNIQUE,
+    name VARCHAR(50) NOT NULL,
+    description TEXT,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER update_score_categories_updated_at BEFORE UPDATE ON score_categories
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- 预设原因；aliases 为同义说法（如“课堂讲话”“talking”），录入时统一为 name
+CREATE TABLE IF NOT EXISTS score_reasons (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    category_id UUID NOT NULL REFERENCES score_categories(id),
+    name VARCHAR(100) NOT NULL,
+    aliases TEXT[] NOT NULL DEFAULT '{}',
+    default_value INTEGER NOT NULL,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(category_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_score_reasons_category ON score_reasons(category_id);
+
+CREATE TRIGGER update_score_reasons_updated_at BEFORE UPDATE ON score_reasons
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- 各角色单次加减分的上限（绝对值），未设置的角色不限制
+CREATE TABLE IF NOT EXISTS score_limits (
+    role VARCHAR(50) PRIMARY KEY,
+    max_points INTEGER NOT NULL CHECK (max_points > 0)
+);
+
+ALTER TABLE scores ADD COLUMN IF NOT EXISTS category_id UUID REFERENCES score_categories(id);
+ALTER TABLE scores ADD COLUMN IF NOT EXISTS reason_id UUID REFERENCES score_reasons(id);
+CREATE INDEX IF NOT EXISTS idx_scores_category ON scores(category_id);
+
+-- 默认类别和原因
+INSERT INTO score_categories (code, name, sort_order)
+VALUES
+    ('discipline', '纪律', 1),
+    ('hygiene', '卫生', 2),
+    ('academics', '学业', 3),
+    ('dormitory', '宿舍', 4)
+ON CONFLICT (code) DO NOTHING;
+
+INSERT INTO score_reasons (category_id, name, aliases, default_value, sort_order)
+SELECT c.id, r.name, r.aliases, r.default_value, r.sort_order
+FROM (VALUES
+    ('discipline', '上课说话', ARRAY['课堂讲话', '上课讲话', 'talking'], -1, 1),
+    ('discipline', '迟到', ARRAY['late'], -1, 2),
+    ('discipline', '遵守纪律表现突出', ARRAY[]::text[], 2, 3),
+    ('hygiene', '值日不认真', ARRAY['值日未完成'], -1, 1),
+    ('hygiene', '卫生检查优秀', ARRAY[]::text[], 2, 2),
+    ('academics', '作业未交', ARRAY['没交作业', '未交作业'], -1, 1),
+    ('academics', '课堂回答问题', ARRAY['回答问题', '积极发言'], 1, 2),
+    ('academics', '考试进步', ARRAY[]::text[], 3, 3),
+    ('dormitory', '熄灯后讲话', ARRAY['夜间讲话'], -2, 1),
+    ('dormitory', '内务整洁', ARRAY['宿舍整洁'], 1, 2)
+) AS r(code, name, aliases, default_value, sort_order)
+JOIN score_categories c ON c.code = r.code
+ON CONFLICT (category_id, name) DO NOTHING;
+
+INSERT INTO score_limits (role, max_points)
+VALUES ('teacher', 5)
+ON CONFLICT (role) DO NOTHING;
+
+-- 积分目录管理权限
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'score.catalog', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
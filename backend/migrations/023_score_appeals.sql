@@ -0,0 +1,65 @@
+-- 扣分申诉：学生或家长对某条个人扣分（scores）或小组扣分（group_score_records）提出申诉，
+-- 由指定的审核人处理；同意后生成一条关联的更正记录抵消或调整原扣分
+
+CREATE TABLE IF NOT EXISTS score_appeals (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- score：个人积分；group_score：小组积分
+    target_type VARCHAR(20) NOT NULL CHECK (target_type IN ('score', 'group_score')),
+    score_id UUID REFERENCES scores(id) ON DELETE SET NULL,
+    group_score_record_id UUID REFERENCES group_score_records(id) ON DELETE SET NULL,
+    -- 申诉涉及的学生（小组积分为申诉的小组成员）
+    student_id UUID NOT NULL REFERENCES persons(id) ON DELETE CASCADE,
+    class_id UUID REFERENCES classes(id) ON DELETE SET NULL,
+    original_value INTEGER NOT NULL,
+    original_reason TEXT NOT NULL,
+    evidence TEXT NOT NULL,
+    attachment_id UUID REFERENCES attachments(id) ON DELETE SET NULL,
+    -- 申诉人认为合理的分值，为空表示请求撤销
+    requested_value INTEGER,
+    -- pending / accepted / rejected / withdrawn
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'accepted', 'rejected', 'withdrawn')),
+    reviewer_id UUID REFERENCES persons(id) ON DELETE SET NULL,
+    submitted_by UUID REFERENCES persons(id) ON DELETE SET NULL,
+    -- 同意后的最终分值和生成的更正记录
+    adjusted_value INTEGER,
+    compensation_score_id UUID REFERENCES scores(id) ON DELETE SET NULL,
+    compensation_group_record_id UUID REFERENCES group_score_records(id) ON DELETE SET NULL,
+    resolved_by UUID REFERENCES persons(id) ON DELETE SET NULL,
+    resolved_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((target_type = 'score' AND group_score_record_id IS NULL)
+        OR (target_type = 'group_score' AND score_id IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_score_appeals_student ON score_appeals(student_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_score_appeals_reviewer ON score_appeals(reviewer_id, status);
+-- 同一条记录同时只能有一个处理中的申诉
+CREATE UNIQUE INDEX IF NOT EXISTS idx_score_appeals_open_score ON score_appeals(score_id)
+    WHERE status = 'pending' AND score_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_score_appeals_open_group ON score_appeals(group_score_record_id, student_id)
+    WHERE status = 'pending' AND group_score_record_id IS NOT NULL;
+
+CREATE TRIGGER update_score_appeals_updated_at BEFORE UPDATE ON score_appeals
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- 申诉过程（提交、补充说明、指派、处理结果），所有相关人员可见
+CREATE TABLE IF NOT EXISTS score_appeal_messages (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    appeal_id UUID NOT NULL REFERENCES score_appeals(id) ON DELETE CASCADE,
+    -- comment / assigned / accepted / rejected / withdrawn
+    kind VARCHAR(20) NOT NULL DEFAULT 'comment',
+    author_id UUID REFERENCES persons(id) ON DELETE SET NULL,
+    content TEXT NOT NULL,
+    attachment_id UUID REFERENCES attachments(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_score_appeal_messages_appeal ON score_appeal_messages(appeal_id, created_at);
+
+-- 申诉审核权限（处理任意申诉、指派审核人）
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'score.appeal.review', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
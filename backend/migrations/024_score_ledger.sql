@@ -0,0 +1,107 @@
+-- 积分流水只增不改：修改、删除个人积分改为追加一条冲销记录（reverses_id 指向原记录），
+-- 小组总分由小组积分记录触发器累加，汇总与流水不一致时由管理员对账修复
+
+ALTER TABLE scores ADD COLUMN IF NOT EXISTS reverses_id UUID REFERENCES scores(id) ON DELETE CASCADE;
+
+-- 每条记录最多被冲销一次，冲销记录本身不能再被冲销（由接口校验）
+CREATE UNIQUE INDEX IF NOT EXISTS idx_scores_reverses ON scores(reverses_id) WHERE reverses_id IS NOT NULL;
+
+-- 积分记录只允许随人员合并、小组删除调整归属；删除只允许随人员删除级联
+CREATE OR REPLACE FUNCTION guard_score_ledger()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        IF EXISTS (SELECT 1 FROM persons WHERE id = OLD.person_id) THEN
+            RAISE EXCEPTION '积分记录不能删除，请使用冲销记录';
+        END IF;
+        RETURN OLD;
+    END IF;
+    IF NEW.value IS DISTINCT FROM OLD.value
+        OR NEW.reason IS DISTINCT FROM OLD.reason
+        OR NEW.score_type IS DISTINCT FROM OLD.score_type
+        OR NEW.category_id IS DISTINCT FROM OLD.category_id
+        OR NEW.reason_id IS DISTINCT FROM OLD.reason_id
+        OR NEW.created_at IS DISTINCT FROM OLD.created_at
+        OR NEW.reverses_id IS DISTINCT FROM OLD.reverses_id THEN
+        RAISE EXCEPTION '积分记录不能修改，请使用冲销记录';
+    END IF;
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+DROP TRIGGER IF EXISTS guard_scores_ledger ON scores;
+CREATE TRIGGER guard_scores_ledger BEFORE UPDATE OR DELETE ON scores
+    FOR EACH ROW EXECUTE FUNCTION guard_score_ledger();
+
+-- 汇总只计入有效记录：冲销记录把原记录从原记录当天的汇总中移出，已冲销的原记录调整归属时不再计入
+CREATE OR REPLACE FUNCTION sync_score_daily_totals()
+RETURNS TRIGGER AS $$
+DECLARE
+    original scores%ROWTYPE;
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        IF NEW.reverses_id IS NULL THEN
+            PERFORM apply_score_daily_total(NEW.created_at, NEW.person_id, NEW.group_id, NEW.score_type, NEW.value, 1);
+        ELSE
+            SELECT * INTO original FROM scores WHERE id = NEW.reverses_id;
+            IF FOUND THEN
+                PERFORM apply_score_daily_total(original.created_at, original.person_id, original.group_id,
+                                                original.score_type, original.value, -1);
+            END IF;
+        END IF;
+        RETURN NULL;
+    END IF;
+
+    IF OLD.reverses_id IS NOT NULL OR EXISTS (SELECT 1 FROM scores WHERE reverses_id = OLD.id) THEN
+        RETURN NULL;
+    END IF;
+    PERFORM apply_score_daily_total(OLD.created_at, OLD.person_id, OLD.group_id, OLD.score_type, OLD.value, -1);
+    IF TG_OP = 'UPDATE' THEN
+        PERFORM apply_score_daily_total(NEW.created_at, NEW.person_id, NEW.group_id, NEW.score_type, NEW.value, 1);
+    END IF;
+    RETURN NULL;
+END;
+$$ language 'plpgsql';
+
+-- 小组积分记录同样只增不改，小组总分随记录累加
+CREATE OR REPLACE FUNCTION guard_group_score_ledger()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        IF EXISTS (SELECT 1 FROM class_groups WHERE id = OLD.group_id) THEN
+            RAISE EXCEPTION '小组积分记录不能删除，请追加更正记录';
+        END IF;
+        RETURN OLD;
+    END IF;
+    IF NEW.group_id IS DISTINCT FROM OLD.group_id
+        OR NEW.score_change IS DISTINCT FROM OLD.score_change
+        OR NEW.reason IS DISTINCT FROM OLD.reason
+        OR NEW.created_at IS DISTINCT FROM OLD.created_at THEN
+        RAISE EXCEPTION '小组积分记录不能修改，请追加更正记录';
+    END IF;
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+DROP TRIGGER IF EXISTS guard_group_score_records_ledger ON group_score_records;
+CREATE TRIGGER guard_group_score_records_ledger BEFORE UPDATE OR DELETE ON group_score_records
+    FOR EACH ROW EXECUTE FUNCTION guard_group_score_ledger();
+
+CREATE OR REPLACE FUNCTION sync_group_score()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE class_groups SET score = score + NEW.score_change, updated_at = CURRENT_TIMESTAMP
+    WHERE id = NEW.group_id;
+    RETURN NULL;
+END;
+$$ language 'plpgsql';
+
+DROP TRIGGER IF EXISTS sync_group_score_records_score ON group_score_records;
+CREATE TRIGGER sync_group_score_records_score AFTER INSERT ON group_score_records
+    FOR EACH ROW EXECUTE FUNCTION sync_group_score();
+
+-- 积分对账权限（查看并修复汇总与流水的差异）
+INSERT INTO permissions (role, permission, value, priority)
+VALUES
+    ('admin', 'score.reconcile', true, 10)
+ON CONFLICT (role, permission) DO UPDATE SET value = EXCLUDED.value, priority = EXCLUDED.priority;
@@ -0,0 +1,8 @@
+-- 同一条扣分记录只能更正一次：处理中和已同意的申诉都占用该记录，小组扣分不再按成员区分
+DROP INDEX IF EXISTS idx_score_appeals_open_score;
+DROP INDEX IF EXISTS idx_score_appeals_open_group;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_score_appeals_open_score ON score_appeals(score_id)
+    WHERE status IN ('pending', 'accepted') AND score_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_score_appeals_open_group ON score_appeals(group_score_record_id)
+    WHERE status IN ('pending', 'accepted') AND group_score_record_id IS NOT NULL;
@@ -0,0 +1,20 @@
+-- 已批准的请假可以撤销，撤销时恢复批准时改动的考勤
+
+ALTER TABLE leave_requests DROP CONSTRAINT IF EXISTS leave_requests_status_check;
+ALTER TABLE leave_requests ADD CONSTRAINT leave_requests_status_check
+    CHECK (status IN ('pending', 'escalated', 'approved', 'rejected', 'cancelled', 'revoked'));
+
+ALTER TABLE leave_approvals DROP CONSTRAINT IF EXISTS leave_approvals_decision_check;
+ALTER TABLE leave_approvals ADD CONSTRAINT leave_approvals_decision_check
+    CHECK (decision IN ('approve', 'reject', 'revoke'));
+
+-- 批准请假时改动的考勤：新建的记录（created）或改为请假前的原值
+CREATE TABLE IF NOT EXISTS leave_attendances (
+    leave_request_id UUID NOT NULL REFERENCES leave_requests(id) ON DELETE CASCADE,
+    attendance_id UUID NOT NULL REFERENCES attendances(id) ON DELETE CASCADE,
+    created BOOLEAN NOT NULL,
+    old_status VARCHAR(20),
+    old_time TIME,
+    old_remark TEXT,
+    PRIMARY KEY (leave_request_id, attendance_id)
+);
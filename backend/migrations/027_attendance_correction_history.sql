@@ -0,0 +1,14 @@
+-- 删除考勤记录时保留更正历史：更正记录不再随考勤记录级联删除，并记下所属人员、日期和时段，
+-- 删除本身也记为一条更正（新值为空）
+
+ALTER TABLE attendance_corrections ADD COLUMN IF NOT EXISTS person_id UUID REFERENCES persons(id) ON DELETE CASCADE;
+ALTER TABLE attendance_corrections ADD COLUMN IF NOT EXISTS date DATE;
+ALTER TABLE attendance_corrections ADD COLUMN IF NOT EXISTS period_id UUID;
+
+UPDATE attendance_corrections c SET person_id = a.person_id, date = a.date, period_id = a.period_id
+FROM attendances a
+WHERE a.id = c.attendance_id AND c.person_id IS NULL;
+
+ALTER TABLE attendance_corrections DROP CONSTRAINT IF EXISTS attendance_corrections_attendance_id_fkey;
+
+CREATE INDEX IF NOT EXISTS idx_attendance_corrections_person ON attendance_corrections(person_id, date);
@@ -0,0 +1,8 @@
+-- 同一学生同一规则同一天只登记一条提醒，并发评估时由唯一约束兜底去重
+DELETE FROM attendance_alerts a
+USING attendance_alerts b
+WHERE a.rule_id = b.rule_id AND a.student_id = b.student_id AND a.date = b.date
+  AND (a.created_at, a.id) > (b.created_at, b.id);
+
+DROP INDEX IF EXISTS idx_attendance_alerts_rule_student;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_attendance_alerts_rule_student ON attendance_alerts(rule_id, student_id, date);
@@ -0,0 +1,3 @@
+-- 记录班级关联是否由任课安排创建：撤销最后一项任课安排时只解除这类关联，
+-- 之前已有的关联和班级权限保持不变（迁移前已有的关联无法区分，一律视为原有关联）
+ALTER TABLE teacher_class ADD COLUMN IF NOT EXISTS via_assignment BOOLEAN NOT NULL DEFAULT false;
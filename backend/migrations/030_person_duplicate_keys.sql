@@ -0,0 +1,6 @@
+-- 重复人员检测按姓名、规范化手机号、规范化邮箱分别找候选对，这些表达式各建索引
+CREATE INDEX IF NOT EXISTS idx_persons_type_name ON persons(type, name) WHERE deleted_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_persons_phone_key ON persons((NULLIF(regexp_replace(phone, '\D', '', 'g'), '')))
+    WHERE deleted_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_persons_email_key ON persons((NULLIF(lower(trim(email)), '')))
+    WHERE deleted_at IS NULL;
@@ -0,0 +1,6 @@
+-- 设备密钥为随机生成的高强度字符串，轮询时按 SHA-256 摘要比对，不再每次计算 bcrypt；
+-- 已有设备在下一次 bcrypt 校验通过后补上摘要
+ALTER TABLE kiosk_devices ADD COLUMN IF NOT EXISTS secret_digest BYTEA;
+
+-- 已使用的签到码过期后即可清理
+CREATE INDEX IF NOT EXISTS idx_kiosk_token_uses_used_at ON kiosk_token_uses(used_at);
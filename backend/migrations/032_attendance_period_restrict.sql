@@ -0,0 +1,4 @@
+-- 删除节次时若改为全天考勤，会与同一天已有的全天考勤冲突；已有考勤的节次不能删除
+ALTER TABLE attendances DROP CONSTRAINT IF EXISTS attendances_period_id_fkey;
+ALTER TABLE attendances ADD CONSTRAINT attendances_period_id_fkey
+    FOREIGN KEY (period_id) REFERENCES periods(id) ON DELETE RESTRICT;
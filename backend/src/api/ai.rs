@@ -474,7 +474,7 @@ pub async fn get_context_data(
     
     // 根据用户权限获取数据
     let classes = if user_permissions.iter().any(|p| p == "class.view") {
-        sqlx::query_as::<_, SimpleClassInfo>("SELECT id, name, grade, teacher_id FROM classes")
+        sqlx::query_as::<_, SimpleClassInfo>("SELECT id, name, grade, teacher_id FROM classes WHERE deleted_at IS NULL")
             .fetch_all(&pool)
             .await
             .unwrap_or_default()
@@ -492,7 +492,7 @@ pub async fn get_context_data(
     };
     
     let departments = if user_permissions.iter().any(|p| p == "department.view") {
-        sqlx::query_as::<_, SimpleDepartmentInfo>("SELECT id, name FROM departments")
+        sqlx::query_as::<_, SimpleDepartmentInfo>("SELECT id, name FROM departments WHERE deleted_at IS NULL")
             .fetch_all(&pool)
             .await
             .unwrap_or_default()
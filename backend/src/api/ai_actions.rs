@@ -5,9 +5,16 @@ use uuid::Uuid;
 use chrono::{Local, NaiveDate, NaiveTime};
 
 use crate::api::routes::AppState;
+use crate::core::alert;
+use crate::core::attendance;
 use crate::core::auth::Claims;
+use crate::core::calendar;
 use crate::core::error::AppError;
 use crate::core::permission::PermissionManager;
+use crate::core::pinyin;
+use crate::core::score as score_rules;
+use crate::core::search::{self, SearchTerm};
+use crate::ws::hub::Hub;
 
 // ========== AI 操作请求/响应结构 ==========
 
@@ -69,7 +76,8 @@ pub struct CreateGroupParams {
 #[derive(Debug, Deserialize)]
 pub struct UpdateGroupScoreParams {
     pub group_id: String,
-    pub score_change: i32,
+    // 不填时使用积分目录中的预设分值
+    pub score_change: Option<i32>,
     pub reason: String,
 }
 
@@ -92,7 +100,8 @@ pub struct RemoveGroupMemberParams {
 pub struct CreateAttendanceParams {
     pub person_id: String,
     pub date: String,
-    pub status: String,
+    // 不填时按签到时间和签到窗口判断
+    pub status: Option<String>,
     pub time: Option<String>,
     pub remark: Option<String>,
 }
@@ -102,11 +111,21 @@ pub struct CreateAttendanceParams {
 pub struct CreateScoreParams {
     pub student_id: String,
     pub reason: String,
-    pub value: i32,
+    // 不填时使用积分目录中的预设分值
+    pub value: Option<i32>,
 }
 
 // ========== 名称解析服务 ==========
 
+// 模糊检索时最多返回的候选项数量
+const FUZZY_CANDIDATE_LIMIT: i64 = 5;
+
+/// 模糊检索只命中一条时，仅当输入与名称全拼完全一致才直接采用，否则仍需用户确认
+fn is_pinyin_match(candidate: &str, input: &str) -> bool {
+    let input = pinyin::full(input);
+    !input.is_empty() && pinyin::full(candidate) == input
+}
+
 pub struct NameResolver;
 
 impl NameResolver {
@@ -132,9 +151,8 @@ impl NameResolver {
             }
         }
         
-        // 按名称搜索人员
-        let persons: Vec<PersonInfo> = sqlx::query_as(
-            r#"SELECT 
+        // 按名称搜索人员：先精确匹配，找不到时再按拼音 / 相似度检索
+        const PERSON_INFO_SELECT: &str = r#"SELECT 
                 p.id, 
                 p.name, 
                 p.gender,
@@ -149,20 +167,40 @@ impl NameResolver {
             LEFT JOIN students s ON p.id = s.person_id
             LEFT JOIN teachers t ON p.id = t.person_id
             LEFT JOIN classes c ON s.class_id = c.id
-            LEFT JOIN departments d ON t.department_id = d.id
-            WHERE p.name = $1 OR p.name ILIKE $1
-            ORDER BY p.name"#,
-        )
+            LEFT JOIN departments d ON t.department_id = d.id"#;
+        let mut persons: Vec<PersonInfo> = sqlx::query_as(&format!(
+            "{} WHERE (p.name = $1 OR p.name ILIKE $1) AND p.deleted_at IS NULL ORDER BY p.name",
+            PERSON_INFO_SELECT
+        ))
         .bind(name)
         .fetch_all(pool)
         .await
         .map_err(|e| AppError::Database(e))?;
+        let mut exact = true;
+        
+        if persons.is_empty() {
+            if let Some(term) = SearchTerm::new(name) {
+                persons = sqlx::query_as(&format!(
+                    "{} WHERE {} AND p.deleted_at IS NULL ORDER BY {} DESC, p.name LIMIT {}",
+                    PERSON_INFO_SELECT,
+                    search::condition("p", 1, 2),
+                    search::score("p", 1, 2),
+                    FUZZY_CANDIDATE_LIMIT
+                ))
+                .bind(&term.raw)
+                .bind(&term.pinyin)
+                .fetch_all(pool)
+                .await
+                .map_err(AppError::Database)?;
+                exact = false;
+            }
+        }
         
         if persons.is_empty() {
             return Ok(ResolutionResult::NotFound(format!("未找到名为 '{}' 的人员", name)));
         }
         
-        if persons.len() == 1 {
+        if persons.len() == 1 && (exact || is_pinyin_match(&persons[0].name, name)) {
             return Ok(ResolutionResult::Single(persons[0].id.to_string()));
         }
         
@@ -199,28 +237,47 @@ impl NameResolver {
             }
         }
         
-        // 按名称搜索小组
-        let groups: Vec<GroupInfo> = sqlx::query_as(
-            r#"SELECT 
+        // 按名称搜索小组：先精确匹配，找不到时再按拼音 / 相似度检索
+        const GROUP_INFO_SELECT: &str = r#"SELECT 
                 cg.id, 
                 cg.name, 
                 c.name as class_name,
                 cg.score
             FROM class_groups cg
-            JOIN classes c ON cg.class_id = c.id
-            WHERE cg.name = $1 OR cg.name ILIKE $1
-            ORDER BY cg.name"#,
-        )
+            JOIN classes c ON cg.class_id = c.id"#;
+        let mut groups: Vec<GroupInfo> = sqlx::query_as(&format!(
+            "{} WHERE cg.name = $1 OR cg.name ILIKE $1 ORDER BY cg.name",
+            GROUP_INFO_SELECT
+        ))
         .bind(name)
         .fetch_all(pool)
         .await
         .map_err(|e| AppError::Database(e))?;
+        let mut exact = true;
+        
+        if groups.is_empty() {
+            if let Some(term) = SearchTerm::new(name) {
+                groups = sqlx::query_as(&format!(
+                    "{} WHERE {} ORDER BY {} DESC, cg.name LIMIT {}",
+                    GROUP_INFO_SELECT,
+                    search::condition("cg", 1, 2),
+                    search::score("cg", 1, 2),
+                    FUZZY_CANDIDATE_LIMIT
+                ))
+                .bind(&term.raw)
+                .bind(&term.pinyin)
+                .fetch_all(pool)
+                .await
+                .map_err(AppError::Database)?;
+                exact = false;
+            }
+        }
         
         if groups.is_empty() {
             return Ok(ResolutionResult::NotFound(format!("未找到名为 '{}' 的小组", name)));
         }
         
-        if groups.len() == 1 {
+        if groups.len() == 1 && (exact || is_pinyin_match(&groups[0].name, name)) {
             return Ok(ResolutionResult::Single(groups[0].id.to_string()));
         }
         
@@ -257,20 +314,39 @@ impl NameResolver {
             }
         }
         
-        // 按名称搜索班级
-        let classes: Vec<ClassInfo> = sqlx::query_as(
-            "SELECT id, name, grade FROM classes WHERE name = $1 OR name ILIKE $1 ORDER BY name"
+        // 按名称搜索班级：先精确匹配，找不到时再按拼音 / 相似度检索
+        let mut classes: Vec<ClassInfo> = sqlx::query_as(
+            "SELECT id, name, grade FROM classes WHERE (name = $1 OR name ILIKE $1) AND deleted_at IS NULL ORDER BY name"
         )
         .bind(name)
         .fetch_all(pool)
         .await
         .map_err(|e| AppError::Database(e))?;
+        let mut exact = true;
+        
+        if classes.is_empty() {
+            if let Some(term) = SearchTerm::new(name) {
+                classes = sqlx::query_as(&format!(
+                    "SELECT c.id, c.name, c.grade FROM classes c
+                     WHERE {} AND c.deleted_at IS NULL ORDER BY {} DESC, c.name LIMIT {}",
+                    search::condition("c", 1, 2),
+                    search::score("c", 1, 2),
+                    FUZZY_CANDIDATE_LIMIT
+                ))
+                .bind(&term.raw)
+                .bind(&term.pinyin)
+                .fetch_all(pool)
+                .await
+                .map_err(AppError::Database)?;
+                exact = false;
+            }
+        }
         
         if classes.is_empty() {
             return Ok(ResolutionResult::NotFound(format!("未找到名为 '{}' 的班级", name)));
         }
         
-        if classes.len() == 1 {
+        if classes.len() == 1 && (exact || is_pinyin_match(&classes[0].name, name)) {
             return Ok(ResolutionResult::Single(classes[0].id.to_string()));
         }
         
@@ -332,26 +408,21 @@ pub struct ParamAutoCompleter;
 
 impl ParamAutoCompleter {
     /// 自动补全日期参数
-    /// 支持：今天、明天、昨天、本周、本月、YYYY-MM-DD
+    /// 支持：今天、明天、昨天、前天、后天、周三、下周一、上星期五、YYYY-MM-DD
     pub fn complete_date(date_str: &str) -> Option<String> {
         let today = Local::now().date_naive();
-        
-        match date_str.trim() {
-            "今天" | "今日" | "today" | "now" => {
-                Some(today.format("%Y-%m-%d").to_string())
-            }
-            "明天" | "明日" | "tomorrow" => {
-                Some((today + chrono::Duration::days(1)).format("%Y-%m-%d").to_string())
-            }
-            "昨天" | "昨日" | "yesterday" => {
-                Some((today - chrono::Duration::days(1)).format("%Y-%m-%d").to_string())
-            }
-            s if s.parse::<NaiveDate>().is_ok() => {
-                // 已经是标准格式
-                Some(s.to_string())
-            }
-            _ => None
+        calendar::parse_relative(date_str, today).map(|d| d.format("%Y-%m-%d").to_string())
+    }
+    
+    /// 结合校历补全日期参数，额外支持：第3周周二、开学第一天、下个上学日、上个上学日
+    pub async fn complete_date_with_calendar(pool: &PgPool, date_str: &str) -> Result<Option<String>, AppError> {
+        if let Some(date) = Self::complete_date(date_str) {
+            return Ok(Some(date));
         }
+        let today = Local::now().date_naive();
+        Ok(calendar::resolve_date(pool, date_str, today)
+            .await?
+            .map(|d| d.format("%Y-%m-%d").to_string()))
     }
     
     /// 自动补全时间参数
@@ -503,6 +574,7 @@ impl AIActionExecutor {
     /// 执行AI请求的操作
     pub async fn execute(
         pool: &PgPool,
+        hub: &Hub,
         action_req: &AIActionRequest,
         user_id: Uuid,
         user_name: &str,
@@ -528,10 +600,10 @@ impl AIActionExecutor {
                 Self::execute_remove_group_member(pool, &action_req.params, &user_permissions).await
             }
             "create_attendance" => {
-                Self::execute_create_attendance(pool, &action_req.params, &user_permissions).await
+                Self::execute_create_attendance(pool, hub, &action_req.params, user_id, &user_permissions).await
             }
             "create_score" => {
-                Self::execute_create_score(pool, &action_req.params, &user_permissions).await
+                Self::execute_create_score(pool, &action_req.params, user_id, &user_permissions).await
             }
             _ => {
                 Ok(AIActionResponse {
@@ -723,6 +795,7 @@ impl AIActionExecutor {
         .fetch_one(pool)
         .await
         .map_err(|e| AppError::Database(e))?;
+        search::index_name(pool, "class_groups", row.id, &row.name).await?;
         
         Ok(AIActionResponse {
             success: true,
@@ -818,7 +891,23 @@ impl AIActionExecutor {
             });
         }
         
-        // 执行积分更新
+        // 按积分目录校验原因和分值
+        let entry = match score_rules::resolve_entry(pool, user_id, "group", None, Some(&score_params.reason), score_params.score_change).await {
+            Ok(entry) => entry,
+            Err(AppError::InvalidInput(msg)) => {
+                return Ok(AIActionResponse {
+                    success: false,
+                    message: msg,
+                    data: None,
+                    user_permissions: user_permissions.to_vec(),
+                    need_confirmation: false,
+                    candidates: None,
+                });
+            }
+            Err(e) => return Err(e),
+        };
+        
+        // 执行积分更新（小组总积分由触发器随记录累加）
         let row = sqlx::query_as::<_, ScoreRecordRow>(
             "INSERT INTO group_score_records (group_id, score_change, reason, created_by) 
              VALUES ($1, $2, $3, $4) 
@@ -826,32 +915,22 @@ impl AIActionExecutor {
              (SELECT name FROM persons WHERE id = $4) as operator_name"
         )
         .bind(group_id)
-        .bind(score_params.score_change)
-        .bind(&score_params.reason)
+        .bind(entry.value)
+        .bind(&entry.reason)
         .bind(user_id)
         .fetch_one(pool)
         .await
         .map_err(|e| AppError::Database(e))?;
         
-        // 更新小组总积分
-        sqlx::query(
-            "UPDATE class_groups SET score = score + $1, updated_at = CURRENT_TIMESTAMP WHERE id = $2"
-        )
-        .bind(score_params.score_change)
-        .bind(group_id)
-        .execute(pool)
-        .await
-        .map_err(|e| AppError::Database(e))?;
-        
-        let action = if score_params.score_change >= 0 { "增加" } else { "扣除" };
+        let action = if entry.value >= 0 { "增加" } else { "扣除" };
         
         Ok(AIActionResponse {
             success: true,
-            message: format!("{}积分 {} 分", action, score_params.score_change.abs()),
+            message: format!("{}积分 {} 分", action, entry.value.abs()),
             data: Some(serde_json::json!({
                 "record_id": row.id.to_string(),
-                "score_change": score_params.score_change,
-                "reason": score_params.reason,
+                "score_change": entry.value,
+                "reason": entry.reason,
                 "created_at": row.created_at.to_rfc3339(),
             })),
             user_permissions: user_permissions.to_vec(),
@@ -1143,7 +1222,9 @@ impl AIActionExecutor {
     /// 执行创建考勤记录操作
     async fn execute_create_attendance(
         pool: &PgPool,
+        hub: &Hub,
         params: &serde_json::Value,
+        user_id: Uuid,
         user_permissions: &[String],
     ) -> Result<AIActionResponse, AppError> {
         // 检查权限
@@ -1199,8 +1280,20 @@ impl AIActionExecutor {
         };
         
         // 自动补全日期
-        let date = ParamAutoCompleter::complete_date(&attendance_params.date)
-            .unwrap_or_else(|| attendance_params.date.clone());
+        let date = match ParamAutoCompleter::complete_date_with_calendar(pool, &attendance_params.date).await {
+            Ok(date) => date.unwrap_or_else(|| attendance_params.date.clone()),
+            Err(AppError::InvalidInput(msg)) => {
+                return Ok(AIActionResponse {
+                    success: false,
+                    message: msg,
+                    data: None,
+                    user_permissions: user_permissions.to_vec(),
+                    need_confirmation: false,
+                    candidates: None,
+                });
+            }
+            Err(e) => return Err(e),
+        };
         
         // 验证日期格式并解析为 NaiveDate
         let naive_date = match NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
@@ -1208,7 +1301,7 @@ impl AIActionExecutor {
             Err(_) => {
                 return Ok(AIActionResponse {
                     success: false,
-                    message: format!("无效的日期格式: {}，请使用 YYYY-MM-DD 格式或相对日期（今天、明天等）", date),
+                    message: format!("无效的日期格式: {}，请使用 YYYY-MM-DD 格式或相对日期（今天、下周一、第3周周二等）", date),
                     data: None,
                     user_permissions: user_permissions.to_vec(),
                     need_confirmation: false,
@@ -1217,21 +1310,20 @@ impl AIActionExecutor {
             }
         };
         
-        // 自动补全考勤状态
-        let status = ParamAutoCompleter::complete_attendance_status(&attendance_params.status)
-            .unwrap_or_else(|| attendance_params.status.clone());
-        
-        // 验证考勤状态
-        let valid_statuses = vec!["present", "late", "absent", "early_leave", "excused"];
-        if !valid_statuses.contains(&status.as_str()) {
-            return Ok(AIActionResponse {
-                success: false,
-                message: format!("无效的考勤状态: {}，可选值: 出勤(present)、迟到(late)、缺勤(absent)、早退(early_leave)、请假(excused)", status),
-                data: None,
-                user_permissions: user_permissions.to_vec(),
-                need_confirmation: false,
-                candidates: None,
-            });
+        // 节假日、周末和假期不记考勤
+        match calendar::require_school_day(pool, naive_date).await {
+            Ok(()) => {}
+            Err(AppError::InvalidInput(msg)) => {
+                return Ok(AIActionResponse {
+                    success: false,
+                    message: msg,
+                    data: None,
+                    user_permissions: user_permissions.to_vec(),
+                    need_confirmation: false,
+                    candidates: None,
+                });
+            }
+            Err(e) => return Err(e),
         }
         
         // 自动补全时间并解析为 NaiveTime
@@ -1258,6 +1350,25 @@ impl AIActionExecutor {
             None
         };
         
+        // 自动补全考勤状态，未指定时与手工录入一样按签到窗口判断
+        let status = attendance_params.status.as_deref().map(|status| {
+            ParamAutoCompleter::complete_attendance_status(status).unwrap_or_else(|| status.to_string())
+        });
+        let status = match attendance::status_for(pool, None, status.as_deref(), naive_time).await {
+            Ok(status) => status,
+            Err(AppError::InvalidInput(msg)) => {
+                return Ok(AIActionResponse {
+                    success: false,
+                    message: msg,
+                    data: None,
+                    user_permissions: user_permissions.to_vec(),
+                    need_confirmation: false,
+                    candidates: None,
+                });
+            }
+            Err(e) => return Err(e),
+        };
+        
         // 获取人员信息
         let person_info: (String,) = sqlx::query_as(
             "SELECT name FROM persons WHERE id = $1"
@@ -1269,8 +1380,8 @@ impl AIActionExecutor {
         
         // 执行创建
         let row = sqlx::query_as::<_, AttendanceRow>(
-            "INSERT INTO attendances (person_id, date, status, time, remark) 
-             VALUES ($1, $2, $3, $4, $5) 
+            "INSERT INTO attendances (person_id, date, status, time, remark, created_by) 
+             VALUES ($1, $2, $3, $4, $5, $6) 
              RETURNING id, person_id, (SELECT name FROM persons WHERE id = $1) as person_name,
              date, status, time, remark, created_at"
         )
@@ -1279,9 +1390,26 @@ impl AIActionExecutor {
         .bind(&status)
         .bind(naive_time)
         .bind(attendance_params.remark.as_deref())
+        .bind(user_id)
         .fetch_one(pool)
-        .await
-        .map_err(|e| AppError::Database(e))?;
+        .await;
+        
+        // 每人每天只有一条全天考勤
+        let row = match row {
+            Ok(row) => row,
+            Err(sqlx::Error::Database(ref db)) if db.is_unique_violation() => {
+                return Ok(AIActionResponse {
+                    success: false,
+                    message: format!("为 '{}' 创建考勤记录失败：{}", person_info.0, attendance::DUPLICATE_MESSAGE),
+                    data: None,
+                    user_permissions: user_permissions.to_vec(),
+                    need_confirmation: false,
+                    candidates: None,
+                });
+            }
+            Err(e) => return Err(AppError::Database(e)),
+        };
+        alert::notify_attendance(pool, hub, &[row.id], user_id).await;
         
         Ok(AIActionResponse {
             success: true,
@@ -1304,6 +1432,7 @@ impl AIActionExecutor {
     async fn execute_create_score(
         pool: &PgPool,
         params: &serde_json::Value,
+        user_id: Uuid,
         user_permissions: &[String],
     ) -> Result<AIActionResponse, AppError> {
         // 检查权限
@@ -1358,7 +1487,21 @@ impl AIActionExecutor {
             }
         };
         
-
+        // 按积分目录校验原因和分值
+        let entry = match score_rules::resolve_entry(pool, user_id, "personal", None, Some(&score_params.reason), score_params.value).await {
+            Ok(entry) => entry,
+            Err(AppError::InvalidInput(msg)) => {
+                return Ok(AIActionResponse {
+                    success: false,
+                    message: msg,
+                    data: None,
+                    user_permissions: user_permissions.to_vec(),
+                    need_confirmation: false,
+                    candidates: None,
+                });
+            }
+            Err(e) => return Err(e),
+        };
         
         // 获取人员信息
         let person_info: (String,) = sqlx::query_as(
@@ -1371,23 +1514,26 @@ impl AIActionExecutor {
         
         // 执行创建
         let row = sqlx::query_as::<_, ScoreRow>(
-            "INSERT INTO scores (person_id, score_type, value, reason, event_id, created_by) 
-             VALUES ($1, $2, $3, $4, NULL, NULL) 
+            "INSERT INTO scores (person_id, score_type, value, reason, event_id, created_by, category_id, reason_id) 
+             VALUES ($1, $2, $3, $4, NULL, $5, $6, $7) 
              RETURNING id, person_id, 
              (SELECT name FROM persons WHERE id = $1) as person_name,
              score_type, value, reason, created_at"
         )
         .bind(student_id)
         .bind("personal")  // score_type
-        .bind(score_params.value)  // value
-        .bind(&score_params.reason)  // reason
+        .bind(entry.value)  // value
+        .bind(&entry.reason)  // reason
+        .bind(user_id)
+        .bind(entry.category_id)
+        .bind(entry.reason_id)
         .fetch_one(pool)
         .await
         .map_err(|e| AppError::Database(e))?;
         
         Ok(AIActionResponse {
             success: true,
-            message: format!("为 '{}' 添加个人积分 {} 分成功，原因：{}", person_info.0, score_params.value, score_params.reason),
+            message: format!("为 '{}' 添加个人积分 {} 分成功，原因：{}", person_info.0, row.value, row.reason),
             data: Some(serde_json::json!({
                 "id": row.id.to_string(),
                 "person_name": row.person_name,
@@ -1444,9 +1590,9 @@ struct AttendanceRow {
     id: Uuid,
     person_id: Uuid,
     person_name: String,
-    date: String,
+    date: NaiveDate,
     status: String,
-    time: Option<String>,
+    time: Option<NaiveTime>,
     remark: Option<String>,
     created_at: chrono::DateTime<chrono::Utc>,
 }
@@ -1485,7 +1631,7 @@ pub async fn execute_action(
     .map_err(|_| AppError::NotFound)?;
     
     // 执行操作
-    let response = AIActionExecutor::execute(&pool, &req, user_id, &user_name).await?;
+    let response = AIActionExecutor::execute(&pool, &state.hub, &req, user_id, &user_name).await?;
     
     Ok(Json(response))
 }
@@ -1535,10 +1681,12 @@ pub async fn get_available_actions(
             "action_type": "update_group_score",
             "name": "更新小组积分",
             "description": "增加或减少小组积分",
-            "required_params": ["group_id", "score_change", "reason"],
-            "optional_params": [],
+            "required_params": ["group_id", "reason"],
+            "optional_params": ["score_change"],
             "param_tips": {
-                "group_id": "可以使用小组名称或UUID"
+                "group_id": "可以使用小组名称或UUID",
+                "reason": "积分目录中的预设原因或其同义说法",
+                "score_change": "积分变化（整数），正数增加负数减少，不填时使用预设分值"
             }
         }));
     }
@@ -1574,12 +1722,12 @@ pub async fn get_available_actions(
             "action_type": "create_attendance",
             "name": "创建考勤记录",
             "description": "为人员创建考勤记录",
-            "required_params": ["person_id", "date", "status"],
-            "optional_params": ["time", "remark"],
+            "required_params": ["person_id", "date"],
+            "optional_params": ["status", "time", "remark"],
             "param_tips": {
                 "person_id": "可以使用人员姓名或UUID",
-                "date": "支持日期格式(YYYY-MM-DD)或相对日期(今天、明天、昨天)",
-                "status": "支持中文(出勤、迟到、缺勤、早退、请假)或英文(present、late、absent、early_leave、excused)",
+                "date": "支持日期格式(YYYY-MM-DD)或相对日期(今天、明天、昨天、下周一、第3周周二、下个上学日)",
+                "status": "支持中文(出勤、迟到、缺勤、早退、请假)或英文(present、late、absent、early_leave、excused)，不填时按签到时间判断",
                 "time": "支持时间格式(HH:MM)或描述(上午8点、下午3点、现在)"
             }
         }));
@@ -1591,11 +1739,12 @@ pub async fn get_available_actions(
             "action_type": "create_score",
             "name": "添加个人积分",
             "description": "为人员添加个人表现积分",
-            "required_params": ["student_id", "reason", "value"],
-            "optional_params": [],
+            "required_params": ["student_id", "reason"],
+            "optional_params": ["value"],
             "param_tips": {
                 "student_id": "可以使用人员姓名或UUID",
-                "value": "积分值（整数），可正可负"
+                "reason": "积分目录中的预设原因或其同义说法（如上课说话、课堂讲话）",
+                "value": "积分值（整数），可正可负，不填时使用预设分值"
             }
         }));
     }
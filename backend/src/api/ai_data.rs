@@ -149,6 +149,7 @@ impl ClassDataService {
             FROM classes c
             LEFT JOIN persons p ON c.teacher_id = p.id
             LEFT JOIN students s ON c.id = s.class_id
+            WHERE c.deleted_at IS NULL
             GROUP BY c.id, c.name, c.grade, c.teacher_id, p.name, c.created_at
             ORDER BY c.grade, c.name
             "#
@@ -200,7 +201,7 @@ impl ClassDataService {
             FROM persons p
             JOIN students s ON p.id = s.person_id
             JOIN classes c ON s.class_id = c.id
-            WHERE c.id = $1
+            WHERE c.id = $1 AND p.deleted_at IS NULL
             ORDER BY s.student_no
             "#,
         )
@@ -224,7 +225,7 @@ impl ClassDataService {
             JOIN teachers t ON p.id = t.person_id
             JOIN teacher_class tc ON t.person_id = tc.teacher_id
             LEFT JOIN departments d ON t.department_id = d.id
-            WHERE tc.class_id = $1
+            WHERE tc.class_id = $1 AND p.deleted_at IS NULL
             ORDER BY p.name
             "#,
         )
@@ -396,6 +397,7 @@ impl DepartmentDataService {
                 d.created_at
             FROM departments d
             LEFT JOIN teachers t ON d.id = t.department_id
+            WHERE d.deleted_at IS NULL
             GROUP BY d.id, d.name, d.created_at
             ORDER BY d.name
             "#
@@ -444,7 +446,7 @@ impl DepartmentDataService {
             FROM persons p
             JOIN teachers t ON p.id = t.person_id
             LEFT JOIN departments d ON t.department_id = d.id
-            WHERE t.department_id = $1
+            WHERE t.department_id = $1 AND p.deleted_at IS NULL
             ORDER BY p.name
             "#,
         )
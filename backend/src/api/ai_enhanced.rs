@@ -87,7 +87,7 @@ impl SystemPromptTemplate {
         if user_permissions.iter().any(|p| p == "group.update.score" || p == "group.update" || p == "group.*") {
             action_descriptions.push(r#"**更新小组积分** (update_group_score)
 - 用途：增加或减少小组积分
-- 参数：group_id(小组ID或名称), score_change(积分变化,正数增加负数减少), reason(原因)
+- 参数：group_id(小组ID或名称), reason(积分目录中的预设原因), score_change(积分变化,正数增加负数减少,可选,默认为预设分值)
 - 示例：{"action_type": "update_group_score", "params": {"group_id": "香芋组", "score_change": 10, "reason": "课堂表现优秀"}, "reason": "奖励优秀表现"}"#);
         }
         
@@ -105,7 +105,7 @@ impl SystemPromptTemplate {
         if user_permissions.iter().any(|p| p == "attendance.create" || p == "attendance.*") {
             action_descriptions.push(r#"**创建考勤记录** (create_attendance)
 - 用途：为人员创建考勤记录
-- 参数：person_id(人员姓名或ID), date(日期,支持:今天/明天/昨天/YYYY-MM-DD), status(状态:出勤/迟到/缺勤/早退/请假或present/late/absent/early_leave/excused), time(时间,支持:现在/上午8点/下午3点/HH:MM), remark(备注,可选)
+- 参数：person_id(人员姓名或ID), date(日期,支持:今天/明天/昨天/下周一/第3周周二/下个上学日/YYYY-MM-DD), status(状态:出勤/迟到/缺勤/早退/请假或present/late/absent/early_leave/excused,可选,不填时按签到时间判断), time(时间,支持:现在/上午8点/下午3点/HH:MM), remark(备注,可选)
 - 示例：{"action_type": "create_attendance", "params": {"person_id": "小绿", "date": "今天", "status": "出勤", "time": "上午8点"}, "reason": "记录出勤"}"#);
         }
         
@@ -174,7 +174,7 @@ impl SystemPromptTemplate {
 - 如果用户没有某个操作的权限，使用 [AI_ANSWER] 回复："抱歉，您没有执行此操作的权限。"
 - 执行操作前，确保所有必需参数都已提供，否则询问用户补充
 - **名称解析**：你可以直接使用人员姓名、小组名称、班级名称代替UUID，系统会自动解析。例如："小绿"、"香芋组"、"一年级1班"
-- **日期自动补全**：可以使用"今天"、"明天"、"昨天"等相对日期，系统会自动转换为标准格式
+- **日期自动补全**：可以使用"今天"、"明天"、"下周一"、"第3周周二"、"下个上学日"等相对日期，系统会按校历自动转换为标准格式
 - **时间自动补全**：可以使用"上午8点"、"下午3点"、"现在"等描述，系统会自动转换
 - **考勤状态**：可以使用中文"出勤"、"迟到"、"缺勤"、"早退"、"请假"，系统会自动转换
 
@@ -716,7 +716,7 @@ pub async fn enhanced_chat(
                     .unwrap_or_else(|_| "未知用户".to_string());
                     
                     // 执行操作
-                    match AIActionExecutor::execute(&pool, &action_req, user_id, &user_name).await {
+                    match AIActionExecutor::execute(&pool, &state.hub, &action_req, user_id, &user_name).await {
                         Ok(action_result) => {
                             if action_result.success {
                                 // 操作成功，构建成功提示
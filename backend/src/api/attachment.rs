@@ -0,0 +1,418 @@
+use axum::{
+    extract::{Extension, Multipart, Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::routes::AppState;
+use crate::core::auth::Claims;
+use crate::core::config::{load_config, Config};
+use crate::core::error::AppError;
+use crate::core::permission::{check_user_permission, PermissionManager, PermissionResult};
+use crate::core::storage::{self, Storage};
+use crate::models::attachment::Attachment;
+
+// 缩略图最长边像素
+const THUMBNAIL_SIZE: u32 = 256;
+
+const ATTACHMENT_SELECT: &str = "SELECT id, person_id, kind, file_name, content_type, size_bytes,
+        storage_key, thumbnail_key, thumbnail_key IS NOT NULL AS has_thumbnail, uploaded_by, created_at
+ FROM attachments";
+
+// 上传头像（仅限图片），会替换该人员原有头像
+pub async fn upload_avatar(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(person_id): Path<Uuid>,
+    multipart: Multipart,
+) -> Result<Json<Attachment>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    // 本人可以修改自己的头像
+    if user_id != person_id {
+        let manager = PermissionManager::new(pool.clone());
+        manager.require_permission(user_id, "attachment.upload").await?;
+    }
+
+    let config = load_config().map_err(|_| AppError::Internal)?;
+    let upload = read_upload(multipart, &config).await?;
+    if !upload.content_type.starts_with("image/") {
+        return Err(AppError::InvalidInput("头像必须是图片".to_string()));
+    }
+
+    let storage = storage::from_config(&config)?;
+    let attachment = save_attachment(&pool, storage.as_ref(), person_id, "avatar", upload, user_id).await?;
+
+    // 删除旧头像
+    let old = sqlx::query_as::<_, Attachment>(&format!(
+        "{} WHERE person_id = $1 AND kind = 'avatar' AND id <> $2",
+        ATTACHMENT_SELECT
+    ))
+    .bind(person_id)
+    .bind(attachment.id)
+    .fetch_all(&pool)
+    .await?;
+    for old in old {
+        remove_attachment(&pool, storage.as_ref(), &old).await?;
+    }
+
+    Ok(Json(attachment))
+}
+
+// 上传文档类附件（如入学登记表扫描件）
+pub async fn upload_document(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(person_id): Path<Uuid>,
+    multipart: Multipart,
+) -> Result<Json<Attachment>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    // 本人和学生的家长可以上传证明材料（请假、申诉等）
+    if user_id != person_id && !is_parent_of(&pool, user_id, person_id).await? {
+        let manager = PermissionManager::new(pool.clone());
+        manager.require_permission(user_id, "attachment.upload").await?;
+    }
+
+    let config = load_config().map_err(|_| AppError::Internal)?;
+    let upload = read_upload(multipart, &config).await?;
+
+    let storage = storage::from_config(&config)?;
+    let attachment = save_attachment(&pool, storage.as_ref(), person_id, "document", upload, user_id).await?;
+
+    Ok(Json(attachment))
+}
+
+// 人员的附件列表
+pub async fn list(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(person_id): Path<Uuid>,
+) -> Result<Json<Vec<Attachment>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    require_view(&pool, user_id, person_id).await?;
+
+    let attachments = sqlx::query_as::<_, Attachment>(&format!(
+        "{} WHERE person_id = $1 ORDER BY kind, created_at DESC",
+        ATTACHMENT_SELECT
+    ))
+    .bind(person_id)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(attachments))
+}
+
+// 下载附件原文件
+pub async fn download(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let attachment = get_attachment(&pool, id).await?;
+    require_view(&pool, user_id, attachment.person_id).await?;
+
+    let config = load_config().map_err(|_| AppError::Internal)?;
+    let storage = storage::from_config(&config)?;
+    let data = storage.get(&attachment.storage_key).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, attachment.content_type),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename*=UTF-8''{}", percent_encode(&attachment.file_name)),
+            ),
+        ],
+        data,
+    ))
+}
+
+// 下载图片缩略图
+pub async fn thumbnail(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let attachment = get_attachment(&pool, id).await?;
+    require_view(&pool, user_id, attachment.person_id).await?;
+
+    let key = attachment.thumbnail_key.ok_or(AppError::NotFound)?;
+    let config = load_config().map_err(|_| AppError::Internal)?;
+    let storage = storage::from_config(&config)?;
+    let data = storage.get(&key).await?;
+
+    Ok(([(header::CONTENT_TYPE, "image/png".to_string())], data))
+}
+
+pub async fn delete(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "attachment.delete").await?;
+
+    let attachment = get_attachment(&pool, id).await?;
+    let config = load_config().map_err(|_| AppError::Internal)?;
+    let storage = storage::from_config(&config)?;
+    remove_attachment(&pool, storage.as_ref(), &attachment).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 人员全部附件的存储路径（含缩略图），用于彻底删除人员后清理文件
+pub async fn person_file_keys(pool: &PgPool, person_id: Uuid) -> Result<Vec<String>, AppError> {
+    let rows: Vec<(String, Option<String>)> =
+        sqlx::query_as("SELECT storage_key, thumbnail_key FROM attachments WHERE person_id = $1")
+            .bind(person_id)
+            .fetch_all(pool)
+            .await?;
+    Ok(rows
+        .into_iter()
+        .flat_map(|(key, thumbnail_key)| std::iter::once(key).chain(thumbnail_key))
+        .collect())
+}
+
+/// 删除存储中的文件（数据库记录已随人员级联删除）
+pub async fn delete_files(keys: &[String]) -> Result<(), AppError> {
+    if keys.is_empty() {
+        return Ok(());
+    }
+    let config = load_config().map_err(|_| AppError::Internal)?;
+    let storage = storage::from_config(&config)?;
+    for key in keys {
+        storage.delete(key).await?;
+    }
+    Ok(())
+}
+
+struct Upload {
+    file_name: String,
+    content_type: String,
+    data: Vec<u8>,
+}
+
+// 读取 multipart 中名为 file 的字段，检查大小和类型
+async fn read_upload(mut multipart: Multipart, config: &Config) -> Result<Upload, AppError> {
+    while let Some(mut field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::InvalidInput(format!("上传数据格式错误: {}", e)))?
+    {
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let file_name = field.file_name().unwrap_or("file").to_string();
+        let content_type = field
+            .content_type()
+            .unwrap_or("application/octet-stream")
+            .to_string();
+        if !config.upload_allowed_types.iter().any(|t| t == &content_type) {
+            return Err(AppError::InvalidInput(format!("不允许上传的文件类型: {}", content_type)));
+        }
+
+        // 分块读取，超过大小上限立即中止
+        let mut data = Vec::new();
+        while let Some(chunk) = field
+            .chunk()
+            .await
+            .map_err(|e| AppError::InvalidInput(format!("读取上传文件失败: {}", e)))?
+        {
+            if data.len() + chunk.len() > config.upload_max_bytes {
+                return Err(AppError::InvalidInput(format!(
+                    "文件大小超过上限 {} 字节",
+                    config.upload_max_bytes
+                )));
+            }
+            data.extend_from_slice(&chunk);
+        }
+        if data.is_empty() {
+            return Err(AppError::InvalidInput("上传文件为空".to_string()));
+        }
+
+        // 按文件内容核对声明的类型，避免伪造扩展名
+        let content_ok = if content_type.starts_with("image/") {
+            image::guess_format(&data).is_ok()
+        } else if content_type == "application/pdf" {
+            data.starts_with(b"%PDF")
+        } else {
+            true
+        };
+        if !content_ok {
+            return Err(AppError::InvalidInput("文件内容与声明的类型不符".to_string()));
+        }
+
+        return Ok(Upload {
+            file_name,
+            content_type,
+            data,
+        });
+    }
+
+    Err(AppError::InvalidInput("缺少上传文件字段 file".to_string()))
+}
+
+async fn save_attachment(
+    pool: &PgPool,
+    storage: &dyn Storage,
+    person_id: Uuid,
+    kind: &str,
+    upload: Upload,
+    uploaded_by: Uuid,
+) -> Result<Attachment, AppError> {
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM persons WHERE id = $1 AND deleted_at IS NULL)")
+        .bind(person_id)
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Err(AppError::NotFound);
+    }
+
+    let id = Uuid::new_v4();
+    let storage_key = format!("persons/{}/{}", person_id, id);
+    storage.put(&storage_key, &upload.data).await?;
+
+    // 图片在后台线程生成缩略图
+    let thumbnail_key = if upload.content_type.starts_with("image/") {
+        let data = upload.data.clone();
+        let thumbnail = tokio::task::spawn_blocking(move || storage::make_thumbnail(&data, THUMBNAIL_SIZE))
+            .await
+            .map_err(|_| AppError::Internal)?;
+        match thumbnail {
+            Some(thumbnail) => {
+                let key = format!("persons/{}/{}_thumb.png", person_id, id);
+                storage.put(&key, &thumbnail).await?;
+                Some(key)
+            }
+            None => None,
+        }
+    } else {
+        None
+    };
+
+    let inserted = sqlx::query(
+        "INSERT INTO attachments (id, person_id, kind, file_name, content_type, size_bytes, storage_key, thumbnail_key, uploaded_by)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+    )
+    .bind(id)
+    .bind(person_id)
+    .bind(kind)
+    .bind(&upload.file_name)
+    .bind(&upload.content_type)
+    .bind(upload.data.len() as i64)
+    .bind(&storage_key)
+    .bind(&thumbnail_key)
+    .bind(uploaded_by)
+    .execute(pool)
+    .await;
+    // 登记失败时清理已写入的文件，避免留下孤立文件
+    if let Err(e) = inserted {
+        for key in std::iter::once(&storage_key).chain(thumbnail_key.as_ref()) {
+            if let Err(err) = storage.delete(key).await {
+                tracing::warn!("清理上传文件 {} 失败: {:?}", key, err);
+            }
+        }
+        return Err(e.into());
+    }
+
+    get_attachment(pool, id).await
+}
+
+async fn remove_attachment(pool: &PgPool, storage: &dyn Storage, attachment: &Attachment) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM attachments WHERE id = $1")
+        .bind(attachment.id)
+        .execute(pool)
+        .await?;
+    storage.delete(&attachment.storage_key).await?;
+    if let Some(key) = attachment.thumbnail_key.as_deref() {
+        storage.delete(key).await?;
+    }
+    Ok(())
+}
+
+async fn get_attachment(pool: &PgPool, id: Uuid) -> Result<Attachment, AppError> {
+    sqlx::query_as::<_, Attachment>(&format!("{} WHERE id = $1", ATTACHMENT_SELECT))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+/// 引用的附件必须是该人员的附件（如请假、申诉的证明材料需上传为学生本人的附件）
+pub async fn require_owned(pool: &PgPool, attachment_id: Uuid, person_id: Uuid) -> Result<(), AppError> {
+    let owned: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM attachments WHERE id = $1 AND person_id = $2)")
+        .bind(attachment_id)
+        .bind(person_id)
+        .fetch_one(pool)
+        .await?;
+    if !owned {
+        return Err(AppError::InvalidInput("附件不存在或不属于该学生".to_string()));
+    }
+    Ok(())
+}
+
+// 查看权限：本人、学生的家长，或拥有 attachment.view 权限；
+// 学生的附件按所在班级判断，班主任可以查看本班学生的请假、申诉证明材料
+async fn require_view(pool: &PgPool, user_id: Uuid, person_id: Uuid) -> Result<(), AppError> {
+    if user_id == person_id || is_parent_of(pool, user_id, person_id).await? {
+        return Ok(());
+    }
+
+    let class_id: Option<Uuid> = sqlx::query_scalar("SELECT class_id FROM students WHERE person_id = $1")
+        .bind(person_id)
+        .fetch_optional(pool)
+        .await?
+        .flatten();
+    let allowed = match class_id {
+        Some(class_id) => {
+            let manager = PermissionManager::new(pool.clone());
+            manager.check_class_permission(user_id, "attachment.view", class_id).await == PermissionResult::Allowed
+        }
+        None => check_user_permission(pool, user_id, "attachment.view").await,
+    };
+    if allowed {
+        return Ok(());
+    }
+
+    Err(AppError::Auth("没有权限执行此操作: attachment.view".to_string()))
+}
+
+async fn is_parent_of(pool: &PgPool, parent_id: Uuid, student_id: Uuid) -> Result<bool, AppError> {
+    let linked: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM student_parent WHERE parent_id = $1 AND student_id = $2)",
+    )
+    .bind(parent_id)
+    .bind(student_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(linked)
+}
+
+// Content-Disposition 文件名编码（RFC 5987）
+pub fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
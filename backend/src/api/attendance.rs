@@ -7,8 +7,13 @@ use uuid::Uuid;
 
 use crate::api::routes::AppState;
 use crate::core::error::AppError;
+use crate::core::alert;
+use crate::core::attendance as rules;
 use crate::core::auth::Claims;
+use crate::core::calendar;
 use crate::core::permission::PermissionManager;
+use crate::models::attendance::{AttendanceCorrection, AttendanceWindow, AttendanceWindowSet};
+use crate::models::calendar::Term;
 
 #[derive(Debug, Deserialize)]
 pub struct AttendanceQuery {
@@ -17,22 +22,31 @@ pub struct AttendanceQuery {
     pub person_id: Option<Uuid>,
     pub date: Option<String>,
     pub status: Option<String>,
+    // 按学期 / 学期第几周筛选，week 需与 term_id 一起使用
+    pub term_id: Option<Uuid>,
+    pub week: Option<i32>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateAttendanceRequest {
     pub person_id: Uuid,
     pub date: String,
-    pub status: String,
+    // 节次，不填为全天考勤
+    pub period_id: Option<Uuid>,
+    // 不填时按签到时间和签到窗口自动判断
+    pub status: Option<String>,
     pub time: Option<String>,
     pub remark: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateAttendanceRequest {
+    // 只修改签到时间时按新的时间重新判断状态
     pub status: Option<String>,
     pub time: Option<String>,
     pub remark: Option<String>,
+    // 更正原因，记入更正记录
+    pub reason: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -41,6 +55,8 @@ pub struct AttendanceResponse {
     pub person_id: Uuid,
     pub person_name: String,
     pub date: String,
+    // 节次，为空表示全天考勤
+    pub period_id: Option<Uuid>,
     pub status: String,
     pub time: Option<String>,
     pub remark: Option<String>,
@@ -74,6 +90,31 @@ pub async fn list(
     };
     println!("DEBUG: query.date = {:?}, parsed_date = {:?}", query.date, parsed_date);
 
+    // 学期 / 周次换算为日期范围
+    let date_range = match query.term_id {
+        Some(term_id) => {
+            let term = sqlx::query_as::<_, Term>(
+                "SELECT id, name, academic_year, start_date, end_date FROM terms WHERE id = $1",
+            )
+            .bind(term_id)
+            .fetch_optional(&pool)
+            .await?
+            .ok_or_else(|| AppError::InvalidInput("学期不存在".to_string()))?;
+            match query.week {
+                Some(week) => {
+                    let start = calendar::date_of_week(&term, week, 1)?.max(term.start_date);
+                    let end = calendar::date_of_week(&term, week, 7)?.min(term.end_date);
+                    Some((start, end))
+                }
+                None => Some((term.start_date, term.end_date)),
+            }
+        }
+        None if query.week.is_some() => {
+            return Err(AppError::InvalidInput("按周筛选需要指定 term_id".to_string()));
+        }
+        None => None,
+    };
+
     // 构建查询条件
     let mut conditions = vec!["1=1".to_string()];
     let mut param_index = 1;
@@ -92,6 +133,11 @@ pub async fn list(
         conditions.push(format!("a.status = ${}", param_index));
         param_index += 1;
     }
+
+    if date_range.is_some() {
+        conditions.push(format!("a.date BETWEEN ${} AND ${}", param_index, param_index + 1));
+        param_index += 2;
+    }
     
     let where_clause = conditions.join(" AND ");
     
@@ -108,6 +154,9 @@ pub async fn list(
     if let Some(ref status) = query.status {
         count_query = count_query.bind(status);
     }
+    if let Some((start, end)) = date_range {
+        count_query = count_query.bind(start).bind(end);
+    }
     
     let total: i64 = count_query
         .fetch_one(&pool)
@@ -116,7 +165,7 @@ pub async fn list(
     
     // 查询数据
     let sql = format!(
-        "SELECT a.id, a.person_id, p.name as person_name, a.date, a.status, a.time, a.remark, a.created_at 
+        "SELECT a.id, a.person_id, p.name as person_name, a.date, a.period_id, a.status, a.time, a.remark, a.created_at 
          FROM attendances a 
          JOIN persons p ON a.person_id = p.id 
          WHERE {} 
@@ -138,6 +187,9 @@ pub async fn list(
     if let Some(ref status) = query.status {
         data_query = data_query.bind(status);
     }
+    if let Some((start, end)) = date_range {
+        data_query = data_query.bind(start).bind(end);
+    }
     
     let attendances = data_query
         .bind(limit)
@@ -157,11 +209,12 @@ pub async fn list(
 }
 
 #[derive(sqlx::FromRow)]
-struct AttendanceRow {
+pub struct AttendanceRow {
     id: Uuid,
     person_id: Uuid,
     person_name: String,
     date: chrono::NaiveDate,
+    period_id: Option<Uuid>,
     status: String,
     time: Option<chrono::NaiveTime>,
     remark: Option<String>,
@@ -175,6 +228,7 @@ impl From<AttendanceRow> for AttendanceResponse {
             person_id: row.person_id,
             person_name: row.person_name,
             date: row.date.to_string(),
+            period_id: row.period_id,
             status: row.status,
             time: row.time.map(|t| t.to_string()),
             remark: row.remark,
@@ -198,6 +252,7 @@ pub async fn create(
     // 解析日期
     let date = chrono::NaiveDate::parse_from_str(&req.date, "%Y-%m-%d")
         .map_err(|_| AppError::InvalidInput("无效的日期格式".to_string()))?;
+    calendar::require_school_day(&pool, date).await?;
     
     // 解析时间
     let time = match req.time {
@@ -207,23 +262,31 @@ pub async fn create(
         ),
         None => None,
     };
+    let status = rules::status_for(&pool, req.period_id, req.status.as_deref(), time).await?;
     
     // 插入数据
     let row = sqlx::query_as::<_, AttendanceRow>(
-        "INSERT INTO attendances (person_id, date, status, time, remark, created_by) 
-         VALUES ($1, $2, $3, $4, $5, $6) 
+        "INSERT INTO attendances (person_id, date, status, time, remark, created_by, period_id) 
+         VALUES ($1, $2, $3, $4, $5, $6, $7) 
          RETURNING id, person_id, (SELECT name FROM persons WHERE id = $1) as person_name, 
-         date, status, time, remark, created_at"
+         date, period_id, status, time, remark, created_at"
     )
     .bind(req.person_id)
     .bind(date)
-    .bind(req.status)
+    .bind(status)
     .bind(time)
     .bind(req.remark)
     .bind(user_id)
+    .bind(req.period_id)
     .fetch_one(&pool)
     .await
-    .map_err(|e| AppError::Database(e))?;
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::InvalidInput(rules::DUPLICATE_MESSAGE.to_string())
+        }
+        e => AppError::Database(e),
+    })?;
+    alert::notify_attendance(&pool, &state.hub, &[row.id], user_id).await;
     
     Ok(Json(row.into()))
 }
@@ -235,7 +298,7 @@ pub async fn get(
     let pool = state.pool.ok_or_else(|| AppError::Internal)?;
     
     let row = sqlx::query_as::<_, AttendanceRow>(
-        "SELECT a.id, a.person_id, p.name as person_name, a.date, a.status, a.time, a.remark, a.created_at 
+        "SELECT a.id, a.person_id, p.name as person_name, a.date, a.period_id, a.status, a.time, a.remark, a.created_at 
          FROM attendances a 
          JOIN persons p ON a.person_id = p.id 
          WHERE a.id = $1"
@@ -262,60 +325,55 @@ pub async fn update(
     let manager = PermissionManager::new(pool.clone());
     manager.require_permission(user_id, "attendance.update").await?;
     
-    // 构建更新字段
-    let mut updates = vec![];
-    let mut param_index = 1;
-    
-    if req.status.is_some() {
-        updates.push(format!("status = ${}", param_index));
-        param_index += 1;
-    }
-    
-    if req.time.is_some() {
-        updates.push(format!("time = ${}", param_index));
-        param_index += 1;
-    }
-    
-    if req.remark.is_some() {
-        updates.push(format!("remark = ${}", param_index));
-        param_index += 1;
-    }
-    
-    if updates.is_empty() {
+    let time = match req.time.as_deref() {
+        Some(t) => Some(
+            chrono::NaiveTime::parse_from_str(t, "%H:%M:%S")
+                .map_err(|_| AppError::InvalidInput("无效的时间格式".to_string()))?,
+        ),
+        None => None,
+    };
+    if req.status.is_none() && time.is_none() && req.remark.is_none() {
         return Err(AppError::InvalidInput("没有要更新的字段".to_string()));
     }
-    
-    let sql = format!(
-        "UPDATE attendances SET {} WHERE id = ${} 
+
+    let mut tx = pool.begin().await?;
+    let (period_id, old_status, old_time, old_remark): (Option<Uuid>, String, Option<chrono::NaiveTime>, Option<String>) =
+        sqlx::query_as("SELECT period_id, status, time, remark FROM attendances WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(AppError::NotFound)?;
+
+    // 更正保留原值：修改前后的值记入 attendance_corrections
+    let status = match (req.status.as_deref(), time) {
+        (None, None) => old_status.clone(),
+        (Some(status), _) => rules::resolve_status(Some(status), None, None)?,
+        // 只改签到时间：配置了签到窗口时按窗口重新判断，否则保留原状态
+        (None, Some(time)) => match rules::window_for(&pool, period_id).await? {
+            Some(window) => rules::derive_status(time, &window).to_string(),
+            None => old_status.clone(),
+        },
+    };
+    let old = (old_status, old_time, old_remark);
+    let new = (status, time.or(old.1), req.remark.or_else(|| old.2.clone()));
+    rules::record_correction(&mut tx, id, &old, &new, req.reason.as_deref(), user_id).await?;
+
+    let row = sqlx::query_as::<_, AttendanceRow>(
+        "UPDATE attendances SET status = $2, time = $3, remark = $4, updated_at = NOW() WHERE id = $1 
          RETURNING id, person_id, (SELECT name FROM persons WHERE id = attendances.person_id) as person_name, 
-         date, status, time, remark, created_at",
-        updates.join(", "),
-        param_index
-    );
-    
-    let mut query = sqlx::query_as::<_, AttendanceRow>(&sql);
-    
-    if let Some(status) = req.status {
-        query = query.bind(status);
-    }
-    
-    if let Some(time_str) = req.time {
-        let time = chrono::NaiveTime::parse_from_str(&time_str, "%H:%M:%S")
-            .map_err(|_| AppError::InvalidInput("无效的时间格式".to_string()))?;
-        query = query.bind(time);
-    }
-    
-    if let Some(remark) = req.remark {
-        query = query.bind(remark);
+         date, period_id, status, time, remark, created_at",
+    )
+    .bind(id)
+    .bind(&new.0)
+    .bind(new.1)
+    .bind(&new.2)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    if new.0 != old.0 {
+        alert::notify_attendance(&pool, &state.hub, &[row.id], user_id).await;
     }
     
-    let row = query
-        .bind(id)
-        .fetch_optional(&pool)
-        .await
-        .map_err(|e| AppError::Database(e))?
-        .ok_or(AppError::NotFound)?;
-    
     Ok(Json(row.into()))
 }
 
@@ -331,15 +389,365 @@ pub async fn delete(
     let manager = PermissionManager::new(pool.clone());
     manager.require_permission(user_id, "attendance.delete").await?;
     
-    let result = sqlx::query("DELETE FROM attendances WHERE id = $1")
+    // 删除记为一条更正，原有的更正记录保留
+    let mut tx = pool.begin().await?;
+    rules::delete_attendance(&mut tx, id, None, user_id).await?;
+    tx.commit().await?;
+    
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+// ========== 班级点名 ==========
+
+#[derive(Debug, Deserialize)]
+pub struct RollCallRequest {
+    pub class_id: Uuid,
+    pub date: String,
+    // 节次，不填为全天考勤
+    pub period_id: Option<Uuid>,
+    // 只需列出非出勤的学生，未列出的学生记为 present（已有记录的保持不变）
+    #[serde(default)]
+    pub records: Vec<RollCallRecord>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RollCallRecord {
+    pub person_id: Uuid,
+    // 不填时按签到时间判断
+    pub status: Option<String>,
+    pub time: Option<String>,
+    pub remark: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RollCallQuery {
+    pub class_id: Uuid,
+    pub date: String,
+    pub period_id: Option<Uuid>,
+}
+
+/// 班级点名表，与点名请求同样的结构；status 为空表示尚未记录
+#[derive(Debug, Serialize)]
+pub struct RollCallSheet {
+    pub class_id: Uuid,
+    pub class_name: String,
+    pub date: String,
+    pub period_id: Option<Uuid>,
+    pub records: Vec<RollCallEntry>,
+    // 各状态人数
+    pub counts: std::collections::BTreeMap<String, i64>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RollCallEntry {
+    pub person_id: Uuid,
+    pub name: String,
+    pub student_no: String,
+    pub attendance_id: Option<Uuid>,
+    pub status: Option<String>,
+    pub time: Option<chrono::NaiveTime>,
+    pub remark: Option<String>,
+}
+
+// 整班点名：一个事务内写入全班考勤，按 (学生, 日期, 节次) 幂等
+pub async fn roll_call(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(req): Json<RollCallRequest>,
+) -> Result<Json<RollCallSheet>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_class_permission(user_id, "attendance.create", req.class_id).await?;
+
+    let date = chrono::NaiveDate::parse_from_str(&req.date, "%Y-%m-%d")
+        .map_err(|_| AppError::InvalidInput("无效的日期格式".to_string()))?;
+    calendar::require_school_day(&pool, date).await?;
+    rules::require_period(&pool, req.period_id).await?;
+
+    // 先校验全部记录，避免写入一半
+    let window = rules::window_for(&pool, req.period_id).await?;
+    let mut records = std::collections::HashMap::new();
+    for record in req.records {
+        let time = match record.time.as_deref() {
+            Some(t) => Some(
+                chrono::NaiveTime::parse_from_str(t, "%H:%M:%S")
+                    .map_err(|_| AppError::InvalidInput("无效的时间格式".to_string()))?,
+            ),
+            None => None,
+        };
+        let status = rules::resolve_status(record.status.as_deref(), time, window.as_ref())?;
+        if records.insert(record.person_id, (status, time, record.remark)).is_some() {
+            return Err(AppError::InvalidInput(format!("学生 {} 重复出现", record.person_id)));
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+    // 同一班级同一天的点名串行执行
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(format!("roll-call:{}:{}", req.class_id, date))
+        .execute(&mut *tx)
+        .await?;
+
+    let students: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT s.person_id FROM students s JOIN persons p ON p.id = s.person_id
+         WHERE s.class_id = $1 AND p.deleted_at IS NULL",
+    )
+    .bind(req.class_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    if students.is_empty() {
+        return Err(AppError::InvalidInput("班级不存在或没有学生".to_string()));
+    }
+    if let Some(outsider) = records.keys().find(|id| !students.contains(id)) {
+        return Err(AppError::InvalidInput(format!("学生 {} 不在该班级", outsider)));
+    }
+
+    // 状态有变化的记录，提交后检查缺勤提醒
+    let mut changed = Vec::new();
+    for person_id in &students {
+        let existing: Option<(Uuid, String, Option<chrono::NaiveTime>, Option<String>)> = sqlx::query_as(
+            "SELECT id, status, time, remark FROM attendances
+             WHERE person_id = $1 AND date = $2 AND period_id IS NOT DISTINCT FROM $3
+             FOR UPDATE",
+        )
+        .bind(person_id)
+        .bind(date)
+        .bind(req.period_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        match (records.get(person_id), existing) {
+            (Some((status, time, remark)), Some((id, old_status, old_time, old_remark))) => {
+                let old = (old_status, old_time, old_remark);
+                let new = (status.clone(), time.or(old.1), remark.clone().or_else(|| old.2.clone()));
+                rules::record_correction(&mut tx, id, &old, &new, Some("整班点名"), user_id).await?;
+                if new.0 != old.0 {
+                    changed.push(id);
+                }
+                sqlx::query(
+                    "UPDATE attendances SET status = $2, time = $3, remark = $4, updated_at = NOW() WHERE id = $1",
+                )
+                .bind(id)
+                .bind(&new.0)
+                .bind(new.1)
+                .bind(&new.2)
+                .execute(&mut *tx)
+                .await?;
+            }
+            // 未列出且已有记录的学生保持原状态
+            (None, Some(_)) => {}
+            (record, None) => {
+                let (status, time, remark) = match record {
+                    Some((status, time, remark)) => (status.as_str(), *time, remark.as_deref()),
+                    None => ("present", None, None),
+                };
+                let id: Uuid = sqlx::query_scalar(
+                    "INSERT INTO attendances (person_id, date, period_id, status, time, remark, created_by)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7)
+                     RETURNING id",
+                )
+                .bind(person_id)
+                .bind(date)
+                .bind(req.period_id)
+                .bind(status)
+                .bind(time)
+                .bind(remark)
+                .bind(user_id)
+                .fetch_one(&mut *tx)
+                .await?;
+                changed.push(id);
+            }
+        }
+    }
+    tx.commit().await?;
+    alert::notify_attendance(&pool, &state.hub, &changed, user_id).await;
+
+    Ok(Json(load_roll_call(&pool, req.class_id, date, req.period_id).await?))
+}
+
+// 读取班级点名表
+pub async fn get_roll_call(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<RollCallQuery>,
+) -> Result<Json<RollCallSheet>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_class_permission(user_id, "attendance.view", query.class_id).await?;
+
+    let date = chrono::NaiveDate::parse_from_str(&query.date, "%Y-%m-%d")
+        .map_err(|_| AppError::InvalidInput("无效的日期格式".to_string()))?;
+    rules::require_period(&pool, query.period_id).await?;
+
+    Ok(Json(load_roll_call(&pool, query.class_id, date, query.period_id).await?))
+}
+
+async fn load_roll_call(
+    pool: &sqlx::PgPool,
+    class_id: Uuid,
+    date: chrono::NaiveDate,
+    period_id: Option<Uuid>,
+) -> Result<RollCallSheet, AppError> {
+    let class_name: String = sqlx::query_scalar("SELECT name FROM classes WHERE id = $1")
+        .bind(class_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let records = sqlx::query_as::<_, RollCallEntry>(
+        "SELECT s.person_id, p.name, s.student_no, a.id AS attendance_id, a.status, a.time, a.remark
+         FROM students s
+         JOIN persons p ON p.id = s.person_id
+         LEFT JOIN LATERAL (
+             SELECT id, status, time, remark FROM attendances
+             WHERE person_id = s.person_id AND date = $2 AND period_id IS NOT DISTINCT FROM $3
+         ) a ON true
+         WHERE s.class_id = $1 AND p.deleted_at IS NULL
+         ORDER BY s.student_no, p.name",
+    )
+    .bind(class_id)
+    .bind(date)
+    .bind(period_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut counts = std::collections::BTreeMap::new();
+    for record in &records {
+        if let Some(status) = &record.status {
+            *counts.entry(status.clone()).or_insert(0) += 1;
+        }
+    }
+
+    Ok(RollCallSheet {
+        class_id,
+        class_name,
+        date: date.to_string(),
+        period_id,
+        records,
+        counts,
+    })
+}
+
+// ========== 更正记录 ==========
+
+pub async fn list_corrections(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<AttendanceCorrection>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "attendance.view").await?;
+
+    let corrections = sqlx::query_as::<_, AttendanceCorrection>(
+        "SELECT c.id, c.attendance_id, c.person_id, c.date, c.period_id, c.old_status, c.new_status, c.old_time, c.new_time,
+                c.old_remark, c.new_remark, c.reason, c.corrected_by, p.name AS corrected_by_name, c.corrected_at
+         FROM attendance_corrections c
+         LEFT JOIN persons p ON p.id = c.corrected_by
+         WHERE c.attendance_id = $1
+         ORDER BY c.corrected_at",
+    )
+    .bind(id)
+    .fetch_all(&pool)
+    .await?;
+    Ok(Json(corrections))
+}
+
+// ========== 签到时间窗口 ==========
+
+const WINDOW_SELECT: &str = "SELECT w.id, w.period_id, pd.name AS period_name, w.on_time_until, w.late_until
+     FROM attendance_windows w
+     LEFT JOIN periods pd ON pd.id = w.period_id";
+
+pub async fn list_windows(State(state): State<AppState>) -> Result<Json<Vec<AttendanceWindow>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let windows = sqlx::query_as::<_, AttendanceWindow>(&format!(
+        "{} ORDER BY pd.period_no NULLS FIRST",
+        WINDOW_SELECT
+    ))
+    .fetch_all(&pool)
+    .await?;
+    Ok(Json(windows))
+}
+
+// 设置全天或某一节的签到窗口（已存在则覆盖）
+pub async fn set_window(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<AttendanceWindowSet>,
+) -> Result<Json<AttendanceWindow>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "attendance.settings").await?;
+
+    if payload.late_until < payload.on_time_until {
+        return Err(AppError::InvalidInput("迟到截止时间不能早于准点截止时间".to_string()));
+    }
+    rules::require_period(&pool, payload.period_id).await?;
+
+    let mut tx = pool.begin().await?;
+    let existing: Option<Uuid> =
+        sqlx::query_scalar("SELECT id FROM attendance_windows WHERE period_id IS NOT DISTINCT FROM $1 FOR UPDATE")
+            .bind(payload.period_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let id: Uuid = match existing {
+        Some(id) => {
+            sqlx::query("UPDATE attendance_windows SET on_time_until = $2, late_until = $3 WHERE id = $1")
+                .bind(id)
+                .bind(payload.on_time_until)
+                .bind(payload.late_until)
+                .execute(&mut *tx)
+                .await?;
+            id
+        }
+        None => {
+            sqlx::query_scalar(
+                "INSERT INTO attendance_windows (period_id, on_time_until, late_until) VALUES ($1, $2, $3) RETURNING id",
+            )
+            .bind(payload.period_id)
+            .bind(payload.on_time_until)
+            .bind(payload.late_until)
+            .fetch_one(&mut *tx)
+            .await?
+        }
+    };
+    tx.commit().await?;
+
+    let window = sqlx::query_as::<_, AttendanceWindow>(&format!("{} WHERE w.id = $1", WINDOW_SELECT))
+        .bind(id)
+        .fetch_one(&pool)
+        .await?;
+    Ok(Json(window))
+}
+
+// 删除签到窗口；删除后该时段不再按时间自动判断（节次按上下课时间判断）
+pub async fn delete_window(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<axum::http::StatusCode, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "attendance.settings").await?;
+
+    let result = sqlx::query("DELETE FROM attendance_windows WHERE id = $1")
         .bind(id)
         .execute(&pool)
-        .await
-        .map_err(|e| AppError::Database(e))?;
-    
+        .await?;
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound);
     }
-    
     Ok(axum::http::StatusCode::NO_CONTENT)
 }
@@ -0,0 +1,278 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    Json,
+};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::routes::AppState;
+use crate::core::auth::Claims;
+use crate::core::error::AppError;
+use crate::core::permission::PermissionManager;
+use crate::models::attendance::{
+    AttendanceAlert, AttendanceAlertQuery, AttendanceAlertRule, AttendanceAlertRuleSave, GradeLeader,
+    GradeLeadersSave,
+};
+
+#[derive(Debug, Serialize)]
+pub struct ListResponse<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
+}
+
+const RULE_SELECT: &str = "SELECT id, name, status, threshold, window_days, notify_parents, notify_head_teacher,
+        notify_grade_leader, is_active
+    FROM attendance_alert_rules";
+
+// ========== 提醒规则 ==========
+
+pub async fn list_rules(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<AttendanceAlertRule>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "attendance.alerts").await?;
+
+    let rules = sqlx::query_as::<_, AttendanceAlertRule>(&format!("{} ORDER BY status, threshold, name", RULE_SELECT))
+        .fetch_all(&pool)
+        .await?;
+    Ok(Json(rules))
+}
+
+pub async fn create_rule(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<AttendanceAlertRuleSave>,
+) -> Result<Json<AttendanceAlertRule>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "attendance.alerts").await?;
+
+    validate_rule(&payload)?;
+    let id: Uuid = sqlx::query_scalar(
+        "INSERT INTO attendance_alert_rules
+             (name, status, threshold, window_days, notify_parents, notify_head_teacher, notify_grade_leader, is_active)
+         VALUES ($1, $2, COALESCE($3, 1), COALESCE($4, 1), $5, $6, $7, COALESCE($8, true))
+         RETURNING id",
+    )
+    .bind(payload.name.trim())
+    .bind(&payload.status)
+    .bind(payload.threshold)
+    .bind(payload.window_days)
+    .bind(payload.notify_parents)
+    .bind(payload.notify_head_teacher)
+    .bind(payload.notify_grade_leader)
+    .bind(payload.is_active)
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(load_rule(&pool, id).await?))
+}
+
+// 整体替换规则内容
+pub async fn update_rule(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<AttendanceAlertRuleSave>,
+) -> Result<Json<AttendanceAlertRule>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "attendance.alerts").await?;
+
+    validate_rule(&payload)?;
+    let updated = sqlx::query(
+        "UPDATE attendance_alert_rules SET
+             name = $2, status = $3, threshold = COALESCE($4, 1), window_days = COALESCE($5, 1),
+             notify_parents = $6, notify_head_teacher = $7, notify_grade_leader = $8,
+             is_active = COALESCE($9, is_active)
+         WHERE id = $1",
+    )
+    .bind(id)
+    .bind(payload.name.trim())
+    .bind(&payload.status)
+    .bind(payload.threshold)
+    .bind(payload.window_days)
+    .bind(payload.notify_parents)
+    .bind(payload.notify_head_teacher)
+    .bind(payload.notify_grade_leader)
+    .bind(payload.is_active)
+    .execute(&pool)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(Json(load_rule(&pool, id).await?))
+}
+
+pub async fn delete_rule(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<axum::http::StatusCode, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "attendance.alerts").await?;
+
+    let deleted = sqlx::query("DELETE FROM attendance_alert_rules WHERE id = $1")
+        .bind(id)
+        .execute(&pool)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(axum::http::StatusCode::NO_CONTENT)
+}
+
+// ========== 已发出的提醒 ==========
+
+pub async fn list_alerts(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<AttendanceAlertQuery>,
+) -> Result<Json<ListResponse<AttendanceAlert>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "attendance.view").await?;
+
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = (page - 1) * limit;
+    let filter = "WHERE ($1::uuid IS NULL OR a.student_id = $1)
+          AND ($2::date IS NULL OR a.date >= $2)
+          AND ($3::date IS NULL OR a.date <= $3)";
+
+    let items = sqlx::query_as::<_, AttendanceAlert>(&format!(
+        "SELECT a.id, a.rule_id, r.name AS rule_name, a.student_id, p.name AS student_name, a.attendance_id,
+                a.date, a.occurrences, a.recipients, a.created_at
+         FROM attendance_alerts a
+         JOIN attendance_alert_rules r ON r.id = a.rule_id
+         JOIN persons p ON p.id = a.student_id
+         {}
+         ORDER BY a.created_at DESC
+         LIMIT $4 OFFSET $5",
+        filter
+    ))
+    .bind(query.student_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&pool)
+    .await?;
+    let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM attendance_alerts a {}", filter))
+        .bind(query.student_id)
+        .bind(query.from)
+        .bind(query.to)
+        .fetch_one(&pool)
+        .await?;
+
+    Ok(Json(ListResponse { items, total, page, limit }))
+}
+
+// ========== 年级组长 ==========
+
+pub async fn list_grade_leaders(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<GradeLeader>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "attendance.alerts").await?;
+
+    Ok(Json(load_grade_leaders(&pool, None).await?))
+}
+
+// 设置某个年级的组长（整体替换）
+pub async fn set_grade_leaders(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(grade): Path<i16>,
+    Json(payload): Json<GradeLeadersSave>,
+) -> Result<Json<Vec<GradeLeader>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "attendance.alerts").await?;
+
+    let teachers: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM teachers t JOIN persons p ON p.id = t.person_id
+         WHERE t.person_id = ANY($1) AND p.deleted_at IS NULL",
+    )
+    .bind(&payload.teacher_ids)
+    .fetch_one(&pool)
+    .await?;
+    let mut unique = payload.teacher_ids.clone();
+    unique.sort();
+    unique.dedup();
+    if teachers != unique.len() as i64 {
+        return Err(AppError::InvalidInput("年级组长必须是教职工".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM grade_leaders WHERE grade = $1")
+        .bind(grade)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("INSERT INTO grade_leaders (grade, teacher_id) SELECT $1, UNNEST($2::uuid[])")
+        .bind(grade)
+        .bind(&unique)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(Json(load_grade_leaders(&pool, Some(grade)).await?))
+}
+
+// ========== 辅助函数 ==========
+
+fn validate_rule(payload: &AttendanceAlertRuleSave) -> Result<(), AppError> {
+    if payload.name.trim().is_empty() {
+        return Err(AppError::InvalidInput("规则名称不能为空".to_string()));
+    }
+    if payload.status != "absent" && payload.status != "late" {
+        return Err(AppError::InvalidInput("提醒只支持缺勤（absent）和迟到（late）".to_string()));
+    }
+    if payload.threshold.unwrap_or(1) < 1 || payload.window_days.unwrap_or(1) < 1 {
+        return Err(AppError::InvalidInput("次数和天数必须大于 0".to_string()));
+    }
+    if payload.threshold.unwrap_or(1) > payload.window_days.unwrap_or(1) {
+        return Err(AppError::InvalidInput("次数不能超过统计天数".to_string()));
+    }
+    if !payload.notify_parents && !payload.notify_head_teacher && !payload.notify_grade_leader {
+        return Err(AppError::InvalidInput("请至少选择一类接收人".to_string()));
+    }
+    Ok(())
+}
+
+async fn load_rule(pool: &PgPool, id: Uuid) -> Result<AttendanceAlertRule, AppError> {
+    sqlx::query_as::<_, AttendanceAlertRule>(&format!("{} WHERE id = $1", RULE_SELECT))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+async fn load_grade_leaders(pool: &PgPool, grade: Option<i16>) -> Result<Vec<GradeLeader>, AppError> {
+    let leaders = sqlx::query_as::<_, GradeLeader>(
+        "SELECT g.grade, g.teacher_id, p.name AS teacher_name
+         FROM grade_leaders g JOIN persons p ON p.id = g.teacher_id
+         WHERE $1::smallint IS NULL OR g.grade = $1
+         ORDER BY g.grade, p.name",
+    )
+    .bind(grade)
+    .fetch_all(pool)
+    .await?;
+    Ok(leaders)
+}
@@ -0,0 +1,422 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::api::attachment::percent_encode;
+use crate::api::routes::AppState;
+use crate::core::attendance as rules;
+use crate::core::auth::Claims;
+use crate::core::calendar;
+use crate::core::error::AppError;
+use crate::core::permission::PermissionManager;
+use crate::models::attendance::{
+    AttendanceReport, AttendanceReportQuery, AttendanceReportRow, AttendanceStatRow, AttendanceStats,
+    AttendanceStatsQuery, AttendanceTrendPoint, AttendanceTrendQuery, StatusCounts,
+};
+
+// 统计对象：学生按班级分组，教职工按部门分组
+struct Member {
+    person_id: Uuid,
+    name: String,
+    group_id: Option<Uuid>,
+    group_name: Option<String>,
+    grade: Option<i16>,
+}
+
+// 每人每天的考勤状态
+type Records = HashMap<Uuid, HashMap<NaiveDate, String>>;
+
+// 按学生、班级、年级或部门统计出勤率、各状态次数和连续缺勤
+pub async fn stats(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<AttendanceStatsQuery>,
+) -> Result<Json<AttendanceStats>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    require_view(&pool, user_id, query.class_id).await?;
+
+    let group_by = query.group_by.clone().unwrap_or_else(|| "student".to_string());
+    if !["student", "class", "grade", "department"].contains(&group_by.as_str()) {
+        return Err(AppError::InvalidInput(format!(
+            "不支持的分组方式: {}，可选 student、class、grade 或 department",
+            group_by
+        )));
+    }
+    let teachers = group_by == "department" || query.department_id.is_some();
+    if teachers && group_by == "grade" {
+        return Err(AppError::InvalidInput("教职工不能按年级统计".to_string()));
+    }
+
+    let school_days = calendar::school_days_between(&pool, query.from, query.to).await?;
+    let calendar_aware = calendar::is_configured(&pool).await?;
+    let members = load_members(&pool, teachers, query.class_id, query.grade, query.department_id, None).await?;
+    let records = load_records(
+        &pool,
+        &members,
+        query.from,
+        query.to,
+        query.period_id,
+        &school_days,
+    )
+    .await?;
+
+    let mut rows: Vec<AttendanceStatRow> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let empty = HashMap::new();
+    for member in &members {
+        let statuses = records.get(&member.person_id).unwrap_or(&empty);
+        let mut counts = StatusCounts::default();
+        for status in statuses.values() {
+            rules::tally(&mut counts, status);
+        }
+        let unrecorded = school_days.iter().filter(|d| !statuses.contains_key(d)).count() as i64;
+        let (longest, current) = rules::absence_streaks(&school_days, statuses);
+
+        let (key, id, name, grade) = match group_by.as_str() {
+            "student" => (member.person_id.to_string(), Some(member.person_id), member.name.clone(), member.grade),
+            "grade" => (
+                format!("{:?}", member.grade),
+                None,
+                member.grade.map(|g| format!("{}年级", g)).unwrap_or_else(|| "未分班".to_string()),
+                member.grade,
+            ),
+            _ => (
+                format!("{:?}", member.group_id),
+                member.group_id,
+                member.group_name.clone().unwrap_or_else(|| {
+                    if teachers { "未分配部门" } else { "未分班" }.to_string()
+                }),
+                member.grade,
+            ),
+        };
+        let position = *index.entry(key).or_insert_with(|| {
+            rows.push(AttendanceStatRow {
+                id,
+                name,
+                grade,
+                people: 0,
+                records: 0,
+                expected: 0,
+                unrecorded: 0,
+                counts: StatusCounts::default(),
+                attendance_rate: None,
+                longest_absence_streak: 0,
+                current_absence_streak: 0,
+            });
+            rows.len() - 1
+        });
+        let row = &mut rows[position];
+        row.people += 1;
+        row.records += statuses.len() as i64;
+        row.expected += school_days.len() as i64;
+        row.unrecorded += unrecorded;
+        rules::add_counts(&mut row.counts, &counts);
+        row.longest_absence_streak = row.longest_absence_streak.max(longest);
+        row.current_absence_streak = row.current_absence_streak.max(current);
+    }
+    for row in &mut rows {
+        row.attendance_rate = rules::attendance_rate(&row.counts);
+    }
+
+    Ok(Json(AttendanceStats {
+        from: query.from,
+        to: query.to,
+        group_by,
+        school_days: school_days.len(),
+        calendar_aware,
+        rows,
+    }))
+}
+
+// 按天、周或月统计的考勤趋势
+pub async fn trend(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<AttendanceTrendQuery>,
+) -> Result<Json<Vec<AttendanceTrendPoint>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    require_view(&pool, user_id, query.class_id).await?;
+
+    let interval = query.interval.as_deref().unwrap_or("day");
+    if !["day", "week", "month"].contains(&interval) {
+        return Err(AppError::InvalidInput(format!("不支持的统计周期: {}，可选 day、week 或 month", interval)));
+    }
+
+    let days = calendar::days_between(&pool, query.from, query.to).await?;
+    let school_days: Vec<NaiveDate> = days.iter().filter(|d| d.is_school_day).map(|d| d.date).collect();
+    let members = load_members(
+        &pool,
+        query.department_id.is_some(),
+        query.class_id,
+        query.grade,
+        query.department_id,
+        query.person_id,
+    )
+    .await?;
+    let records = load_records(
+        &pool,
+        &members,
+        query.from,
+        query.to,
+        query.period_id,
+        &school_days,
+    )
+    .await?;
+
+    let mut points: Vec<AttendanceTrendPoint> = Vec::new();
+    let mut index: HashMap<NaiveDate, usize> = HashMap::new();
+    for day in &days {
+        let start = rules::interval_start(day.date, interval).max(query.from);
+        let position = *index.entry(start).or_insert_with(|| {
+            points.push(AttendanceTrendPoint {
+                start,
+                end: start,
+                school_days: 0,
+                records: 0,
+                counts: StatusCounts::default(),
+                attendance_rate: None,
+            });
+            points.len() - 1
+        });
+        let point = &mut points[position];
+        point.end = day.date;
+        if day.is_school_day {
+            point.school_days += 1;
+        }
+    }
+    for statuses in records.values() {
+        for (date, status) in statuses {
+            let start = rules::interval_start(*date, interval).max(query.from);
+            if let Some(&position) = index.get(&start) {
+                points[position].records += 1;
+                rules::tally(&mut points[position].counts, status);
+            }
+        }
+    }
+    for point in &mut points {
+        point.attendance_rate = rules::attendance_rate(&point.counts);
+    }
+
+    Ok(Json(points))
+}
+
+// 班级月度考勤表，format=csv / xlsx 时下载文件
+pub async fn class_report(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(class_id): Path<Uuid>,
+    Query(query): Query<AttendanceReportQuery>,
+) -> Result<Response, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    require_view(&pool, user_id, Some(class_id)).await?;
+
+    let first = match query.month.as_deref() {
+        Some(month) => NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+            .map_err(|_| AppError::InvalidInput("无效的月份格式，应为 YYYY-MM".to_string()))?,
+        None => Local::now().date_naive().with_day(1).unwrap_or_else(|| Local::now().date_naive()),
+    };
+    let next = first
+        .checked_add_months(chrono::Months::new(1))
+        .ok_or_else(|| AppError::InvalidInput("无效的月份".to_string()))?;
+    let last = next - Duration::days(1);
+
+    let class_name: String = sqlx::query_scalar("SELECT name FROM classes WHERE id = $1 AND deleted_at IS NULL")
+        .bind(class_id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let students: Vec<(Uuid, String, String)> = sqlx::query_as(
+        "SELECT p.id, p.name, COALESCE(s.student_no, '')
+         FROM persons p
+         JOIN students s ON s.person_id = p.id
+         WHERE s.class_id = $1 AND p.deleted_at IS NULL
+         ORDER BY s.student_no, p.name",
+    )
+    .bind(class_id)
+    .fetch_all(&pool)
+    .await?;
+
+    // 表格的列为当月上学日，只统计这些日子的全天考勤
+    let days = calendar::school_days_between(&pool, first, last).await?;
+    let members: Vec<Member> = students
+        .iter()
+        .map(|(person_id, name, _)| Member {
+            person_id: *person_id,
+            name: name.clone(),
+            group_id: Some(class_id),
+            group_name: None,
+            grade: None,
+        })
+        .collect();
+    let records = load_records(&pool, &members, first, last, None, &days).await?;
+
+    let empty = HashMap::new();
+    let report = AttendanceReport {
+        class_id,
+        class_name,
+        month: first.format("%Y-%m").to_string(),
+        students: students
+            .into_iter()
+            .map(|(person_id, name, student_no)| {
+                let statuses = records.get(&person_id).unwrap_or(&empty);
+                let mut counts = StatusCounts::default();
+                for status in statuses.values() {
+                    rules::tally(&mut counts, status);
+                }
+                AttendanceReportRow {
+                    person_id,
+                    student_no,
+                    name,
+                    statuses: days.iter().map(|d| statuses.get(d).cloned()).collect(),
+                    attendance_rate: rules::attendance_rate(&counts),
+                    counts,
+                }
+            })
+            .collect(),
+        days,
+    };
+
+    let file_name = |ext: &str| {
+        format!(
+            "attachment; filename*=UTF-8''{}",
+            percent_encode(&format!("{}-{}-考勤.{}", report.class_name, report.month, ext))
+        )
+    };
+    match query.format.as_deref().unwrap_or("json") {
+        "json" => Ok(Json(report).into_response()),
+        "csv" => Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (header::CONTENT_DISPOSITION, file_name("csv")),
+            ],
+            rules::render_report_csv(&report),
+        )
+            .into_response()),
+        "xlsx" => Ok((
+            [
+                (
+                    header::CONTENT_TYPE,
+                    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet".to_string(),
+                ),
+                (header::CONTENT_DISPOSITION, file_name("xlsx")),
+            ],
+            rules::render_report_xlsx(&report)?,
+        )
+            .into_response()),
+        other => Err(AppError::InvalidInput(format!("不支持的格式: {}，可选 json、csv 或 xlsx", other))),
+    }
+}
+
+// 指定班级时按班级权限检查（班主任可查看本班），否则需要全校的考勤查看权限
+async fn require_view(pool: &PgPool, user_id: Uuid, class_id: Option<Uuid>) -> Result<(), AppError> {
+    let manager = PermissionManager::new(pool.clone());
+    match class_id {
+        Some(class_id) => manager.require_class_permission(user_id, "attendance.view", class_id).await,
+        None => manager.require_permission(user_id, "attendance.view").await,
+    }
+}
+
+// (人员ID, 姓名, 班级或部门ID, 班级或部门名称, 年级)
+type MemberRow = (Uuid, String, Option<Uuid>, Option<String>, Option<i16>);
+
+/// 统计范围内的人员：指定部门时为该部门及下级部门的教职工，否则为学生（当前所在班级）
+async fn load_members(
+    pool: &PgPool,
+    teachers: bool,
+    class_id: Option<Uuid>,
+    grade: Option<i16>,
+    department_id: Option<Uuid>,
+    person_id: Option<Uuid>,
+) -> Result<Vec<Member>, AppError> {
+    let rows: Vec<MemberRow> = if teachers {
+        if class_id.is_some() || grade.is_some() {
+            return Err(AppError::InvalidInput("统计教职工时不能指定班级或年级".to_string()));
+        }
+        sqlx::query_as(
+            "WITH RECURSIVE scope AS (
+                 SELECT id FROM departments WHERE id = $1
+                 UNION
+                 SELECT d.id FROM departments d JOIN scope ON d.parent_id = scope.id
+             )
+             SELECT p.id, p.name, d.id, d.name, NULL::smallint
+             FROM persons p
+             JOIN teachers t ON t.person_id = p.id
+             LEFT JOIN departments d ON d.id = t.department_id AND d.deleted_at IS NULL
+             WHERE p.deleted_at IS NULL
+               AND ($1::uuid IS NULL OR t.department_id IN (SELECT id FROM scope))
+               AND ($2::uuid IS NULL OR p.id = $2)
+             ORDER BY d.name NULLS LAST, p.name",
+        )
+        .bind(department_id)
+        .bind(person_id)
+        .fetch_all(pool)
+        .await?
+    } else {
+        sqlx::query_as(
+            "SELECT p.id, p.name, c.id, c.name, c.grade
+             FROM persons p
+             JOIN students s ON s.person_id = p.id
+             LEFT JOIN classes c ON c.id = s.class_id AND c.deleted_at IS NULL
+             WHERE p.deleted_at IS NULL
+               AND ($1::uuid IS NULL OR s.class_id = $1)
+               AND ($2::smallint IS NULL OR c.grade = $2)
+               AND ($3::uuid IS NULL OR p.id = $3)
+             ORDER BY c.grade NULLS LAST, c.name NULLS LAST, s.student_no, p.name",
+        )
+        .bind(class_id)
+        .bind(grade)
+        .bind(person_id)
+        .fetch_all(pool)
+        .await?
+    };
+
+    Ok(rows
+        .into_iter()
+        .map(|(person_id, name, group_id, group_name, grade)| Member { person_id, name, group_id, group_name, grade })
+        .collect())
+}
+
+/// 人员在 [from, to] 内、days 中各天的考勤状态；days 为上学日（未配置校历时为工作日），
+/// 与应到天数的口径一致，周末补录的记录不计入出勤率
+async fn load_records(
+    pool: &PgPool,
+    members: &[Member],
+    from: NaiveDate,
+    to: NaiveDate,
+    period_id: Option<Uuid>,
+    days: &[NaiveDate],
+) -> Result<Records, AppError> {
+    let ids: Vec<Uuid> = members.iter().map(|m| m.person_id).collect();
+    let rows: Vec<(Uuid, NaiveDate, String)> = sqlx::query_as(
+        "SELECT person_id, date, status FROM attendances
+         WHERE person_id = ANY($1) AND date BETWEEN $2 AND $3
+           AND period_id IS NOT DISTINCT FROM $4
+           AND date = ANY($5)",
+    )
+    .bind(&ids)
+    .bind(from)
+    .bind(to)
+    .bind(period_id)
+    .bind(days)
+    .fetch_all(pool)
+    .await?;
+
+    let mut records: Records = HashMap::new();
+    for (person_id, date, status) in rows {
+        records.entry(person_id).or_default().insert(date, status);
+    }
+    Ok(records)
+}
@@ -7,6 +7,7 @@ use crate::core::auth::generate_token;
 use crate::core::config::load_config;
 use crate::core::password::{verify_password, hash_password};
 use crate::core::permission;
+use crate::core::search;
 
 #[derive(Debug, Deserialize)]
 pub struct LoginRequest {
@@ -74,7 +75,7 @@ pub async fn login(
 
     let user = sqlx::query_as!(
         LoginUser,
-        "SELECT id as \"id!: _\", username as \"username!: _\", password_hash as \"password_hash!: _\", role as \"role!: _\", name as \"name!: _\", email as \"email?\", is_active as \"is_active?\" FROM persons WHERE username = $1 AND is_active = true",
+        "SELECT id as \"id!: _\", username as \"username!: _\", password_hash as \"password_hash!: _\", role as \"role!: _\", name as \"name!: _\", email as \"email?\", is_active as \"is_active?\" FROM persons WHERE username = $1 AND is_active = true AND deleted_at IS NULL",
         login_req.username
     )
     .fetch_optional(pool)
@@ -352,6 +353,9 @@ pub async fn register(
     .execute(pool)
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    search::index_name(pool, "persons", user_id, &register_req.name)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
     
     // 5. 根据用户类型，可能需要插入到相关表（students/teachers/parents）
     // 注意：这里只创建基础persons记录，扩展表需要额外处理
@@ -0,0 +1,314 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{Local, NaiveDate};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::api::routes::AppState;
+use crate::core::auth::Claims;
+use crate::core::calendar;
+use crate::core::error::AppError;
+use crate::core::permission::PermissionManager;
+use crate::models::calendar::{CalendarDay, CalendarDaySet, DayInfo, Term, TermCreate, TermUpdate};
+
+#[derive(Debug, Deserialize)]
+pub struct CalendarQuery {
+    // 不填时从今天开始
+    pub from: Option<NaiveDate>,
+    // 不填时查询 7 天
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DayQuery {
+    // YYYY-MM-DD 或日期表达式（下周一、第3周周二、下个上学日等），不填为今天
+    pub date: Option<String>,
+}
+
+// ========== 学期 ==========
+
+pub async fn list_terms(State(state): State<AppState>) -> Result<Json<Vec<Term>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let terms = sqlx::query_as::<_, Term>(
+        "SELECT id, name, academic_year, start_date, end_date FROM terms ORDER BY start_date",
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(Json(terms))
+}
+
+pub async fn create_term(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<TermCreate>,
+) -> Result<Json<Term>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "calendar.manage").await?;
+
+    let term = save_term(
+        &pool,
+        None,
+        &payload.name,
+        &payload.academic_year,
+        payload.start_date,
+        payload.end_date,
+    )
+    .await?;
+    Ok(Json(term))
+}
+
+pub async fn update_term(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<TermUpdate>,
+) -> Result<Json<Term>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "calendar.manage").await?;
+
+    let existing = sqlx::query_as::<_, Term>(
+        "SELECT id, name, academic_year, start_date, end_date FROM terms WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    let term = save_term(
+        &pool,
+        Some(id),
+        payload.name.as_deref().unwrap_or(&existing.name),
+        payload.academic_year.as_deref().unwrap_or(&existing.academic_year),
+        payload.start_date.unwrap_or(existing.start_date),
+        payload.end_date.unwrap_or(existing.end_date),
+    )
+    .await?;
+    Ok(Json(term))
+}
+
+pub async fn delete_term(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "calendar.manage").await?;
+
+    let result = sqlx::query("DELETE FROM terms WHERE id = $1")
+        .bind(id)
+        .execute(&pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn save_term(
+    pool: &sqlx::PgPool,
+    id: Option<Uuid>,
+    name: &str,
+    academic_year: &str,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Term, AppError> {
+    if name.trim().is_empty() || academic_year.trim().is_empty() {
+        return Err(AppError::InvalidInput("学期名称和学年不能为空".to_string()));
+    }
+    if end_date < start_date {
+        return Err(AppError::InvalidInput("结束日期不能早于开始日期".to_string()));
+    }
+    if (end_date - start_date).num_days() >= calendar::MAX_RANGE_DAYS {
+        return Err(AppError::InvalidInput(format!("学期不能超过 {} 天", calendar::MAX_RANGE_DAYS)));
+    }
+    // 学期之间不能重叠，否则一天会落在两个学期里
+    let overlap: Option<String> = sqlx::query_scalar(
+        "SELECT name FROM terms
+         WHERE ($1::uuid IS NULL OR id <> $1) AND start_date <= $3 AND end_date >= $2
+         LIMIT 1",
+    )
+    .bind(id)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_optional(pool)
+    .await?;
+    if let Some(other) = overlap {
+        return Err(AppError::InvalidInput(format!("日期范围与 {} 重叠", other)));
+    }
+
+    let result = match id {
+        Some(id) => {
+            sqlx::query_as::<_, Term>(
+                "UPDATE terms SET name = $2, academic_year = $3, start_date = $4, end_date = $5
+                 WHERE id = $1
+                 RETURNING id, name, academic_year, start_date, end_date",
+            )
+            .bind(id)
+            .bind(name.trim())
+            .bind(academic_year.trim())
+            .bind(start_date)
+            .bind(end_date)
+            .fetch_one(pool)
+            .await
+        }
+        None => {
+            sqlx::query_as::<_, Term>(
+                "INSERT INTO terms (name, academic_year, start_date, end_date)
+                 VALUES ($1, $2, $3, $4)
+                 RETURNING id, name, academic_year, start_date, end_date",
+            )
+            .bind(name.trim())
+            .bind(academic_year.trim())
+            .bind(start_date)
+            .bind(end_date)
+            .fetch_one(pool)
+            .await
+        }
+    };
+    result.map_err(|e| match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::InvalidInput("该学年已有同名学期".to_string())
+        }
+        e => AppError::Database(e),
+    })
+}
+
+// ========== 校历查询 ==========
+
+// 区间内每天的校历情况
+pub async fn list_days(
+    State(state): State<AppState>,
+    Query(query): Query<CalendarQuery>,
+) -> Result<Json<Vec<DayInfo>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let from = query.from.unwrap_or_else(|| Local::now().date_naive());
+    let to = match query.to {
+        Some(to) => to,
+        None => calendar::add_days(from, 6)?,
+    };
+    let days = calendar::days_between(&pool, from, to).await?;
+    Ok(Json(days))
+}
+
+// 单日查询：是否上学日、第几周
+pub async fn get_day(
+    State(state): State<AppState>,
+    Query(query): Query<DayQuery>,
+) -> Result<Json<DayInfo>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let today = Local::now().date_naive();
+    let date = match query.date.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(expr) => calendar::resolve_date(&pool, expr, today)
+            .await?
+            .ok_or_else(|| AppError::InvalidInput(format!("无法识别的日期: {}", expr)))?,
+        None => today,
+    };
+    let info = calendar::day_info(&pool, date).await?;
+    Ok(Json(info))
+}
+
+// ========== 节假日与调休 ==========
+
+pub async fn set_days(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CalendarDaySet>,
+) -> Result<Json<Vec<CalendarDay>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "calendar.manage").await?;
+
+    let end_date = payload.end_date.unwrap_or(payload.start_date);
+    if end_date < payload.start_date {
+        return Err(AppError::InvalidInput("结束日期不能早于开始日期".to_string()));
+    }
+    if (end_date - payload.start_date).num_days() >= calendar::MAX_RANGE_DAYS {
+        return Err(AppError::InvalidInput(format!("一次最多设置 {} 天", calendar::MAX_RANGE_DAYS)));
+    }
+    if payload.name.trim().is_empty() {
+        return Err(AppError::InvalidInput("名称不能为空".to_string()));
+    }
+    match payload.kind.as_str() {
+        "holiday" => {
+            if payload.follows_day_of_week.is_some() {
+                return Err(AppError::InvalidInput("放假日不能指定按星期几上课".to_string()));
+            }
+        }
+        "workday" => {
+            if let Some(dow) = payload.follows_day_of_week {
+                if !(1..=7).contains(&dow) {
+                    return Err(AppError::InvalidInput("follows_day_of_week 必须在 1~7 之间".to_string()));
+                }
+            }
+        }
+        _ => return Err(AppError::InvalidInput("kind 只能为 holiday 或 workday".to_string())),
+    }
+
+    let mut tx = pool.begin().await?;
+    let mut days = Vec::new();
+    let count = (end_date - payload.start_date).num_days() as usize + 1;
+    for date in payload.start_date.iter_days().take(count) {
+        // 调休上课只对周末有意义，范围内的工作日跳过
+        if payload.kind == "workday" && calendar::day_of_week(date) < 6 {
+            continue;
+        }
+        let day = sqlx::query_as::<_, CalendarDay>(
+            "INSERT INTO calendar_days (date, kind, name, follows_day_of_week)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (date) DO UPDATE
+                 SET kind = EXCLUDED.kind, name = EXCLUDED.name, follows_day_of_week = EXCLUDED.follows_day_of_week
+             RETURNING date, kind, name, follows_day_of_week",
+        )
+        .bind(date)
+        .bind(&payload.kind)
+        .bind(payload.name.trim())
+        .bind(payload.follows_day_of_week)
+        .fetch_one(&mut *tx)
+        .await?;
+        days.push(day);
+    }
+    if days.is_empty() {
+        return Err(AppError::InvalidInput("调休上课日应为周末".to_string()));
+    }
+    tx.commit().await?;
+
+    Ok(Json(days))
+}
+
+pub async fn delete_day(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(date): Path<NaiveDate>,
+) -> Result<StatusCode, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "calendar.manage").await?;
+
+    let result = sqlx::query("DELETE FROM calendar_days WHERE date = $1")
+        .bind(date)
+        .execute(&pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
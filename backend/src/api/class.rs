@@ -3,14 +3,24 @@ use axum::{
     http::StatusCode,
     Json,
 };
+use axum_extra::{TypedHeader, headers::{authorization::Bearer, Authorization}};
 use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 use crate::api::routes::AppState;
-use crate::core::auth::Claims;
+use crate::core::archive::{self, ArchiveTarget};
+use crate::core::auth::{optional_user_id, Claims};
+use crate::core::custom_field;
+use crate::core::config::load_config;
 use crate::core::error::AppError;
+use crate::core::history::{self, PersonSnapshot};
 use crate::core::permission::PermissionManager;
-use crate::models::class::{Class, ClassCreate, ClassResponse, ClassUpdate};
+use crate::core::search;
+use crate::models::class::{
+    Class, ClassCreate, ClassMergeRequest, ClassReorganizeResult, ClassResponse, ClassSplitRequest, ClassUpdate,
+};
 
 #[derive(Debug, Deserialize)]
 pub struct ListQuery {
@@ -18,6 +28,10 @@ pub struct ListQuery {
     pub limit: Option<i64>,
     pub search: Option<String>,
     pub grade: Option<i16>,
+    // 为true时只返回已归档（软删除）的班级
+    pub archived: Option<bool>,
+    // 自定义字段过滤，JSON对象
+    pub custom_fields: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -30,14 +44,40 @@ pub struct ListResponse<T> {
 
 pub async fn list(
     State(state): State<AppState>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
     Query(query): Query<ListQuery>,
 ) -> Result<Json<ListResponse<ClassResponse>>, AppError> {
     let page = query.page.unwrap_or(1);
     let limit = query.limit.unwrap_or(20);
 
     if let Some(pool) = state.pool {
-        let (items, total) =
-            list_classes(&pool, query.search.as_deref(), query.grade, page, limit).await?;
+        let user_id = optional_user_id(bearer.as_ref().map(|b| b.token()));
+        let archived = query.archived.unwrap_or(false);
+        // 已归档班级（包括合并、拆分后归档的原班级）只对登录用户可见
+        if archived && user_id.is_none() {
+            return Err(AppError::Auth("查看已归档班级需要登录".to_string()));
+        }
+        let hidden = custom_field::hidden_keys(&pool, "class", user_id).await?;
+        let custom_fields = match query.custom_fields.as_deref().filter(|s| !s.is_empty()) {
+            Some(raw) => Some(custom_field::parse_filter(raw, &hidden)?),
+            None => None,
+        };
+
+        let (mut items, total) =
+            list_classes(
+                &pool,
+                query.search.as_deref(),
+                query.grade,
+                archived,
+                custom_fields.as_ref(),
+                page,
+                limit,
+            )
+            .await?;
+
+        for item in items.iter_mut() {
+            item.custom_fields.retain(|key, _| !hidden.contains(key));
+        }
 
         Ok(Json(ListResponse {
             items,
@@ -67,11 +107,17 @@ pub async fn create(
 
 pub async fn get(
     State(state): State<AppState>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<ClassResponse>, AppError> {
     let pool = state.pool.ok_or_else(|| AppError::Internal)?;
 
-    let class = get_class(&pool, id).await?;
+    let mut class = get_class(&pool, id).await?;
+
+    let user_id = optional_user_id(bearer.as_ref().map(|b| b.token()));
+    let hidden = custom_field::hidden_keys(&pool, "class", user_id).await?;
+    class.custom_fields.retain(|key, _| !hidden.contains(key));
+
     Ok(Json(class))
 }
 
@@ -107,10 +153,81 @@ pub async fn delete(
     let manager = PermissionManager::new(pool.clone());
     manager.require_permission(user_id, "class.delete").await?;
 
-    delete_class(&pool, id).await?;
+    // 软删除：学生、小组和历史记录仍然保留原班级
+    archive::soft_delete(&pool, ArchiveTarget::Class, id, user_id).await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
+// 恢复已归档的班级
+pub async fn restore(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ClassResponse>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "class.restore").await?;
+
+    archive::restore(&pool, ArchiveTarget::Class, id).await?;
+
+    let class = get_class(&pool, id).await?;
+    Ok(Json(class))
+}
+
+// 彻底删除已归档的班级（需超过保留期）
+pub async fn purge(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "class.purge").await?;
+
+    let config = load_config().map_err(|_| AppError::Internal)?;
+    archive::purge(&pool, ArchiveTarget::Class, id, config.archive_retention_days).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// 合并班级：原班级的学生、小组、老师迁入目标班级，原班级默认归档
+pub async fn merge(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ClassMergeRequest>,
+) -> Result<Json<ClassReorganizeResult>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "class.merge").await?;
+
+    let result = merge_classes(&pool, id, payload, user_id).await?;
+    Ok(Json(result))
+}
+
+// 拆分班级：把部分学生分到已有班级或新建班级
+pub async fn split(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ClassSplitRequest>,
+) -> Result<Json<ClassReorganizeResult>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "class.split").await?;
+
+    let result = split_classes(&pool, id, payload, user_id).await?;
+    Ok(Json(result))
+}
+
 // 获取班级的学生列表
 pub async fn get_class_students(
     State(state): State<AppState>,
@@ -133,9 +250,10 @@ pub async fn get_class_teachers(
     Ok(Json(teachers))
 }
 
-use crate::models::person::{PersonResponse, StudentResponse, TeacherResponse};
+use crate::models::custom_field::CustomFields;
+use crate::models::person::{PersonResponse, StudentResponse, TeacherClassInfo, TeacherResponse};
 
-async fn get_class_students_list(
+pub async fn get_class_students_list(
     pool: &sqlx::PgPool,
     class_id: Uuid,
 ) -> Result<Vec<PersonResponse>, AppError> {
@@ -144,7 +262,7 @@ async fn get_class_students_list(
                 s.student_no, s.enrollment_date, s.status
          FROM persons p
          JOIN students s ON p.id = s.person_id
-         WHERE s.class_id = $1
+         WHERE s.class_id = $1 AND p.deleted_at IS NULL
          ORDER BY p.name",
         class_id
     )
@@ -163,7 +281,9 @@ async fn get_class_students_list(
             class_id: Some(class_id),
             class_name: None, // 需要额外查询
             enrollment_date: row.enrollment_date,
-            status: row.status.expect("Student status is required")
+            status: row.status.expect("Student status is required"),
+            guardians: Vec::new(),
+            custom_fields: CustomFields::new(),
         })
     }).collect();
 
@@ -177,11 +297,17 @@ async fn get_class_teachers_list(
     let rows: Vec<_> = sqlx::query!(
         "SELECT p.id, p.name, p.gender, p.birthday, p.phone, p.email, p.type,
                 t.employee_no, t.department_id, t.title, t.hire_date,
-                tc.is_main_teacher
+                tc.is_main_teacher, c.name as class_name,
+                ARRAY(SELECT s.name FROM teaching_assignments ta
+                      JOIN subjects s ON s.id = ta.subject_id
+                      WHERE ta.teacher_id = tc.teacher_id AND ta.class_id = tc.class_id
+                        AND (c.academic_year IS NULL OR ta.academic_year = c.academic_year)
+                      ORDER BY s.name) as subjects
          FROM persons p
          JOIN teachers t ON p.id = t.person_id
          JOIN teacher_class tc ON t.person_id = tc.teacher_id
-         WHERE tc.class_id = $1
+         JOIN classes c ON c.id = tc.class_id
+         WHERE tc.class_id = $1 AND p.deleted_at IS NULL
          ORDER BY tc.is_main_teacher DESC, p.name",
         class_id
     )
@@ -199,9 +325,16 @@ async fn get_class_teachers_list(
             employee_no: row.employee_no,
             department_id: row.department_id,
             department_name: None, // 需要额外查询
-            classes: Vec::new(), // 需要额外查询
+            // 只包含当前班级：是否班主任及所教学科
+            classes: vec![TeacherClassInfo {
+                class_id,
+                class_name: row.class_name,
+                is_main_teacher: row.is_main_teacher.unwrap_or(false),
+                subjects: row.subjects.unwrap_or_default(),
+            }],
             title: row.title,
-            hire_date: row.hire_date
+            hire_date: row.hire_date,
+            custom_fields: CustomFields::new(),
         })
     }).collect();
 
@@ -212,99 +345,84 @@ async fn list_classes(
     pool: &sqlx::PgPool,
     search: Option<&str>,
     grade: Option<i16>,
+    archived: bool,
+    custom_fields: Option<&serde_json::Value>,
     page: i64,
     limit: i64,
 ) -> Result<(Vec<ClassResponse>, i64), AppError> {
     let offset = (page - 1) * limit;
 
-    let total = if let Some(s) = search {
-        if let Some(g) = grade {
-            sqlx::query_scalar::<_, i64>(
-                "SELECT COUNT(*) FROM classes c WHERE c.name ILIKE $1 AND c.grade = $2",
-            )
-            .bind(format!("%{}%", s))
-            .bind(g)
-            .fetch_one(pool)
-            .await?
-        } else {
-            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM classes c WHERE c.name ILIKE $1")
-                .bind(format!("%{}%", s))
-                .fetch_one(pool)
-                .await?
-        }
-    } else if let Some(g) = grade {
-        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM classes c WHERE c.grade = $1")
-            .bind(g)
-            .fetch_one(pool)
-            .await?
+    // 构建查询条件：默认只查询未归档班级，archived=true 时只查询已归档班级
+    let mut conditions = vec![if archived {
+        "c.deleted_at IS NOT NULL".to_string()
     } else {
-        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM classes c")
-            .fetch_one(pool)
-            .await?
-    };
+        "c.deleted_at IS NULL".to_string()
+    }];
+    let mut param_index = 1;
 
-    let rows = if let Some(s) = search {
-        if let Some(g) = grade {
-            sqlx::query_as::<_, ClassWithTeacher>(
-                "SELECT c.id, c.name, c.grade, c.teacher_id, c.academic_year, c.created_at,
-                        p.name as teacher_name
-                 FROM classes c
-                 LEFT JOIN persons p ON c.teacher_id = p.id
-                 WHERE c.name ILIKE $1 AND c.grade = $2
-                 ORDER BY c.created_at DESC
-                 LIMIT $3 OFFSET $4",
-            )
-            .bind(format!("%{}%", s))
-            .bind(g)
-            .bind(limit)
-            .bind(offset)
-            .fetch_all(pool)
-            .await?
-        } else {
-            sqlx::query_as::<_, ClassWithTeacher>(
-                "SELECT c.id, c.name, c.grade, c.teacher_id, c.academic_year, c.created_at,
-                        p.name as teacher_name
-                 FROM classes c
-                 LEFT JOIN persons p ON c.teacher_id = p.id
-                 WHERE c.name ILIKE $1
-                 ORDER BY c.created_at DESC
-                 LIMIT $2 OFFSET $3",
-            )
-            .bind(format!("%{}%", s))
-            .bind(limit)
-            .bind(offset)
-            .fetch_all(pool)
-            .await?
-        }
-    } else if let Some(g) = grade {
-        sqlx::query_as::<_, ClassWithTeacher>(
-            "SELECT c.id, c.name, c.grade, c.teacher_id, c.academic_year, c.created_at,
-                    p.name as teacher_name
-             FROM classes c
-             LEFT JOIN persons p ON c.teacher_id = p.id
-             WHERE c.grade = $1
-             ORDER BY c.created_at DESC
-             LIMIT $2 OFFSET $3",
-        )
-        .bind(g)
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(pool)
-        .await?
-    } else {
-        sqlx::query_as::<_, ClassWithTeacher>(
-            "SELECT c.id, c.name, c.grade, c.teacher_id, c.academic_year, c.created_at,
-                    p.name as teacher_name
-             FROM classes c
-             LEFT JOIN persons p ON c.teacher_id = p.id
-             ORDER BY c.created_at DESC
-             LIMIT $1 OFFSET $2",
-        )
+    if search.is_some() {
+        conditions.push(format!("c.name ILIKE ${}", param_index));
+        param_index += 1;
+    }
+
+    if grade.is_some() {
+        conditions.push(format!("c.grade = ${}", param_index));
+        param_index += 1;
+    }
+
+    if custom_fields.is_some() {
+        conditions.push(format!("c.custom_fields @> ${}", param_index));
+        param_index += 1;
+    }
+
+    let where_clause = conditions.join(" AND ");
+
+    // 查询总数
+    let count_sql = format!("SELECT COUNT(*) FROM classes c WHERE {}", where_clause);
+    let mut count_query = sqlx::query_scalar::<_, i64>(&count_sql);
+
+    if let Some(s) = search {
+        count_query = count_query.bind(format!("%{}%", s));
+    }
+    if let Some(g) = grade {
+        count_query = count_query.bind(g);
+    }
+    if let Some(custom_fields) = custom_fields {
+        count_query = count_query.bind(sqlx::types::Json(custom_fields));
+    }
+
+    let total = count_query.fetch_one(pool).await?;
+
+    // 查询数据
+    let sql = format!(
+        "SELECT c.id, c.name, c.grade, c.teacher_id, c.academic_year, c.created_at, c.custom_fields,
+                p.name as teacher_name
+         FROM classes c
+         LEFT JOIN persons p ON c.teacher_id = p.id
+         WHERE {}
+         ORDER BY c.created_at DESC
+         LIMIT ${} OFFSET ${}",
+        where_clause,
+        param_index,
+        param_index + 1
+    );
+    let mut data_query = sqlx::query_as::<_, ClassWithTeacher>(&sql);
+
+    if let Some(s) = search {
+        data_query = data_query.bind(format!("%{}%", s));
+    }
+    if let Some(g) = grade {
+        data_query = data_query.bind(g);
+    }
+    if let Some(custom_fields) = custom_fields {
+        data_query = data_query.bind(sqlx::types::Json(custom_fields));
+    }
+
+    let rows = data_query
         .bind(limit)
         .bind(offset)
         .fetch_all(pool)
-        .await?
-    };
+        .await?;
 
     let items: Vec<ClassResponse> = rows.into_iter().map(|row| row.into_response()).collect();
 
@@ -313,7 +431,7 @@ async fn list_classes(
 
 async fn get_class(pool: &sqlx::PgPool, id: Uuid) -> Result<ClassResponse, AppError> {
     let row = sqlx::query_as::<_, ClassWithTeacher>(
-        "SELECT c.id, c.name, c.grade, c.teacher_id, c.academic_year, c.created_at,
+        "SELECT c.id, c.name, c.grade, c.teacher_id, c.academic_year, c.created_at, c.custom_fields,
                 p.name as teacher_name
          FROM classes c
          LEFT JOIN persons p ON c.teacher_id = p.id
@@ -331,6 +449,11 @@ async fn create_class(
     pool: &sqlx::PgPool,
     payload: ClassCreate,
 ) -> Result<ClassResponse, AppError> {
+    // 校验自定义字段
+    let custom_fields = payload.custom_fields.clone().unwrap_or_default();
+    let definitions = custom_field::load_definitions(pool, "class", None).await?;
+    custom_field::validate(&definitions, &custom_fields)?;
+
     let mut tx = pool.begin().await?;
     let id = Uuid::new_v4();
 
@@ -341,16 +464,18 @@ async fn create_class(
     let teacher_id = payload.teacher_id.and_then(|id_str| Uuid::parse_str(&id_str).ok());
 
     sqlx::query(
-        "INSERT INTO classes (id, name, grade, teacher_id, academic_year)
-         VALUES ($1, $2, $3, $4, $5)",
+        "INSERT INTO classes (id, name, grade, teacher_id, academic_year, custom_fields)
+         VALUES ($1, $2, $3, $4, $5, $6)",
     )
     .bind(id)
     .bind(&payload.name)
     .bind(grade)
     .bind(teacher_id)
     .bind(&payload.academic_year)
+    .bind(sqlx::types::Json(&custom_fields))
     .execute(&mut *tx)
     .await?;
+    search::index_name(&mut *tx, "classes", id, &payload.name).await?;
 
     // 如果设置了班主任，同步到teacher_class表并植入权限
     if let Some(teacher_id) = teacher_id {
@@ -359,7 +484,7 @@ async fn create_class(
             "INSERT INTO teacher_class (teacher_id, class_id, is_main_teacher)
              VALUES ($1, $2, true)
              ON CONFLICT (teacher_id, class_id) 
-             DO UPDATE SET is_main_teacher = true",
+             DO UPDATE SET is_main_teacher = true, via_assignment = false",
         )
         .bind(teacher_id)
         .bind(id)
@@ -402,10 +527,11 @@ async fn update_class(
 
     if let Some(name) = payload.name {
         sqlx::query("UPDATE classes SET name = $1 WHERE id = $2")
-            .bind(name)
+            .bind(&name)
             .bind(id)
             .execute(&mut *tx)
             .await?;
+        search::index_name(&mut *tx, "classes", id, &name).await?;
     }
     if let Some(grade) = payload.grade {
         // Convert i32 grade to i16 for database
@@ -437,7 +563,7 @@ async fn update_class(
                 "INSERT INTO teacher_class (teacher_id, class_id, is_main_teacher)
                  VALUES ($1, $2, true)
                  ON CONFLICT (teacher_id, class_id) 
-                 DO UPDATE SET is_main_teacher = true",
+                 DO UPDATE SET is_main_teacher = true, via_assignment = false",
             )
             .bind(new_teacher_id)
             .bind(id)
@@ -493,24 +619,614 @@ async fn update_class(
             .execute(&mut *tx)
             .await?;
     }
+    // 自定义字段：与已有取值合并后整体校验；未修改自定义字段时同样校验，
+    // 之后新增的必填字段需要先补全才能保存
+    let existing: sqlx::types::Json<CustomFields> =
+        sqlx::query_scalar("SELECT custom_fields FROM classes WHERE id = $1")
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+    let custom_fields_changed = payload.custom_fields.is_some();
+    let merged = custom_field::merge(existing.0, payload.custom_fields.unwrap_or_default());
+    let definitions = custom_field::load_definitions(pool, "class", None).await?;
+    custom_field::validate(&definitions, &merged)?;
+    if custom_fields_changed {
+        sqlx::query("UPDATE classes SET custom_fields = $1 WHERE id = $2")
+            .bind(sqlx::types::Json(&merged))
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
 
     tx.commit().await?;
     get_class(pool, id).await
 }
 
-async fn delete_class(pool: &sqlx::PgPool, id: Uuid) -> Result<(), AppError> {
-    let result = sqlx::query("DELETE FROM classes WHERE id = $1")
-        .bind(id)
-        .execute(pool)
-        .await?;
+// ========== 班级合并 / 拆分 ==========
 
-    if result.rows_affected() == 0 {
+#[derive(Debug, sqlx::FromRow)]
+struct LockedClass {
+    id: Uuid,
+    name: String,
+    grade: i16,
+    teacher_id: Option<Uuid>,
+    academic_year: Option<String>,
+}
+
+/// 锁定参与合并 / 拆分的班级，避免并发修改；任一班级不存在或已归档时返回 NotFound
+async fn lock_classes(conn: &mut PgConnection, ids: &[Uuid]) -> Result<Vec<LockedClass>, AppError> {
+    let classes = sqlx::query_as::<_, LockedClass>(
+        "SELECT id, name, grade, teacher_id, academic_year FROM classes
+         WHERE id = ANY($1) AND deleted_at IS NULL
+         ORDER BY id FOR UPDATE",
+    )
+    .bind(ids)
+    .fetch_all(&mut *conn)
+    .await?;
+    if classes.len() != ids.len() {
         return Err(AppError::NotFound);
     }
+    Ok(classes)
+}
+
+/// 班级的所有老师（teacher_id, class_id, 是否班主任），用于提交后撤销班级权限
+async fn class_teachers(conn: &mut PgConnection, class_ids: &[Uuid]) -> Result<Vec<(Uuid, Uuid, bool)>, AppError> {
+    let rows = sqlx::query_as::<_, (Uuid, Uuid, bool)>(
+        "SELECT teacher_id, class_id, COALESCE(is_main_teacher, false) FROM teacher_class WHERE class_id = ANY($1)",
+    )
+    .bind(class_ids)
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(rows)
+}
+
+async fn snapshot_all(conn: &mut PgConnection, ids: &[Uuid]) -> Result<Vec<(Uuid, PersonSnapshot)>, AppError> {
+    let mut snapshots = Vec::with_capacity(ids.len());
+    for id in ids {
+        snapshots.push((*id, history::snapshot(&mut *conn, *id).await?));
+    }
+    Ok(snapshots)
+}
+
+/// 与变更前快照比较，写入人员变更历史（学生班级、老师任教班级）
+async fn record_all(conn: &mut PgConnection, before: &[(Uuid, PersonSnapshot)], operator_id: Uuid) -> Result<(), AppError> {
+    for (id, snapshot) in before {
+        let after = history::snapshot(&mut *conn, *id).await?;
+        history::record(&mut *conn, *id, snapshot, &after, operator_id).await?;
+    }
+    Ok(())
+}
+
+/// 调整学生所在班级，变更写入人员历史，按日期查询班级时旧记录仍归属原班级
+async fn move_students(conn: &mut PgConnection, moves: &[(Uuid, Uuid)], operator_id: Uuid) -> Result<u64, AppError> {
+    for (student_id, class_id) in moves {
+        let before = history::snapshot(&mut *conn, *student_id).await?;
+        sqlx::query("UPDATE students SET class_id = $1 WHERE person_id = $2")
+            .bind(class_id)
+            .bind(student_id)
+            .execute(&mut *conn)
+            .await?;
+        let after = history::snapshot(&mut *conn, *student_id).await?;
+        history::record(&mut *conn, *student_id, &before, &after, operator_id).await?;
+    }
+    Ok(moves.len() as u64)
+}
+
+/// 小组整体迁入目标班级（积分与成员不变）；目标班级已有同名小组时在名称后注明原班级
+async fn move_groups(conn: &mut PgConnection, source: &LockedClass, target_id: Uuid) -> Result<u64, AppError> {
+    let groups = sqlx::query_as::<_, (Uuid, String)>(
+        "UPDATE class_groups g SET class_id = $1,
+                name = CASE WHEN EXISTS (SELECT 1 FROM class_groups x WHERE x.class_id = $1 AND x.name = g.name)
+                            THEN g.name || '（' || $3 || '）' ELSE g.name END,
+                updated_at = CURRENT_TIMESTAMP
+         WHERE g.class_id = $2
+         RETURNING g.id, g.name",
+    )
+    .bind(target_id)
+    .bind(source.id)
+    .bind(&source.name)
+    .fetch_all(&mut *conn)
+    .await?;
+    for (id, name) in &groups {
+        search::index_name(&mut *conn, "class_groups", *id, name).await?;
+    }
+    Ok(groups.len() as u64)
+}
+
+/// 在学生的新班级重建原小组：按（原小组, 新班级）新建同名小组（新班级已有同名小组则直接加入），
+/// 学生退出原小组，原小组及其积分记录留在原班级
+async fn rebuild_groups(conn: &mut PgConnection, source_id: Uuid, moves: &[(Uuid, Uuid)]) -> Result<u64, AppError> {
+    let mut rebuilt: HashMap<(Uuid, Uuid), Uuid> = HashMap::new();
+    let mut created = 0;
+    for (student_id, class_id) in moves {
+        let groups = sqlx::query_as::<_, (Uuid, String, Option<String>)>(
+            "SELECT g.id, g.name, g.description FROM class_groups g
+             JOIN group_members gm ON gm.group_id = g.id
+             WHERE g.class_id = $1 AND gm.person_id = $2",
+        )
+        .bind(source_id)
+        .bind(student_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        for (group_id, name, description) in groups {
+            let new_group_id = match rebuilt.get(&(group_id, *class_id)) {
+                Some(id) => *id,
+                None => {
+                    let existing: Option<Uuid> = sqlx::query_scalar(
+                        "SELECT id FROM class_groups WHERE class_id = $1 AND name = $2 LIMIT 1",
+                    )
+                    .bind(class_id)
+                    .bind(&name)
+                    .fetch_optional(&mut *conn)
+                    .await?;
+                    let id = match existing {
+                        Some(id) => id,
+                        None => {
+                            let id = Uuid::new_v4();
+                            sqlx::query(
+                                "INSERT INTO class_groups (id, class_id, name, description) VALUES ($1, $2, $3, $4)",
+                            )
+                            .bind(id)
+                            .bind(class_id)
+                            .bind(&name)
+                            .bind(&description)
+                            .execute(&mut *conn)
+                            .await?;
+                            search::index_name(&mut *conn, "class_groups", id, &name).await?;
+                            created += 1;
+                            id
+                        }
+                    };
+                    rebuilt.insert((group_id, *class_id), id);
+                    id
+                }
+            };
+
+            sqlx::query(
+                "INSERT INTO group_members (group_id, person_id) VALUES ($1, $2)
+                 ON CONFLICT (group_id, person_id) DO NOTHING",
+            )
+            .bind(new_group_id)
+            .bind(student_id)
+            .execute(&mut *conn)
+            .await?;
+            sqlx::query("DELETE FROM group_members WHERE group_id = $1 AND person_id = $2")
+                .bind(group_id)
+                .bind(student_id)
+                .execute(&mut *conn)
+                .await?;
+        }
+    }
+    Ok(created)
+}
 
+/// 班级不再上课：清空老师关联和本学年课程表，并归档
+async fn retire_class(conn: &mut PgConnection, class: &LockedClass, operator_id: Uuid) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM teacher_class WHERE class_id = $1")
+        .bind(class.id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM timetable_entries WHERE class_id = $1 AND academic_year = $2")
+        .bind(class.id)
+        .bind(&class.academic_year)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("UPDATE classes SET teacher_id = NULL WHERE id = $1")
+        .bind(class.id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("UPDATE classes SET deleted_at = NOW(), deleted_by = $2 WHERE id = $1")
+        .bind(class.id)
+        .bind(operator_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// 按 teacher_class 与任课安排重新植入班级权限：班主任获得班级通用权限，任课老师获得小组查看与积分权限
+async fn sync_class_permissions(pool: &sqlx::PgPool, class_id: Uuid) -> Result<(), AppError> {
+    let rows = sqlx::query_as::<_, (Uuid, bool, bool)>(
+        "SELECT tc.teacher_id, COALESCE(tc.is_main_teacher, false),
+                EXISTS (SELECT 1 FROM teaching_assignments ta
+                        WHERE ta.teacher_id = tc.teacher_id AND ta.class_id = tc.class_id)
+         FROM teacher_class tc WHERE tc.class_id = $1",
+    )
+    .bind(class_id)
+    .fetch_all(pool)
+    .await?;
+
+    let permission_manager = PermissionManager::new(pool.clone());
+    for (teacher_id, is_main, teaches) in rows {
+        if is_main {
+            permission_manager.add_class_permissions_for_teacher(teacher_id, class_id).await
+        } else if teaches {
+            permission_manager.add_class_permissions_for_subject_teacher(teacher_id, class_id).await
+        } else {
+            Ok(())
+        }
+        .map_err(|e| AppError::InternalWithMessage(format!("植入权限失败: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// 撤销老师在原班级的权限
+async fn revoke_class_permissions(pool: &sqlx::PgPool, teachers: &[(Uuid, Uuid, bool)]) -> Result<(), AppError> {
+    let permission_manager = PermissionManager::new(pool.clone());
+    for (teacher_id, class_id, is_main) in teachers {
+        if *is_main {
+            permission_manager.remove_class_permissions_for_teacher(*teacher_id, *class_id).await
+        } else {
+            permission_manager.remove_class_permissions_for_subject_teacher(*teacher_id, *class_id).await
+        }
+        .map_err(|e| AppError::InternalWithMessage(format!("移除权限失败: {}", e)))?;
+    }
     Ok(())
 }
 
+async fn merge_classes(
+    pool: &sqlx::PgPool,
+    target_id: Uuid,
+    payload: ClassMergeRequest,
+    operator_id: Uuid,
+) -> Result<ClassReorganizeResult, AppError> {
+    let group_mode = payload.group_mode.as_deref().unwrap_or("move");
+    if !["move", "rebuild"].contains(&group_mode) {
+        return Err(AppError::InvalidInput("group_mode 只能为 move 或 rebuild".to_string()));
+    }
+    let mut source_ids = payload.source_ids;
+    source_ids.sort();
+    source_ids.dedup();
+    if source_ids.is_empty() {
+        return Err(AppError::InvalidInput("请指定要合并的班级".to_string()));
+    }
+    if source_ids.contains(&target_id) {
+        return Err(AppError::InvalidInput("不能将班级合并到自身".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let mut all_ids = source_ids.clone();
+    all_ids.push(target_id);
+    let classes = lock_classes(&mut tx, &all_ids).await?;
+    let (targets, sources): (Vec<LockedClass>, Vec<LockedClass>) =
+        classes.into_iter().partition(|c| c.id == target_id);
+    let target = &targets[0];
+    for source in &sources {
+        if source.grade != target.grade || source.academic_year != target.academic_year {
+            return Err(AppError::InvalidInput(format!(
+                "{} 与 {} 不在同一年级或学年，不能合并",
+                source.name, target.name
+            )));
+        }
+    }
+
+    let archive = payload.archive_sources.unwrap_or(true);
+    // 只有归档的原班级才解除老师关联、收回权限；不归档时原班级保持原样
+    let source_teachers = class_teachers(&mut tx, &source_ids).await?;
+    let mut teacher_ids: Vec<Uuid> = source_teachers.iter().map(|(t, _, _)| *t).collect();
+    let revoked = if archive { source_teachers } else { Vec::new() };
+    teacher_ids.sort();
+    teacher_ids.dedup();
+    let teacher_snapshots = snapshot_all(&mut tx, &teacher_ids).await?;
+
+    let mut result = ClassReorganizeResult {
+        class_ids: vec![target_id],
+        archived: Vec::new(),
+        students: 0,
+        groups_moved: 0,
+        groups_created: 0,
+        teachers: 0,
+        assignments: 0,
+    };
+    let mut main_teacher = target.teacher_id;
+
+    for source in &sources {
+        // 学生
+        let students: Vec<Uuid> = sqlx::query_scalar("SELECT person_id FROM students WHERE class_id = $1")
+            .bind(source.id)
+            .fetch_all(&mut *tx)
+            .await?;
+        let moves: Vec<(Uuid, Uuid)> = students.into_iter().map(|s| (s, target_id)).collect();
+        result.students += move_students(&mut tx, &moves, operator_id).await?;
+
+        // 小组
+        if group_mode == "move" {
+            result.groups_moved += move_groups(&mut tx, source, target_id).await?;
+        } else {
+            result.groups_created += rebuild_groups(&mut tx, source.id, &moves).await?;
+        }
+
+        // 本学年任课安排迁入目标班级（不归档原班级时为复制），目标班级已有相同安排的直接丢弃；
+        // 往年安排留在原班级
+        if archive {
+            result.assignments += sqlx::query(
+                "UPDATE teaching_assignments ta SET class_id = $1
+                 WHERE ta.class_id = $2 AND ta.academic_year = $3
+                   AND NOT EXISTS (SELECT 1 FROM teaching_assignments x
+                                   WHERE x.class_id = $1 AND x.teacher_id = ta.teacher_id
+                                     AND x.subject_id = ta.subject_id AND x.academic_year = ta.academic_year)",
+            )
+            .bind(target_id)
+            .bind(source.id)
+            .bind(&source.academic_year)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            sqlx::query("DELETE FROM teaching_assignments WHERE class_id = $1 AND academic_year = $2")
+                .bind(source.id)
+                .bind(&source.academic_year)
+                .execute(&mut *tx)
+                .await?;
+        } else {
+            result.assignments += sqlx::query(
+                "INSERT INTO teaching_assignments (teacher_id, subject_id, class_id, academic_year)
+                 SELECT teacher_id, subject_id, $1, academic_year FROM teaching_assignments
+                 WHERE class_id = $2 AND academic_year = $3
+                 ON CONFLICT (teacher_id, subject_id, class_id, academic_year) DO NOTHING",
+            )
+            .bind(target_id)
+            .bind(source.id)
+            .bind(&source.academic_year)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+
+        // 原班级老师以任课老师身份加入目标班级；目标班级没有班主任时由原班级班主任接任
+        result.teachers += sqlx::query(
+            "INSERT INTO teacher_class (teacher_id, class_id, is_main_teacher, via_assignment)
+             SELECT teacher_id, $1, false, via_assignment AND NOT COALESCE(is_main_teacher, false)
+             FROM teacher_class WHERE class_id = $2
+             ON CONFLICT (teacher_id, class_id) DO NOTHING",
+        )
+        .bind(target_id)
+        .bind(source.id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if main_teacher.is_none() {
+            if let Some(teacher_id) = source.teacher_id {
+                sqlx::query("UPDATE classes SET teacher_id = $1 WHERE id = $2")
+                    .bind(teacher_id)
+                    .bind(target_id)
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query("UPDATE teacher_class SET is_main_teacher = (teacher_id = $1) WHERE class_id = $2")
+                    .bind(teacher_id)
+                    .bind(target_id)
+                    .execute(&mut *tx)
+                    .await?;
+                main_teacher = Some(teacher_id);
+            }
+        }
+
+        if archive {
+            retire_class(&mut tx, source, operator_id).await?;
+            result.archived.push(source.id);
+        }
+    }
+
+    record_all(&mut tx, &teacher_snapshots, operator_id).await?;
+    tx.commit().await?;
+
+    revoke_class_permissions(pool, &revoked).await?;
+    sync_class_permissions(pool, target_id).await?;
+
+    Ok(result)
+}
+
+async fn split_classes(
+    pool: &sqlx::PgPool,
+    source_id: Uuid,
+    payload: ClassSplitRequest,
+    operator_id: Uuid,
+) -> Result<ClassReorganizeResult, AppError> {
+    let group_mode = payload.group_mode.as_deref().unwrap_or("rebuild");
+    if !["rebuild", "dissolve"].contains(&group_mode) {
+        return Err(AppError::InvalidInput("group_mode 只能为 rebuild 或 dissolve".to_string()));
+    }
+    if payload.targets.is_empty() {
+        return Err(AppError::InvalidInput("请指定拆分去向".to_string()));
+    }
+    let copy_assignments = payload.copy_assignments.unwrap_or(true);
+    let archive = payload.archive_source.unwrap_or(false);
+
+    // 同一学生只能有一个去向
+    let mut assigned = HashSet::new();
+    for target in &payload.targets {
+        match target.class_id {
+            Some(id) if id == source_id => {
+                return Err(AppError::InvalidInput("拆分去向不能是原班级".to_string()));
+            }
+            Some(_) if target.teacher_id.is_some() => {
+                return Err(AppError::InvalidInput("分到已有班级时不能指定班主任".to_string()));
+            }
+            Some(_) => {}
+            None if target.name.as_deref().map(str::trim).unwrap_or("").is_empty() => {
+                return Err(AppError::InvalidInput("新建班级需要指定名称".to_string()));
+            }
+            None => {}
+        }
+        for student_id in &target.student_ids {
+            if !assigned.insert(*student_id) {
+                return Err(AppError::InvalidInput(format!("学生 {} 被分到多个班级", student_id)));
+            }
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let mut ids: Vec<Uuid> = payload.targets.iter().filter_map(|t| t.class_id).collect();
+    ids.sort();
+    ids.dedup();
+    ids.push(source_id);
+    let classes = lock_classes(&mut tx, &ids).await?;
+    let source = classes.iter().find(|c| c.id == source_id).ok_or(AppError::NotFound)?;
+    for class in classes.iter().filter(|c| c.id != source_id) {
+        if class.grade != source.grade || class.academic_year != source.academic_year {
+            return Err(AppError::InvalidInput(format!(
+                "{} 与 {} 不在同一年级或学年",
+                class.name, source.name
+            )));
+        }
+    }
+
+    let students: HashSet<Uuid> = sqlx::query_scalar::<_, Uuid>("SELECT person_id FROM students WHERE class_id = $1")
+        .bind(source_id)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .collect();
+    if let Some(student_id) = assigned.iter().find(|s| !students.contains(s)) {
+        return Err(AppError::InvalidInput(format!("学生 {} 不在 {}", student_id, source.name)));
+    }
+    if archive && assigned.len() != students.len() {
+        return Err(AppError::InvalidInput("归档原班级前需要为所有学生分配去向".to_string()));
+    }
+
+    // 涉及的老师：新班级班主任、本学年任课老师，归档时还有原班级的所有老师
+    let revoked = if archive { class_teachers(&mut tx, &[source_id]).await? } else { Vec::new() };
+    let mut teacher_ids: Vec<Uuid> = payload.targets.iter().filter_map(|t| t.teacher_id).collect();
+    teacher_ids.extend(revoked.iter().map(|(t, _, _)| *t));
+    if copy_assignments {
+        let subject_teachers: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT DISTINCT teacher_id FROM teaching_assignments WHERE class_id = $1 AND academic_year = $2",
+        )
+        .bind(source_id)
+        .bind(&source.academic_year)
+        .fetch_all(&mut *tx)
+        .await?;
+        teacher_ids.extend(subject_teachers);
+    }
+    teacher_ids.sort();
+    teacher_ids.dedup();
+    let teacher_snapshots = snapshot_all(&mut tx, &teacher_ids).await?;
+
+    let mut result = ClassReorganizeResult {
+        class_ids: Vec::new(),
+        archived: Vec::new(),
+        students: 0,
+        groups_moved: 0,
+        groups_created: 0,
+        teachers: 0,
+        assignments: 0,
+    };
+    let mut moves = Vec::new();
+
+    for target in &payload.targets {
+        let class_id = match target.class_id {
+            Some(id) => id,
+            None => {
+                if let Some(teacher_id) = target.teacher_id {
+                    let is_teacher: bool = sqlx::query_scalar(
+                        "SELECT EXISTS(SELECT 1 FROM teachers t JOIN persons p ON p.id = t.person_id
+                                       WHERE t.person_id = $1 AND p.deleted_at IS NULL)",
+                    )
+                    .bind(teacher_id)
+                    .fetch_one(&mut *tx)
+                    .await?;
+                    if !is_teacher {
+                        return Err(AppError::InvalidInput("班主任必须是在职老师".to_string()));
+                    }
+                }
+
+                let id = Uuid::new_v4();
+                let name = target.name.as_deref().unwrap_or("").trim();
+                sqlx::query(
+                    "INSERT INTO classes (id, name, grade, teacher_id, academic_year) VALUES ($1, $2, $3, $4, $5)",
+                )
+                .bind(id)
+                .bind(name)
+                .bind(source.grade)
+                .bind(target.teacher_id)
+                .bind(&source.academic_year)
+                .execute(&mut *tx)
+                .await?;
+                search::index_name(&mut *tx, "classes", id, name).await?;
+
+                if let Some(teacher_id) = target.teacher_id {
+                    sqlx::query("INSERT INTO teacher_class (teacher_id, class_id, is_main_teacher) VALUES ($1, $2, true)")
+                        .bind(teacher_id)
+                        .bind(id)
+                        .execute(&mut *tx)
+                        .await?;
+                    result.teachers += 1;
+                }
+                id
+            }
+        };
+
+        // 复制本学年任课安排，任课老师同时加入新班级
+        if copy_assignments {
+            result.assignments += sqlx::query(
+                "INSERT INTO teaching_assignments (teacher_id, subject_id, class_id, academic_year)
+                 SELECT teacher_id, subject_id, $1, academic_year FROM teaching_assignments
+                 WHERE class_id = $2 AND academic_year = $3
+                 ON CONFLICT (teacher_id, subject_id, class_id, academic_year) DO NOTHING",
+            )
+            .bind(class_id)
+            .bind(source_id)
+            .bind(&source.academic_year)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            result.teachers += sqlx::query(
+                "INSERT INTO teacher_class (teacher_id, class_id, is_main_teacher, via_assignment)
+                 SELECT DISTINCT teacher_id, $1, false, true FROM teaching_assignments
+                 WHERE class_id = $2 AND academic_year = $3
+                 ON CONFLICT (teacher_id, class_id) DO NOTHING",
+            )
+            .bind(class_id)
+            .bind(source_id)
+            .bind(&source.academic_year)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+
+        moves.extend(target.student_ids.iter().map(|s| (*s, class_id)));
+        result.class_ids.push(class_id);
+    }
+
+    result.students = move_students(&mut tx, &moves, operator_id).await?;
+
+    // 小组
+    if group_mode == "rebuild" {
+        result.groups_created = rebuild_groups(&mut tx, source_id, &moves).await?;
+    } else {
+        let moved: Vec<Uuid> = moves.iter().map(|(s, _)| *s).collect();
+        sqlx::query(
+            "DELETE FROM group_members gm USING class_groups g
+             WHERE g.id = gm.group_id AND g.class_id = $1 AND gm.person_id = ANY($2)",
+        )
+        .bind(source_id)
+        .bind(&moved)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    if archive {
+        retire_class(&mut tx, source, operator_id).await?;
+        sqlx::query("DELETE FROM teaching_assignments WHERE class_id = $1 AND academic_year = $2")
+            .bind(source_id)
+            .bind(&source.academic_year)
+            .execute(&mut *tx)
+            .await?;
+        result.archived.push(source_id);
+    }
+
+    record_all(&mut tx, &teacher_snapshots, operator_id).await?;
+    tx.commit().await?;
+
+    revoke_class_permissions(pool, &revoked).await?;
+    for class_id in &result.class_ids {
+        sync_class_permissions(pool, *class_id).await?;
+    }
+
+    Ok(result)
+}
+
 #[derive(Debug, sqlx::FromRow)]
 struct ClassWithTeacher {
     id: Uuid,
@@ -520,6 +1236,7 @@ struct ClassWithTeacher {
     academic_year: String,
     created_at: chrono::DateTime<chrono::Utc>,
     teacher_name: Option<String>,
+    custom_fields: sqlx::types::Json<CustomFields>,
 }
 
 impl ClassWithTeacher {
@@ -532,6 +1249,7 @@ impl ClassWithTeacher {
             teacher_name: self.teacher_name,
             academic_year: self.academic_year,
             created_at: self.created_at,
+            custom_fields: self.custom_fields.0,
         }
     }
 }
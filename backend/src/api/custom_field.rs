@@ -0,0 +1,186 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::api::routes::AppState;
+use crate::core::auth::Claims;
+use crate::core::custom_field::{self, FIELD_TYPES};
+use crate::core::error::AppError;
+use crate::core::permission::PermissionManager;
+use crate::models::custom_field::{CustomFieldCreate, CustomFieldDefinition, CustomFieldUpdate};
+
+#[derive(Debug, Deserialize)]
+pub struct ListQuery {
+    // 'person' 或 'class'
+    pub entity: String,
+    pub person_type: Option<String>,
+}
+
+// 获取自定义字段定义
+pub async fn list(
+    State(state): State<AppState>,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<CustomFieldDefinition>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let defs = custom_field::load_definitions(
+        &pool,
+        &query.entity,
+        query.person_type.as_deref().filter(|t| !t.is_empty()),
+    )
+    .await?;
+    Ok(Json(defs))
+}
+
+pub async fn create(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CustomFieldCreate>,
+) -> Result<Json<CustomFieldDefinition>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "custom_field.manage").await?;
+
+    if !["person", "class"].contains(&payload.entity.as_str()) {
+        return Err(AppError::InvalidInput("entity 必须是 person 或 class".to_string()));
+    }
+    if payload.key.is_empty()
+        || !payload.key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        return Err(AppError::InvalidInput("字段键只能包含小写字母、数字和下划线".to_string()));
+    }
+    if !FIELD_TYPES.contains(&payload.field_type.as_str()) {
+        return Err(AppError::InvalidInput(format!(
+            "字段类型必须是 {}",
+            FIELD_TYPES.join("、")
+        )));
+    }
+    let options = payload.options.unwrap_or_default();
+    if payload.field_type == "enum" && options.is_empty() {
+        return Err(AppError::InvalidInput("枚举字段必须提供可选值".to_string()));
+    }
+    if payload.person_type.is_some() && payload.entity != "person" {
+        return Err(AppError::InvalidInput("只有人员字段可以限定人员类型".to_string()));
+    }
+
+    let def = sqlx::query_as::<_, CustomFieldDefinition>(
+        "INSERT INTO custom_field_definitions
+            (entity, key, label, field_type, required, options, person_type, visibility_permission, sort_order)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+         RETURNING *",
+    )
+    .bind(&payload.entity)
+    .bind(&payload.key)
+    .bind(&payload.label)
+    .bind(&payload.field_type)
+    .bind(payload.required.unwrap_or(false))
+    .bind(sqlx::types::Json(&options))
+    .bind(payload.person_type.filter(|t| !t.is_empty()))
+    .bind(payload.visibility_permission.filter(|p| !p.is_empty()))
+    .bind(payload.sort_order.unwrap_or(0))
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::InvalidInput(format!("字段键 {} 已存在", payload.key))
+        }
+        e => AppError::Database(e),
+    })?;
+
+    Ok(Json(def))
+}
+
+pub async fn update(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<CustomFieldUpdate>,
+) -> Result<Json<CustomFieldDefinition>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "custom_field.manage").await?;
+
+    let existing = sqlx::query_as::<_, CustomFieldDefinition>("SELECT * FROM custom_field_definitions WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    if let Some(options) = payload.options.as_ref() {
+        if existing.field_type == "enum" && options.is_empty() {
+            return Err(AppError::InvalidInput("枚举字段必须提供可选值".to_string()));
+        }
+    }
+
+    // visibility_permission 传空字符串表示取消可见性限制
+    let visibility_permission = match payload.visibility_permission {
+        Some(p) if p.is_empty() => None,
+        Some(p) => Some(p),
+        None => existing.visibility_permission,
+    };
+
+    let def = sqlx::query_as::<_, CustomFieldDefinition>(
+        "UPDATE custom_field_definitions
+         SET label = $2, required = $3, options = $4, visibility_permission = $5, sort_order = $6
+         WHERE id = $1
+         RETURNING *",
+    )
+    .bind(id)
+    .bind(payload.label.unwrap_or(existing.label))
+    .bind(payload.required.unwrap_or(existing.required))
+    .bind(match payload.options {
+        Some(options) => serde_json::json!(options),
+        None => existing.options,
+    })
+    .bind(visibility_permission)
+    .bind(payload.sort_order.unwrap_or(existing.sort_order))
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(def))
+}
+
+// 删除字段定义，同时清除所有记录中该字段的取值
+pub async fn delete(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "custom_field.manage").await?;
+
+    let mut tx = pool.begin().await?;
+
+    let def = sqlx::query_as::<_, CustomFieldDefinition>(
+        "DELETE FROM custom_field_definitions WHERE id = $1 RETURNING *",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    // 表名为固定值，按实体选择
+    let table = if def.entity == "class" { "classes" } else { "persons" };
+    sqlx::query(&format!(
+        "UPDATE {} SET custom_fields = custom_fields - $1 WHERE custom_fields ? $1",
+        table
+    ))
+    .bind(&def.key)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -4,14 +4,19 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 use crate::api::routes::AppState;
+use crate::core::archive::{self, ArchiveTarget};
 use crate::core::auth::Claims;
+use crate::core::config::load_config;
 use crate::core::error::AppError;
 use crate::core::permission::PermissionManager;
 use crate::models::department::{
-    Department, DepartmentCreate, DepartmentResponse, DepartmentUpdate,
+    Department, DepartmentCreate, DepartmentHeadUpdate, DepartmentMove, DepartmentPathItem, DepartmentResponse,
+    DepartmentTreeNode, DepartmentUpdate,
 };
 
 #[derive(Debug, Deserialize)]
@@ -19,6 +24,21 @@ pub struct ListQuery {
     pub page: Option<i64>,
     pub limit: Option<i64>,
     pub search: Option<String>,
+    // 为true时只返回已归档（软删除）的部门
+    pub archived: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TreeQuery {
+    // 只返回以该部门为根的子树
+    pub root: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteQuery {
+    // restrict（默认）：存在下级部门或教师时拒绝删除
+    // reparent：下级部门和教师移交给被删除部门的上级后再删除
+    pub mode: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -37,7 +57,14 @@ pub async fn list(
     let limit = query.limit.unwrap_or(20);
 
     if let Some(pool) = state.pool {
-        let (items, total) = list_departments(&pool, query.search.as_deref(), page, limit).await?;
+        let (items, total) = list_departments(
+            &pool,
+            query.search.as_deref(),
+            query.archived.unwrap_or(false),
+            page,
+            limit,
+        )
+        .await?;
         Ok(Json(ListResponse {
             items,
             total,
@@ -82,10 +109,14 @@ pub async fn update(
 ) -> Result<Json<DepartmentResponse>, AppError> {
     let pool = state.pool.ok_or_else(|| AppError::Internal)?;
     
-    // 检查权限：任何部门更新都需要department.update权限
+    // 检查权限：需要department.update权限，或是该部门（或其上级部门）的负责人
     let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
     let manager = PermissionManager::new(pool.clone());
-    manager.require_permission(user_id, "department.update").await?;
+    manager.require_department_permission(user_id, "department.update", id).await?;
+    // 调整上级部门时，对新上级部门同样需要权限
+    if let Some(parent_id) = payload.parent_id.as_deref().and_then(|s| Uuid::parse_str(s).ok()) {
+        manager.require_department_permission(user_id, "department.update", parent_id).await?;
+    }
     
     let department = update_department(&pool, id, payload).await?;
     Ok(Json(department))
@@ -95,61 +126,307 @@ pub async fn delete(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
+    Query(query): Query<DeleteQuery>,
 ) -> Result<StatusCode, AppError> {
     let pool = state.pool.ok_or_else(|| AppError::Internal)?;
     
     // 检查删除部门权限
     let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
     let manager = PermissionManager::new(pool.clone());
-    manager.require_permission(user_id, "department.delete").await?;
+    manager.require_department_permission(user_id, "department.delete", id).await?;
+
+    let mode = query.mode.as_deref().unwrap_or("restrict");
+    if mode != "restrict" && mode != "reparent" {
+        return Err(AppError::InvalidInput("mode 必须是 restrict 或 reparent".to_string()));
+    }
+
+    // 检查、移交和软删除在同一事务中完成；锁定部门行，并发加入的下级部门或教师需等待提交
+    let mut tx = pool.begin().await?;
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(TREE_LOCK_KEY)
+        .execute(&mut *tx)
+        .await?;
+    let parent_id: Option<Uuid> = sqlx::query_scalar(
+        "SELECT parent_id FROM departments WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    if mode == "restrict" {
+        let (children, teachers) = count_dependents(&mut tx, id).await?;
+        if children > 0 || teachers > 0 {
+            return Err(AppError::InvalidInput(format!(
+                "该部门下还有 {} 个下级部门、{} 名教师，请先移走或使用 mode=reparent",
+                children, teachers
+            )));
+        }
+    } else {
+        reparent_dependents(&mut tx, id, parent_id).await?;
+    }
+
+    // 软删除：不再级联删除子部门
+    archive::mark_deleted(&mut tx, ArchiveTarget::Department, id, user_id).await?;
+    tx.commit().await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// 获取部门树（嵌套结构），可通过 root 只取某个部门的子树
+pub async fn tree(
+    State(state): State<AppState>,
+    Query(query): Query<TreeQuery>,
+) -> Result<Json<Vec<DepartmentTreeNode>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let tree = build_tree(&pool, query.root).await?;
+    Ok(Json(tree))
+}
+
+// 获取部门的所有上级，从直接上级到顶层部门
+pub async fn ancestors(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<DepartmentPathItem>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    get_department(&pool, id).await?;
+    let items = sqlx::query_as::<_, DepartmentPathItem>(
+        "WITH RECURSIVE chain AS (
+             SELECT d.id, d.name, d.parent_id, 0 AS depth
+             FROM departments d WHERE d.id = $1
+             UNION ALL
+             SELECT p.id, p.name, p.parent_id, chain.depth + 1
+             FROM departments p
+             JOIN chain ON p.id = chain.parent_id
+             WHERE chain.depth < $2
+         )
+         SELECT id, name, parent_id, depth FROM chain WHERE depth > 0 ORDER BY depth",
+    )
+    .bind(id)
+    .bind(MAX_DEPTH)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(items))
+}
+
+// 获取部门的所有下级（不含已归档部门），按层级排列
+pub async fn descendants(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<DepartmentPathItem>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    get_department(&pool, id).await?;
+    let items = sqlx::query_as::<_, DepartmentPathItem>(
+        "WITH RECURSIVE sub AS (
+             SELECT d.id, d.name, d.parent_id, 0 AS depth
+             FROM departments d WHERE d.id = $1
+             UNION ALL
+             SELECT c.id, c.name, c.parent_id, sub.depth + 1
+             FROM departments c
+             JOIN sub ON c.parent_id = sub.id
+             WHERE c.deleted_at IS NULL AND sub.depth < $2
+         )
+         SELECT id, name, parent_id, depth FROM sub WHERE depth > 0 ORDER BY depth, name",
+    )
+    .bind(id)
+    .bind(MAX_DEPTH)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(items))
+}
+
+// 移动部门（连同其子树）到新的上级部门下
+pub async fn move_subtree(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<DepartmentMove>,
+) -> Result<Json<DepartmentResponse>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_department_permission(user_id, "department.update", id).await?;
+    match payload.parent_id {
+        Some(parent_id) => manager.require_department_permission(user_id, "department.update", parent_id).await?,
+        // 移动到顶层需要全局权限
+        None => manager.require_permission(user_id, "department.update").await?,
+    }
+
+    set_parent(&pool, id, payload.parent_id).await?;
+
+    let department = get_department(&pool, id).await?;
+    Ok(Json(department))
+}
+
+// 设置或取消部门负责人，负责人获得本部门及下级部门的管理权限
+pub async fn set_head(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<DepartmentHeadUpdate>,
+) -> Result<Json<DepartmentResponse>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_department_permission(user_id, "department.update.head", id).await?;
+
+    let old_head: Option<Uuid> = sqlx::query_scalar(
+        "SELECT head_id FROM departments WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    if let Some(head_id) = payload.head_id {
+        let is_teacher: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM teachers t JOIN persons p ON p.id = t.person_id
+                           WHERE t.person_id = $1 AND p.deleted_at IS NULL)",
+        )
+        .bind(head_id)
+        .fetch_one(&pool)
+        .await?;
+        if !is_teacher {
+            return Err(AppError::InvalidInput("部门负责人必须是在职教师".to_string()));
+        }
+    }
+
+    sqlx::query("UPDATE departments SET head_id = $1 WHERE id = $2")
+        .bind(payload.head_id)
+        .bind(id)
+        .execute(&pool)
+        .await?;
+
+    if old_head != payload.head_id {
+        if let Some(old_head) = old_head {
+            manager.remove_department_permissions_for_head(old_head, id).await
+                .map_err(|e| AppError::InternalWithMessage(format!("移除权限失败: {}", e)))?;
+        }
+        if let Some(head_id) = payload.head_id {
+            manager.add_department_permissions_for_head(head_id, id).await
+                .map_err(|e| AppError::InternalWithMessage(format!("植入权限失败: {}", e)))?;
+        }
+    }
+
+    let department = get_department(&pool, id).await?;
+    Ok(Json(department))
+}
+
+// 恢复已归档的部门
+pub async fn restore(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DepartmentResponse>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "department.restore").await?;
+
+    archive::restore(&pool, ArchiveTarget::Department, id).await?;
+
+    let department = get_department(&pool, id).await?;
+    Ok(Json(department))
+}
+
+// 彻底删除已归档的部门（需超过保留期）
+pub async fn purge(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "department.purge").await?;
+
+    // 下级部门（包括已归档的）仍引用该部门时不能彻底删除
+    let has_children: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM departments WHERE parent_id = $1)",
+    )
+    .bind(id)
+    .fetch_one(&pool)
+    .await?;
+    if has_children {
+        return Err(AppError::InvalidInput("该部门仍有下级部门，不能彻底删除".to_string()));
+    }
+
+    let config = load_config().map_err(|_| AppError::Internal)?;
+    archive::purge(&pool, ArchiveTarget::Department, id, config.archive_retention_days).await?;
 
-    delete_department(&pool, id).await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
 async fn list_departments(
     pool: &sqlx::PgPool,
     search: Option<&str>,
+    archived: bool,
     page: i64,
     limit: i64,
 ) -> Result<(Vec<DepartmentResponse>, i64), AppError> {
     let offset = (page - 1) * limit;
 
+    // 默认只查询未归档部门，archived=true 时只查询已归档部门
+    let archived_clause = if archived {
+        "d.deleted_at IS NOT NULL"
+    } else {
+        "d.deleted_at IS NULL"
+    };
+
     let total = if let Some(s) = search {
-        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM departments d WHERE d.name ILIKE $1")
-            .bind(format!("%{}%", s))
-            .fetch_one(pool)
-            .await?
+        sqlx::query_scalar::<_, i64>(&format!(
+            "SELECT COUNT(*) FROM departments d WHERE {} AND d.name ILIKE $1",
+            archived_clause
+        ))
+        .bind(format!("%{}%", s))
+        .fetch_one(pool)
+        .await?
     } else {
-        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM departments d")
-            .fetch_one(pool)
-            .await?
+        sqlx::query_scalar::<_, i64>(&format!(
+            "SELECT COUNT(*) FROM departments d WHERE {}",
+            archived_clause
+        ))
+        .fetch_one(pool)
+        .await?
     };
 
     let rows = if let Some(s) = search {
-        sqlx::query_as::<_, DepartmentWithParent>(
+        sqlx::query_as::<_, DepartmentWithParent>(&format!(
             "SELECT d.id, d.name, d.parent_id, d.created_at,
-                    p.name as parent_name
+                    p.name as parent_name, d.head_id, h.name as head_name
              FROM departments d
              LEFT JOIN departments p ON d.parent_id = p.id
-             WHERE d.name ILIKE $1
+             LEFT JOIN persons h ON d.head_id = h.id
+             WHERE {} AND d.name ILIKE $1
              ORDER BY d.created_at DESC
              LIMIT $2 OFFSET $3",
-        )
+            archived_clause
+        ))
         .bind(format!("%{}%", s))
         .bind(limit)
         .bind(offset)
         .fetch_all(pool)
         .await?
     } else {
-        sqlx::query_as::<_, DepartmentWithParent>(
+        sqlx::query_as::<_, DepartmentWithParent>(&format!(
             "SELECT d.id, d.name, d.parent_id, d.created_at,
-                    p.name as parent_name
+                    p.name as parent_name, d.head_id, h.name as head_name
              FROM departments d
              LEFT JOIN departments p ON d.parent_id = p.id
+             LEFT JOIN persons h ON d.head_id = h.id
+             WHERE {}
              ORDER BY d.created_at DESC
              LIMIT $1 OFFSET $2",
-        )
+            archived_clause
+        ))
         .bind(limit)
         .bind(offset)
         .fetch_all(pool)
@@ -164,9 +441,10 @@ async fn list_departments(
 async fn get_department(pool: &sqlx::PgPool, id: Uuid) -> Result<DepartmentResponse, AppError> {
     let row = sqlx::query_as::<_, DepartmentWithParent>(
         "SELECT d.id, d.name, d.parent_id, d.created_at,
-                p.name as parent_name
+                p.name as parent_name, d.head_id, h.name as head_name
          FROM departments d
          LEFT JOIN departments p ON d.parent_id = p.id
+         LEFT JOIN persons h ON d.head_id = h.id
          WHERE d.id = $1",
     )
     .bind(id)
@@ -185,6 +463,9 @@ async fn create_department(
 
     // Convert string parent_id to Uuid if provided
     let parent_id = payload.parent_id.and_then(|id_str| Uuid::parse_str(&id_str).ok());
+    if let Some(parent_id) = parent_id {
+        ensure_active(pool, parent_id).await?;
+    }
 
     sqlx::query(
         "INSERT INTO departments (id, name, parent_id)
@@ -220,29 +501,190 @@ async fn update_department(
     if payload.parent_id.is_some() {
         // Convert string parent_id to Uuid if provided
         let parent_id = payload.parent_id.as_ref().and_then(|id_str| Uuid::parse_str(id_str).ok());
-        sqlx::query("UPDATE departments SET parent_id = $1 WHERE id = $2")
-            .bind(parent_id)
-            .bind(id)
-            .execute(pool)
-            .await?;
+        set_parent(pool, id, parent_id).await?;
     }
 
     get_department(pool, id).await
 }
 
-async fn delete_department(pool: &sqlx::PgPool, id: Uuid) -> Result<(), AppError> {
-    let result = sqlx::query("DELETE FROM departments WHERE id = $1")
-        .bind(id)
-        .execute(pool)
+// 递归查询的最大层级，防止历史脏数据中的环导致无限递归
+const MAX_DEPTH: i32 = 64;
+
+// 部门树调整使用的事务级咨询锁，避免并发移动时绕过环检测
+const TREE_LOCK_KEY: i64 = 0x6465_7074;
+
+async fn ensure_active(pool: &sqlx::PgPool, id: Uuid) -> Result<(), AppError> {
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM departments WHERE id = $1 AND deleted_at IS NULL)",
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+    if !exists {
+        return Err(AppError::InvalidInput("上级部门不存在或已归档".to_string()));
+    }
+    Ok(())
+}
+
+/// 修改上级部门：新上级不能是自身或自身的下级
+async fn set_parent(pool: &sqlx::PgPool, id: Uuid, parent_id: Option<Uuid>) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(TREE_LOCK_KEY)
+        .execute(&mut *tx)
         .await?;
 
-    if result.rows_affected() == 0 {
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM departments WHERE id = $1 AND deleted_at IS NULL)",
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if !exists {
         return Err(AppError::NotFound);
     }
 
+    if let Some(parent_id) = parent_id {
+        if parent_id == id {
+            return Err(AppError::InvalidInput("不能将部门设为自己的上级".to_string()));
+        }
+        let parent_active: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM departments WHERE id = $1 AND deleted_at IS NULL)",
+        )
+        .bind(parent_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if !parent_active {
+            return Err(AppError::InvalidInput("上级部门不存在或已归档".to_string()));
+        }
+
+        // 沿新上级向上查找，若经过当前部门则会形成环
+        let creates_cycle: bool = sqlx::query_scalar(
+            "WITH RECURSIVE chain AS (
+                 SELECT id, parent_id, 0 AS depth FROM departments WHERE id = $1
+                 UNION ALL
+                 SELECT d.id, d.parent_id, chain.depth + 1
+                 FROM departments d
+                 JOIN chain ON d.id = chain.parent_id
+                 WHERE chain.depth < $3
+             )
+             SELECT EXISTS(SELECT 1 FROM chain WHERE id = $2)",
+        )
+        .bind(parent_id)
+        .bind(id)
+        .bind(MAX_DEPTH)
+        .fetch_one(&mut *tx)
+        .await?;
+        if creates_cycle {
+            return Err(AppError::InvalidInput("不能将部门移动到其下级部门之下".to_string()));
+        }
+    }
+
+    sqlx::query("UPDATE departments SET parent_id = $1 WHERE id = $2")
+        .bind(parent_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
     Ok(())
 }
 
+/// 统计未归档的直接下级部门数和所属教师数
+async fn count_dependents(conn: &mut PgConnection, id: Uuid) -> Result<(i64, i64), AppError> {
+    let counts: (i64, i64) = sqlx::query_as(
+        "SELECT
+             (SELECT COUNT(*) FROM departments WHERE parent_id = $1 AND deleted_at IS NULL),
+             (SELECT COUNT(*) FROM teachers t JOIN persons p ON p.id = t.person_id
+              WHERE t.department_id = $1 AND p.deleted_at IS NULL)",
+    )
+    .bind(id)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(counts)
+}
+
+/// 将下级部门与教师移交给被删除部门的上级（顶层部门则移到顶层 / 清空部门）
+async fn reparent_dependents(conn: &mut PgConnection, id: Uuid, parent_id: Option<Uuid>) -> Result<(), AppError> {
+    sqlx::query("UPDATE departments SET parent_id = $1 WHERE parent_id = $2 AND deleted_at IS NULL")
+        .bind(parent_id)
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("UPDATE teachers SET department_id = $1 WHERE department_id = $2")
+        .bind(parent_id)
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct TreeRow {
+    id: Uuid,
+    name: String,
+    parent_id: Option<Uuid>,
+    head_id: Option<Uuid>,
+    head_name: Option<String>,
+    teacher_count: i64,
+}
+
+/// 组装部门树；上级已归档的部门作为顶层节点展示
+async fn build_tree(pool: &sqlx::PgPool, root: Option<Uuid>) -> Result<Vec<DepartmentTreeNode>, AppError> {
+    let rows = sqlx::query_as::<_, TreeRow>(
+        "SELECT d.id, d.name, d.parent_id, d.head_id, h.name AS head_name,
+                (SELECT COUNT(*) FROM teachers t JOIN persons p ON p.id = t.person_id
+                 WHERE t.department_id = d.id AND p.deleted_at IS NULL) AS teacher_count
+         FROM departments d
+         LEFT JOIN persons h ON d.head_id = h.id
+         WHERE d.deleted_at IS NULL
+         ORDER BY d.name",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    if let Some(root) = root {
+        if !rows.iter().any(|r| r.id == root) {
+            return Err(AppError::NotFound);
+        }
+    }
+
+    let ids: std::collections::HashSet<Uuid> = rows.iter().map(|r| r.id).collect();
+    let mut children: HashMap<Option<Uuid>, Vec<TreeRow>> = HashMap::new();
+    let mut root_row = None;
+    for row in rows {
+        if Some(row.id) == root {
+            root_row = Some(row);
+            continue;
+        }
+        let parent = row.parent_id.filter(|p| ids.contains(p));
+        children.entry(parent).or_default().push(row);
+    }
+
+    fn attach(row: TreeRow, children: &mut HashMap<Option<Uuid>, Vec<TreeRow>>) -> DepartmentTreeNode {
+        let kids = children.remove(&Some(row.id)).unwrap_or_default();
+        DepartmentTreeNode {
+            id: row.id,
+            name: row.name,
+            parent_id: row.parent_id,
+            head_id: row.head_id,
+            head_name: row.head_name,
+            teacher_count: row.teacher_count,
+            children: kids.into_iter().map(|k| attach(k, children)).collect(),
+        }
+    }
+
+    Ok(match root_row {
+        Some(row) => vec![attach(row, &mut children)],
+        None => children
+            .remove(&None)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|row| attach(row, &mut children))
+            .collect(),
+    })
+}
+
 #[derive(Debug, sqlx::FromRow)]
 struct DepartmentWithParent {
     id: Uuid,
@@ -250,6 +692,8 @@ struct DepartmentWithParent {
     parent_id: Option<Uuid>,
     created_at: chrono::DateTime<chrono::Utc>,
     parent_name: Option<String>,
+    head_id: Option<Uuid>,
+    head_name: Option<String>,
 }
 
 impl DepartmentWithParent {
@@ -259,6 +703,8 @@ impl DepartmentWithParent {
             name: self.name,
             parent_id: self.parent_id,
             parent_name: self.parent_name,
+            head_id: self.head_id,
+            head_name: self.head_name,
             created_at: self.created_at,
         }
     }
@@ -9,6 +9,8 @@ use crate::api::routes::AppState;
 use crate::core::auth::Claims;
 use crate::core::error::AppError;
 use crate::core::permission::PermissionManager;
+use crate::core::score as score_rules;
+use crate::core::search;
 use crate::models::group::*;
 
 // 小组列表（按班级）
@@ -299,6 +301,7 @@ async fn create_group(pool: &sqlx::PgPool, payload: GroupCreate) -> Result<Group
     .bind(&payload.description)
     .execute(pool)
     .await?;
+    search::index_name(pool, "class_groups", id, &payload.name).await?;
     
     get_group(pool, id).await
 }
@@ -337,10 +340,11 @@ async fn update_group(pool: &sqlx::PgPool, id: Uuid, payload: GroupUpdate) -> Re
     
     if let Some(name) = payload.name {
         sqlx::query("UPDATE class_groups SET name = $1, updated_at = CURRENT_TIMESTAMP WHERE id = $2")
-            .bind(name)
+            .bind(&name)
             .bind(id)
             .execute(&mut *tx)
             .await?;
+        search::index_name(&mut *tx, "class_groups", id, &name).await?;
     }
     
     if let Some(description) = payload.description {
@@ -451,36 +455,26 @@ async fn remove_group_member(pool: &sqlx::PgPool, group_id: Uuid, person_id: Uui
 }
 
 async fn update_group_score(pool: &sqlx::PgPool, group_id: Uuid, user_id: Uuid, payload: GroupScoreChange) -> Result<GroupScoreRecord, AppError> {
-    let mut tx = pool.begin().await?;
+    // 按积分目录校验原因和分值
+    let entry = score_rules::resolve_entry(pool, user_id, "group", payload.reason_id, payload.reason.as_deref(), payload.score_change).await?;
     let record_id = Uuid::new_v4();
     
-    // 插入积分记录
+    // 插入积分记录（小组总分由触发器随记录累加）
     sqlx::query(
         "INSERT INTO group_score_records (id, group_id, score_change, reason, created_by)
          VALUES ($1, $2, $3, $4, $5)"
     )
     .bind(record_id)
     .bind(group_id)
-    .bind(payload.score_change)
-    .bind(&payload.reason)
+    .bind(entry.value)
+    .bind(&entry.reason)
     .bind(user_id)
-    .execute(&mut *tx)
-    .await?;
-    
-    // 更新小组积分
-    sqlx::query(
-        "UPDATE class_groups SET score = score + $1, updated_at = CURRENT_TIMESTAMP WHERE id = $2"
-    )
-    .bind(payload.score_change)
-    .bind(group_id)
-    .execute(&mut *tx)
+    .execute(pool)
     .await?;
     
-    tx.commit().await?;
-    
     // 获取创建的记录
     let record = sqlx::query_as::<_, GroupScoreRecord>(
-        "SELECT id, group_id, score_change, reason, created_by, created_at 
+        "SELECT id, group_id, class_id, score_change, reason, created_by, created_at 
          FROM group_score_records WHERE id = $1"
     )
     .bind(record_id)
@@ -492,7 +486,7 @@ async fn update_group_score(pool: &sqlx::PgPool, group_id: Uuid, user_id: Uuid,
 
 async fn get_group_score_records(pool: &sqlx::PgPool, group_id: Uuid) -> Result<Vec<GroupScoreRecord>, AppError> {
     let records = sqlx::query_as::<_, GroupScoreRecord>(
-        "SELECT id, group_id, score_change, reason, created_by, created_at 
+        "SELECT id, group_id, class_id, score_change, reason, created_by, created_at 
          FROM group_score_records 
          WHERE group_id = $1 
          ORDER BY created_at DESC"
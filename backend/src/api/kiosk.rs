@@ -0,0 +1,468 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::HeaderMap,
+    Json,
+};
+use chrono::{Local, TimeZone, Timelike, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::routes::AppState;
+use crate::core::alert;
+use crate::core::attendance as rules;
+use crate::core::auth::Claims;
+use crate::core::calendar;
+use crate::core::config::load_config;
+use crate::core::error::AppError;
+use crate::core::kiosk;
+use crate::core::password::{hash_password, verify_password};
+use crate::core::permission::PermissionManager;
+use crate::models::kiosk::{
+    KioskCheckIn, KioskCheckInResult, KioskDevice, KioskDeviceCreate, KioskDeviceCredentials, KioskDeviceUpdate,
+    KioskEvent, KioskEventQuery, KioskToken,
+};
+
+#[derive(Debug, Serialize)]
+pub struct ListResponse<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
+}
+
+// 签到时校验用的设备信息：(班级ID, 是否启用, 纬度, 经度, 签到范围)
+type DeviceLocation = (Uuid, bool, Option<f64>, Option<f64>, i32);
+
+const DEVICE_SELECT: &str = "SELECT d.id, d.name, d.class_id, c.name AS class_name, d.latitude, d.longitude,
+        d.radius_m, d.is_active, d.last_seen_at, d.created_at
+    FROM kiosk_devices d LEFT JOIN classes c ON c.id = d.class_id";
+
+// ========== 设备管理 ==========
+
+pub async fn list_devices(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<KioskDevice>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "kiosk.manage").await?;
+
+    let devices = sqlx::query_as::<_, KioskDevice>(&format!("{} ORDER BY c.name, d.name", DEVICE_SELECT))
+        .fetch_all(&pool)
+        .await?;
+    Ok(Json(devices))
+}
+
+// 登记设备，返回的 secret 需配置到设备上，之后不能再次查看
+pub async fn create_device(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<KioskDeviceCreate>,
+) -> Result<Json<KioskDeviceCredentials>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "kiosk.manage").await?;
+
+    if payload.name.trim().is_empty() {
+        return Err(AppError::InvalidInput("设备名称不能为空".to_string()));
+    }
+    kiosk::validate_location(payload.latitude, payload.longitude)?;
+    require_class(&pool, payload.class_id).await?;
+
+    let secret = kiosk::generate_secret();
+    let secret_hash = hash_password(&secret).map_err(|_| AppError::Internal)?;
+    let id: Uuid = sqlx::query_scalar(
+        "INSERT INTO kiosk_devices (name, class_id, latitude, longitude, radius_m, secret_hash, secret_digest, created_by)
+         VALUES ($1, $2, $3, $4, COALESCE($5, 100), $6, sha256(convert_to($7, 'UTF8')), $8)
+         RETURNING id",
+    )
+    .bind(payload.name.trim())
+    .bind(payload.class_id)
+    .bind(payload.latitude)
+    .bind(payload.longitude)
+    .bind(payload.radius_m)
+    .bind(&secret_hash)
+    .bind(&secret)
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(invalid_radius)?;
+
+    Ok(Json(KioskDeviceCredentials { device: load_device(&pool, id).await?, secret }))
+}
+
+pub async fn update_device(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<KioskDeviceUpdate>,
+) -> Result<Json<KioskDevice>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "kiosk.manage").await?;
+
+    let clear_location = payload.clear_location.unwrap_or(false);
+    if clear_location && (payload.latitude.is_some() || payload.longitude.is_some()) {
+        return Err(AppError::InvalidInput("清除位置时不能同时提供经纬度".to_string()));
+    }
+    kiosk::validate_location(payload.latitude, payload.longitude)?;
+    if let Some(class_id) = payload.class_id {
+        require_class(&pool, class_id).await?;
+    }
+
+    let updated = sqlx::query(
+        "UPDATE kiosk_devices SET
+             name = COALESCE($2, name),
+             class_id = COALESCE($3, class_id),
+             latitude = CASE WHEN $8 THEN NULL WHEN $4::float8 IS NULL THEN latitude ELSE $4 END,
+             longitude = CASE WHEN $8 THEN NULL WHEN $4::float8 IS NULL THEN longitude ELSE $5 END,
+             radius_m = COALESCE($6, radius_m),
+             is_active = COALESCE($7, is_active)
+         WHERE id = $1",
+    )
+    .bind(id)
+    .bind(payload.name.as_deref().map(str::trim))
+    .bind(payload.class_id)
+    .bind(payload.latitude)
+    .bind(payload.longitude)
+    .bind(payload.radius_m)
+    .bind(payload.is_active)
+    .bind(clear_location)
+    .execute(&pool)
+    .await
+    .map_err(invalid_radius)?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(Json(load_device(&pool, id).await?))
+}
+
+// 重置设备密钥，原密钥立即失效
+pub async fn reset_secret(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<KioskDeviceCredentials>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "kiosk.manage").await?;
+
+    let secret = kiosk::generate_secret();
+    let secret_hash = hash_password(&secret).map_err(|_| AppError::Internal)?;
+    let updated = sqlx::query(
+        "UPDATE kiosk_devices SET secret_hash = $2, secret_digest = sha256(convert_to($3, 'UTF8')) WHERE id = $1",
+    )
+    .bind(id)
+    .bind(&secret_hash)
+    .bind(&secret)
+    .execute(&pool)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(Json(KioskDeviceCredentials { device: load_device(&pool, id).await?, secret }))
+}
+
+pub async fn list_events(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<KioskEventQuery>,
+) -> Result<Json<ListResponse<KioskEvent>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "kiosk.manage").await?;
+
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = (page - 1) * limit;
+
+    let items = sqlx::query_as::<_, KioskEvent>(
+        "SELECT e.id, e.device_id, d.name AS device_name, e.event, e.person_id, p.name AS person_name,
+                e.detail, e.created_at
+         FROM kiosk_events e
+         LEFT JOIN kiosk_devices d ON d.id = e.device_id
+         LEFT JOIN persons p ON p.id = e.person_id
+         WHERE ($1::uuid IS NULL OR e.device_id = $1) AND ($2::varchar IS NULL OR e.event = $2)
+         ORDER BY e.created_at DESC
+         LIMIT $3 OFFSET $4",
+    )
+    .bind(query.device_id)
+    .bind(&query.event)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&pool)
+    .await?;
+    let total: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM kiosk_events e
+         WHERE ($1::uuid IS NULL OR e.device_id = $1) AND ($2::varchar IS NULL OR e.event = $2)",
+    )
+    .bind(query.device_id)
+    .bind(&query.event)
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(ListResponse { items, total, page, limit }))
+}
+
+// ========== 设备端 ==========
+
+// 设备获取当前的签到码；设备通过 X-Kiosk-Device / X-Kiosk-Secret 请求头认证
+pub async fn token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<KioskToken>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let device_id = authenticate_device(&pool, &headers).await?;
+    let (class_id, class_name): (Uuid, String) = sqlx::query_as(
+        "SELECT c.id, c.name FROM kiosk_devices d JOIN classes c ON c.id = d.class_id WHERE d.id = $1",
+    )
+    .bind(device_id)
+    .fetch_one(&pool)
+    .await?;
+
+    let config = load_config().map_err(|_| AppError::Internal)?;
+    let (token, exp) = kiosk::sign_token(device_id, class_id, &config.jwt_secret, config.kiosk_token_ttl_secs)?;
+    sqlx::query("UPDATE kiosk_devices SET last_seen_at = NOW() WHERE id = $1")
+        .bind(device_id)
+        .execute(&pool)
+        .await?;
+    log_event(&pool, Some(device_id), "token_issued", None, None).await?;
+    // 签到码过期后不会再被接受，对应的使用记录可以清理
+    sqlx::query("DELETE FROM kiosk_token_uses WHERE used_at < NOW() - make_interval(secs => $1)")
+        .bind(config.kiosk_token_ttl_secs as f64)
+        .execute(&pool)
+        .await?;
+
+    Ok(Json(KioskToken {
+        token,
+        class_id,
+        class_name,
+        expires_at: Utc.timestamp_opt(exp as i64, 0).single().ok_or(AppError::Internal)?,
+    }))
+}
+
+// ========== 学生签到 ==========
+
+// 学生扫描一体机上的二维码签到，记录全天考勤和签到时间
+pub async fn check_in(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<KioskCheckIn>,
+) -> Result<Json<KioskCheckInResult>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let config = load_config().map_err(|_| AppError::Internal)?;
+    let token = match kiosk::verify_token(&payload.token, &config.jwt_secret) {
+        Ok(token) => token,
+        Err(e) => return Err(reject(&pool, None, user_id, e).await),
+    };
+    let device_id = Uuid::parse_str(&token.sub).map_err(|_| AppError::InvalidInput("无效的签到码".to_string()))?;
+    let nonce = Uuid::parse_str(&token.nonce).map_err(|_| AppError::InvalidInput("无效的签到码".to_string()))?;
+
+    let device: Option<DeviceLocation> = sqlx::query_as(
+        "SELECT class_id, is_active, latitude, longitude, radius_m FROM kiosk_devices WHERE id = $1",
+    )
+    .bind(device_id)
+    .fetch_optional(&pool)
+    .await?;
+    let Some((class_id, is_active, latitude, longitude, radius_m)) = device else {
+        return Err(reject(&pool, None, user_id, AppError::InvalidInput("无效的签到码".to_string())).await);
+    };
+    let reject_with = |message: &str| AppError::InvalidInput(message.to_string());
+
+    // 签到码签发后设备可能已停用或改到其他班级
+    if !is_active || token.class_id != class_id.to_string() {
+        return Err(reject(&pool, Some(device_id), user_id, reject_with("签到码已失效，请扫描屏幕上最新的二维码")).await);
+    }
+
+    let student_class: Option<Option<Uuid>> = sqlx::query_scalar(
+        "SELECT s.class_id FROM students s JOIN persons p ON p.id = s.person_id
+         WHERE s.person_id = $1 AND p.deleted_at IS NULL",
+    )
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await?;
+    match student_class {
+        None => return Err(reject(&pool, Some(device_id), user_id, reject_with("只有学生可以签到")).await),
+        Some(c) if c != Some(class_id) => {
+            return Err(reject(&pool, Some(device_id), user_id, reject_with("不是本班的一体机，请在自己班级签到")).await)
+        }
+        Some(_) => {}
+    }
+
+    if let (Some(lat), Some(lng)) = (latitude, longitude) {
+        if let Err(e) = kiosk::validate_location(payload.latitude, payload.longitude) {
+            return Err(reject(&pool, Some(device_id), user_id, e).await);
+        }
+        let (Some(at_lat), Some(at_lng)) = (payload.latitude, payload.longitude) else {
+            return Err(reject(&pool, Some(device_id), user_id, reject_with("请开启定位后再签到")).await);
+        };
+        let distance = kiosk::distance_m(lat, lng, at_lat, at_lng);
+        if distance > radius_m as f64 {
+            let message = format!("当前位置距离一体机约 {:.0} 米，请到教室内签到", distance);
+            return Err(reject(&pool, Some(device_id), user_id, AppError::InvalidInput(message)).await);
+        }
+    }
+
+    let now = Local::now();
+    let (date, time) = (now.date_naive(), now.time().with_nanosecond(0).unwrap_or_else(|| now.time()));
+    if let Err(e) = calendar::require_school_day(&pool, date).await {
+        return Err(reject(&pool, Some(device_id), user_id, e).await);
+    }
+    let window = rules::window_for(&pool, None).await?;
+    let status = match window {
+        Some(window) => rules::derive_status(time, &window).to_string(),
+        None => "present".to_string(),
+    };
+
+    let mut tx = pool.begin().await?;
+    let fresh = sqlx::query("INSERT INTO kiosk_token_uses (nonce, person_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(nonce)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+        > 0;
+    if !fresh {
+        drop(tx);
+        return Err(reject(&pool, Some(device_id), user_id, reject_with("该签到码已使用过")).await);
+    }
+    let inserted: Result<Uuid, sqlx::Error> = sqlx::query_scalar(
+        "INSERT INTO attendances (person_id, date, status, time, remark, created_by)
+         VALUES ($1, $2, $3, $4, '一体机签到', $1)
+         RETURNING id",
+    )
+    .bind(user_id)
+    .bind(date)
+    .bind(&status)
+    .bind(time)
+    .fetch_one(&mut *tx)
+    .await;
+    let attendance_id = match inserted {
+        Ok(id) => id,
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            drop(tx);
+            return Err(reject(&pool, Some(device_id), user_id, reject_with("今天已有考勤记录，无需重复签到")).await);
+        }
+        Err(e) => return Err(e.into()),
+    };
+    tx.commit().await?;
+
+    log_event(&pool, Some(device_id), "check_in", Some(user_id), Some(&status)).await?;
+    alert::notify_attendance(&pool, &state.hub, &[attendance_id], user_id).await;
+
+    Ok(Json(KioskCheckInResult { attendance_id, class_id, date, time, status }))
+}
+
+// ========== 辅助函数 ==========
+
+async fn authenticate_device(pool: &PgPool, headers: &HeaderMap) -> Result<Uuid, AppError> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let device_id = header("x-kiosk-device")
+        .and_then(|v| Uuid::parse_str(v).ok())
+        .ok_or_else(|| AppError::Auth("缺少设备凭据".to_string()))?;
+    let secret = header("x-kiosk-secret").ok_or_else(|| AppError::Auth("缺少设备凭据".to_string()))?;
+
+    // (bcrypt 哈希, 是否已有摘要, 摘要是否一致, 是否启用)
+    let device: Option<(String, bool, bool, bool)> = sqlx::query_as(
+        "SELECT secret_hash, secret_digest IS NOT NULL,
+                COALESCE(secret_digest = sha256(convert_to($2, 'UTF8')), false), is_active
+         FROM kiosk_devices WHERE id = $1",
+    )
+    .bind(device_id)
+    .bind(secret)
+    .fetch_optional(pool)
+    .await?;
+    let Some((secret_hash, has_digest, digest_matches, is_active)) = device else {
+        return Err(AppError::Auth("设备凭据无效".to_string()));
+    };
+    // 没有摘要的旧设备按 bcrypt 校验一次，通过后补上摘要
+    let verified = if has_digest {
+        digest_matches
+    } else if verify_password(secret, &secret_hash).unwrap_or(false) {
+        sqlx::query("UPDATE kiosk_devices SET secret_digest = sha256(convert_to($2, 'UTF8')) WHERE id = $1")
+            .bind(device_id)
+            .bind(secret)
+            .execute(pool)
+            .await?;
+        true
+    } else {
+        false
+    };
+    if !verified {
+        log_event(pool, Some(device_id), "auth_failed", None, Some("设备密钥错误")).await?;
+        return Err(AppError::Auth("设备凭据无效".to_string()));
+    }
+    if !is_active {
+        log_event(pool, Some(device_id), "auth_failed", None, Some("设备已停用")).await?;
+        return Err(AppError::Auth("设备已停用".to_string()));
+    }
+    Ok(device_id)
+}
+
+async fn log_event(
+    pool: &PgPool,
+    device_id: Option<Uuid>,
+    event: &str,
+    person_id: Option<Uuid>,
+    detail: Option<&str>,
+) -> Result<(), AppError> {
+    sqlx::query("INSERT INTO kiosk_events (device_id, event, person_id, detail) VALUES ($1, $2, $3, $4)")
+        .bind(device_id)
+        .bind(event)
+        .bind(person_id)
+        .bind(detail)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// 记录被拒绝的签到并返回原错误
+async fn reject(pool: &PgPool, device_id: Option<Uuid>, person_id: Uuid, error: AppError) -> AppError {
+    let detail = match &error {
+        AppError::InvalidInput(message) | AppError::Auth(message) => message.clone(),
+        other => format!("{:?}", other),
+    };
+    if let Err(e) = log_event(pool, device_id, "check_in_rejected", Some(person_id), Some(&detail)).await {
+        return e;
+    }
+    error
+}
+
+async fn load_device(pool: &PgPool, id: Uuid) -> Result<KioskDevice, AppError> {
+    sqlx::query_as::<_, KioskDevice>(&format!("{} WHERE d.id = $1", DEVICE_SELECT))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+async fn require_class(pool: &PgPool, class_id: Uuid) -> Result<(), AppError> {
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM classes WHERE id = $1 AND deleted_at IS NULL)")
+        .bind(class_id)
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Err(AppError::InvalidInput("班级不存在".to_string()));
+    }
+    Ok(())
+}
+
+fn invalid_radius(e: sqlx::Error) -> AppError {
+    match e {
+        sqlx::Error::Database(ref db) if db.is_check_violation() => {
+            AppError::InvalidInput("签到范围必须大于 0 米".to_string())
+        }
+        e => e.into(),
+    }
+}
@@ -0,0 +1,435 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    Json,
+};
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::attachment;
+use crate::api::routes::AppState;
+use crate::core::auth::Claims;
+use crate::core::calendar;
+use crate::core::error::AppError;
+use crate::core::leave::{self as rules, LEAVE_TYPES};
+use crate::core::permission::check_user_permission;
+use crate::models::leave::{
+    LeaveApproval, LeaveDecision, LeaveQuery, LeaveRequest, LeaveRequestCreate, LeaveRequestDetail,
+};
+
+#[derive(Debug, Serialize)]
+pub struct ListResponse<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
+}
+
+const LEAVE_SELECT: &str = "SELECT l.id, l.student_id, s.name AS student_name, l.class_id, c.name AS class_name,
+        l.leave_type, l.start_date, l.end_date, l.school_days, l.reason, l.attachment_id, l.status,
+        l.submitted_by, sb.name AS submitted_by_name, l.created_at, l.updated_at
+    FROM leave_requests l
+    JOIN persons s ON s.id = l.student_id
+    LEFT JOIN classes c ON c.id = l.class_id
+    LEFT JOIN persons sb ON sb.id = l.submitted_by";
+
+// 请假列表：有 leave.view 权限的可查看全部；其他人只能看到自己、孩子和所带班级（班主任）的请假
+pub async fn list(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<LeaveQuery>,
+) -> Result<Json<ListResponse<LeaveRequest>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let view_all = check_user_permission(&pool, user_id, "leave.view").await;
+
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = (page - 1) * limit;
+
+    let filter = "WHERE ($1 OR l.student_id = $2 OR l.submitted_by = $2
+               OR l.student_id IN (SELECT student_id FROM student_parent WHERE parent_id = $2)
+               OR l.class_id IN (SELECT class_id FROM teacher_class WHERE teacher_id = $2 AND is_main_teacher))
+          AND ($3::varchar IS NULL OR l.status = $3)
+          AND ($4::uuid IS NULL OR l.class_id = $4)
+          AND ($5::uuid IS NULL OR l.student_id = $5)";
+
+    let items = sqlx::query_as::<_, LeaveRequest>(&format!(
+        "{} {} ORDER BY l.created_at DESC LIMIT $6 OFFSET $7",
+        LEAVE_SELECT, filter
+    ))
+    .bind(view_all)
+    .bind(user_id)
+    .bind(&query.status)
+    .bind(query.class_id)
+    .bind(query.student_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&pool)
+    .await?;
+
+    let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM leave_requests l {}", filter))
+        .bind(view_all)
+        .bind(user_id)
+        .bind(&query.status)
+        .bind(query.class_id)
+        .bind(query.student_id)
+        .fetch_one(&pool)
+        .await?;
+
+    Ok(Json(ListResponse { items, total, page, limit }))
+}
+
+pub async fn get(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<LeaveRequestDetail>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let request = load_leave(&pool, id).await?;
+    require_view(&pool, user_id, &request).await?;
+
+    Ok(Json(detail(&pool, request).await?))
+}
+
+// 提交请假：学生本人或学生的家长
+pub async fn create(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<LeaveRequestCreate>,
+) -> Result<Json<LeaveRequestDetail>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let student_id = payload.student_id.unwrap_or(user_id);
+    if student_id != user_id && !is_parent_of(&pool, user_id, student_id).await? {
+        return Err(AppError::Auth("只能为自己或自己的孩子请假".to_string()));
+    }
+
+    if !LEAVE_TYPES.contains(&payload.leave_type.as_str()) {
+        return Err(AppError::InvalidInput(format!(
+            "无效的请假类型: {}，可选 {}",
+            payload.leave_type,
+            LEAVE_TYPES.join("、")
+        )));
+    }
+    if payload.reason.trim().is_empty() {
+        return Err(AppError::InvalidInput("请填写请假原因".to_string()));
+    }
+
+    let class_id: Option<Uuid> = sqlx::query_scalar(
+        "SELECT s.class_id FROM students s JOIN persons p ON p.id = s.person_id
+         WHERE s.person_id = $1 AND p.deleted_at IS NULL",
+    )
+    .bind(student_id)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::InvalidInput("只有学生可以请假".to_string()))?;
+    let class_id = class_id.ok_or_else(|| AppError::InvalidInput("学生尚未分班，无法确定审批的班主任".to_string()))?;
+
+    let school_days = calendar::school_days_between(&pool, payload.start_date, payload.end_date).await?;
+    if school_days.is_empty() {
+        return Err(AppError::InvalidInput("请假期间没有上学日".to_string()));
+    }
+
+    if let Some(attachment_id) = payload.attachment_id {
+        attachment::require_owned(&pool, attachment_id, student_id).await?;
+    }
+
+    // 同一学生的请假申请串行提交，避免并发提交时重叠检查同时通过
+    let mut tx = pool.begin().await?;
+    sqlx::query("SELECT id FROM persons WHERE id = $1 FOR UPDATE")
+        .bind(student_id)
+        .execute(&mut *tx)
+        .await?;
+    let overlapping: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM leave_requests
+                       WHERE student_id = $1 AND status IN ('pending', 'escalated', 'approved')
+                         AND start_date <= $3 AND end_date >= $2)",
+    )
+    .bind(student_id)
+    .bind(payload.start_date)
+    .bind(payload.end_date)
+    .fetch_one(&mut *tx)
+    .await?;
+    if overlapping {
+        return Err(AppError::InvalidInput("该时间段已有请假申请".to_string()));
+    }
+
+    let id: Uuid = sqlx::query_scalar(
+        "INSERT INTO leave_requests
+             (student_id, class_id, leave_type, start_date, end_date, school_days, reason, attachment_id, submitted_by)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+         RETURNING id",
+    )
+    .bind(student_id)
+    .bind(class_id)
+    .bind(&payload.leave_type)
+    .bind(payload.start_date)
+    .bind(payload.end_date)
+    .bind(school_days.len() as i32)
+    .bind(payload.reason.trim())
+    .bind(payload.attachment_id)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    let request = load_leave(&pool, id).await?;
+    Ok(Json(detail(&pool, request).await?))
+}
+
+// 同意：班主任审批；长假转复核，复核通过后生成请假考勤
+pub async fn approve(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<LeaveDecision>,
+) -> Result<Json<LeaveRequestDetail>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    decide(&pool, user_id, id, "approve", payload.comment).await?;
+
+    let request = load_leave(&pool, id).await?;
+    Ok(Json(detail(&pool, request).await?))
+}
+
+pub async fn reject(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<LeaveDecision>,
+) -> Result<Json<LeaveRequestDetail>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    decide(&pool, user_id, id, "reject", payload.comment).await?;
+
+    let request = load_leave(&pool, id).await?;
+    Ok(Json(detail(&pool, request).await?))
+}
+
+// 撤回：提交人或学生本人，只能撤回尚未处理完的申请
+pub async fn cancel(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<LeaveRequestDetail>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let request = load_leave(&pool, id).await?;
+    if request.submitted_by != Some(user_id) && request.student_id != user_id {
+        return Err(AppError::Auth("只能撤回自己提交的请假".to_string()));
+    }
+
+    let updated = sqlx::query(
+        "UPDATE leave_requests SET status = 'cancelled' WHERE id = $1 AND status IN ('pending', 'escalated')",
+    )
+    .bind(id)
+    .execute(&pool)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::InvalidInput("该请假申请已处理，不能撤回".to_string()));
+    }
+
+    let request = load_leave(&pool, id).await?;
+    Ok(Json(detail(&pool, request).await?))
+}
+
+// 撤销已批准的请假：提交人、学生本人、班主任或有审批权限的人员，恢复批准时改动的考勤
+pub async fn revoke(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<LeaveDecision>,
+) -> Result<Json<LeaveRequestDetail>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let mut tx = pool.begin().await?;
+    let (student_id, class_id, submitted_by, status): (Uuid, Option<Uuid>, Option<Uuid>, String) = sqlx::query_as(
+        "SELECT student_id, class_id, submitted_by, status FROM leave_requests WHERE id = $1 FOR UPDATE",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    if status != "approved" {
+        return Err(AppError::InvalidInput("只能撤销已批准的请假".to_string()));
+    }
+    let allowed = submitted_by == Some(user_id)
+        || student_id == user_id
+        || check_user_permission(&pool, user_id, "leave.approve").await
+        || match class_id {
+            Some(class_id) => is_head_teacher(&pool, user_id, class_id).await?,
+            None => false,
+        };
+    if !allowed {
+        return Err(AppError::Auth("没有权限撤销该请假".to_string()));
+    }
+
+    sqlx::query(
+        "INSERT INTO leave_approvals (leave_request_id, step, decision, approver_id, comment)
+         VALUES ($1, $2, 'revoke', $3, $4)",
+    )
+    .bind(id)
+    .bind(rules::approval_step(&status))
+    .bind(user_id)
+    .bind(&payload.comment)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("UPDATE leave_requests SET status = 'revoked' WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    rules::restore_attendances(&mut tx, id, user_id).await?;
+    tx.commit().await?;
+
+    let request = load_leave(&pool, id).await?;
+    Ok(Json(detail(&pool, request).await?))
+}
+
+// ========== 审批 ==========
+
+async fn decide(
+    pool: &PgPool,
+    user_id: Uuid,
+    id: Uuid,
+    decision: &str,
+    comment: Option<String>,
+) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    let (student_id, class_id, start_date, end_date, school_days, status, reason): (
+        Uuid,
+        Option<Uuid>,
+        NaiveDate,
+        NaiveDate,
+        i32,
+        String,
+        String,
+    ) = sqlx::query_as(
+        "SELECT student_id, class_id, start_date, end_date, school_days, status, reason
+         FROM leave_requests WHERE id = $1 FOR UPDATE",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    if status != "pending" && status != "escalated" {
+        return Err(AppError::InvalidInput("该请假申请已处理".to_string()));
+    }
+    let can_review = check_user_permission(pool, user_id, "leave.approve").await;
+    let head_teacher = match class_id {
+        Some(class_id) => is_head_teacher(pool, user_id, class_id).await?,
+        None => false,
+    };
+    if !(can_review || (status == "pending" && head_teacher)) {
+        return Err(AppError::Auth(if status == "pending" {
+            "只有该班班主任可以审批".to_string()
+        } else {
+            "该请假超过规定天数，需要有复核权限的人员审批".to_string()
+        }));
+    }
+
+    let next = match decision {
+        "approve" => rules::after_approval(&status, school_days, can_review)?,
+        _ => "rejected",
+    };
+
+    sqlx::query(
+        "INSERT INTO leave_approvals (leave_request_id, step, decision, approver_id, comment)
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(id)
+    .bind(rules::approval_step(&status))
+    .bind(decision)
+    .bind(user_id)
+    .bind(&comment)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("UPDATE leave_requests SET status = $2 WHERE id = $1")
+        .bind(id)
+        .bind(next)
+        .execute(&mut *tx)
+        .await?;
+
+    if next == "approved" {
+        // 按批准时的校历计算上学日
+        let days = calendar::school_days_between(pool, start_date, end_date).await?;
+        rules::record_excused(&mut tx, id, student_id, &days, &format!("请假：{}", reason), user_id).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+// ========== 查询 ==========
+
+async fn load_leave(pool: &PgPool, id: Uuid) -> Result<LeaveRequest, AppError> {
+    sqlx::query_as::<_, LeaveRequest>(&format!("{} WHERE l.id = $1", LEAVE_SELECT))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+async fn detail(pool: &PgPool, request: LeaveRequest) -> Result<LeaveRequestDetail, AppError> {
+    let approvals = sqlx::query_as::<_, LeaveApproval>(
+        "SELECT a.id, a.step, a.decision, a.approver_id, p.name AS approver_name, a.comment, a.created_at
+         FROM leave_approvals a LEFT JOIN persons p ON p.id = a.approver_id
+         WHERE a.leave_request_id = $1
+         ORDER BY a.created_at",
+    )
+    .bind(request.id)
+    .fetch_all(pool)
+    .await?;
+    Ok(LeaveRequestDetail { request, approvals })
+}
+
+// 学生本人、提交人、家长、班主任以及有 leave.view 权限的人员可以查看
+async fn require_view(pool: &PgPool, user_id: Uuid, request: &LeaveRequest) -> Result<(), AppError> {
+    if request.student_id == user_id || request.submitted_by == Some(user_id) {
+        return Ok(());
+    }
+    if is_parent_of(pool, user_id, request.student_id).await? {
+        return Ok(());
+    }
+    if let Some(class_id) = request.class_id {
+        if is_head_teacher(pool, user_id, class_id).await? {
+            return Ok(());
+        }
+    }
+    if check_user_permission(pool, user_id, "leave.view").await {
+        return Ok(());
+    }
+    Err(AppError::Auth("没有权限执行此操作: leave.view".to_string()))
+}
+
+async fn is_parent_of(pool: &PgPool, parent_id: Uuid, student_id: Uuid) -> Result<bool, AppError> {
+    let linked: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM student_parent WHERE parent_id = $1 AND student_id = $2)",
+    )
+    .bind(parent_id)
+    .bind(student_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(linked)
+}
+
+async fn is_head_teacher(pool: &PgPool, teacher_id: Uuid, class_id: Uuid) -> Result<bool, AppError> {
+    let head: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM teacher_class WHERE teacher_id = $1 AND class_id = $2 AND is_main_teacher)",
+    )
+    .bind(teacher_id)
+    .bind(class_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(head)
+}
@@ -0,0 +1,533 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    Json,
+};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::attendance::{AttendanceResponse, AttendanceRow};
+use crate::api::notice::{NoticeResponse, NoticeRow};
+use crate::api::person::get_person;
+use crate::api::routes::AppState;
+use crate::api::score::{ScoreResponse, ScoreRow, SCORE_SELECT};
+use crate::core::auth::Claims;
+use crate::core::calendar;
+use crate::core::custom_field;
+use crate::core::error::AppError;
+use crate::models::group::GroupScoreRecord;
+use crate::models::me::{AttendanceSummary, MeQuery, MyClass, MyClassTeacher, MyGroup, MyGroupMember};
+use crate::models::person::PersonResponse;
+
+// 小组最近积分变动条数
+const RECENT_RECORDS: i64 = 10;
+
+#[derive(Debug, Serialize)]
+pub struct AttendanceView {
+    pub summary: AttendanceSummary,
+    pub items: Vec<AttendanceResponse>,
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScoreView {
+    // 区间内个人积分合计
+    pub total_value: i64,
+    pub items: Vec<ScoreResponse>,
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListResponse<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
+}
+
+// ========== 当前用户 ==========
+
+// 个人资料
+pub async fn profile(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<PersonResponse>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+
+    let person = visible_person(&pool, user_id, user_id).await?;
+    Ok(Json(person))
+}
+
+pub async fn my_class(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Option<MyClass>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+
+    require_student(&pool, user_id).await?;
+    Ok(Json(class_of(&pool, user_id).await?))
+}
+
+pub async fn my_groups(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<MyGroup>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+
+    require_student(&pool, user_id).await?;
+    Ok(Json(groups_of(&pool, user_id).await?))
+}
+
+pub async fn my_attendance(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<MeQuery>,
+) -> Result<Json<AttendanceView>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+
+    require_student(&pool, user_id).await?;
+    Ok(Json(attendance_of(&pool, user_id, &query).await?))
+}
+
+pub async fn my_scores(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<MeQuery>,
+) -> Result<Json<ScoreView>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+
+    require_student(&pool, user_id).await?;
+    Ok(Json(scores_of(&pool, user_id, &query).await?))
+}
+
+// 发给我的通知：全校通知，以及我所在（任教、孩子所在）班级、小组、部门的通知
+pub async fn my_notices(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<MeQuery>,
+) -> Result<Json<ListResponse<NoticeResponse>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+
+    Ok(Json(notices_for(&pool, user_id, &query).await?))
+}
+
+// ========== 家长查看孩子 ==========
+
+pub async fn child_profile(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(student_id): Path<Uuid>,
+) -> Result<Json<PersonResponse>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+
+    require_child(&pool, user_id, student_id).await?;
+    Ok(Json(visible_person(&pool, student_id, user_id).await?))
+}
+
+pub async fn child_class(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(student_id): Path<Uuid>,
+) -> Result<Json<Option<MyClass>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+
+    require_child(&pool, user_id, student_id).await?;
+    Ok(Json(class_of(&pool, student_id).await?))
+}
+
+pub async fn child_groups(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(student_id): Path<Uuid>,
+) -> Result<Json<Vec<MyGroup>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+
+    require_child(&pool, user_id, student_id).await?;
+    Ok(Json(groups_of(&pool, student_id).await?))
+}
+
+pub async fn child_attendance(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(student_id): Path<Uuid>,
+    Query(query): Query<MeQuery>,
+) -> Result<Json<AttendanceView>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+
+    require_child(&pool, user_id, student_id).await?;
+    Ok(Json(attendance_of(&pool, student_id, &query).await?))
+}
+
+pub async fn child_scores(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(student_id): Path<Uuid>,
+    Query(query): Query<MeQuery>,
+) -> Result<Json<ScoreView>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+
+    require_child(&pool, user_id, student_id).await?;
+    Ok(Json(scores_of(&pool, student_id, &query).await?))
+}
+
+// 孩子收到的通知（全校、孩子所在班级和小组）
+pub async fn child_notices(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(student_id): Path<Uuid>,
+    Query(query): Query<MeQuery>,
+) -> Result<Json<ListResponse<NoticeResponse>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+
+    require_child(&pool, user_id, student_id).await?;
+    Ok(Json(notices_for(&pool, student_id, &query).await?))
+}
+
+// ========== 访问控制 ==========
+
+async fn require_student(pool: &PgPool, user_id: Uuid) -> Result<(), AppError> {
+    let is_student: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM students WHERE person_id = $1)")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    if !is_student {
+        return Err(AppError::InvalidInput(
+            "当前用户不是学生，家长请通过 /api/me/children/:id 查看孩子信息".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// 只能查看通过 student_parent 关联的孩子
+async fn require_child(pool: &PgPool, parent_id: Uuid, student_id: Uuid) -> Result<(), AppError> {
+    let linked: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM student_parent sp JOIN persons p ON p.id = sp.student_id
+                       WHERE sp.parent_id = $1 AND sp.student_id = $2 AND p.deleted_at IS NULL)",
+    )
+    .bind(parent_id)
+    .bind(student_id)
+    .fetch_one(pool)
+    .await?;
+    if !linked {
+        return Err(AppError::Auth("只能查看自己孩子的信息".to_string()));
+    }
+    Ok(())
+}
+
+// ========== 视图查询 ==========
+
+async fn visible_person(pool: &PgPool, person_id: Uuid, viewer_id: Uuid) -> Result<PersonResponse, AppError> {
+    let mut person = get_person(pool, person_id).await?;
+    let hidden = custom_field::hidden_keys(pool, "person", Some(viewer_id)).await?;
+    person.custom_fields_mut().retain(|key, _| !hidden.contains(key));
+    Ok(person)
+}
+
+async fn class_of(pool: &PgPool, student_id: Uuid) -> Result<Option<MyClass>, AppError> {
+    let class = sqlx::query_as::<_, MyClass>(
+        "SELECT c.id, c.name, c.grade, c.academic_year, c.teacher_id, t.name AS teacher_name,
+                (SELECT COUNT(*) FROM students s2 JOIN persons p2 ON p2.id = s2.person_id
+                 WHERE s2.class_id = c.id AND p2.deleted_at IS NULL) AS student_count
+         FROM students s
+         JOIN classes c ON c.id = s.class_id
+         LEFT JOIN persons t ON t.id = c.teacher_id
+         WHERE s.person_id = $1 AND c.deleted_at IS NULL",
+    )
+    .bind(student_id)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(mut class) = class else {
+        return Ok(None);
+    };
+    class.teachers = sqlx::query_as::<_, MyClassTeacher>(
+        "SELECT tc.teacher_id, p.name, COALESCE(tc.is_main_teacher, false) AS is_main_teacher,
+                ARRAY(SELECT s.name FROM teaching_assignments ta
+                      JOIN subjects s ON s.id = ta.subject_id
+                      WHERE ta.teacher_id = tc.teacher_id AND ta.class_id = tc.class_id
+                        AND (c.academic_year IS NULL OR ta.academic_year = c.academic_year)
+                      ORDER BY s.name) AS subjects
+         FROM teacher_class tc
+         JOIN persons p ON p.id = tc.teacher_id
+         JOIN classes c ON c.id = tc.class_id
+         WHERE tc.class_id = $1 AND p.deleted_at IS NULL
+         ORDER BY tc.is_main_teacher DESC, p.name",
+    )
+    .bind(class.id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Some(class))
+}
+
+async fn groups_of(pool: &PgPool, student_id: Uuid) -> Result<Vec<MyGroup>, AppError> {
+    let mut groups = sqlx::query_as::<_, MyGroup>(
+        "SELECT g.id, g.name, g.description, g.score, g.class_id, c.name AS class_name,
+                (SELECT COUNT(*) + 1 FROM class_groups x WHERE x.class_id = g.class_id AND x.score > g.score) AS rank,
+                (SELECT COUNT(*) FROM class_groups x WHERE x.class_id = g.class_id) AS group_count
+         FROM class_groups g
+         JOIN group_members gm ON gm.group_id = g.id
+         LEFT JOIN classes c ON c.id = g.class_id
+         WHERE gm.person_id = $1 AND c.deleted_at IS NULL
+         ORDER BY g.name",
+    )
+    .bind(student_id)
+    .fetch_all(pool)
+    .await?;
+
+    for group in groups.iter_mut() {
+        group.members = sqlx::query_as::<_, MyGroupMember>(
+            "SELECT p.id AS person_id, p.name FROM group_members gm
+             JOIN persons p ON p.id = gm.person_id
+             WHERE gm.group_id = $1 AND p.deleted_at IS NULL
+             ORDER BY p.name",
+        )
+        .bind(group.id)
+        .fetch_all(pool)
+        .await?;
+        group.recent_records = sqlx::query_as::<_, GroupScoreRecord>(
+            "SELECT id, group_id, class_id, score_change, reason, created_by, created_at
+             FROM group_score_records WHERE group_id = $1
+             ORDER BY created_at DESC LIMIT $2",
+        )
+        .bind(group.id)
+        .bind(RECENT_RECORDS)
+        .fetch_all(pool)
+        .await?;
+    }
+
+    Ok(groups)
+}
+
+async fn attendance_of(pool: &PgPool, student_id: Uuid, query: &MeQuery) -> Result<AttendanceView, AppError> {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = (page - 1) * limit;
+
+    let counts = sqlx::query_as::<_, (String, i64)>(
+        "SELECT status, COUNT(*) FROM attendances
+         WHERE person_id = $1 AND ($2::date IS NULL OR date >= $2) AND ($3::date IS NULL OR date <= $3)
+         GROUP BY status",
+    )
+    .bind(student_id)
+    .bind(query.from)
+    .bind(query.to)
+    .fetch_all(pool)
+    .await?;
+    let total = counts.iter().map(|(_, n)| n).sum();
+
+    let school_days = match (query.from, query.to) {
+        (Some(from), Some(to)) => Some(calendar::school_days_between(pool, from, to).await?.len()),
+        _ => None,
+    };
+
+    let rows = sqlx::query_as::<_, AttendanceRow>(
+        "SELECT a.id, a.person_id, p.name as person_name, a.date, a.period_id, a.status, a.time, a.remark, a.created_at
+         FROM attendances a
+         JOIN persons p ON a.person_id = p.id
+         WHERE a.person_id = $1 AND ($2::date IS NULL OR a.date >= $2) AND ($3::date IS NULL OR a.date <= $3)
+         ORDER BY a.date DESC, a.created_at DESC
+         LIMIT $4 OFFSET $5",
+    )
+    .bind(student_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(AttendanceView {
+        summary: AttendanceSummary {
+            by_status: counts.into_iter().collect(),
+            school_days,
+        },
+        items: rows.into_iter().map(|row| row.into()).collect(),
+        total,
+        page,
+        limit,
+    })
+}
+
+async fn scores_of(pool: &PgPool, student_id: Uuid, query: &MeQuery) -> Result<ScoreView, AppError> {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = (page - 1) * limit;
+
+    let (total, total_value): (i64, i64) = sqlx::query_as(
+        "SELECT COALESCE(SUM(record_count), 0)::bigint, COALESCE(SUM(total), 0)::bigint FROM score_daily_totals
+         WHERE person_id = $1
+           AND ($2::date IS NULL OR score_date >= $2) AND ($3::date IS NULL OR score_date <= $3)",
+    )
+    .bind(student_id)
+    .bind(query.from)
+    .bind(query.to)
+    .fetch_one(pool)
+    .await?;
+
+    let sql = format!(
+        "{} WHERE s.person_id = $1
+           AND ($2::date IS NULL OR s.created_at >= $2) AND ($3::date IS NULL OR s.created_at < $3::date + 1)
+         ORDER BY s.created_at DESC
+         LIMIT $4 OFFSET $5",
+        SCORE_SELECT
+    );
+    let rows = sqlx::query_as::<_, ScoreRow>(&sql)
+        .bind(student_id)
+        .bind(query.from)
+        .bind(query.to)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(ScoreView {
+        total_value,
+        items: rows.into_iter().map(|row| row.into()).collect(),
+        total,
+        page,
+        limit,
+    })
+}
+
+/// 人员能收到的通知：全校通知；本人（学生）、任教（老师）或孩子（家长）所在班级和小组的通知；
+/// 老师所在部门及其上级部门的通知
+async fn notices_for(pool: &PgPool, person_id: Uuid, query: &MeQuery) -> Result<ListResponse<NoticeResponse>, AppError> {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = (page - 1) * limit;
+
+    let class_ids: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT class_id FROM students WHERE person_id = $1 AND class_id IS NOT NULL
+         UNION
+         SELECT class_id FROM teacher_class WHERE teacher_id = $1
+         UNION
+         SELECT s.class_id FROM student_parent sp JOIN students s ON s.person_id = sp.student_id
+         WHERE sp.parent_id = $1 AND s.class_id IS NOT NULL",
+    )
+    .bind(person_id)
+    .fetch_all(pool)
+    .await?;
+    let group_ids: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT group_id FROM group_members WHERE person_id = $1
+         UNION
+         SELECT gm.group_id FROM student_parent sp JOIN group_members gm ON gm.person_id = sp.student_id
+         WHERE sp.parent_id = $1",
+    )
+    .bind(person_id)
+    .fetch_all(pool)
+    .await?;
+    let department_ids: Vec<Uuid> = sqlx::query_scalar(
+        "WITH RECURSIVE chain AS (
+             SELECT d.id, d.parent_id, 0 AS depth FROM teachers t
+             JOIN departments d ON d.id = t.department_id
+             WHERE t.person_id = $1
+             UNION ALL
+             SELECT d.id, d.parent_id, chain.depth + 1
+             FROM departments d
+             JOIN chain ON d.id = chain.parent_id
+             WHERE chain.depth < 64
+         )
+         SELECT id FROM chain",
+    )
+    .bind(person_id)
+    .fetch_all(pool)
+    .await?;
+
+    let condition = "(n.target_type = 'school'
+          OR (n.target_type = 'class' AND n.target_id = ANY($1))
+          OR (n.target_type = 'group' AND n.target_id = ANY($2))
+          OR (n.target_type = 'department' AND n.target_id = ANY($3))
+          OR (n.target_type = 'person' AND n.target_id = $4))";
+
+    let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM notices n WHERE {}", condition))
+        .bind(&class_ids)
+        .bind(&group_ids)
+        .bind(&department_ids)
+        .bind(person_id)
+        .fetch_one(pool)
+        .await?;
+
+    let rows = sqlx::query_as::<_, NoticeRow>(&format!(
+        "SELECT n.id, n.title, n.content, n.author_id, p.name as author_name,
+                n.target_type, n.target_id, n.is_important, n.created_at
+         FROM notices n
+         JOIN persons p ON n.author_id = p.id
+         WHERE {}
+         ORDER BY n.is_important DESC, n.created_at DESC
+         LIMIT $5 OFFSET $6",
+        condition
+    ))
+    .bind(&class_ids)
+    .bind(&group_ids)
+    .bind(&department_ids)
+    .bind(person_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ListResponse {
+        items: rows.into_iter().map(|row| row.into()).collect(),
+        total,
+        page,
+        limit,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::score_ledger;
+
+    // 需要已执行迁移的数据库，未设置 DATABASE_URL 时跳过
+    #[tokio::test]
+    async fn test_scores_of_reads_ledger_columns() {
+        let Ok(url) = std::env::var("DATABASE_URL") else {
+            return;
+        };
+        let pool = PgPool::connect(&url).await.unwrap();
+        let student_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO persons (name, gender, type, role) VALUES ('积分查询测试', 1, 'student', 'student') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let score_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO scores (person_id, score_type, value, reason) VALUES ($1, 'personal', 2, '测试') RETURNING id",
+        )
+        .bind(student_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+        let reversal_id = score_ledger::reverse(&mut conn, score_id, student_id).await.unwrap();
+
+        let query = MeQuery { page: None, limit: None, from: None, to: None };
+        let view = scores_of(&pool, student_id, &query).await;
+        sqlx::query("DELETE FROM persons WHERE id = $1").bind(student_id).execute(&pool).await.unwrap();
+
+        let view = view.unwrap();
+        assert_eq!(view.items.len(), 2);
+        let original = view.items.iter().find(|s| s.id == score_id).unwrap();
+        assert_eq!(original.reversed_by, Some(reversal_id));
+        let reversal = view.items.iter().find(|s| s.id == reversal_id).unwrap();
+        assert_eq!(reversal.reverses_id, Some(score_id));
+    }
+}
@@ -2,14 +2,30 @@ pub mod ai;
 pub mod ai_actions;
 pub mod ai_data;
 pub mod ai_enhanced;
+pub mod attachment;
 pub mod attendance;
+pub mod attendance_alert;
+pub mod attendance_stats;
 pub mod auth;
+pub mod calendar;
 pub mod class;
+pub mod custom_field;
 pub mod debug;
 pub mod department;
 pub mod group;
+pub mod kiosk;
+pub mod leave;
+pub mod me;
 pub mod notice;
 pub mod permission;
 pub mod person;
+pub mod roster;
 pub mod routes;
 pub mod score;
+pub mod score_appeal;
+pub mod score_catalog;
+pub mod score_ledger;
+pub mod score_stats;
+pub mod search;
+pub mod subject;
+pub mod timetable;
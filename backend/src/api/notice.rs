@@ -72,7 +72,7 @@ pub async fn list(
         
         // 查询总数
         let total: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM notices n WHERE (n.title ILIKE $1 OR n.content ILIKE $1)"
+            "SELECT COUNT(*) FROM notices n WHERE (n.title ILIKE $1 OR n.content ILIKE $1) AND n.target_type <> 'person'"
         )
         .bind(&search_pattern)
         .fetch_one(&pool)
@@ -85,7 +85,7 @@ pub async fn list(
              n.target_type, n.target_id, n.is_important, n.created_at 
              FROM notices n 
              JOIN persons p ON n.author_id = p.id 
-             WHERE (n.title ILIKE $1 OR n.content ILIKE $1)
+             WHERE (n.title ILIKE $1 OR n.content ILIKE $1) AND n.target_type <> 'person'
              ORDER BY n.is_important DESC, n.created_at DESC 
              LIMIT $2 OFFSET $3"
         )
@@ -106,8 +106,8 @@ pub async fn list(
         }));
     }
     
-    // 构建查询条件
-    let mut conditions = vec!["1=1".to_string()];
+    // 构建查询条件；个人通知（如考勤提醒）只在 /api/me/notices 中展示
+    let mut conditions = vec!["n.target_type <> 'person'".to_string()];
     let mut param_index = 1;
     
     if query.target_type.is_some() {
@@ -179,7 +179,7 @@ pub async fn list(
 }
 
 #[derive(sqlx::FromRow)]
-struct NoticeRow {
+pub struct NoticeRow {
     id: Uuid,
     title: String,
     content: String,
@@ -241,9 +241,11 @@ pub async fn create(
 
 pub async fn get(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<NoticeResponse>, AppError> {
     let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
     
     let row = sqlx::query_as::<_, NoticeRow>(
         "SELECT n.id, n.title, n.content, n.author_id, p.name as author_name, 
@@ -257,6 +259,11 @@ pub async fn get(
     .await
     .map_err(|e| AppError::Database(e))?
     .ok_or(AppError::NotFound)?;
+
+    // 个人通知（如考勤提醒）只有接收人和发送人能查看，与列表保持一致
+    if row.target_type == "person" && row.target_id != Some(user_id) && row.author_id != user_id {
+        return Err(AppError::NotFound);
+    }
     
     Ok(Json(row.into()))
 }
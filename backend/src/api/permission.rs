@@ -344,6 +344,12 @@ pub async fn get_permission_translations(
         ("person.update", "更新人员信息"),
         ("person.update.status", "更新人员状态"),
         ("person.delete", "删除人员"),
+        ("person.restore", "恢复已归档人员"),
+        ("person.purge", "彻底删除人员"),
+        ("person.duplicates", "查找重复人员"),
+        ("person.merge", "合并重复人员"),
+        ("person.guardian.update", "管理家长与学生关联"),
+        ("person.history.view", "查看人员变更历史"),
         ("person.*", "所有人员权限"),
         
         // 班级权限
@@ -355,6 +361,11 @@ pub async fn get_permission_translations(
         ("class.update.grade", "修改班级年级"),
         ("class.update.teacher", "修改班主任"),
         ("class.delete", "删除班级"),
+        ("class.restore", "恢复已归档班级"),
+        ("class.purge", "彻底删除班级"),
+        ("class.merge", "合并班级"),
+        ("class.split", "拆分班级"),
+        ("class.seating.update", "编辑座位表"),
         ("class.*", "所有班级权限"),
         
         // 部门权限
@@ -362,22 +373,58 @@ pub async fn get_permission_translations(
         ("department.create", "创建部门"),
         ("department.update", "更新部门"),
         ("department.delete", "删除部门"),
+        ("department.update.head", "设置部门负责人"),
+        ("department.restore", "恢复已归档部门"),
+        ("department.purge", "彻底删除部门"),
         ("department.*", "所有部门权限"),
         
+        // 自定义字段权限
+        ("custom_field.manage", "管理自定义字段"),
+        
+        // 附件权限
+        ("attachment.upload", "上传头像和附件"),
+        ("attachment.view", "查看他人附件"),
+        ("attachment.delete", "删除附件"),
+        
+        // 学科与任课权限
+        ("subject.create", "创建学科"),
+        ("subject.update", "更新学科"),
+        ("subject.delete", "删除学科"),
+        ("subject.assign", "安排任课教师"),
+        
+        // 课程表权限
+        ("timetable.manage", "管理作息节次与课程表"),
+        
+        // 校历权限
+        ("calendar.manage", "管理学期、节假日与调休"),
+        
         // 考勤权限
         ("attendance.view", "查看所有考勤"),
         ("attendance.view.own", "查看自己的考勤"),
         ("attendance.create", "创建考勤记录"),
         ("attendance.update", "更新考勤"),
         ("attendance.delete", "删除考勤"),
+        ("attendance.settings", "设置签到时间窗口"),
+        ("attendance.alerts", "设置缺勤提醒规则和年级组长"),
         ("attendance.*", "所有考勤权限"),
         
+        // 请假权限
+        ("leave.view", "查看所有请假申请"),
+        ("leave.approve", "复核请假申请"),
+        ("leave.*", "所有请假权限"),
+        
+        // 一体机权限
+        ("kiosk.manage", "管理一体机设备"),
+        
         // 成绩权限
         ("score.view", "查看所有成绩"),
         ("score.view.own", "查看自己的成绩"),
         ("score.create", "创建成绩"),
         ("score.update", "更新成绩"),
         ("score.delete", "删除成绩"),
+        ("score.catalog", "管理积分类别和预设原因"),
+        ("score.appeal.review", "审核扣分申诉"),
+        ("score.reconcile", "积分对账"),
         ("score.*", "所有成绩权限"),
         
         // 通知权限
@@ -431,6 +478,12 @@ pub async fn get_all_permission_keys(
         "person.update".to_string(),
         "person.update.status".to_string(),
         "person.delete".to_string(),
+        "person.restore".to_string(),
+        "person.purge".to_string(),
+        "person.duplicates".to_string(),
+        "person.merge".to_string(),
+        "person.guardian.update".to_string(),
+        "person.history.view".to_string(),
         "person.*".to_string(),
         
         // 班级权限
@@ -442,6 +495,11 @@ pub async fn get_all_permission_keys(
         "class.update.grade".to_string(),
         "class.update.teacher".to_string(),
         "class.delete".to_string(),
+        "class.restore".to_string(),
+        "class.purge".to_string(),
+        "class.merge".to_string(),
+        "class.split".to_string(),
+        "class.seating.update".to_string(),
         "class.*".to_string(),
         
         // 部门权限
@@ -449,22 +507,58 @@ pub async fn get_all_permission_keys(
         "department.create".to_string(),
         "department.update".to_string(),
         "department.delete".to_string(),
+        "department.update.head".to_string(),
+        "department.restore".to_string(),
+        "department.purge".to_string(),
         "department.*".to_string(),
         
+        // 自定义字段权限
+        "custom_field.manage".to_string(),
+        
+        // 附件权限
+        "attachment.upload".to_string(),
+        "attachment.view".to_string(),
+        "attachment.delete".to_string(),
+        
+        // 学科与任课权限
+        "subject.create".to_string(),
+        "subject.update".to_string(),
+        "subject.delete".to_string(),
+        "subject.assign".to_string(),
+        
+        // 课程表权限
+        "timetable.manage".to_string(),
+        
+        // 校历权限
+        "calendar.manage".to_string(),
+        
         // 考勤权限
         "attendance.view".to_string(),
         "attendance.view.own".to_string(),
         "attendance.create".to_string(),
         "attendance.update".to_string(),
         "attendance.delete".to_string(),
+        "attendance.settings".to_string(),
+        "attendance.alerts".to_string(),
         "attendance.*".to_string(),
         
+        // 请假权限
+        "leave.view".to_string(),
+        "leave.approve".to_string(),
+        "leave.*".to_string(),
+        
+        // 一体机权限
+        "kiosk.manage".to_string(),
+        
         // 成绩权限
         "score.view".to_string(),
         "score.view.own".to_string(),
         "score.create".to_string(),
         "score.update".to_string(),
         "score.delete".to_string(),
+        "score.catalog".to_string(),
+        "score.appeal.review".to_string(),
+        "score.reconcile".to_string(),
         "score.*".to_string(),
         
         // 通知权限
@@ -4,17 +4,27 @@ use axum::{
     Json,
     Extension,
 };
+use axum_extra::{TypedHeader, headers::{authorization::Bearer, Authorization}};
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
 use uuid::Uuid;
 
+use crate::api::attachment;
 use crate::api::routes::AppState;
-use crate::core::auth::Claims;
+use crate::core::archive::{self, ArchiveTarget};
+use crate::core::auth::{optional_user_id, Claims};
+use crate::core::custom_field;
+use crate::core::config::load_config;
 use crate::core::error::AppError;
+use crate::core::history::{self, PersonSnapshot};
 use crate::core::password::hash_password;
-use crate::core::permission::PermissionManager;
+use crate::core::permission::{check_user_permission, PermissionManager};
+use crate::core::search::{self, SearchTerm};
+use crate::models::custom_field::CustomFields;
+use crate::models::history::{ClassAtDate, FieldChange, PersonHistoryEntry};
 use crate::models::person::{
-    ParentResponse, Person, PersonCreate, PersonResponse, PersonUpdate, StudentResponse,
+    ChildInfo, DuplicateCandidate, DuplicatePersonInfo, GuardianInfo, GuardianLink,
+    ParentResponse, Person, PersonCreate, PersonMergeRequest, PersonMergeResult, PersonResponse, PersonUpdate, StudentResponse,
     TeacherResponse,
 };
 
@@ -26,6 +36,10 @@ pub struct ListQuery {
     pub search: Option<String>,
     pub class_id: Option<Uuid>,
     pub department_id: Option<Uuid>,
+    // 为true时只返回已归档（软删除）的人员
+    pub archived: Option<bool>,
+    // 自定义字段过滤，JSON对象，如 {"dorm":"301"}
+    pub custom_fields: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -38,6 +52,7 @@ pub struct ListResponse<T> {
 
 pub async fn list(
     State(state): State<AppState>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
     Query(query): Query<ListQuery>,
 ) -> Result<Json<ListResponse<PersonResponse>>, AppError> {
     let page = query.page.unwrap_or(1);
@@ -45,16 +60,27 @@ pub async fn list(
 
     // 检查数据库连接
     if let Some(pool) = state.pool {
-        let (items, total) = list_persons(
-            &pool,
-            query.r#type.as_deref(),
-            query.search.as_deref(),
-            query.class_id,
-            query.department_id,
-            page,
-            limit,
-        )
-        .await?;
+        // 公开路由：登录用户可看到其有权查看的自定义字段
+        let user_id = optional_user_id(bearer.as_ref().map(|b| b.token()));
+        let hidden = custom_field::hidden_keys(&pool, "person", user_id).await?;
+        let custom_fields = match query.custom_fields.as_deref().filter(|s| !s.is_empty()) {
+            Some(raw) => Some(custom_field::parse_filter(raw, &hidden)?),
+            None => None,
+        };
+
+        let filter = PersonFilter {
+            person_type: query.r#type.as_deref(),
+            search: query.search.as_deref(),
+            class_id: query.class_id,
+            department_id: query.department_id,
+            archived: query.archived.unwrap_or(false),
+            custom_fields: custom_fields.as_ref(),
+        };
+        let (mut items, total) = list_persons(&pool, filter, page, limit).await?;
+
+        for item in items.iter_mut() {
+            item.custom_fields_mut().retain(|key, _| !hidden.contains(key));
+        }
 
         Ok(Json(ListResponse {
             items,
@@ -82,22 +108,37 @@ pub async fn create(
     println!("Received payload: {:?}", payload);
     let pool = state.pool.ok_or_else(|| AppError::Internal)?;
     
-    // 检查创建人员权限
+    // 检查创建人员权限：部门负责人可在本部门（含下级部门）内创建教师
     let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
     let manager = PermissionManager::new(pool.clone());
-    manager.require_permission(user_id, "person.create").await?;
+    match payload.department_id.filter(|_| payload.type_ == "teacher") {
+        Some(department_id) => {
+            manager.require_department_permission(user_id, "person.create", department_id).await?;
+            // 分配任课班级会授予班级权限，需要全局权限
+            if payload.classes.is_some() && !check_user_permission(&pool, user_id, "person.create").await {
+                return Err(AppError::Auth("分配任课班级需要 person.create 权限".to_string()));
+            }
+        }
+        None => manager.require_permission(user_id, "person.create").await?,
+    }
 
-    let person = create_person(&pool, payload).await?;
+    let person = create_person(&pool, payload, user_id).await?;
     Ok(Json(person))
 }
 
 pub async fn get(
     State(state): State<AppState>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<PersonResponse>, AppError> {
     let pool = state.pool.ok_or_else(|| AppError::NotFound)?;
 
-    let person = get_person(&pool, id).await?;
+    let mut person = get_person(&pool, id).await?;
+
+    let user_id = optional_user_id(bearer.as_ref().map(|b| b.token()));
+    let hidden = custom_field::hidden_keys(&pool, "person", user_id).await?;
+    person.custom_fields_mut().retain(|key, _| !hidden.contains(key));
+
     Ok(Json(person))
 }
 
@@ -109,12 +150,30 @@ pub async fn update(
 ) -> Result<Json<PersonResponse>, AppError> {
     let pool = state.pool.ok_or_else(|| AppError::Internal)?;
     
-    // 检查更新人员权限
+    // 检查更新人员权限：教师可由所在部门（或其上级部门）的负责人管理
     let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
     let manager = PermissionManager::new(pool.clone());
-    manager.require_permission(user_id, "person.update").await?;
+    match teacher_department(&pool, id).await? {
+        Some(department_id) => {
+            manager.require_department_permission(user_id, "person.update", department_id).await?;
+            // 调到其他部门时，对目标部门同样需要权限
+            if let Some(target) = payload.department_id.filter(|d| *d != department_id) {
+                manager.require_department_permission(user_id, "person.update", target).await?;
+            }
+            // 仅凭部门权限不能修改自己，也不能修改密码和任课班级
+            if !check_user_permission(&pool, user_id, "person.update").await {
+                if id == user_id {
+                    return Err(AppError::Auth("不能修改自己的信息".to_string()));
+                }
+                if payload.password.is_some() || payload.classes.is_some() {
+                    return Err(AppError::Auth("修改密码或任课班级需要 person.update 权限".to_string()));
+                }
+            }
+        }
+        None => manager.require_permission(user_id, "person.update").await?,
+    }
 
-    let person = update_person(&pool, id, payload).await?;
+    let person = update_person(&pool, id, payload, user_id).await?;
     Ok(Json(person))
 }
 
@@ -125,16 +184,321 @@ pub async fn delete(
 ) -> Result<StatusCode, AppError> {
     let pool = state.pool.ok_or_else(|| AppError::Internal)?;
     
-    // 检查删除人员权限
+    // 检查删除人员权限：教师可由所在部门（或其上级部门）的负责人删除
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    match teacher_department(&pool, id).await? {
+        Some(department_id) => manager.require_department_permission(user_id, "person.delete", department_id).await?,
+        None => manager.require_permission(user_id, "person.delete").await?,
+    }
+
+    // 软删除：保留考勤、积分、小组等历史记录
+    archive::soft_delete(&pool, ArchiveTarget::Person, id, user_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// 教师所属部门，非教师或未分配部门时返回None
+async fn teacher_department(pool: &sqlx::PgPool, person_id: Uuid) -> Result<Option<Uuid>, AppError> {
+    let department_id: Option<Option<Uuid>> =
+        sqlx::query_scalar("SELECT department_id FROM teachers WHERE person_id = $1")
+            .bind(person_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(department_id.flatten())
+}
+
+// 恢复已归档的人员
+pub async fn restore(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PersonResponse>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "person.restore").await?;
+
+    archive::restore(&pool, ArchiveTarget::Person, id).await?;
+
+    let person = get_person(&pool, id).await?;
+    Ok(Json(person))
+}
+
+// 彻底删除已归档的人员（需超过保留期）
+pub async fn purge(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "person.purge").await?;
+
+    let config = load_config().map_err(|_| AppError::Internal)?;
+    // 先记下附件文件，彻底删除成功后再清理存储
+    let file_keys = attachment::person_file_keys(&pool, id).await?;
+    archive::purge(&pool, ArchiveTarget::Person, id, config.archive_retention_days).await?;
+    attachment::delete_files(&file_keys).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DuplicateQuery {
+    pub r#type: Option<String>,
+    // 最低综合得分，默认0.5
+    pub min_score: Option<f64>,
+    pub limit: Option<i64>,
+}
+
+// 查找疑似重复的人员
+pub async fn duplicates(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<DuplicateQuery>,
+) -> Result<Json<Vec<DuplicateCandidate>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "person.duplicates").await?;
+
+    let candidates = find_duplicates(
+        &pool,
+        query.r#type.as_deref().filter(|t| !t.is_empty()),
+        query.min_score.unwrap_or(0.5),
+        query.limit.unwrap_or(50),
+    )
+    .await?;
+
+    Ok(Json(candidates))
+}
+
+// 将 source 合并到路径中的目标人员
+pub async fn merge(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<PersonMergeRequest>,
+) -> Result<Json<PersonMergeResult>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "person.merge").await?;
+
+    let result = merge_persons(&pool, id, payload.source_id, user_id).await?;
+    Ok(Json(result))
+}
+
+// 关联家长到学生（已存在则更新关系类型和首要联系人标记）
+pub async fn link_guardian(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(student_id): Path<Uuid>,
+    Json(payload): Json<GuardianLink>,
+) -> Result<Json<Vec<GuardianInfo>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
     let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
     let manager = PermissionManager::new(pool.clone());
-    manager.require_permission(user_id, "person.delete").await?;
+    manager.require_permission(user_id, "person.guardian.update").await?;
 
-    delete_person(&pool, id).await?;
+    if !["father", "mother", "guardian"].contains(&payload.relationship.as_str()) {
+        return Err(AppError::InvalidInput(
+            "关系类型必须是 father、mother 或 guardian".to_string(),
+        ));
+    }
+
+    // 学生和家长都必须存在且未归档
+    let student_exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM students s JOIN persons p ON s.person_id = p.id WHERE s.person_id = $1 AND p.deleted_at IS NULL)",
+    )
+    .bind(student_id)
+    .fetch_one(&pool)
+    .await?;
+    if !student_exists {
+        return Err(AppError::InvalidInput("学生不存在".to_string()));
+    }
+    let parent_exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM parents pa JOIN persons p ON pa.person_id = p.id WHERE pa.person_id = $1 AND p.deleted_at IS NULL)",
+    )
+    .bind(payload.parent_id)
+    .fetch_one(&pool)
+    .await?;
+    if !parent_exists {
+        return Err(AppError::InvalidInput("家长不存在".to_string()));
+    }
+
+    let is_primary = payload.is_primary_contact.unwrap_or(false);
+    let mut tx = pool.begin().await?;
+
+    // 设为首要联系人时，先取消该学生其他家长的首要标记
+    if is_primary {
+        sqlx::query("UPDATE student_parent SET is_primary_contact = false WHERE student_id = $1 AND parent_id <> $2")
+            .bind(student_id)
+            .bind(payload.parent_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sqlx::query(
+        "INSERT INTO student_parent (student_id, parent_id, relationship, is_primary_contact)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (student_id, parent_id)
+         DO UPDATE SET relationship = EXCLUDED.relationship, is_primary_contact = EXCLUDED.is_primary_contact",
+    )
+    .bind(student_id)
+    .bind(payload.parent_id)
+    .bind(&payload.relationship)
+    .bind(is_primary)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let guardians = get_guardians(&pool, student_id).await?;
+    Ok(Json(guardians))
+}
+
+// 解除家长与学生的关联
+pub async fn unlink_guardian(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path((student_id, parent_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "person.guardian.update").await?;
+
+    let result = sqlx::query("DELETE FROM student_parent WHERE student_id = $1 AND parent_id = $2")
+        .bind(student_id)
+        .bind(parent_id)
+        .execute(&pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
 
     Ok(StatusCode::NO_CONTENT)
 }
 
+// 当前登录家长的孩子列表
+pub async fn my_children(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<ChildInfo>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+
+    let children = sqlx::query_as::<_, ChildInfo>(
+        "SELECT s.person_id as student_id, p.name, s.student_no, s.class_id, c.name as class_name,
+                sp.relationship, sp.is_primary_contact
+         FROM student_parent sp
+         JOIN students s ON sp.student_id = s.person_id
+         JOIN persons p ON s.person_id = p.id
+         LEFT JOIN classes c ON s.class_id = c.id
+         WHERE sp.parent_id = $1 AND p.deleted_at IS NULL
+         ORDER BY p.name",
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(children))
+}
+
+// 人员变更历史（按变更倒序）
+pub async fn get_history(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<PersonHistoryEntry>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "person.history.view").await?;
+
+    let rows = sqlx::query(
+        "SELECT h.change_id, h.changed_by, p.name as changed_by_name, h.changed_at,
+                h.field, h.old_value, h.new_value
+         FROM person_history h
+         LEFT JOIN persons p ON h.changed_by = p.id
+         WHERE h.person_id = $1
+         ORDER BY h.changed_at DESC, h.change_id, h.field",
+    )
+    .bind(id)
+    .fetch_all(&pool)
+    .await?;
+
+    // 按 change_id 聚合为版本
+    let mut entries: Vec<PersonHistoryEntry> = Vec::new();
+    for row in rows {
+        let change_id: Uuid = row.get("change_id");
+        let change = FieldChange {
+            field: row.get("field"),
+            old_value: row.get("old_value"),
+            new_value: row.get("new_value"),
+        };
+        match entries.last_mut() {
+            Some(entry) if entry.change_id == change_id => entry.changes.push(change),
+            _ => entries.push(PersonHistoryEntry {
+                change_id,
+                changed_by: row.get("changed_by"),
+                changed_by_name: row.get("changed_by_name"),
+                changed_at: row.get("changed_at"),
+                changes: vec![change],
+            }),
+        }
+    }
+
+    Ok(Json(entries))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClassAtQuery {
+    pub date: chrono::NaiveDate,
+}
+
+// 学生在指定日期所在的班级
+pub async fn class_at(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ClassAtQuery>,
+) -> Result<Json<ClassAtDate>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "person.history.view").await?;
+
+    let class_id = history::class_on_date(&pool, id, query.date).await?;
+    let class_name: Option<String> = match class_id {
+        Some(class_id) => sqlx::query_scalar("SELECT name FROM classes WHERE id = $1")
+            .bind(class_id)
+            .fetch_optional(&pool)
+            .await?,
+        None => None,
+    };
+
+    Ok(Json(ClassAtDate {
+        student_id: id,
+        date: query.date,
+        class_id,
+        class_name,
+    }))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct TeacherClassesQuery {
     pub teacher_id: Uuid,
@@ -147,6 +511,8 @@ pub struct ClassWithTeacherInfo {
     pub grade: i16,
     pub academic_year: String,
     pub is_main_teacher: bool,
+    // 在该班级当前学年所教的学科
+    pub subjects: Vec<String>,
 }
 
 pub async fn get_teacher_classes(
@@ -159,10 +525,15 @@ pub async fn get_teacher_classes(
                     COALESCE(c.name, '') as name, 
                     COALESCE(c.grade, 0) as grade, 
                     COALESCE(c.academic_year, '') as academic_year, 
-                    COALESCE(tc.is_main_teacher, false) as is_main_teacher
+                    COALESCE(tc.is_main_teacher, false) as is_main_teacher,
+                    ARRAY(SELECT s.name FROM teaching_assignments ta
+                          JOIN subjects s ON s.id = ta.subject_id
+                          WHERE ta.teacher_id = tc.teacher_id AND ta.class_id = c.id
+                            AND (c.academic_year IS NULL OR ta.academic_year = c.academic_year)
+                          ORDER BY s.name) as subjects
              FROM classes c
              JOIN teacher_class tc ON c.id = tc.class_id
-             WHERE tc.teacher_id = $1
+             WHERE tc.teacher_id = $1 AND c.deleted_at IS NULL
              ORDER BY c.grade, c.name",
             query.teacher_id
         )
@@ -175,7 +546,8 @@ pub async fn get_teacher_classes(
                 name: row.name.unwrap_or_default(),
                 grade: row.grade.unwrap_or(0) as i16,
                 academic_year: row.academic_year.unwrap_or_default(),
-                is_main_teacher: row.is_main_teacher.unwrap_or(false)
+                is_main_teacher: row.is_main_teacher.unwrap_or(false),
+                subjects: row.subjects.unwrap_or_default(),
             }
         }).collect();
 
@@ -185,308 +557,160 @@ pub async fn get_teacher_classes(
     }
 }
 
+// 人员查询的基础SELECT（含子类型和班级、部门名称）
+const PERSON_SELECT: &str = "SELECT p.id, p.name, p.gender, p.birthday, p.phone, p.email, p.type, p.custom_fields,
+                s.student_no, s.class_id, s.enrollment_date, s.status,
+                t.employee_no, t.department_id, t.title, t.hire_date,
+                pa.wechat_openid, pa.occupation,
+                c.name as class_name, d.name as department_name
+         FROM persons p
+         LEFT JOIN students s ON p.id = s.person_id
+         LEFT JOIN teachers t ON p.id = t.person_id
+         LEFT JOIN parents pa ON p.id = pa.person_id
+         LEFT JOIN classes c ON s.class_id = c.id
+         LEFT JOIN departments d ON t.department_id = d.id";
+
+// 人员列表的过滤条件
+struct PersonFilter<'a> {
+    person_type: Option<&'a str>,
+    search: Option<&'a str>,
+    class_id: Option<Uuid>,
+    department_id: Option<Uuid>,
+    archived: bool,
+    custom_fields: Option<&'a serde_json::Value>,
+}
+
 async fn list_persons(
     pool: &sqlx::PgPool,
-    person_type: Option<&str>,
-    search: Option<&str>,
-    _class_id: Option<Uuid>,
-    _department_id: Option<Uuid>,
+    filter: PersonFilter<'_>,
     page: i64,
     limit: i64,
 ) -> Result<(Vec<PersonResponse>, i64), AppError> {
+    let PersonFilter {
+        person_type,
+        search,
+        class_id,
+        department_id,
+        archived,
+        custom_fields,
+    } = filter;
     let offset = (page - 1) * limit;
     
     // 处理空字符串情况，视为None
     let person_type = person_type.filter(|&t| !t.is_empty());
 
-    let total = if let Some(t) = person_type {
-        if let Some(s) = search {
-            sqlx::query_scalar::<_, i64>(
-                "SELECT COUNT(*) FROM persons p WHERE p.type = $1 AND p.name ILIKE $2",
-            )
-            .bind(t)
-            .bind(format!("%{}%", s))
-            .fetch_one(pool)
-            .await?
-        } else {
-            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM persons p WHERE p.type = $1")
-                .bind(t)
-                .fetch_one(pool)
-                .await?
-        }
-    } else if let Some(s) = search {
-        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM persons p WHERE p.name ILIKE $1")
-            .bind(format!("%{}%", s))
-            .fetch_one(pool)
-            .await?
+    // 构建查询条件：默认只查询未归档人员，archived=true 时只查询已归档人员
+    let mut conditions = vec![if archived {
+        "p.deleted_at IS NOT NULL".to_string()
     } else {
-        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM persons p")
-            .fetch_one(pool)
-            .await?
-    };
+        "p.deleted_at IS NULL".to_string()
+    }];
+    let mut param_index = 1;
 
-    let rows = if let Some(t) = person_type {
-        if let Some(s) = search {
-            if let Some(class_id) = _class_id {
-                // 根据人员类型使用不同的class_id过滤逻辑
-                let query = if t == "teacher" {
-                    // 老师通过teacher_class表关联
-                    "SELECT p.id, p.name, p.gender, p.birthday, p.phone, p.email, p.type, 
-                            s.student_no, s.class_id, s.enrollment_date, s.status,
-                            t.employee_no, t.department_id, t.title, t.hire_date,
-                            pa.wechat_openid, pa.occupation,
-                            c.name as class_name, d.name as department_name
-                     FROM persons p
-                     LEFT JOIN students s ON p.id = s.person_id
-                     LEFT JOIN teachers t ON p.id = t.person_id
-                     LEFT JOIN parents pa ON p.id = pa.person_id
-                     LEFT JOIN classes c ON s.class_id = c.id
-                     LEFT JOIN departments d ON t.department_id = d.id
-                     INNER JOIN teacher_class tc ON p.id = tc.teacher_id AND tc.class_id = $3
-                     WHERE p.type = $1 AND p.name ILIKE $2
-                     ORDER BY p.created_at DESC
-                     LIMIT $4 OFFSET $5"
-                } else if t == "student" {
-                    // 学生通过students.class_id关联
-                    "SELECT p.id, p.name, p.gender, p.birthday, p.phone, p.email, p.type, 
-                            s.student_no, s.class_id, s.enrollment_date, s.status,
-                            t.employee_no, t.department_id, t.title, t.hire_date,
-                            pa.wechat_openid, pa.occupation,
-                            c.name as class_name, d.name as department_name
-                     FROM persons p
-                     LEFT JOIN students s ON p.id = s.person_id
-                     LEFT JOIN teachers t ON p.id = t.person_id
-                     LEFT JOIN parents pa ON p.id = pa.person_id
-                     LEFT JOIN classes c ON s.class_id = c.id
-                     LEFT JOIN departments d ON t.department_id = d.id
-                     WHERE p.type = $1 AND p.name ILIKE $2 AND s.class_id = $3
-                     ORDER BY p.created_at DESC
-                     LIMIT $4 OFFSET $5"
-                } else {
-                    // 其他类型（如家长）不支持class_id过滤，只按name搜索
-                    "SELECT p.id, p.name, p.gender, p.birthday, p.phone, p.email, p.type, 
-                            s.student_no, s.class_id, s.enrollment_date, s.status,
-                            t.employee_no, t.department_id, t.title, t.hire_date,
-                            pa.wechat_openid, pa.occupation,
-                            c.name as class_name, d.name as department_name
-                     FROM persons p
-                     LEFT JOIN students s ON p.id = s.person_id
-                     LEFT JOIN teachers t ON p.id = t.person_id
-                     LEFT JOIN parents pa ON p.id = pa.person_id
-                     LEFT JOIN classes c ON s.class_id = c.id
-                     LEFT JOIN departments d ON t.department_id = d.id
-                     WHERE p.type = $1 AND p.name ILIKE $2
-                     ORDER BY p.created_at DESC
-                     LIMIT $4 OFFSET $5"
-                };
-                
-                sqlx::query_as::<_, PersonWithRelations>(query)
-                    .bind(t)
-                    .bind(format!("%{}%", s))
-                    .bind(class_id)
-                    .bind(limit)
-                    .bind(offset)
-                    .fetch_all(pool)
-                    .await?
-            } else if let Some(department_id) = _department_id {
-                sqlx::query_as::<_, PersonWithRelations>(
-                    "SELECT p.id, p.name, p.gender, p.birthday, p.phone, p.email, p.type, 
-                            s.student_no, s.class_id, s.enrollment_date, s.status,
-                            t.employee_no, t.department_id, t.title, t.hire_date,
-                            pa.wechat_openid, pa.occupation,
-                            c.name as class_name, d.name as department_name
-                     FROM persons p
-                     LEFT JOIN students s ON p.id = s.person_id
-                     LEFT JOIN teachers t ON p.id = t.person_id
-                     LEFT JOIN parents pa ON p.id = pa.person_id
-                     LEFT JOIN classes c ON s.class_id = c.id
-                     LEFT JOIN departments d ON t.department_id = d.id
-                     WHERE p.type = $1 AND p.name ILIKE $2 AND t.department_id = $3
-                     ORDER BY p.created_at DESC
-                     LIMIT $4 OFFSET $5",
-                )
-                .bind(t)
-                .bind(format!("%{}%", s))
-                .bind(department_id)
-                .bind(limit)
-                .bind(offset)
-                .fetch_all(pool)
-                .await?
-            } else {
-                sqlx::query_as::<_, PersonWithRelations>(
-                    "SELECT p.id, p.name, p.gender, p.birthday, p.phone, p.email, p.type, 
-                            s.student_no, s.class_id, s.enrollment_date, s.status,
-                            t.employee_no, t.department_id, t.title, t.hire_date,
-                            pa.wechat_openid, pa.occupation,
-                            c.name as class_name, d.name as department_name
-                     FROM persons p
-                     LEFT JOIN students s ON p.id = s.person_id
-                     LEFT JOIN teachers t ON p.id = t.person_id
-                     LEFT JOIN parents pa ON p.id = pa.person_id
-                     LEFT JOIN classes c ON s.class_id = c.id
-                     LEFT JOIN departments d ON t.department_id = d.id
-                     WHERE p.type = $1 AND p.name ILIKE $2
-                     ORDER BY p.created_at DESC
-                     LIMIT $3 OFFSET $4",
-                )
-                .bind(t)
-                .bind(format!("%{}%", s))
-                .bind(limit)
-                .bind(offset)
-                .fetch_all(pool)
-                .await?
-            }
+    if person_type.is_some() {
+        conditions.push(format!("p.type = ${}", param_index));
+        param_index += 1;
+    }
+
+    // 名称检索同时匹配汉字、全拼和首字母，结果按相关度排序
+    let search = search.and_then(SearchTerm::new);
+    let mut order_by = "p.created_at DESC".to_string();
+    if search.is_some() {
+        conditions.push(search::condition("p", param_index, param_index + 1));
+        order_by = format!("{} DESC, {}", search::score("p", param_index, param_index + 1), order_by);
+        param_index += 2;
+    }
+
+    // 班级和部门过滤只在指定人员类型时生效
+    let class_id = class_id.filter(|_| matches!(person_type, Some("teacher") | Some("student")));
+    let department_id = department_id.filter(|_| person_type.is_some() && class_id.is_none());
+
+    if class_id.is_some() {
+        if person_type == Some("teacher") {
+            // 老师通过teacher_class表关联
+            conditions.push(format!(
+                "EXISTS (SELECT 1 FROM teacher_class tc WHERE tc.teacher_id = p.id AND tc.class_id = ${})",
+                param_index
+            ));
         } else {
-            if let Some(class_id) = _class_id {
-                // 根据人员类型使用不同的class_id过滤逻辑
-                let query = if t == "teacher" {
-                    // 老师通过teacher_class表关联
-                    "SELECT p.id, p.name, p.gender, p.birthday, p.phone, p.email, p.type, 
-                            s.student_no, s.class_id, s.enrollment_date, s.status,
-                            t.employee_no, t.department_id, t.title, t.hire_date,
-                            pa.wechat_openid, pa.occupation,
-                            c.name as class_name, d.name as department_name
-                     FROM persons p
-                     LEFT JOIN students s ON p.id = s.person_id
-                     LEFT JOIN teachers t ON p.id = t.person_id
-                     LEFT JOIN parents pa ON p.id = pa.person_id
-                     LEFT JOIN classes c ON s.class_id = c.id
-                     LEFT JOIN departments d ON t.department_id = d.id
-                     INNER JOIN teacher_class tc ON p.id = tc.teacher_id AND tc.class_id = $2
-                     WHERE p.type = $1
-                     ORDER BY p.created_at DESC
-                     LIMIT $3 OFFSET $4"
-                } else if t == "student" {
-                    // 学生通过students.class_id关联
-                    "SELECT p.id, p.name, p.gender, p.birthday, p.phone, p.email, p.type, 
-                            s.student_no, s.class_id, s.enrollment_date, s.status,
-                            t.employee_no, t.department_id, t.title, t.hire_date,
-                            pa.wechat_openid, pa.occupation,
-                            c.name as class_name, d.name as department_name
-                     FROM persons p
-                     LEFT JOIN students s ON p.id = s.person_id
-                     LEFT JOIN teachers t ON p.id = t.person_id
-                     LEFT JOIN parents pa ON p.id = pa.person_id
-                     LEFT JOIN classes c ON s.class_id = c.id
-                     LEFT JOIN departments d ON t.department_id = d.id
-                     WHERE p.type = $1 AND s.class_id = $2
-                     ORDER BY p.created_at DESC
-                     LIMIT $3 OFFSET $4"
-                } else {
-                    // 其他类型（如家长）不支持class_id过滤
-                    "SELECT p.id, p.name, p.gender, p.birthday, p.phone, p.email, p.type, 
-                            s.student_no, s.class_id, s.enrollment_date, s.status,
-                            t.employee_no, t.department_id, t.title, t.hire_date,
-                            pa.wechat_openid, pa.occupation,
-                            c.name as class_name, d.name as department_name
-                     FROM persons p
-                     LEFT JOIN students s ON p.id = s.person_id
-                     LEFT JOIN teachers t ON p.id = t.person_id
-                     LEFT JOIN parents pa ON p.id = pa.person_id
-                     LEFT JOIN classes c ON s.class_id = c.id
-                     LEFT JOIN departments d ON t.department_id = d.id
-                     WHERE p.type = $1
-                     ORDER BY p.created_at DESC
-                     LIMIT $3 OFFSET $4"
-                };
-                
-                sqlx::query_as::<_, PersonWithRelations>(query)
-                    .bind(t)
-                    .bind(class_id)
-                    .bind(limit)
-                    .bind(offset)
-                    .fetch_all(pool)
-                    .await?
-            } else if let Some(department_id) = _department_id {
-                sqlx::query_as::<_, PersonWithRelations>(
-                    "SELECT p.id, p.name, p.gender, p.birthday, p.phone, p.email, p.type, 
-                            s.student_no, s.class_id, s.enrollment_date, s.status,
-                            t.employee_no, t.department_id, t.title, t.hire_date,
-                            pa.wechat_openid, pa.occupation,
-                            c.name as class_name, d.name as department_name
-                     FROM persons p
-                     LEFT JOIN students s ON p.id = s.person_id
-                     LEFT JOIN teachers t ON p.id = t.person_id
-                     LEFT JOIN parents pa ON p.id = pa.person_id
-                     LEFT JOIN classes c ON s.class_id = c.id
-                     LEFT JOIN departments d ON t.department_id = d.id
-                     WHERE p.type = $1 AND t.department_id = $2
-                     ORDER BY p.created_at DESC
-                     LIMIT $3 OFFSET $4",
-                )
-                .bind(t)
-                .bind(department_id)
-                .bind(limit)
-                .bind(offset)
-                .fetch_all(pool)
-                .await?
-            } else {
-                sqlx::query_as::<_, PersonWithRelations>(
-                    "SELECT p.id, p.name, p.gender, p.birthday, p.phone, p.email, p.type, 
-                            s.student_no, s.class_id, s.enrollment_date, s.status,
-                            t.employee_no, t.department_id, t.title, t.hire_date,
-                            pa.wechat_openid, pa.occupation,
-                            c.name as class_name, d.name as department_name
-                     FROM persons p
-                     LEFT JOIN students s ON p.id = s.person_id
-                     LEFT JOIN teachers t ON p.id = t.person_id
-                     LEFT JOIN parents pa ON p.id = pa.person_id
-                     LEFT JOIN classes c ON s.class_id = c.id
-                     LEFT JOIN departments d ON t.department_id = d.id
-                     WHERE p.type = $1
-                     ORDER BY p.created_at DESC
-                     LIMIT $2 OFFSET $3",
-                )
-                .bind(t)
-                .bind(limit)
-                .bind(offset)
-                .fetch_all(pool)
-                .await?
-            }
+            // 学生通过students.class_id关联
+            conditions.push(format!("s.class_id = ${}", param_index));
         }
-    } else if let Some(s) = search {
-        sqlx::query_as::<_, PersonWithRelations>(
-            "SELECT p.id, p.name, p.gender, p.birthday, p.phone, p.email, p.type, 
-                    s.student_no, s.class_id, s.enrollment_date, s.status,
-                    t.employee_no, t.department_id, t.title, t.hire_date,
-                    pa.wechat_openid, pa.occupation,
-                    c.name as class_name, d.name as department_name
-             FROM persons p
-             LEFT JOIN students s ON p.id = s.person_id
-             LEFT JOIN teachers t ON p.id = t.person_id
-             LEFT JOIN parents pa ON p.id = pa.person_id
-             LEFT JOIN classes c ON s.class_id = c.id
-             LEFT JOIN departments d ON t.department_id = d.id
-             WHERE p.name ILIKE $1
-             ORDER BY p.created_at DESC
-             LIMIT $2 OFFSET $3",
-        )
-        .bind(format!("%{}%", s))
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(pool)
-        .await?
-    } else {
-        sqlx::query_as::<_, PersonWithRelations>(
-            "SELECT p.id, p.name, p.gender, p.birthday, p.phone, p.email, p.type, 
-                    s.student_no, s.class_id, s.enrollment_date, s.status,
-                    t.employee_no, t.department_id, t.title, t.hire_date,
-                    pa.wechat_openid, pa.occupation,
-                    c.name as class_name, d.name as department_name
-             FROM persons p
-             LEFT JOIN students s ON p.id = s.person_id
-             LEFT JOIN teachers t ON p.id = t.person_id
-             LEFT JOIN parents pa ON p.id = pa.person_id
-             LEFT JOIN classes c ON s.class_id = c.id
-             LEFT JOIN departments d ON t.department_id = d.id
-             ORDER BY p.created_at DESC
-             LIMIT $1 OFFSET $2",
-        )
+        param_index += 1;
+    }
+
+    if department_id.is_some() {
+        conditions.push(format!("t.department_id = ${}", param_index));
+        param_index += 1;
+    }
+
+    if custom_fields.is_some() {
+        conditions.push(format!("p.custom_fields @> ${}", param_index));
+        param_index += 1;
+    }
+
+    let where_clause = conditions.join(" AND ");
+
+    // 查询总数
+    let count_sql = format!(
+        "SELECT COUNT(*) FROM persons p
+         LEFT JOIN students s ON p.id = s.person_id
+         LEFT JOIN teachers t ON p.id = t.person_id
+         WHERE {}",
+        where_clause
+    );
+    let mut count_query = sqlx::query_scalar::<_, i64>(&count_sql);
+
+    if let Some(t) = person_type {
+        count_query = count_query.bind(t);
+    }
+    if let Some(s) = &search {
+        count_query = count_query.bind(&s.raw).bind(&s.pinyin);
+    }
+    if let Some(class_id) = class_id {
+        count_query = count_query.bind(class_id);
+    }
+    if let Some(department_id) = department_id {
+        count_query = count_query.bind(department_id);
+    }
+    if let Some(custom_fields) = custom_fields {
+        count_query = count_query.bind(sqlx::types::Json(custom_fields));
+    }
+
+    let total = count_query.fetch_one(pool).await?;
+
+    // 查询数据
+    let sql = format!(
+        "{} WHERE {} ORDER BY {} LIMIT ${} OFFSET ${}",
+        PERSON_SELECT,
+        where_clause,
+        order_by,
+        param_index,
+        param_index + 1
+    );
+    let mut data_query = sqlx::query_as::<_, PersonWithRelations>(&sql);
+
+    if let Some(t) = person_type {
+        data_query = data_query.bind(t);
+    }
+    if let Some(s) = &search {
+        data_query = data_query.bind(&s.raw).bind(&s.pinyin);
+    }
+    if let Some(class_id) = class_id {
+        data_query = data_query.bind(class_id);
+    }
+    if let Some(department_id) = department_id {
+        data_query = data_query.bind(department_id);
+    }
+    if let Some(custom_fields) = custom_fields {
+        data_query = data_query.bind(sqlx::types::Json(custom_fields));
+    }
+
+    let rows = data_query
         .bind(limit)
         .bind(offset)
         .fetch_all(pool)
-        .await?
-    };
+        .await?;
 
     let items: Vec<PersonResponse> = rows.into_iter().map(|row| {
         let mut response = row.into_response();
@@ -500,27 +724,15 @@ async fn list_persons(
     Ok((items, total))
 }
 
-async fn get_person(pool: &sqlx::PgPool, id: Uuid) -> Result<PersonResponse, AppError> {
+pub async fn get_person(pool: &sqlx::PgPool, id: Uuid) -> Result<PersonResponse, AppError> {
     println!("=== GET_PERSON DEBUG ===");
     println!("Fetching person with ID: {}", id);
     
-    let row = sqlx::query_as::<_, PersonWithRelations>(
-        "SELECT p.id, p.name, p.gender, p.birthday, p.phone, p.email, p.type, 
-                s.student_no, s.class_id, s.enrollment_date, s.status,
-                t.employee_no, t.department_id, t.title, t.hire_date,
-                pa.wechat_openid, pa.occupation,
-                c.name as class_name, d.name as department_name
-         FROM persons p
-         LEFT JOIN students s ON p.id = s.person_id
-         LEFT JOIN teachers t ON p.id = t.person_id
-         LEFT JOIN parents pa ON p.id = pa.person_id
-         LEFT JOIN classes c ON s.class_id = c.id
-         LEFT JOIN departments d ON t.department_id = d.id
-         WHERE p.id = $1",
-    )
-    .bind(id)
-    .fetch_optional(pool)
-    .await?;
+    // 已归档人员同样可以按ID查询，保证历史记录能解析到姓名
+    let row = sqlx::query_as::<_, PersonWithRelations>(&format!("{} WHERE p.id = $1", PERSON_SELECT))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
 
     let row = match row {
         Some(row) => {
@@ -541,7 +753,12 @@ async fn get_person(pool: &sqlx::PgPool, id: Uuid) -> Result<PersonResponse, App
     if let PersonResponse::Teacher(ref mut teacher_response) = response {
         println!("Fetching classes for teacher...");
         let classes: Vec<_> = sqlx::query!(
-            "SELECT tc.class_id, c.name as class_name, tc.is_main_teacher
+            "SELECT tc.class_id, c.name as class_name, tc.is_main_teacher,
+                    ARRAY(SELECT s.name FROM teaching_assignments ta
+                          JOIN subjects s ON s.id = ta.subject_id
+                          WHERE ta.teacher_id = tc.teacher_id AND ta.class_id = tc.class_id
+                            AND (c.academic_year IS NULL OR ta.academic_year = c.academic_year)
+                          ORDER BY s.name) as subjects
              FROM teacher_class tc
              LEFT JOIN classes c ON tc.class_id = c.id
              WHERE tc.teacher_id = $1",
@@ -556,18 +773,40 @@ async fn get_person(pool: &sqlx::PgPool, id: Uuid) -> Result<PersonResponse, App
             crate::models::person::TeacherClassInfo {
                 class_id: c.class_id,
                 class_name: c.class_name,
-                is_main_teacher: c.is_main_teacher.unwrap_or(false)
+                is_main_teacher: c.is_main_teacher.unwrap_or(false),
+                subjects: c.subjects.unwrap_or_default(),
             }
         }).collect();
     }
     
+    // 如果是学生，附带家长信息
+    if let PersonResponse::Student(ref mut student_response) = response {
+        student_response.guardians = get_guardians(pool, id).await?;
+    }
+    
     println!("Response created successfully");
     Ok(response)
 }
 
+async fn get_guardians(pool: &sqlx::PgPool, student_id: Uuid) -> Result<Vec<GuardianInfo>, AppError> {
+    let guardians = sqlx::query_as::<_, GuardianInfo>(
+        "SELECT sp.parent_id, p.name, p.phone, sp.relationship, sp.is_primary_contact
+         FROM student_parent sp
+         JOIN persons p ON sp.parent_id = p.id
+         WHERE sp.student_id = $1 AND p.deleted_at IS NULL
+         ORDER BY sp.is_primary_contact DESC, p.name",
+    )
+    .bind(student_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(guardians)
+}
+
 async fn create_person(
     pool: &sqlx::PgPool,
     payload: PersonCreate,
+    created_by: Uuid,
 ) -> Result<PersonResponse, AppError> {
     let mut tx = pool.begin().await?;
 
@@ -577,6 +816,11 @@ async fn create_person(
 
     let person_id = Uuid::new_v4();
 
+    // 校验自定义字段
+    let custom_fields = payload.custom_fields.clone().unwrap_or_default();
+    let definitions = custom_field::load_definitions(pool, "person", Some(&payload.type_)).await?;
+    custom_field::validate(&definitions, &custom_fields)?;
+
     // 转换日期字符串为NaiveDate
     let birthday = payload.birthday.and_then(|s| chrono::NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok());
     let enrollment_date = payload.enrollment_date.and_then(|s| chrono::NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok());
@@ -631,6 +875,7 @@ async fn create_person(
     .bind(&payload.type_)
     .execute(&mut *tx)
     .await?;
+    search::index_name(&mut *tx, "persons", person_id, &payload.name).await?;
 
     match payload.type_.as_str() {
         "student" => {
@@ -717,6 +962,16 @@ async fn create_person(
         }
     }
 
+    sqlx::query("UPDATE persons SET custom_fields = $1 WHERE id = $2")
+        .bind(sqlx::types::Json(&custom_fields))
+        .bind(person_id)
+        .execute(&mut *tx)
+        .await?;
+
+    // 记录初始版本
+    let after = history::snapshot(&mut tx, person_id).await?;
+    history::record(&mut tx, person_id, &PersonSnapshot::new(), &after, created_by).await?;
+
     tx.commit().await?;
 
     get_person(pool, person_id).await
@@ -726,6 +981,7 @@ async fn update_person(
     pool: &sqlx::PgPool,
     id: Uuid,
     payload: PersonUpdate,
+    changed_by: Uuid,
 ) -> Result<PersonResponse, AppError> {
     println!("=== UPDATE PERSON DEBUG ===");
     println!("Person ID: {}", id);
@@ -749,7 +1005,8 @@ async fn update_person(
     };
 
     println!("Fetching person data...");
-    let person = match sqlx::query_as::<_, Person>("SELECT * FROM persons WHERE id = $1")
+    // 已归档人员不能修改，需先恢复
+    let person = match sqlx::query_as::<_, Person>("SELECT * FROM persons WHERE id = $1 AND deleted_at IS NULL FOR UPDATE")
         .bind(id)
         .fetch_optional(&mut *tx)
         .await {
@@ -767,10 +1024,32 @@ async fn update_person(
         }
     };
 
+    // 修改前的快照，用于生成字段级变更历史
+    let before = history::snapshot(&mut tx, id).await?;
+
+    // 自定义字段：与已有取值合并后整体校验；未修改自定义字段时同样校验，
+    // 之后新增的必填字段需要先补全才能保存
+    let existing: sqlx::types::Json<CustomFields> =
+        sqlx::query_scalar("SELECT custom_fields FROM persons WHERE id = $1")
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+    let custom_fields_changed = payload.custom_fields.is_some();
+    let merged = custom_field::merge(existing.0, payload.custom_fields.unwrap_or_default());
+    let definitions = custom_field::load_definitions(pool, "person", Some(&person.type_)).await?;
+    custom_field::validate(&definitions, &merged)?;
+    if custom_fields_changed {
+        sqlx::query("UPDATE persons SET custom_fields = $1 WHERE id = $2")
+            .bind(sqlx::types::Json(&merged))
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
     if let Some(name) = payload.name {
         println!("Updating name to: {}", name);
         match sqlx::query("UPDATE persons SET name = $1 WHERE id = $2")
-            .bind(name)
+            .bind(&name)
             .bind(id)
             .execute(&mut *tx)
             .await {
@@ -780,6 +1059,7 @@ async fn update_person(
                 return Err(AppError::Database(e));
             }
         };
+        search::index_name(&mut *tx, "persons", id, &name).await?;
     }
     if let Some(gender) = payload.gender {
         println!("Updating gender to: {} (converted to i16: {})
@@ -1028,6 +1308,9 @@ async fn update_person(
         _ => {}
     }
 
+    let after = history::snapshot(&mut tx, id).await?;
+    history::record(&mut tx, id, &before, &after, changed_by).await?;
+
     println!("About to commit transaction...");
     match tx.commit().await {
         Ok(_) => {
@@ -1082,17 +1365,350 @@ async fn update_person(
     }
 }
 
-async fn delete_person(pool: &sqlx::PgPool, id: Uuid) -> Result<(), AppError> {
-    let result = sqlx::query("DELETE FROM persons WHERE id = $1")
-        .bind(id)
-        .execute(pool)
-        .await?;
+#[derive(Debug, sqlx::FromRow)]
+struct DuplicatePairRow {
+    a_id: Uuid,
+    a_name: String,
+    a_phone: Option<String>,
+    a_email: Option<String>,
+    a_birthday: Option<chrono::NaiveDate>,
+    a_created_at: chrono::DateTime<chrono::Utc>,
+    b_id: Uuid,
+    b_name: String,
+    b_phone: Option<String>,
+    b_email: Option<String>,
+    b_birthday: Option<chrono::NaiveDate>,
+    b_created_at: chrono::DateTime<chrono::Utc>,
+    name_similarity: f64,
+    same_phone: bool,
+    same_email: bool,
+    same_birthday: bool,
+}
 
-    if result.rows_affected() == 0 {
+// 计算重复得分：姓名相似度占0.4，手机号0.3，邮箱0.2，生日0.1
+fn duplicate_score(
+    name_similarity: f64,
+    same_phone: bool,
+    same_email: bool,
+    same_birthday: bool,
+) -> (f64, Vec<String>) {
+    let mut score = name_similarity * 0.4;
+    let mut reasons = Vec::new();
+
+    if name_similarity >= 0.999 {
+        reasons.push("姓名相同".to_string());
+    } else if name_similarity >= 0.3 {
+        reasons.push(format!("姓名相似({:.0}%)", name_similarity * 100.0));
+    }
+    if same_phone {
+        score += 0.3;
+        reasons.push("手机号相同".to_string());
+    }
+    if same_email {
+        score += 0.2;
+        reasons.push("邮箱相同".to_string());
+    }
+    if same_birthday {
+        score += 0.1;
+        reasons.push("生日相同".to_string());
+    }
+
+    (score, reasons)
+}
+
+async fn find_duplicates(
+    pool: &sqlx::PgPool,
+    person_type: Option<&str>,
+    min_score: f64,
+    limit: i64,
+) -> Result<Vec<DuplicateCandidate>, AppError> {
+    // 只比较同类型的未归档人员；候选对需满足姓名相同/三元组相似或手机号/邮箱相同。
+    // 每种条件单独找候选对（等值条件走表达式索引，相似姓名走三元组索引），合并后再计算各项指标，
+    // 避免全表两两比较。中文短姓名在非UTF-8 locale下三元组相似度可能为0，因此姓名完全相同单独判断
+    let sql = r"WITH pairs AS (
+             SELECT a.id AS a_id, b.id AS b_id FROM persons a
+             JOIN persons b ON b.type = a.type AND b.name = a.name AND b.id > a.id AND b.deleted_at IS NULL
+             WHERE a.deleted_at IS NULL AND ($1::text IS NULL OR a.type = $1)
+             UNION
+             SELECT a.id, b.id FROM persons a
+             JOIN persons b ON b.name % a.name AND b.type = a.type AND b.id > a.id AND b.deleted_at IS NULL
+             WHERE a.deleted_at IS NULL AND ($1::text IS NULL OR a.type = $1)
+             UNION
+             SELECT a.id, b.id FROM persons a
+             JOIN persons b ON NULLIF(regexp_replace(b.phone, '\D', '', 'g'), '') = NULLIF(regexp_replace(a.phone, '\D', '', 'g'), '')
+                           AND b.type = a.type AND b.id > a.id AND b.deleted_at IS NULL
+             WHERE a.deleted_at IS NULL AND ($1::text IS NULL OR a.type = $1)
+             UNION
+             SELECT a.id, b.id FROM persons a
+             JOIN persons b ON NULLIF(lower(trim(b.email)), '') = NULLIF(lower(trim(a.email)), '')
+                           AND b.type = a.type AND b.id > a.id AND b.deleted_at IS NULL
+             WHERE a.deleted_at IS NULL AND ($1::text IS NULL OR a.type = $1)
+         )
+         SELECT a.id AS a_id, a.name AS a_name, a.phone AS a_phone, a.email AS a_email,
+                a.birthday AS a_birthday, a.created_at AS a_created_at,
+                b.id AS b_id, b.name AS b_name, b.phone AS b_phone, b.email AS b_email,
+                b.birthday AS b_birthday, b.created_at AS b_created_at,
+                CASE WHEN a.name = b.name THEN 1.0 ELSE similarity(a.name, b.name) END::float8 AS name_similarity,
+                COALESCE(NULLIF(regexp_replace(a.phone, '\D', '', 'g'), '') = regexp_replace(b.phone, '\D', '', 'g'), false) AS same_phone,
+                COALESCE(NULLIF(lower(trim(a.email)), '') = lower(trim(b.email)), false) AS same_email,
+                COALESCE(a.birthday = b.birthday, false) AS same_birthday
+         FROM pairs
+         JOIN persons a ON a.id = pairs.a_id
+         JOIN persons b ON b.id = pairs.b_id";
+
+    let query = sqlx::query_as::<_, DuplicatePairRow>(sql).bind(person_type);
+    let rows = query.fetch_all(pool).await?;
+
+    let mut candidates: Vec<DuplicateCandidate> = rows
+        .into_iter()
+        .filter_map(|row| {
+            let (score, reasons) = duplicate_score(
+                row.name_similarity,
+                row.same_phone,
+                row.same_email,
+                row.same_birthday,
+            );
+            if score < min_score {
+                return None;
+            }
+            Some(DuplicateCandidate {
+                person_a: DuplicatePersonInfo {
+                    id: row.a_id,
+                    name: row.a_name,
+                    phone: row.a_phone,
+                    email: row.a_email,
+                    birthday: row.a_birthday,
+                    created_at: row.a_created_at,
+                },
+                person_b: DuplicatePersonInfo {
+                    id: row.b_id,
+                    name: row.b_name,
+                    phone: row.b_phone,
+                    email: row.b_email,
+                    birthday: row.b_birthday,
+                    created_at: row.b_created_at,
+                },
+                score,
+                reasons,
+            })
+        })
+        .collect();
+
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    candidates.truncate(limit.max(0) as usize);
+
+    Ok(candidates)
+}
+
+// 在一个事务中把 source 的关联数据迁移到 target，并归档 source
+async fn merge_persons(
+    pool: &sqlx::PgPool,
+    target_id: Uuid,
+    source_id: Uuid,
+    operator_id: Uuid,
+) -> Result<PersonMergeResult, AppError> {
+    if target_id == source_id {
+        return Err(AppError::InvalidInput("不能将人员合并到自身".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    // 锁定两条记录，避免合并过程中被并发修改
+    let rows = sqlx::query("SELECT id, type FROM persons WHERE id = ANY($1) AND deleted_at IS NULL FOR UPDATE")
+        .bind(vec![target_id, source_id])
+        .fetch_all(&mut *tx)
+        .await?;
+    if rows.len() != 2 {
         return Err(AppError::NotFound);
     }
+    let types: Vec<String> = rows.iter().map(|r| r.get("type")).collect();
+    if types[0] != types[1] {
+        return Err(AppError::InvalidInput("只能合并相同类型的人员".to_string()));
+    }
+    let person_type = types[0].clone();
+    let before = history::snapshot(&mut tx, target_id).await?;
+
+    // 家长-学生关系：已存在相同关系的直接丢弃，其余迁移
+    let student_parent = match person_type.as_str() {
+        "parent" => {
+            let moved = sqlx::query(
+                "UPDATE student_parent sp SET parent_id = $1
+                 WHERE sp.parent_id = $2
+                   AND NOT EXISTS (SELECT 1 FROM student_parent x WHERE x.parent_id = $1 AND x.student_id = sp.student_id)",
+            )
+            .bind(target_id)
+            .bind(source_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            sqlx::query("DELETE FROM student_parent WHERE parent_id = $1")
+                .bind(source_id)
+                .execute(&mut *tx)
+                .await?;
+            moved
+        }
+        "student" => {
+            let moved = sqlx::query(
+                "UPDATE student_parent sp SET student_id = $1,
+                        is_primary_contact = sp.is_primary_contact
+                            AND NOT EXISTS (SELECT 1 FROM student_parent y WHERE y.student_id = $1 AND y.is_primary_contact)
+                 WHERE sp.student_id = $2
+                   AND NOT EXISTS (SELECT 1 FROM student_parent x WHERE x.student_id = $1 AND x.parent_id = sp.parent_id)",
+            )
+            .bind(target_id)
+            .bind(source_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            sqlx::query("DELETE FROM student_parent WHERE student_id = $1")
+                .bind(source_id)
+                .execute(&mut *tx)
+                .await?;
+            moved
+        }
+        _ => 0,
+    };
+
+    // 老师-班级关系
+    let teacher_classes = if person_type == "teacher" {
+        let moved = sqlx::query(
+            "UPDATE teacher_class tc SET teacher_id = $1
+             WHERE tc.teacher_id = $2
+               AND NOT EXISTS (SELECT 1 FROM teacher_class x WHERE x.teacher_id = $1 AND x.class_id = tc.class_id)",
+        )
+        .bind(target_id)
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        sqlx::query("DELETE FROM teacher_class WHERE teacher_id = $1")
+            .bind(source_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE classes SET teacher_id = $1 WHERE teacher_id = $2")
+            .bind(target_id)
+            .bind(source_id)
+            .execute(&mut *tx)
+            .await?;
+        // 任课安排，目标已有相同安排的直接丢弃
+        sqlx::query(
+            "UPDATE teaching_assignments ta SET teacher_id = $1
+             WHERE ta.teacher_id = $2
+               AND NOT EXISTS (SELECT 1 FROM teaching_assignments x
+                               WHERE x.teacher_id = $1 AND x.subject_id = ta.subject_id
+                                 AND x.class_id = ta.class_id AND x.academic_year = ta.academic_year)",
+        )
+        .bind(target_id)
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM teaching_assignments WHERE teacher_id = $1")
+            .bind(source_id)
+            .execute(&mut *tx)
+            .await?;
+        moved
+    } else {
+        0
+    };
+
+    // 考勤：目标同一天同一时段已有记录的，保留目标的记录
+    sqlx::query(
+        "DELETE FROM attendances a
+         WHERE a.person_id = $2
+           AND EXISTS (SELECT 1 FROM attendances x
+                       WHERE x.person_id = $1 AND x.date = a.date AND x.period_id IS NOT DISTINCT FROM a.period_id)",
+    )
+    .bind(target_id)
+    .bind(source_id)
+    .execute(&mut *tx)
+    .await?;
+    let attendances = sqlx::query("UPDATE attendances SET person_id = $1 WHERE person_id = $2")
+        .bind(target_id)
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+    let scores = sqlx::query("UPDATE scores SET person_id = $1 WHERE person_id = $2")
+        .bind(target_id)
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+    // 小组成员：目标已在同一小组的不重复加入
+    let group_members = sqlx::query(
+        "UPDATE group_members gm SET person_id = $1
+         WHERE gm.person_id = $2
+           AND NOT EXISTS (SELECT 1 FROM group_members x WHERE x.person_id = $1 AND x.group_id = gm.group_id)",
+    )
+    .bind(target_id)
+    .bind(source_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    sqlx::query("DELETE FROM group_members WHERE person_id = $1")
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await?;
+
+    // 用户权限：目标已有的同名权限保持不变
+    let permissions = sqlx::query(
+        "UPDATE user_permissions up SET user_id = $1, updated_at = NOW()
+         WHERE up.user_id = $2
+           AND NOT EXISTS (SELECT 1 FROM user_permissions x WHERE x.user_id = $1 AND x.permission = up.permission)",
+    )
+    .bind(target_id)
+    .bind(source_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    sqlx::query("DELETE FROM user_permissions WHERE user_id = $1")
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await?;
+
+    // 目标缺失的联系方式用 source 的补齐
+    sqlx::query(
+        "UPDATE persons t SET
+            phone = COALESCE(t.phone, s.phone),
+            email = COALESCE(t.email, s.email),
+            birthday = COALESCE(t.birthday, s.birthday),
+            updated_at = NOW()
+         FROM persons s
+         WHERE t.id = $1 AND s.id = $2",
+    )
+    .bind(target_id)
+    .bind(source_id)
+    .execute(&mut *tx)
+    .await?;
+
+    let after = history::snapshot(&mut tx, target_id).await?;
+    history::record(&mut tx, target_id, &before, &after, operator_id).await?;
+
+    // 归档 source 并记录合并去向
+    sqlx::query(
+        "UPDATE persons SET deleted_at = NOW(), deleted_by = $2, merged_into = $3, is_active = false
+         WHERE id = $1",
+    )
+    .bind(source_id)
+    .bind(operator_id)
+    .bind(target_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
 
-    Ok(())
+    Ok(PersonMergeResult {
+        target_id,
+        source_id,
+        student_parent,
+        attendances,
+        scores,
+        group_members,
+        permissions,
+        teacher_classes,
+    })
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -1117,6 +1733,7 @@ struct PersonWithRelations {
     occupation: Option<String>,
     class_name: Option<String>,
     department_name: Option<String>,
+    custom_fields: sqlx::types::Json<CustomFields>,
 }
 
 impl PersonWithRelations {
@@ -1139,6 +1756,8 @@ impl PersonWithRelations {
                 class_name: self.class_name,
                 enrollment_date: self.enrollment_date,
                 status: self.status.unwrap_or_else(|| "enrolled".to_string()),
+                guardians: Vec::new(),
+                custom_fields: self.custom_fields.0,
             }),
             "teacher" => PersonResponse::Teacher(TeacherResponse {
                 id: self.id,
@@ -1153,6 +1772,7 @@ impl PersonWithRelations {
                 classes: Vec::new(), // 暂时返回空数组，需要在get_person函数中填充
                 title: self.title,
                 hire_date: self.hire_date,
+                custom_fields: self.custom_fields.0,
             }),
             "parent" => PersonResponse::Parent(ParentResponse {
                 id: self.id,
@@ -1163,6 +1783,7 @@ impl PersonWithRelations {
                 email: self.email,
                 wechat_openid: self.wechat_openid,
                 occupation: self.occupation,
+                custom_fields: self.custom_fields.0,
             }),
             _ => PersonResponse::Teacher(TeacherResponse {
                 id: self.id,
@@ -1177,6 +1798,7 @@ impl PersonWithRelations {
                 classes: Vec::new(),
                 title: None,
                 hire_date: None,
+                custom_fields: self.custom_fields.0,
             }),
         }
     }
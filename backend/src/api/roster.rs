@@ -0,0 +1,342 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Local, NaiveDate};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::api::class::get_class_students_list;
+use crate::api::routes::AppState;
+use crate::core::auth::Claims;
+use crate::core::error::AppError;
+use crate::core::history;
+use crate::core::permission::PermissionManager;
+use crate::core::roster::{self as roster_core, Candidate, DEFAULT_COLS, MAX_SEATING_SIZE};
+use crate::models::person::PersonResponse;
+use crate::models::roster::{
+    Roster, RosterQuery, RosterStudent, Seat, SeatPlacement, SeatingArrange, SeatingChart, SeatingChartSave,
+    SeatingQuery,
+};
+
+// ========== 花名册 ==========
+
+// 班级花名册，format=html 时返回可打印页面
+pub async fn roster(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(class_id): Path<Uuid>,
+    Query(query): Query<RosterQuery>,
+) -> Result<Response, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_class_permission(user_id, "class.view.detail", class_id).await?;
+
+    let roster = build_roster(&pool, class_id, query.date).await?;
+    respond(query.format.as_deref(), &roster, roster_core::render_roster_html)
+}
+
+// ========== 座位表 ==========
+
+pub async fn get_seating(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(class_id): Path<Uuid>,
+    Query(query): Query<SeatingQuery>,
+) -> Result<Response, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_class_permission(user_id, "class.view.detail", class_id).await?;
+
+    let chart = load_seating(&pool, class_id).await?;
+    respond(query.format.as_deref(), &chart, roster_core::render_seating_html)
+}
+
+// 手动保存座位表（整体替换）
+pub async fn save_seating(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(class_id): Path<Uuid>,
+    Json(payload): Json<SeatingChartSave>,
+) -> Result<Json<SeatingChart>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_class_permission(user_id, "class.seating.update", class_id).await?;
+
+    let roster = build_roster(&pool, class_id, None).await?;
+    let students: HashSet<Uuid> = roster.students.iter().map(|s| s.person_id).collect();
+    write_seats(&pool, class_id, payload.rows, payload.cols, &payload.seats, &students, user_id).await?;
+
+    Ok(Json(load_seating(&pool, class_id).await?))
+}
+
+// 自动排座：按小组、男女交错或随机，覆盖原有安排
+pub async fn arrange_seating(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(class_id): Path<Uuid>,
+    Json(payload): Json<SeatingArrange>,
+) -> Result<Json<SeatingChart>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_class_permission(user_id, "class.seating.update", class_id).await?;
+
+    if !matches!(payload.mode.as_str(), "group" | "gender" | "random") {
+        return Err(AppError::InvalidInput("mode 只能为 group、gender 或 random".to_string()));
+    }
+
+    let roster = build_roster(&pool, class_id, None).await?;
+    if roster.students.is_empty() {
+        return Err(AppError::InvalidInput("班级没有学生".to_string()));
+    }
+
+    let existing: Option<(i16, i16)> =
+        sqlx::query_as("SELECT row_count, col_count FROM seating_charts WHERE class_id = $1")
+            .bind(class_id)
+            .fetch_optional(&pool)
+            .await?;
+    let count = roster.students.len() as i16;
+    let cols = payload.cols.or(existing.map(|(_, c)| c)).unwrap_or(DEFAULT_COLS);
+    if cols < 1 {
+        return Err(AppError::InvalidInput("列数必须大于 0".to_string()));
+    }
+    let rows = payload
+        .rows
+        .or(existing.map(|(r, _)| r))
+        .unwrap_or((count + cols - 1) / cols);
+
+    if (rows as i32) * (cols as i32) < count as i32 {
+        return Err(AppError::InvalidInput(format!(
+            "{} 排 × {} 列共 {} 个座位，不足 {} 名学生",
+            rows,
+            cols,
+            rows as i32 * cols as i32,
+            count
+        )));
+    }
+
+    // 男女交错和随机排座先把学生顺序打乱；按小组时组内按学号
+    let mut candidates: Vec<Candidate> = roster
+        .students
+        .iter()
+        .map(|s| Candidate { person_id: s.person_id, gender: s.gender, group: s.groups.first().cloned() })
+        .collect();
+    if payload.mode != "group" {
+        let ids: Vec<Uuid> = candidates.iter().map(|c| c.person_id).collect();
+        let shuffled: Vec<Uuid> = sqlx::query_scalar("SELECT id FROM unnest($1::uuid[]) AS id ORDER BY random()")
+            .bind(&ids)
+            .fetch_all(&pool)
+            .await?;
+        let position: HashMap<Uuid, usize> = shuffled.into_iter().enumerate().map(|(i, id)| (id, i)).collect();
+        candidates.sort_by_key(|c| position.get(&c.person_id).copied().unwrap_or(usize::MAX));
+    }
+
+    let placements = roster_core::arrange(candidates, cols, &payload.mode);
+    let students: HashSet<Uuid> = roster.students.iter().map(|s| s.person_id).collect();
+    write_seats(&pool, class_id, rows, cols, &placements, &students, user_id).await?;
+
+    Ok(Json(load_seating(&pool, class_id).await?))
+}
+
+// ========== 内部函数 ==========
+
+fn respond<T: Serialize>(format: Option<&str>, value: &T, render: fn(&T) -> String) -> Result<Response, AppError> {
+    match format.unwrap_or("json") {
+        "json" => Ok(Json(value).into_response()),
+        "html" => Ok(([(header::CONTENT_TYPE, "text/html; charset=utf-8")], render(value)).into_response()),
+        other => Err(AppError::InvalidInput(format!("不支持的格式: {}，可选 json 或 html", other))),
+    }
+}
+
+/// 班级花名册。指定过去的日期时，按人员变更历史找出当天在该班的学生；小组为当前小组
+async fn build_roster(pool: &PgPool, class_id: Uuid, date: Option<NaiveDate>) -> Result<Roster, AppError> {
+    let (class_name, grade, academic_year, teacher_name): (String, i16, Option<String>, Option<String>) =
+        sqlx::query_as(
+            "SELECT c.name, c.grade, c.academic_year, t.name
+             FROM classes c LEFT JOIN persons t ON t.id = c.teacher_id
+             WHERE c.id = $1",
+        )
+        .bind(class_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let today = Local::now().date_naive();
+    let date = date.unwrap_or(today);
+
+    // (id, 姓名, 性别, 学号, 状态)
+    let mut students: Vec<(Uuid, String, i16, String, String)> = if date >= today {
+        get_class_students_list(pool, class_id)
+            .await?
+            .into_iter()
+            .filter_map(|p| match p {
+                PersonResponse::Student(s) => Some((s.id, s.name, s.gender, s.student_no, s.status)),
+                _ => None,
+            })
+            .collect()
+    } else {
+        let members = history::class_members_on_date(pool, class_id, date).await?;
+        sqlx::query_as(
+            "SELECT p.id, p.name, p.gender, s.student_no, s.status
+             FROM persons p JOIN students s ON s.person_id = p.id
+             WHERE p.id = ANY($1) AND p.deleted_at IS NULL",
+        )
+        .bind(&members)
+        .fetch_all(pool)
+        .await?
+    };
+    students.sort_by(|a, b| a.3.cmp(&b.3).then_with(|| a.1.cmp(&b.1)));
+
+    let memberships: Vec<(Uuid, String)> = sqlx::query_as(
+        "SELECT gm.person_id, g.name FROM group_members gm
+         JOIN class_groups g ON g.id = gm.group_id
+         WHERE g.class_id = $1
+         ORDER BY g.name",
+    )
+    .bind(class_id)
+    .fetch_all(pool)
+    .await?;
+    let mut groups: HashMap<Uuid, Vec<String>> = HashMap::new();
+    for (person_id, name) in memberships {
+        groups.entry(person_id).or_default().push(name);
+    }
+
+    Ok(Roster {
+        class_id,
+        class_name,
+        grade,
+        academic_year,
+        teacher_name,
+        date,
+        students: students
+            .into_iter()
+            .enumerate()
+            .map(|(i, (person_id, name, gender, student_no, status))| RosterStudent {
+                seq: i + 1,
+                person_id,
+                name,
+                gender,
+                student_no,
+                status,
+                groups: groups.remove(&person_id).unwrap_or_default(),
+            })
+            .collect(),
+    })
+}
+
+/// 读取座位表；已离开班级的学生不再显示在座位上
+async fn load_seating(pool: &PgPool, class_id: Uuid) -> Result<SeatingChart, AppError> {
+    let roster = build_roster(pool, class_id, None).await?;
+
+    let chart: Option<(i16, i16, chrono::DateTime<chrono::Utc>)> =
+        sqlx::query_as("SELECT row_count, col_count, updated_at FROM seating_charts WHERE class_id = $1")
+            .bind(class_id)
+            .fetch_optional(pool)
+            .await?;
+    let placements: Vec<(i16, i16, Uuid)> = sqlx::query_as(
+        "SELECT seat_row, seat_col, person_id FROM seat_assignments
+         WHERE class_id = $1 ORDER BY seat_row, seat_col",
+    )
+    .bind(class_id)
+    .fetch_all(pool)
+    .await?;
+
+    let students: HashMap<Uuid, &RosterStudent> = roster.students.iter().map(|s| (s.person_id, s)).collect();
+    let seats: Vec<Seat> = placements
+        .into_iter()
+        .filter_map(|(row, col, person_id)| {
+            students.get(&person_id).map(|s| Seat {
+                row,
+                col,
+                person_id,
+                name: s.name.clone(),
+                gender: s.gender,
+                student_no: s.student_no.clone(),
+                groups: s.groups.clone(),
+            })
+        })
+        .collect();
+    let seated: HashSet<Uuid> = seats.iter().map(|s| s.person_id).collect();
+    let unseated = roster.students.iter().filter(|s| !seated.contains(&s.person_id)).cloned().collect();
+
+    Ok(SeatingChart {
+        class_id,
+        class_name: roster.class_name,
+        rows: chart.map(|c| c.0).unwrap_or(0),
+        cols: chart.map(|c| c.1).unwrap_or(0),
+        seats,
+        unseated,
+        updated_at: chart.map(|c| c.2),
+    })
+}
+
+async fn write_seats(
+    pool: &PgPool,
+    class_id: Uuid,
+    rows: i16,
+    cols: i16,
+    seats: &[SeatPlacement],
+    students: &HashSet<Uuid>,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    if !(1..=MAX_SEATING_SIZE).contains(&rows) || !(1..=MAX_SEATING_SIZE).contains(&cols) {
+        return Err(AppError::InvalidInput(format!("行数和列数必须在 1~{} 之间", MAX_SEATING_SIZE)));
+    }
+    let mut positions = HashSet::new();
+    let mut persons = HashSet::new();
+    for seat in seats {
+        if !(1..=rows).contains(&seat.row) || !(1..=cols).contains(&seat.col) {
+            return Err(AppError::InvalidInput(format!("座位 第{}排第{}列 超出范围", seat.row, seat.col)));
+        }
+        if !students.contains(&seat.person_id) {
+            return Err(AppError::InvalidInput(format!("学生 {} 不在该班级", seat.person_id)));
+        }
+        if !positions.insert((seat.row, seat.col)) {
+            return Err(AppError::InvalidInput(format!("座位 第{}排第{}列 重复安排", seat.row, seat.col)));
+        }
+        if !persons.insert(seat.person_id) {
+            return Err(AppError::InvalidInput(format!("学生 {} 被安排了多个座位", seat.person_id)));
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "INSERT INTO seating_charts (class_id, row_count, col_count, updated_by)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (class_id) DO UPDATE
+             SET row_count = EXCLUDED.row_count, col_count = EXCLUDED.col_count, updated_by = EXCLUDED.updated_by",
+    )
+    .bind(class_id)
+    .bind(rows)
+    .bind(cols)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM seat_assignments WHERE class_id = $1")
+        .bind(class_id)
+        .execute(&mut *tx)
+        .await?;
+    for seat in seats {
+        sqlx::query("INSERT INTO seat_assignments (class_id, seat_row, seat_col, person_id) VALUES ($1, $2, $3, $4)")
+            .bind(class_id)
+            .bind(seat.row)
+            .bind(seat.col)
+            .bind(seat.person_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
@@ -1,22 +1,40 @@
-use axum::{extract::State, Json, middleware, routing::delete, routing::get, routing::post, routing::put, Router};
+use axum::{extract::{DefaultBodyLimit, State}, Json, middleware, routing::delete, routing::get, routing::post, routing::put, Router};
 use sqlx::PgPool;
 
-use crate::api::{ai, ai_actions, ai_data, ai_enhanced, attendance, auth, class, department, debug, group, notice, permission, person, score};
+use crate::api::{ai, ai_actions, ai_data, ai_enhanced, attachment, attendance, attendance_alert, attendance_stats, auth, calendar, class, custom_field, department, debug, group, kiosk, leave, me, notice, permission, person, roster, score, score_appeal, score_catalog, score_ledger, score_stats, search, subject, timetable};
 use crate::core::middleware::auth_middleware;
 use crate::core::plugin::PluginManager;
+use crate::ws::{handler::ws_handler, hub::Hub};
 
 // 应用状态
 #[derive(Clone)]
 pub struct AppState {
     pub pool: Option<PgPool>,
     pub plugin_manager: PluginManager,
+    // WebSocket 推送
+    pub hub: Hub,
 }
 
+// multipart 中文件以外的字段和分隔符所占的余量
+const UPLOAD_BODY_OVERHEAD: usize = 64 * 1024;
+
 pub fn create_router(pool: Option<PgPool>, plugin_manager: PluginManager) -> Router {
     let state = AppState {
         pool,
         plugin_manager,
+        hub: Hub::new(),
     };
+    let config = crate::core::config::load_config().ok();
+    let ws_path = config
+        .as_ref()
+        .map(|config| config.ws_path.clone())
+        .unwrap_or_else(|| "/ws".to_string());
+    // 上传请求体上限：文件上限加上 multipart 其他字段和分隔符的余量
+    let upload_body_limit = config
+        .as_ref()
+        .map(|config| config.upload_max_bytes)
+        .unwrap_or(10 * 1024 * 1024)
+        + UPLOAD_BODY_OVERHEAD;
 
 
 
@@ -38,11 +56,28 @@ pub fn create_router(pool: Option<PgPool>, plugin_manager: PluginManager) -> Rou
         .route("/api/classes/:id", get(class::get))
         .route("/api/classes/:id/students", get(class::get_class_students))
         .route("/api/classes/:id/teachers", get(class::get_class_teachers))
+        // 一体机设备端（设备凭据认证）
+        .route("/api/kiosk/token", get(kiosk::token))
         .route("/api/departments", get(department::list))
+        .route("/api/departments/tree", get(department::tree))
         .route("/api/departments/:id", get(department::get))
+        .route("/api/departments/:id/ancestors", get(department::ancestors))
+        .route("/api/departments/:id/descendants", get(department::descendants))
         .route("/api/attendances", get(attendance::list))
+        .route("/api/attendance-windows", get(attendance::list_windows))
         .route("/api/scores", get(score::list))
         .route("/api/notices", get(notice::list))
+        .route("/api/subjects", get(subject::list))
+        .route("/api/teaching-assignments", get(subject::list_assignments))
+        .route("/api/periods", get(timetable::list_periods))
+        .route("/api/timetable/class/:class_id", get(timetable::class_timetable))
+        .route("/api/timetable/teacher/:teacher_id", get(timetable::teacher_timetable))
+        // 校历
+        .route("/api/terms", get(calendar::list_terms))
+        .route("/api/calendar", get(calendar::list_days))
+        .route("/api/calendar/day", get(calendar::get_day))
+        // 统一检索（名称 / 拼音 / 首字母）
+        .route("/api/search", get(search::search))
         .route("/api/permission/teacher/classes", get(person::get_teacher_classes))
         // 小组管理路由（公开查看）
         .route("/api/groups", get(group::list_all))
@@ -50,28 +85,141 @@ pub fn create_router(pool: Option<PgPool>, plugin_manager: PluginManager) -> Rou
         .route("/api/groups/:id", get(group::get))
         .route("/api/groups/:id/members", get(group::get_members))
         .route("/api/groups/:id/score-records", get(group::get_score_records))
-        // WebSocket路由
-        .route("/ws", get(crate::ws::handler::ws_handler));
+        // WebSocket路由（令牌通过查询参数认证）
+        .route(&ws_path, get(ws_handler));
 
     // 需要认证的路由
     let protected_routes = Router::new()
         .route("/api/persons", post(person::create))
         .route("/api/persons/:id", put(person::update))
         .route("/api/persons/:id", delete(person::delete))
+        .route("/api/persons/:id/restore", post(person::restore))
+        .route("/api/persons/:id/purge", delete(person::purge))
+        .route("/api/persons/duplicates", get(person::duplicates))
+        .route("/api/persons/:id/merge", post(person::merge))
+        .route("/api/persons/:id/guardians", post(person::link_guardian))
+        .route("/api/persons/:id/guardians/:parent_id", delete(person::unlink_guardian))
+        .route("/api/persons/:id/history", get(person::get_history))
+        .route("/api/persons/:id/class-at", get(person::class_at))
+        .route("/api/me/children", get(person::my_children))
+        .route("/api/me", get(me::profile))
+        .route("/api/me/class", get(me::my_class))
+        .route("/api/me/groups", get(me::my_groups))
+        .route("/api/me/attendance", get(me::my_attendance))
+        .route("/api/me/scores", get(me::my_scores))
+        .route("/api/me/notices", get(me::my_notices))
+        .route("/api/me/children/:student_id", get(me::child_profile))
+        .route("/api/me/children/:student_id/class", get(me::child_class))
+        .route("/api/me/children/:student_id/groups", get(me::child_groups))
+        .route("/api/me/children/:student_id/attendance", get(me::child_attendance))
+        .route("/api/me/children/:student_id/scores", get(me::child_scores))
+        .route("/api/me/children/:student_id/notices", get(me::child_notices))
+        // 头像与附件（请求体按上传上限放宽，文件大小在处理函数中再按配置检查）
+        .route("/api/persons/:id/avatar", post(attachment::upload_avatar).layer(DefaultBodyLimit::max(upload_body_limit)))
+        .route("/api/persons/:id/attachments", post(attachment::upload_document).layer(DefaultBodyLimit::max(upload_body_limit)))
+        .route("/api/persons/:id/attachments", get(attachment::list))
+        .route("/api/attachments/:id/download", get(attachment::download))
+        .route("/api/attachments/:id/thumbnail", get(attachment::thumbnail))
+        .route("/api/attachments/:id", delete(attachment::delete))
         .route("/api/classes", post(class::create))
         .route("/api/classes/:id", put(class::update))
         .route("/api/classes/:id", delete(class::delete))
+        .route("/api/classes/:id/restore", post(class::restore))
+        .route("/api/classes/:id/purge", delete(class::purge))
+        .route("/api/classes/:id/merge", post(class::merge))
+        .route("/api/classes/:id/split", post(class::split))
+        .route("/api/classes/:id/roster", get(roster::roster))
+        .route("/api/classes/:id/seating", get(roster::get_seating))
+        .route("/api/classes/:id/seating", put(roster::save_seating))
+        .route("/api/classes/:id/seating/arrange", post(roster::arrange_seating))
         .route("/api/departments", post(department::create))
         .route("/api/departments/:id", put(department::update))
         .route("/api/departments/:id", delete(department::delete))
+        .route("/api/departments/:id/restore", post(department::restore))
+        .route("/api/departments/:id/move", post(department::move_subtree))
+        .route("/api/departments/:id/head", put(department::set_head))
+        .route("/api/departments/:id/purge", delete(department::purge))
+        // 学科与任课安排
+        .route("/api/subjects", post(subject::create))
+        .route("/api/subjects/:id", put(subject::update))
+        .route("/api/subjects/:id", delete(subject::delete))
+        .route("/api/teaching-assignments", post(subject::create_assignment))
+        .route("/api/teaching-assignments/:id", delete(subject::delete_assignment))
+        // 作息节次与课程表
+        .route("/api/periods", post(timetable::create_period))
+        .route("/api/periods/:id", put(timetable::update_period))
+        .route("/api/periods/:id", delete(timetable::delete_period))
+        .route("/api/timetable", post(timetable::create_entry))
+        .route("/api/timetable/:id", put(timetable::update_entry))
+        .route("/api/timetable/:id", delete(timetable::delete_entry))
+        // 学期与节假日
+        .route("/api/terms", post(calendar::create_term))
+        .route("/api/terms/:id", put(calendar::update_term))
+        .route("/api/terms/:id", delete(calendar::delete_term))
+        .route("/api/calendar/days", post(calendar::set_days))
+        .route("/api/calendar/days/:date", delete(calendar::delete_day))
+        // 自定义字段定义
+        .route("/api/custom-fields", get(custom_field::list))
+        .route("/api/custom-fields", post(custom_field::create))
+        .route("/api/custom-fields/:id", put(custom_field::update))
+        .route("/api/custom-fields/:id", delete(custom_field::delete))
         .route("/api/attendances", post(attendance::create))
+        .route("/api/attendances/roll-call", get(attendance::get_roll_call))
+        .route("/api/attendances/roll-call", post(attendance::roll_call))
         .route("/api/attendances/:id", get(attendance::get))
         .route("/api/attendances/:id", put(attendance::update))
         .route("/api/attendances/:id", delete(attendance::delete))
+        .route("/api/attendances/:id/corrections", get(attendance::list_corrections))
+        .route("/api/attendance-windows", put(attendance::set_window))
+        .route("/api/attendance-windows/:id", delete(attendance::delete_window))
+        .route("/api/attendance-stats", get(attendance_stats::stats))
+        .route("/api/attendance-stats/trend", get(attendance_stats::trend))
+        .route("/api/classes/:id/attendance-report", get(attendance_stats::class_report))
+        .route("/api/attendance-alert-rules", get(attendance_alert::list_rules))
+        .route("/api/attendance-alert-rules", post(attendance_alert::create_rule))
+        .route("/api/attendance-alert-rules/:id", put(attendance_alert::update_rule))
+        .route("/api/attendance-alert-rules/:id", delete(attendance_alert::delete_rule))
+        .route("/api/attendance-alerts", get(attendance_alert::list_alerts))
+        .route("/api/grade-leaders", get(attendance_alert::list_grade_leaders))
+        .route("/api/grade-leaders/:grade", put(attendance_alert::set_grade_leaders))
+        .route("/api/leave-requests", get(leave::list))
+        .route("/api/leave-requests", post(leave::create))
+        .route("/api/leave-requests/:id", get(leave::get))
+        .route("/api/leave-requests/:id/approve", post(leave::approve))
+        .route("/api/leave-requests/:id/reject", post(leave::reject))
+        .route("/api/leave-requests/:id/cancel", post(leave::cancel))
+        .route("/api/leave-requests/:id/revoke", post(leave::revoke))
+        .route("/api/kiosk/devices", get(kiosk::list_devices))
+        .route("/api/kiosk/devices", post(kiosk::create_device))
+        .route("/api/kiosk/devices/:id", put(kiosk::update_device))
+        .route("/api/kiosk/devices/:id/secret", post(kiosk::reset_secret))
+        .route("/api/kiosk/events", get(kiosk::list_events))
+        .route("/api/kiosk/check-in", post(kiosk::check_in))
         .route("/api/scores", post(score::create))
         .route("/api/scores/:id", get(score::get))
         .route("/api/scores/:id", put(score::update))
         .route("/api/scores/:id", delete(score::delete))
+        .route("/api/score-categories", get(score_catalog::list_categories))
+        .route("/api/score-categories", post(score_catalog::create_category))
+        .route("/api/score-categories/:id", put(score_catalog::update_category))
+        .route("/api/score-categories/:id", delete(score_catalog::delete_category))
+        .route("/api/score-categories/:id/reasons", post(score_catalog::create_reason))
+        .route("/api/score-reasons/:id", put(score_catalog::update_reason))
+        .route("/api/score-reasons/:id", delete(score_catalog::delete_reason))
+        .route("/api/score-limits", get(score_catalog::list_limits))
+        .route("/api/score-limits", put(score_catalog::set_limits))
+        .route("/api/score-appeals", get(score_appeal::list))
+        .route("/api/score-appeals", post(score_appeal::create))
+        .route("/api/score-appeals/:id", get(score_appeal::get))
+        .route("/api/score-appeals/:id/messages", post(score_appeal::add_comment))
+        .route("/api/score-appeals/:id/reviewer", put(score_appeal::assign))
+        .route("/api/score-appeals/:id/accept", post(score_appeal::accept))
+        .route("/api/score-appeals/:id/reject", post(score_appeal::reject))
+        .route("/api/score-appeals/:id/withdraw", post(score_appeal::withdraw))
+        .route("/api/score-reconciliation", get(score_ledger::reconciliation))
+        .route("/api/score-reconciliation", post(score_ledger::reconcile))
+        .route("/api/score-leaderboard", get(score_stats::leaderboard))
+        .route("/api/persons/:id/score-summary", get(score_stats::summary))
         .route("/api/notices", post(notice::create))
         .route("/api/notices/:id", get(notice::get))
         .route("/api/notices/:id", put(notice::update))
@@ -9,6 +9,8 @@ use crate::api::routes::AppState;
 use crate::core::error::AppError;
 use crate::core::auth::Claims;
 use crate::core::permission::PermissionManager;
+use crate::core::score as rules;
+use crate::core::score_ledger as ledger;
 
 #[derive(Debug, Deserialize)]
 pub struct ScoreQuery {
@@ -17,15 +19,18 @@ pub struct ScoreQuery {
     pub person_id: Option<Uuid>,
     pub group_id: Option<Uuid>,
     pub score_type: Option<String>,
+    pub category_id: Option<Uuid>,
 }
 
+// 原因从积分目录中选择（reason_id），也可以填写原因名称或同义说法；不填分值时使用预设分值
 #[derive(Debug, Deserialize)]
 pub struct CreateScoreRequest {
     pub person_id: Uuid,
     pub group_id: Option<Uuid>,
     pub score_type: String,
-    pub value: i32,
-    pub reason: String,
+    pub value: Option<i32>,
+    pub reason_id: Option<Uuid>,
+    pub reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -42,8 +47,14 @@ pub struct ScoreResponse {
     pub group_id: Option<Uuid>,
     pub group_name: Option<String>,
     pub score_type: String,
+    pub category_id: Option<Uuid>,
+    pub category_name: Option<String>,
+    pub reason_id: Option<Uuid>,
     pub value: i32,
     pub reason: String,
+    // 冲销记录指向被冲销的原记录；原记录被冲销后 reversed_by 为冲销记录
+    pub reverses_id: Option<Uuid>,
+    pub reversed_by: Option<Uuid>,
     pub created_at: String,
 }
 
@@ -84,6 +95,11 @@ pub async fn list(
         param_index += 1;
     }
     
+    if query.category_id.is_some() {
+        conditions.push(format!("s.category_id = ${}", param_index));
+        param_index += 1;
+    }
+    
     let where_clause = conditions.join(" AND ");
     
     // 查询总数
@@ -99,6 +115,9 @@ pub async fn list(
     if let Some(ref score_type) = query.score_type {
         count_query = count_query.bind(score_type);
     }
+    if let Some(ref category_id) = query.category_id {
+        count_query = count_query.bind(category_id);
+    }
     
     let total: i64 = count_query
         .fetch_one(&pool)
@@ -107,14 +126,8 @@ pub async fn list(
     
     // 查询数据
     let sql = format!(
-        "SELECT s.id, s.person_id, p.name as person_name, s.group_id, cg.name as group_name, 
-         s.score_type, s.value, s.reason, s.created_at 
-         FROM scores s 
-         JOIN persons p ON s.person_id = p.id 
-         LEFT JOIN class_groups cg ON s.group_id = cg.id 
-         WHERE {} 
-         ORDER BY s.created_at DESC 
-         LIMIT ${} OFFSET ${}",
+        "{} WHERE {} ORDER BY s.created_at DESC LIMIT ${} OFFSET ${}",
+        SCORE_SELECT,
         where_clause,
         param_index,
         param_index + 1
@@ -131,6 +144,9 @@ pub async fn list(
     if let Some(ref score_type) = query.score_type {
         data_query = data_query.bind(score_type);
     }
+    if let Some(ref category_id) = query.category_id {
+        data_query = data_query.bind(category_id);
+    }
     
     let scores = data_query
         .bind(limit)
@@ -149,16 +165,44 @@ pub async fn list(
     }))
 }
 
+// 修改积分时读取的原记录，default_value 为原记录对应目录原因的预设分值
+#[derive(sqlx::FromRow)]
+struct ScoreOriginal {
+    person_id: Uuid,
+    group_id: Option<Uuid>,
+    score_type: String,
+    value: i32,
+    reason: String,
+    category_id: Option<Uuid>,
+    reason_id: Option<Uuid>,
+    default_value: Option<i32>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// 读取 ScoreRow 的公共 SELECT，调用方在后面追加 WHERE、ORDER BY 等子句
+pub const SCORE_SELECT: &str = "SELECT s.id, s.person_id, p.name as person_name, s.group_id, cg.name as group_name,
+         s.score_type, s.category_id, sc.name as category_name, s.reason_id, s.value, s.reason, s.reverses_id,
+         (SELECT r.id FROM scores r WHERE r.reverses_id = s.id) as reversed_by, s.created_at
+         FROM scores s
+         JOIN persons p ON s.person_id = p.id
+         LEFT JOIN class_groups cg ON s.group_id = cg.id
+         LEFT JOIN score_categories sc ON s.category_id = sc.id";
+
 #[derive(sqlx::FromRow)]
-struct ScoreRow {
+pub struct ScoreRow {
     id: Uuid,
     person_id: Uuid,
     person_name: String,
     group_id: Option<Uuid>,
     group_name: Option<String>,
     score_type: String,
+    category_id: Option<Uuid>,
+    category_name: Option<String>,
+    reason_id: Option<Uuid>,
     value: i32,
     reason: String,
+    reverses_id: Option<Uuid>,
+    reversed_by: Option<Uuid>,
     created_at: chrono::DateTime<chrono::Utc>,
 }
 
@@ -171,8 +215,13 @@ impl From<ScoreRow> for ScoreResponse {
             group_id: row.group_id,
             group_name: row.group_name,
             score_type: row.score_type,
+            category_id: row.category_id,
+            category_name: row.category_name,
+            reason_id: row.reason_id,
             value: row.value,
             reason: row.reason,
+            reverses_id: row.reverses_id,
+            reversed_by: row.reversed_by,
             created_at: row.created_at.to_rfc3339(),
         }
     }
@@ -190,20 +239,26 @@ pub async fn create(
     let manager = PermissionManager::new(pool.clone());
     manager.require_permission(user_id, "score.create").await?;
     
+    // 按积分目录校验原因和分值
+    let entry = rules::resolve_entry(&pool, user_id, &req.score_type, req.reason_id, req.reason.as_deref(), req.value).await?;
+    
     // 插入数据
     let row = sqlx::query_as::<_, ScoreRow>(
-        "INSERT INTO scores (person_id, group_id, score_type, value, reason, created_by) 
-         VALUES ($1, $2, $3, $4, $5, $6) 
+        "INSERT INTO scores (person_id, group_id, score_type, value, reason, created_by, category_id, reason_id) 
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8) 
          RETURNING id, person_id, (SELECT name FROM persons WHERE id = $1) as person_name, 
          group_id, (SELECT name FROM class_groups WHERE id = $2) as group_name, 
-         score_type, value, reason, created_at"
+         score_type, category_id, (SELECT name FROM score_categories WHERE id = $7) as category_name, 
+         reason_id, value, reason, reverses_id, NULL::UUID as reversed_by, created_at"
     )
     .bind(req.person_id)
     .bind(req.group_id)
     .bind(&req.score_type)
-    .bind(req.value)
-    .bind(&req.reason)
+    .bind(entry.value)
+    .bind(&entry.reason)
     .bind(user_id)
+    .bind(entry.category_id)
+    .bind(entry.reason_id)
     .fetch_one(&pool)
     .await
     .map_err(|e| AppError::Database(e))?;
@@ -217,19 +272,13 @@ pub async fn get(
 ) -> Result<Json<ScoreResponse>, AppError> {
     let pool = state.pool.ok_or_else(|| AppError::Internal)?;
     
-    let row = sqlx::query_as::<_, ScoreRow>(
-        "SELECT s.id, s.person_id, p.name as person_name, s.group_id, cg.name as group_name, 
-         s.score_type, s.value, s.reason, s.created_at 
-         FROM scores s 
-         JOIN persons p ON s.person_id = p.id 
-         LEFT JOIN class_groups cg ON s.group_id = cg.id 
-         WHERE s.id = $1"
-    )
-    .bind(id)
-    .fetch_optional(&pool)
-    .await
-    .map_err(|e| AppError::Database(e))?
-    .ok_or(AppError::NotFound)?;
+    let sql = format!("{} WHERE s.id = $1", SCORE_SELECT);
+    let row = sqlx::query_as::<_, ScoreRow>(&sql)
+        .bind(id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| AppError::Database(e))?
+        .ok_or(AppError::NotFound)?;
     
     Ok(Json(row.into()))
 }
@@ -247,49 +296,60 @@ pub async fn update(
     let manager = PermissionManager::new(pool.clone());
     manager.require_permission(user_id, "score.update").await?;
     
-    // 构建更新字段
-    let mut updates = vec![];
-    let mut param_index = 1;
-    
-    if req.value.is_some() {
-        updates.push(format!("value = ${}", param_index));
-        param_index += 1;
-    }
-    
-    if req.reason.is_some() {
-        updates.push(format!("reason = ${}", param_index));
-        param_index += 1;
-    }
-    
-    if updates.is_empty() {
+    if req.value.is_none() && req.reason.is_none() {
         return Err(AppError::InvalidInput("没有要更新的字段".to_string()));
     }
     
-    let sql = format!(
-        "UPDATE scores SET {} WHERE id = ${} 
-         RETURNING id, person_id, (SELECT name FROM persons WHERE id = scores.person_id) as person_name, 
-         group_id, (SELECT name FROM class_groups WHERE id = scores.group_id) as group_name, 
-         score_type, value, reason, created_at",
-        updates.join(", "),
-        param_index
-    );
-    
-    let mut query = sqlx::query_as::<_, ScoreRow>(&sql);
-    
-    if let Some(value) = req.value {
-        query = query.bind(value);
-    }
+    // 未修改的部分沿用原记录；修改了原因时按积分目录重新校验，
+    // 原因不变时（包括目录建立前的自由文本原因）只检查分值和单次上限
+    let original = sqlx::query_as::<_, ScoreOriginal>(
+        "SELECT s.person_id, s.group_id, s.score_type, s.value, s.reason, s.category_id, s.reason_id, r.default_value, s.created_at
+         FROM scores s LEFT JOIN score_reasons r ON r.id = s.reason_id
+         WHERE s.id = $1"
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| AppError::Database(e))?
+    .ok_or(AppError::NotFound)?;
     
-    if let Some(reason) = req.reason {
-        query = query.bind(reason);
-    }
+    let value = req.value.unwrap_or(original.value);
+    let (category_id, reason_id, reason) = match req.reason {
+        Some(new_reason) if new_reason.trim() != original.reason.trim() => {
+            let entry = rules::resolve_entry(&pool, user_id, &original.score_type, None, Some(&new_reason), Some(value)).await?;
+            (Some(entry.category_id), Some(entry.reason_id), entry.reason)
+        }
+        _ => {
+            rules::check_entry_value(&pool, user_id, value, original.default_value.unwrap_or(0)).await?;
+            (original.category_id, original.reason_id, original.reason)
+        }
+    };
     
-    let row = query
-        .bind(id)
-        .fetch_optional(&pool)
-        .await
-        .map_err(|e| AppError::Database(e))?
-        .ok_or(AppError::NotFound)?;
+    // 积分流水不改原记录：冲销原记录后追加一条更正后的记录，沿用原记录的时间，
+    // 更正计入原来那一天的汇总，历史排行榜不受影响
+    let mut tx = pool.begin().await?;
+    ledger::reverse(&mut tx, id, user_id).await?;
+    let row = sqlx::query_as::<_, ScoreRow>(
+        "INSERT INTO scores (person_id, group_id, score_type, value, reason, created_by, category_id, reason_id, created_at) 
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) 
+         RETURNING id, person_id, (SELECT name FROM persons WHERE id = $1) as person_name, 
+         group_id, (SELECT name FROM class_groups WHERE id = $2) as group_name, 
+         score_type, category_id, (SELECT name FROM score_categories WHERE id = $7) as category_name, 
+         reason_id, value, reason, reverses_id, NULL::UUID as reversed_by, created_at"
+    )
+    .bind(original.person_id)
+    .bind(original.group_id)
+    .bind(&original.score_type)
+    .bind(value)
+    .bind(&reason)
+    .bind(user_id)
+    .bind(category_id)
+    .bind(reason_id)
+    .bind(original.created_at)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| AppError::Database(e))?;
+    tx.commit().await?;
     
     Ok(Json(row.into()))
 }
@@ -306,15 +366,10 @@ pub async fn delete(
     let manager = PermissionManager::new(pool.clone());
     manager.require_permission(user_id, "score.delete").await?;
     
-    let result = sqlx::query("DELETE FROM scores WHERE id = $1")
-        .bind(id)
-        .execute(&pool)
-        .await
-        .map_err(|e| AppError::Database(e))?;
-    
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound);
-    }
+    // 删除即追加冲销记录，原记录保留在流水中
+    let mut tx = pool.begin().await?;
+    ledger::reverse(&mut tx, id, user_id).await?;
+    tx.commit().await?;
     
     Ok(axum::http::StatusCode::NO_CONTENT)
 }
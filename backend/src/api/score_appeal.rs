@@ -0,0 +1,541 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    Json,
+};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::attachment;
+use crate::api::routes::AppState;
+use crate::core::auth::Claims;
+use crate::core::error::AppError;
+use crate::core::permission::check_user_permission;
+use crate::core::score_appeal as rules;
+use crate::core::score_ledger as ledger;
+use crate::models::score_appeal::{
+    ScoreAppeal, ScoreAppealAssign, ScoreAppealCreate, ScoreAppealDecision, ScoreAppealDetail, ScoreAppealMessage,
+    ScoreAppealMessageCreate, ScoreAppealQuery,
+};
+
+#[derive(Debug, Serialize)]
+pub struct ListResponse<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
+}
+
+const APPEAL_FROM: &str = "FROM score_appeals a
+    JOIN persons st ON st.id = a.student_id
+    LEFT JOIN scores sc ON sc.id = a.score_id
+    LEFT JOIN group_score_records gr ON gr.id = a.group_score_record_id
+    LEFT JOIN class_groups g ON g.id = COALESCE(sc.group_id, gr.group_id)
+    LEFT JOIN classes c ON c.id = a.class_id
+    LEFT JOIN persons rv ON rv.id = a.reviewer_id
+    LEFT JOIN persons sb ON sb.id = a.submitted_by";
+
+const APPEAL_COLUMNS: &str = "SELECT a.id, a.target_type, a.score_id, a.group_score_record_id,
+        g.id AS group_id, g.name AS group_name, a.student_id, st.name AS student_name,
+        a.class_id, c.name AS class_name, a.original_value, a.original_reason,
+        COALESCE(sc.created_by, gr.created_by) AS scored_by, a.evidence, a.attachment_id, a.requested_value,
+        a.status, a.reviewer_id, rv.name AS reviewer_name, a.submitted_by, sb.name AS submitted_by_name,
+        a.adjusted_value, a.compensation_score_id, a.compensation_group_record_id, a.resolved_by, a.resolved_at,
+        a.created_at, a.updated_at";
+
+// 申诉列表：有 score.appeal.review 权限的可查看全部；其他人只能看到与自己相关的申诉
+// （学生本人、家长、提交人、审核人、原扣分录入人、班主任）
+pub async fn list(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<ScoreAppealQuery>,
+) -> Result<Json<ListResponse<ScoreAppeal>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let view_all = check_user_permission(&pool, user_id, "score.appeal.review").await;
+
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = (page - 1) * limit;
+
+    let filter = "WHERE ($1 OR a.student_id = $2 OR a.submitted_by = $2 OR a.reviewer_id = $2
+               OR sc.created_by = $2 OR gr.created_by = $2
+               OR a.student_id IN (SELECT student_id FROM student_parent WHERE parent_id = $2)
+               OR a.class_id IN (SELECT class_id FROM teacher_class WHERE teacher_id = $2 AND is_main_teacher))
+          AND ($3::varchar IS NULL OR a.status = $3)
+          AND ($4::uuid IS NULL OR a.student_id = $4)
+          AND (NOT $5 OR a.reviewer_id = $2)";
+
+    let items = sqlx::query_as::<_, ScoreAppeal>(&format!(
+        "{} {} {} ORDER BY a.created_at DESC LIMIT $6 OFFSET $7",
+        APPEAL_COLUMNS, APPEAL_FROM, filter
+    ))
+    .bind(view_all)
+    .bind(user_id)
+    .bind(&query.status)
+    .bind(query.student_id)
+    .bind(query.assigned_to_me.unwrap_or(false))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&pool)
+    .await?;
+
+    let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) {} {}", APPEAL_FROM, filter))
+        .bind(view_all)
+        .bind(user_id)
+        .bind(&query.status)
+        .bind(query.student_id)
+        .bind(query.assigned_to_me.unwrap_or(false))
+        .fetch_one(&pool)
+        .await?;
+
+    Ok(Json(ListResponse { items, total, page, limit }))
+}
+
+pub async fn get(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ScoreAppealDetail>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let appeal = load_appeal(&pool, id).await?;
+    require_view(&pool, user_id, &appeal).await?;
+
+    Ok(Json(detail(&pool, appeal).await?))
+}
+
+// 提交申诉：学生本人或家长，针对一条个人扣分或所在小组的扣分；默认由班主任审核
+pub async fn create(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<ScoreAppealCreate>,
+) -> Result<Json<ScoreAppealDetail>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    if payload.evidence.trim().is_empty() {
+        return Err(AppError::InvalidInput("请填写申诉理由".to_string()));
+    }
+
+    // (类型, 学生, 分值, 原因, 小组所在班级)
+    let (target_type, student_id, original_value, original_reason, group_class_id) =
+        match (payload.score_id, payload.group_score_record_id) {
+            (Some(score_id), None) => {
+                let (person_id, value, reason, reverses_id, reversed_by): (Uuid, i32, String, Option<Uuid>, Option<Uuid>) =
+                    sqlx::query_as(
+                        "SELECT person_id, value, reason, reverses_id, (SELECT r.id FROM scores r WHERE r.reverses_id = s.id)
+                         FROM scores s WHERE s.id = $1",
+                    )
+                    .bind(score_id)
+                    .fetch_optional(&pool)
+                    .await?
+                    .ok_or(AppError::NotFound)?;
+                ledger::check_reversible(reverses_id, reversed_by)?;
+                if payload.student_id.is_some_and(|s| s != person_id) {
+                    return Err(AppError::InvalidInput("该积分记录不属于指定学生".to_string()));
+                }
+                ("score", person_id, value, reason, None)
+            }
+            (None, Some(record_id)) => {
+                let (group_id, class_id, value, reason): (Uuid, Option<Uuid>, i32, String) = sqlx::query_as(
+                    "SELECT group_id, class_id, score_change, reason FROM group_score_records WHERE id = $1",
+                )
+                .bind(record_id)
+                .fetch_optional(&pool)
+                .await?
+                .ok_or(AppError::NotFound)?;
+                let student_id = payload.student_id.unwrap_or(user_id);
+                let member: bool = sqlx::query_scalar(
+                    "SELECT EXISTS(SELECT 1 FROM group_members WHERE group_id = $1 AND person_id = $2)",
+                )
+                .bind(group_id)
+                .bind(student_id)
+                .fetch_one(&pool)
+                .await?;
+                if !member {
+                    return Err(AppError::InvalidInput("只有小组成员可以对小组扣分申诉".to_string()));
+                }
+                ("group_score", student_id, value, reason, class_id)
+            }
+            _ => return Err(AppError::InvalidInput("请指定一条个人积分或小组积分记录".to_string())),
+        };
+
+    if student_id != user_id && !is_parent_of(&pool, user_id, student_id).await? {
+        return Err(AppError::Auth("只能为自己或自己的孩子申诉".to_string()));
+    }
+    // 校验是扣分记录，以及期望分值的范围
+    rules::compensation(original_value, payload.requested_value)?;
+
+    if let Some(attachment_id) = payload.attachment_id {
+        attachment::require_owned(&pool, attachment_id, student_id).await?;
+    }
+
+    let class_id: Option<Uuid> = match group_class_id {
+        Some(class_id) => Some(class_id),
+        None => sqlx::query_scalar("SELECT class_id FROM students WHERE person_id = $1")
+            .bind(student_id)
+            .fetch_optional(&pool)
+            .await?
+            .flatten(),
+    };
+    let reviewer_id = match class_id {
+        Some(class_id) => head_teacher_of(&pool, class_id).await?,
+        None => None,
+    };
+
+    let mut tx = pool.begin().await?;
+    rules::ensure_not_compensated(&mut tx, payload.score_id, payload.group_score_record_id, None).await?;
+    let id: Uuid = sqlx::query_scalar(
+        "INSERT INTO score_appeals
+             (target_type, score_id, group_score_record_id, student_id, class_id, original_value, original_reason,
+              evidence, attachment_id, requested_value, reviewer_id, submitted_by)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+         RETURNING id",
+    )
+    .bind(target_type)
+    .bind(payload.score_id)
+    .bind(payload.group_score_record_id)
+    .bind(student_id)
+    .bind(class_id)
+    .bind(original_value)
+    .bind(&original_reason)
+    .bind(payload.evidence.trim())
+    .bind(payload.attachment_id)
+    .bind(payload.requested_value)
+    .bind(reviewer_id)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::InvalidInput("该记录已有处理中或已同意的申诉".to_string())
+        }
+        e => AppError::Database(e),
+    })?;
+    if let Some(reviewer_id) = reviewer_id {
+        add_message(&mut tx, id, "assigned", None, &assigned_text(&pool, reviewer_id).await?, None).await?;
+    }
+    tx.commit().await?;
+
+    let appeal = load_appeal(&pool, id).await?;
+    Ok(Json(detail(&pool, appeal).await?))
+}
+
+// 补充说明或回复：所有相关人员
+pub async fn add_comment(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ScoreAppealMessageCreate>,
+) -> Result<Json<ScoreAppealDetail>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let appeal = load_appeal(&pool, id).await?;
+    require_view(&pool, user_id, &appeal).await?;
+
+    if payload.content.trim().is_empty() {
+        return Err(AppError::InvalidInput("内容不能为空".to_string()));
+    }
+    if let Some(attachment_id) = payload.attachment_id {
+        attachment::require_owned(&pool, attachment_id, appeal.student_id).await?;
+    }
+
+    let mut tx = pool.begin().await?;
+    add_message(&mut tx, id, "comment", Some(user_id), payload.content.trim(), payload.attachment_id).await?;
+    tx.commit().await?;
+
+    Ok(Json(detail(&pool, appeal).await?))
+}
+
+// 指派审核人（需要 score.appeal.review 权限）
+pub async fn assign(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ScoreAppealAssign>,
+) -> Result<Json<ScoreAppealDetail>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    if !check_user_permission(&pool, user_id, "score.appeal.review").await {
+        return Err(AppError::Auth("没有权限执行此操作: score.appeal.review".to_string()));
+    }
+
+    let teacher: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM teachers t JOIN persons p ON p.id = t.person_id
+                       WHERE t.person_id = $1 AND p.deleted_at IS NULL)",
+    )
+    .bind(payload.reviewer_id)
+    .fetch_one(&pool)
+    .await?;
+    if !teacher {
+        return Err(AppError::InvalidInput("审核人必须是教职工".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+    let updated = sqlx::query("UPDATE score_appeals SET reviewer_id = $2 WHERE id = $1 AND status = 'pending'")
+        .bind(id)
+        .bind(payload.reviewer_id)
+        .execute(&mut *tx)
+        .await?;
+    if updated.rows_affected() == 0 {
+        load_appeal(&pool, id).await?;
+        return Err(AppError::InvalidInput("该申诉已处理".to_string()));
+    }
+    add_message(&mut tx, id, "assigned", Some(user_id), &assigned_text(&pool, payload.reviewer_id).await?, None)
+        .await?;
+    tx.commit().await?;
+
+    let appeal = load_appeal(&pool, id).await?;
+    Ok(Json(detail(&pool, appeal).await?))
+}
+
+// 同意：生成更正记录，撤销或调整原扣分
+pub async fn accept(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ScoreAppealDecision>,
+) -> Result<Json<ScoreAppealDetail>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    decide(&pool, user_id, id, true, payload).await?;
+
+    let appeal = load_appeal(&pool, id).await?;
+    Ok(Json(detail(&pool, appeal).await?))
+}
+
+pub async fn reject(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ScoreAppealDecision>,
+) -> Result<Json<ScoreAppealDetail>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    decide(&pool, user_id, id, false, payload).await?;
+
+    let appeal = load_appeal(&pool, id).await?;
+    Ok(Json(detail(&pool, appeal).await?))
+}
+
+// 撤回：提交人或学生本人，只能撤回尚未处理的申诉
+pub async fn withdraw(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ScoreAppealDetail>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let appeal = load_appeal(&pool, id).await?;
+    if appeal.submitted_by != Some(user_id) && appeal.student_id != user_id {
+        return Err(AppError::Auth("只能撤回自己提交的申诉".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+    let updated = sqlx::query("UPDATE score_appeals SET status = 'withdrawn' WHERE id = $1 AND status = 'pending'")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::InvalidInput("该申诉已处理，不能撤回".to_string()));
+    }
+    add_message(&mut tx, id, "withdrawn", Some(user_id), "撤回申诉", None).await?;
+    tx.commit().await?;
+
+    let appeal = load_appeal(&pool, id).await?;
+    Ok(Json(detail(&pool, appeal).await?))
+}
+
+// ========== 审核 ==========
+
+async fn decide(
+    pool: &PgPool,
+    user_id: Uuid,
+    id: Uuid,
+    accept: bool,
+    payload: ScoreAppealDecision,
+) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    let (status, reviewer_id, score_id, group_score_record_id, original_value): (
+        String,
+        Option<Uuid>,
+        Option<Uuid>,
+        Option<Uuid>,
+        i32,
+    ) = sqlx::query_as(
+        "SELECT status, reviewer_id, score_id, group_score_record_id, original_value
+         FROM score_appeals WHERE id = $1 FOR UPDATE",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    if status != "pending" {
+        return Err(AppError::InvalidInput("该申诉已处理".to_string()));
+    }
+    if reviewer_id != Some(user_id) && !check_user_permission(pool, user_id, "score.appeal.review").await {
+        return Err(AppError::Auth("只有指派的审核人可以处理该申诉".to_string()));
+    }
+    let comment = payload.comment.as_deref().map(str::trim).filter(|c| !c.is_empty());
+
+    if accept {
+        let value = rules::compensation(original_value, payload.adjusted_value)?;
+        let adjusted_value = original_value + value;
+        rules::ensure_not_compensated(&mut tx, score_id, group_score_record_id, Some(id)).await?;
+        let (compensation_score_id, compensation_group_record_id) =
+            rules::record_compensation(&mut tx, score_id, group_score_record_id, value, user_id).await?;
+        sqlx::query(
+            "UPDATE score_appeals SET status = 'accepted', adjusted_value = $2, compensation_score_id = $3,
+                 compensation_group_record_id = $4, resolved_by = $5, resolved_at = NOW()
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(adjusted_value)
+        .bind(compensation_score_id)
+        .bind(compensation_group_record_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        let content = match adjusted_value {
+            0 => "同意申诉，撤销原扣分".to_string(),
+            v => format!("同意申诉，分值由 {} 调整为 {}", original_value, v),
+        };
+        let content = match comment {
+            Some(comment) => format!("{}：{}", content, comment),
+            None => content,
+        };
+        add_message(&mut tx, id, "accepted", Some(user_id), &content, None).await?;
+    } else {
+        let comment = comment.ok_or_else(|| AppError::InvalidInput("请填写驳回理由".to_string()))?;
+        sqlx::query("UPDATE score_appeals SET status = 'rejected', resolved_by = $2, resolved_at = NOW() WHERE id = $1")
+            .bind(id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        add_message(&mut tx, id, "rejected", Some(user_id), comment, None).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+// ========== 查询 ==========
+
+async fn load_appeal(pool: &PgPool, id: Uuid) -> Result<ScoreAppeal, AppError> {
+    sqlx::query_as::<_, ScoreAppeal>(&format!("{} {} WHERE a.id = $1", APPEAL_COLUMNS, APPEAL_FROM))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+async fn detail(pool: &PgPool, appeal: ScoreAppeal) -> Result<ScoreAppealDetail, AppError> {
+    let messages = sqlx::query_as::<_, ScoreAppealMessage>(
+        "SELECT m.id, m.kind, m.author_id, p.name AS author_name, m.content, m.attachment_id, m.created_at
+         FROM score_appeal_messages m LEFT JOIN persons p ON p.id = m.author_id
+         WHERE m.appeal_id = $1
+         ORDER BY m.created_at",
+    )
+    .bind(appeal.id)
+    .fetch_all(pool)
+    .await?;
+    Ok(ScoreAppealDetail { appeal, messages })
+}
+
+async fn add_message(
+    conn: &mut sqlx::PgConnection,
+    appeal_id: Uuid,
+    kind: &str,
+    author_id: Option<Uuid>,
+    content: &str,
+    attachment_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO score_appeal_messages (appeal_id, kind, author_id, content, attachment_id)
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(appeal_id)
+    .bind(kind)
+    .bind(author_id)
+    .bind(content)
+    .bind(attachment_id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+async fn assigned_text(pool: &PgPool, reviewer_id: Uuid) -> Result<String, AppError> {
+    let name: String = sqlx::query_scalar("SELECT name FROM persons WHERE id = $1")
+        .bind(reviewer_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(format!("指派 {} 审核", name))
+}
+
+// 学生本人、家长、提交人、审核人、原扣分录入人、班主任以及有 score.appeal.review 权限的人员可以查看
+async fn require_view(pool: &PgPool, user_id: Uuid, appeal: &ScoreAppeal) -> Result<(), AppError> {
+    if appeal.student_id == user_id
+        || appeal.submitted_by == Some(user_id)
+        || appeal.reviewer_id == Some(user_id)
+        || appeal.scored_by == Some(user_id)
+    {
+        return Ok(());
+    }
+    if is_parent_of(pool, user_id, appeal.student_id).await? {
+        return Ok(());
+    }
+    if let Some(class_id) = appeal.class_id {
+        if is_head_teacher(pool, user_id, class_id).await? {
+            return Ok(());
+        }
+    }
+    if check_user_permission(pool, user_id, "score.appeal.review").await {
+        return Ok(());
+    }
+    Err(AppError::Auth("没有权限执行此操作: score.appeal.review".to_string()))
+}
+
+// 默认审核人：班主任，没有时为班级负责教师
+async fn head_teacher_of(pool: &PgPool, class_id: Uuid) -> Result<Option<Uuid>, AppError> {
+    let teacher_id: Option<Uuid> = sqlx::query_scalar(
+        "SELECT teacher_id FROM (
+             SELECT teacher_id, 0 AS priority FROM teacher_class WHERE class_id = $1 AND is_main_teacher
+             UNION ALL
+             SELECT teacher_id, 1 FROM classes WHERE id = $1 AND teacher_id IS NOT NULL
+         ) t ORDER BY priority LIMIT 1",
+    )
+    .bind(class_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(teacher_id)
+}
+
+async fn is_parent_of(pool: &PgPool, parent_id: Uuid, student_id: Uuid) -> Result<bool, AppError> {
+    let linked: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM student_parent WHERE parent_id = $1 AND student_id = $2)",
+    )
+    .bind(parent_id)
+    .bind(student_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(linked)
+}
+
+async fn is_head_teacher(pool: &PgPool, teacher_id: Uuid, class_id: Uuid) -> Result<bool, AppError> {
+    let head: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM teacher_class WHERE teacher_id = $1 AND class_id = $2 AND is_main_teacher)",
+    )
+    .bind(teacher_id)
+    .bind(class_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(head)
+}
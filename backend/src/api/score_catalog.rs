@@ -0,0 +1,334 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::routes::AppState;
+use crate::core::auth::Claims;
+use crate::core::error::AppError;
+use crate::core::permission::PermissionManager;
+use crate::models::score::{
+    ScoreCategory, ScoreCategoryDetail, ScoreCategorySave, ScoreLimit, ScoreLimitsSave, ScoreReason, ScoreReasonSave,
+};
+
+const CATEGORY_SELECT: &str = "SELECT id, code, name, description, sort_order, is_active FROM score_categories";
+const REASON_SELECT: &str = "SELECT id, category_id, name, aliases, default_value, sort_order, is_active FROM score_reasons";
+
+// ========== 积分类别 ==========
+
+// 类别及其预设原因（录入积分时选择），包含已停用的项目
+pub async fn list_categories(State(state): State<AppState>) -> Result<Json<Vec<ScoreCategoryDetail>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let categories = sqlx::query_as::<_, ScoreCategory>(&format!("{} ORDER BY sort_order, name", CATEGORY_SELECT))
+        .fetch_all(&pool)
+        .await?;
+    let reasons = sqlx::query_as::<_, ScoreReason>(&format!("{} ORDER BY sort_order, name", REASON_SELECT))
+        .fetch_all(&pool)
+        .await?;
+
+    let details = categories
+        .into_iter()
+        .map(|category| {
+            let reasons = reasons.iter().filter(|r| r.category_id == category.id).cloned().collect();
+            ScoreCategoryDetail { category, reasons }
+        })
+        .collect();
+    Ok(Json(details))
+}
+
+pub async fn create_category(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<ScoreCategorySave>,
+) -> Result<Json<ScoreCategory>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "score.catalog").await?;
+
+    validate_category(&payload)?;
+    let category = sqlx::query_as::<_, ScoreCategory>(
+        "INSERT INTO score_categories (code, name, description, sort_order, is_active)
+         VALUES ($1, $2, $3, COALESCE($4, 0), COALESCE($5, true))
+         RETURNING id, code, name, description, sort_order, is_active",
+    )
+    .bind(payload.code.trim())
+    .bind(payload.name.trim())
+    .bind(&payload.description)
+    .bind(payload.sort_order)
+    .bind(payload.is_active)
+    .fetch_one(&pool)
+    .await
+    .map_err(catalog_conflict)?;
+
+    Ok(Json(category))
+}
+
+pub async fn update_category(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ScoreCategorySave>,
+) -> Result<Json<ScoreCategory>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "score.catalog").await?;
+
+    validate_category(&payload)?;
+    let category = sqlx::query_as::<_, ScoreCategory>(
+        "UPDATE score_categories SET code = $2, name = $3, description = $4,
+             sort_order = COALESCE($5, sort_order), is_active = COALESCE($6, is_active)
+         WHERE id = $1
+         RETURNING id, code, name, description, sort_order, is_active",
+    )
+    .bind(id)
+    .bind(payload.code.trim())
+    .bind(payload.name.trim())
+    .bind(&payload.description)
+    .bind(payload.sort_order)
+    .bind(payload.is_active)
+    .fetch_optional(&pool)
+    .await
+    .map_err(catalog_conflict)?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(Json(category))
+}
+
+// 删除类别及其原因；已有积分记录使用时只能停用
+pub async fn delete_category(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "score.catalog").await?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM score_reasons WHERE category_id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(catalog_conflict)?;
+    let result = sqlx::query("DELETE FROM score_categories WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(catalog_conflict)?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ========== 预设原因 ==========
+
+pub async fn create_reason(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(category_id): Path<Uuid>,
+    Json(payload): Json<ScoreReasonSave>,
+) -> Result<Json<ScoreReason>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "score.catalog").await?;
+
+    let aliases = validate_reason(&payload)?;
+    ensure_unique_alias(&pool, None, payload.name.trim(), &aliases).await?;
+    let reason = sqlx::query_as::<_, ScoreReason>(
+        "INSERT INTO score_reasons (category_id, name, aliases, default_value, sort_order, is_active)
+         VALUES ($1, $2, $3, $4, COALESCE($5, 0), COALESCE($6, true))
+         RETURNING id, category_id, name, aliases, default_value, sort_order, is_active",
+    )
+    .bind(category_id)
+    .bind(payload.name.trim())
+    .bind(&aliases)
+    .bind(payload.default_value)
+    .bind(payload.sort_order)
+    .bind(payload.is_active)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref db) if db.is_foreign_key_violation() => AppError::NotFound,
+        e => catalog_conflict(e),
+    })?;
+
+    Ok(Json(reason))
+}
+
+pub async fn update_reason(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ScoreReasonSave>,
+) -> Result<Json<ScoreReason>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "score.catalog").await?;
+
+    let aliases = validate_reason(&payload)?;
+    ensure_unique_alias(&pool, Some(id), payload.name.trim(), &aliases).await?;
+    let reason = sqlx::query_as::<_, ScoreReason>(
+        "UPDATE score_reasons SET name = $2, aliases = $3, default_value = $4,
+             sort_order = COALESCE($5, sort_order), is_active = COALESCE($6, is_active)
+         WHERE id = $1
+         RETURNING id, category_id, name, aliases, default_value, sort_order, is_active",
+    )
+    .bind(id)
+    .bind(payload.name.trim())
+    .bind(&aliases)
+    .bind(payload.default_value)
+    .bind(payload.sort_order)
+    .bind(payload.is_active)
+    .fetch_optional(&pool)
+    .await
+    .map_err(catalog_conflict)?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(Json(reason))
+}
+
+pub async fn delete_reason(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "score.catalog").await?;
+
+    let result = sqlx::query("DELETE FROM score_reasons WHERE id = $1")
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(catalog_conflict)?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ========== 单次加减分上限 ==========
+
+pub async fn list_limits(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<ScoreLimit>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "score.catalog").await?;
+
+    let limits = sqlx::query_as::<_, ScoreLimit>("SELECT role, max_points FROM score_limits ORDER BY role")
+        .fetch_all(&pool)
+        .await?;
+    Ok(Json(limits))
+}
+
+// 整体替换各角色的上限，未列出的角色不限制
+pub async fn set_limits(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<ScoreLimitsSave>,
+) -> Result<Json<Vec<ScoreLimit>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    PermissionManager::new(pool.clone()).require_permission(user_id, "score.catalog").await?;
+
+    if payload.limits.iter().any(|l| l.role.trim().is_empty() || l.max_points < 1) {
+        return Err(AppError::InvalidInput("角色不能为空，上限必须大于 0".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM score_limits").execute(&mut *tx).await?;
+    for limit in &payload.limits {
+        sqlx::query(
+            "INSERT INTO score_limits (role, max_points) VALUES ($1, $2)
+             ON CONFLICT (role) DO UPDATE SET max_points = EXCLUDED.max_points",
+        )
+        .bind(limit.role.trim())
+        .bind(limit.max_points)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    let limits = sqlx::query_as::<_, ScoreLimit>("SELECT role, max_points FROM score_limits ORDER BY role")
+        .fetch_all(&pool)
+        .await?;
+    Ok(Json(limits))
+}
+
+// ========== 辅助函数 ==========
+
+fn validate_category(payload: &ScoreCategorySave) -> Result<(), AppError> {
+    if payload.code.trim().is_empty() || payload.name.trim().is_empty() {
+        return Err(AppError::InvalidInput("类别代码和名称不能为空".to_string()));
+    }
+    Ok(())
+}
+
+// 返回去重、去空白后的同义说法
+fn validate_reason(payload: &ScoreReasonSave) -> Result<Vec<String>, AppError> {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(AppError::InvalidInput("原因名称不能为空".to_string()));
+    }
+    if payload.default_value == 0 {
+        return Err(AppError::InvalidInput("预设分值不能为 0".to_string()));
+    }
+    let mut aliases: Vec<String> = Vec::new();
+    for alias in payload.aliases.iter().flatten() {
+        let alias = alias.trim();
+        if !alias.is_empty() && alias != name && !aliases.iter().any(|a| a.to_lowercase() == alias.to_lowercase()) {
+            aliases.push(alias.to_string());
+        }
+    }
+    Ok(aliases)
+}
+
+// 名称和同义说法在整个目录中不能与其他原因重复，否则录入时无法确定是哪个原因
+async fn ensure_unique_alias(pool: &PgPool, id: Option<Uuid>, name: &str, aliases: &[String]) -> Result<(), AppError> {
+    let mut words: Vec<String> = aliases.iter().map(|a| a.to_lowercase()).collect();
+    words.push(name.to_lowercase());
+
+    let conflict: Option<String> = sqlx::query_scalar(
+        "SELECT r.name FROM score_reasons r
+         WHERE ($1::uuid IS NULL OR r.id <> $1)
+           AND (LOWER(r.name) = ANY($2) OR EXISTS (SELECT 1 FROM UNNEST(r.aliases) a WHERE LOWER(a) = ANY($2)))
+         LIMIT 1",
+    )
+    .bind(id)
+    .bind(&words)
+    .fetch_optional(pool)
+    .await?;
+    match conflict {
+        Some(other) => Err(AppError::InvalidInput(format!("名称或同义说法与原因“{}”重复", other))),
+        None => Ok(()),
+    }
+}
+
+fn catalog_conflict(e: sqlx::Error) -> AppError {
+    match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::InvalidInput("类别代码或原因名称已存在".to_string())
+        }
+        sqlx::Error::Database(ref db) if db.is_foreign_key_violation() => {
+            AppError::InvalidInput("已有积分记录使用，请改为停用".to_string())
+        }
+        e => AppError::Database(e),
+    }
+}
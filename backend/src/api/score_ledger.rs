@@ -0,0 +1,50 @@
+use axum::{
+    extract::{Extension, State},
+    Json,
+};
+use uuid::Uuid;
+
+use crate::api::routes::AppState;
+use crate::core::auth::Claims;
+use crate::core::error::AppError;
+use crate::core::permission::PermissionManager;
+use crate::core::score_ledger as ledger;
+use crate::models::score::ScoreReconciliation;
+
+// 查看小组总分、积分汇总与积分流水的差异
+pub async fn reconciliation(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ScoreReconciliation>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "score.reconcile").await?;
+
+    let mut conn = pool.acquire().await?;
+    let report = ledger::reconcile(&mut conn, false).await?;
+    Ok(Json(report))
+}
+
+// 按积分流水重算有差异的汇总，返回修复前发现的差异
+pub async fn reconcile(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ScoreReconciliation>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "score.reconcile").await?;
+
+    let mut tx = pool.begin().await?;
+    let report = ledger::reconcile(&mut tx, true).await?;
+    tx.commit().await?;
+    tracing::info!(
+        "积分对账修复：小组总分 {} 处，按天汇总 {} 处",
+        report.group_scores.len(),
+        report.daily_totals.len()
+    );
+    Ok(Json(report))
+}
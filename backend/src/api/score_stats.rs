@@ -0,0 +1,179 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    Json,
+};
+use chrono::{Local, NaiveDate};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::routes::AppState;
+use crate::core::auth::Claims;
+use crate::core::error::AppError;
+use crate::core::permission::PermissionManager;
+use crate::core::score as rules;
+use crate::models::score::{Leaderboard, LeaderboardEntry, LeaderboardQuery, ScoreSummary, ScoreSummaryQuery};
+
+// (ID, 名称, 班级ID, 班级名, 总分, 加分次数, 扣分次数)
+type TotalRow = (Uuid, String, Option<Uuid>, Option<String>, i64, i64, i64);
+
+// 学生、小组或班级积分排行榜，附带与上一周期相比的名次变化
+pub async fn leaderboard(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<Leaderboard>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    match query.class_id {
+        Some(class_id) => manager.require_class_permission(user_id, "score.view", class_id).await?,
+        None => manager.require_permission(user_id, "score.view").await?,
+    }
+
+    let scope = query.scope.clone().unwrap_or_else(|| "student".to_string());
+    if !rules::LEADERBOARD_SCOPES.contains(&scope.as_str()) {
+        return Err(AppError::InvalidInput("排行范围只能是 student、group 或 class".to_string()));
+    }
+    let (from, to, previous_from, previous_to) = rules::periods(query.from, query.to, Local::now().date_naive())?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+
+    let current = load_totals(&pool, &scope, from, to, &query).await?;
+    let previous = load_totals(&pool, &scope, previous_from, previous_to, &query).await?;
+
+    let ranks = rules::competition_ranks(&current.iter().map(|row| (row.0, row.4)).collect::<Vec<_>>());
+    let previous_ranks = rules::competition_ranks(&previous.iter().map(|row| (row.0, row.4)).collect::<Vec<_>>());
+
+    let mut items: Vec<LeaderboardEntry> = current
+        .into_iter()
+        .map(|(id, name, class_id, class_name, total_score, positive_count, negative_count)| {
+            let rank = ranks[&id];
+            let previous_rank = previous_ranks.get(&id).copied();
+            LeaderboardEntry {
+                id,
+                name,
+                class_id,
+                class_name,
+                total_score,
+                positive_count,
+                negative_count,
+                rank,
+                previous_rank,
+                rank_change: rules::rank_change(rank, previous_rank),
+            }
+        })
+        .collect();
+    items.sort_by(|a, b| a.rank.cmp(&b.rank).then_with(|| a.name.cmp(&b.name)));
+    items.truncate(limit as usize);
+
+    Ok(Json(Leaderboard { scope, from, to, previous_from, previous_to, items }))
+}
+
+// 个人积分汇总：本人需要 score.view.own，查看他人需要 score.view
+pub async fn summary(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(person_id): Path<Uuid>,
+    Query(query): Query<ScoreSummaryQuery>,
+) -> Result<Json<ScoreSummary>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let permission = if user_id == person_id { "score.view.own" } else { "score.view" };
+    PermissionManager::new(pool.clone()).require_permission(user_id, permission).await?;
+
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM persons WHERE id = $1 AND deleted_at IS NULL)")
+        .bind(person_id)
+        .fetch_one(&pool)
+        .await?;
+    if !exists {
+        return Err(AppError::NotFound);
+    }
+
+    let summary = sqlx::query_as::<_, ScoreSummary>(
+        "SELECT $1 AS person_id, COALESCE(SUM(total), 0)::int AS total_score,
+                COALESCE(SUM(positive_count), 0)::int AS positive_count,
+                COALESCE(SUM(negative_count), 0)::int AS negative_count
+         FROM score_daily_totals
+         WHERE person_id = $1
+           AND ($2::date IS NULL OR score_date >= $2) AND ($3::date IS NULL OR score_date <= $3)
+           AND ($4::varchar IS NULL OR score_type = $4)",
+    )
+    .bind(person_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(&query.score_type)
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(summary))
+}
+
+/// 周期内的积分汇总。
+/// 学生和班级来自 score_daily_totals，按学生当前所在班级统计；
+/// 小组来自小组积分记录（与小组总分同源），按小组当前所在班级筛选，不区分积分类型
+async fn load_totals(
+    pool: &PgPool,
+    scope: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+    query: &LeaderboardQuery,
+) -> Result<Vec<TotalRow>, AppError> {
+    if scope == "group" {
+        let rows: Vec<TotalRow> = sqlx::query_as(
+            "SELECT g.id, g.name, c.id, c.name, SUM(r.score_change)::bigint,
+                    COUNT(*) FILTER (WHERE r.score_change > 0), COUNT(*) FILTER (WHERE r.score_change < 0)
+             FROM group_score_records r
+             JOIN class_groups g ON g.id = r.group_id
+             LEFT JOIN classes c ON c.id = g.class_id
+             WHERE r.created_at::date BETWEEN $1 AND $2
+               AND ($3::uuid IS NULL OR c.id = $3)
+               AND ($4::smallint IS NULL OR c.grade = $4)
+             GROUP BY g.id, g.name, c.id, c.name",
+        )
+        .bind(from)
+        .bind(to)
+        .bind(query.class_id)
+        .bind(query.grade)
+        .fetch_all(pool)
+        .await?;
+        return Ok(rows);
+    }
+
+    let (select, join, group_by) = match scope {
+        "class" => (
+            "c.id, c.name, c.id, c.name",
+            "JOIN persons p ON p.id = t.person_id AND p.deleted_at IS NULL
+             JOIN students s ON s.person_id = t.person_id
+             JOIN classes c ON c.id = s.class_id",
+            "c.id, c.name",
+        ),
+        _ => (
+            "p.id, p.name, c.id, c.name",
+            "JOIN persons p ON p.id = t.person_id AND p.deleted_at IS NULL
+             JOIN students s ON s.person_id = t.person_id
+             LEFT JOIN classes c ON c.id = s.class_id",
+            "p.id, p.name, c.id, c.name",
+        ),
+    };
+
+    let rows: Vec<TotalRow> = sqlx::query_as(&format!(
+        "SELECT {}, SUM(t.total)::bigint, SUM(t.positive_count)::bigint, SUM(t.negative_count)::bigint
+         FROM score_daily_totals t
+         {}
+         WHERE t.score_date BETWEEN $1 AND $2
+           AND ($3::varchar IS NULL OR t.score_type = $3)
+           AND ($4::uuid IS NULL OR c.id = $4)
+           AND ($5::smallint IS NULL OR c.grade = $5)
+         GROUP BY {}",
+        select, join, group_by
+    ))
+    .bind(from)
+    .bind(to)
+    .bind(&query.score_type)
+    .bind(query.class_id)
+    .bind(query.grade)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
@@ -0,0 +1,97 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::Deserialize;
+
+use crate::api::routes::AppState;
+use crate::core::error::AppError;
+use crate::core::search::{self, SearchTerm};
+use crate::models::search::SearchResult;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    // 只检索某一类：person / class / group
+    #[serde(rename = "type")]
+    pub type_: Option<String>,
+    pub limit: Option<i64>,
+}
+
+// 统一检索：人员、班级、小组按名称 / 全拼 / 首字母混合检索，按相关度排序
+pub async fn search(
+    State(state): State<AppState>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Vec<SearchResult>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let term = match SearchTerm::new(&query.q) {
+        Some(term) => term,
+        None => return Ok(Json(Vec::new())),
+    };
+    let type_ = query.type_.as_deref().filter(|t| !t.is_empty());
+    if let Some(t) = type_ {
+        if !["person", "class", "group"].contains(&t) {
+            return Err(AppError::InvalidInput("type 必须是 person、class 或 group".to_string()));
+        }
+    }
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    let mut parts = Vec::new();
+    if type_.is_none_or(|t| t == "person") {
+        parts.push(format!(
+            "SELECT 'person' AS type, p.id, p.name::text AS name,
+                    CASE p.type
+                        WHEN 'student' THEN '学生 · ' || COALESCE(sc.name, '未分班')
+                        WHEN 'teacher' THEN '教师 · ' || COALESCE(d.name, '未分配部门')
+                        WHEN 'parent' THEN '家长'
+                        ELSE p.type
+                    END AS subtitle,
+                    {} AS score
+             FROM persons p
+             LEFT JOIN students s ON s.person_id = p.id
+             LEFT JOIN classes sc ON sc.id = s.class_id
+             LEFT JOIN teachers t ON t.person_id = p.id
+             LEFT JOIN departments d ON d.id = t.department_id
+             WHERE p.deleted_at IS NULL AND {}",
+            search::score("p", 1, 2),
+            search::condition("p", 1, 2)
+        ));
+    }
+    if type_.is_none_or(|t| t == "class") {
+        parts.push(format!(
+            "SELECT 'class' AS type, c.id, c.name::text AS name,
+                    c.grade || ' 年级' || COALESCE(' · ' || c.academic_year, '') AS subtitle,
+                    {} AS score
+             FROM classes c
+             WHERE c.deleted_at IS NULL AND {}",
+            search::score("c", 1, 2),
+            search::condition("c", 1, 2)
+        ));
+    }
+    if type_.is_none_or(|t| t == "group") {
+        parts.push(format!(
+            "SELECT 'group' AS type, cg.id, cg.name::text AS name,
+                    '小组 · ' || c.name AS subtitle,
+                    {} AS score
+             FROM class_groups cg
+             JOIN classes c ON c.id = cg.class_id
+             WHERE c.deleted_at IS NULL AND {}",
+            search::score("cg", 1, 2),
+            search::condition("cg", 1, 2)
+        ));
+    }
+
+    let sql = format!(
+        "SELECT * FROM ({}) results ORDER BY score DESC, name LIMIT $3",
+        parts.join(" UNION ALL ")
+    );
+    let results = sqlx::query_as::<_, SearchResult>(&sql)
+        .bind(&term.raw)
+        .bind(&term.pinyin)
+        .bind(limit)
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(Json(results))
+}
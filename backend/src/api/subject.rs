@@ -0,0 +1,339 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::api::routes::AppState;
+use crate::core::auth::Claims;
+use crate::core::error::AppError;
+use crate::core::permission::PermissionManager;
+use crate::models::subject::{
+    Subject, SubjectCreate, SubjectUpdate, TeachingAssignment, TeachingAssignmentCreate,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct AssignmentQuery {
+    pub teacher_id: Option<Uuid>,
+    pub class_id: Option<Uuid>,
+    pub subject_id: Option<Uuid>,
+    pub academic_year: Option<String>,
+}
+
+const ASSIGNMENT_SELECT: &str = "SELECT ta.id, ta.teacher_id, p.name AS teacher_name,
+            ta.subject_id, s.name AS subject_name,
+            ta.class_id, c.name AS class_name,
+            ta.academic_year, ta.created_at
+     FROM teaching_assignments ta
+     JOIN persons p ON p.id = ta.teacher_id
+     JOIN subjects s ON s.id = ta.subject_id
+     JOIN classes c ON c.id = ta.class_id";
+
+// 获取学科列表
+pub async fn list(State(state): State<AppState>) -> Result<Json<Vec<Subject>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let subjects = sqlx::query_as::<_, Subject>("SELECT * FROM subjects ORDER BY code NULLS LAST, name")
+        .fetch_all(&pool)
+        .await?;
+    Ok(Json(subjects))
+}
+
+pub async fn create(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<SubjectCreate>,
+) -> Result<Json<Subject>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "subject.create").await?;
+
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(AppError::InvalidInput("学科名称不能为空".to_string()));
+    }
+
+    let subject = sqlx::query_as::<_, Subject>(
+        "INSERT INTO subjects (name, code) VALUES ($1, $2) RETURNING *",
+    )
+    .bind(name)
+    .bind(payload.code.filter(|c| !c.is_empty()))
+    .fetch_one(&pool)
+    .await
+    .map_err(duplicate_subject)?;
+
+    Ok(Json(subject))
+}
+
+pub async fn update(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<SubjectUpdate>,
+) -> Result<Json<Subject>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "subject.update").await?;
+
+    let existing = sqlx::query_as::<_, Subject>("SELECT * FROM subjects WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    // code 传空字符串表示清除学科代码
+    let code = match payload.code {
+        Some(c) if c.is_empty() => None,
+        Some(c) => Some(c),
+        None => existing.code,
+    };
+
+    let subject = sqlx::query_as::<_, Subject>(
+        "UPDATE subjects SET name = $2, code = $3 WHERE id = $1 RETURNING *",
+    )
+    .bind(id)
+    .bind(payload.name.unwrap_or(existing.name))
+    .bind(code)
+    .fetch_one(&pool)
+    .await
+    .map_err(duplicate_subject)?;
+
+    Ok(Json(subject))
+}
+
+// 删除学科，同时撤销相关任课安排及其班级权限
+pub async fn delete(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "subject.delete").await?;
+
+    // 删除学科与解除班级关联在同一事务中完成，权限在提交后收回
+    let mut tx = pool.begin().await?;
+    let affected: Vec<(Uuid, Uuid)> = sqlx::query_as(
+        "SELECT DISTINCT teacher_id, class_id FROM teaching_assignments WHERE subject_id = $1",
+    )
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let result = sqlx::query("DELETE FROM subjects WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    let mut released = Vec::new();
+    for (teacher_id, class_id) in affected {
+        if release_class(&mut tx, teacher_id, class_id).await? {
+            released.push((teacher_id, class_id));
+        }
+    }
+    tx.commit().await?;
+
+    for (teacher_id, class_id) in released {
+        revoke_class_permissions(&manager, teacher_id, class_id).await?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// 查询任课安排，可按教师、班级、学科、学年过滤
+pub async fn list_assignments(
+    State(state): State<AppState>,
+    Query(query): Query<AssignmentQuery>,
+) -> Result<Json<Vec<TeachingAssignment>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let assignments = sqlx::query_as::<_, TeachingAssignment>(&format!(
+        "{} WHERE ($1::uuid IS NULL OR ta.teacher_id = $1)
+            AND ($2::uuid IS NULL OR ta.class_id = $2)
+            AND ($3::uuid IS NULL OR ta.subject_id = $3)
+            AND ($4::text IS NULL OR ta.academic_year = $4)
+         ORDER BY ta.academic_year DESC, c.grade, c.name, s.name",
+        ASSIGNMENT_SELECT
+    ))
+    .bind(query.teacher_id)
+    .bind(query.class_id)
+    .bind(query.subject_id)
+    .bind(query.academic_year.filter(|y| !y.is_empty()))
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(assignments))
+}
+
+// 新增任课安排，任课教师同时关联到班级并获得班级范围权限
+pub async fn create_assignment(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<TeachingAssignmentCreate>,
+) -> Result<Json<TeachingAssignment>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "subject.assign").await?;
+
+    let is_teacher: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM teachers t JOIN persons p ON p.id = t.person_id
+                       WHERE t.person_id = $1 AND p.deleted_at IS NULL)",
+    )
+    .bind(payload.teacher_id)
+    .fetch_one(&pool)
+    .await?;
+    if !is_teacher {
+        return Err(AppError::InvalidInput("任课教师不存在".to_string()));
+    }
+
+    let class_year: Option<Option<String>> = sqlx::query_scalar(
+        "SELECT academic_year FROM classes WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(payload.class_id)
+    .fetch_optional(&pool)
+    .await?;
+    let class_year = class_year.ok_or_else(|| AppError::InvalidInput("班级不存在".to_string()))?;
+    let academic_year = payload
+        .academic_year
+        .filter(|y| !y.is_empty())
+        .or(class_year)
+        .ok_or_else(|| AppError::InvalidInput("班级未设置学年，请指定 academic_year".to_string()))?;
+
+    let mut tx = pool.begin().await?;
+    let id: Uuid = sqlx::query_scalar(
+        "INSERT INTO teaching_assignments (teacher_id, subject_id, class_id, academic_year)
+         VALUES ($1, $2, $3, $4)
+         RETURNING id",
+    )
+    .bind(payload.teacher_id)
+    .bind(payload.subject_id)
+    .bind(payload.class_id)
+    .bind(&academic_year)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::InvalidInput("该任课安排已存在".to_string())
+        }
+        sqlx::Error::Database(ref db) if db.is_foreign_key_violation() => {
+            AppError::InvalidInput("学科不存在".to_string())
+        }
+        e => AppError::Database(e),
+    })?;
+
+    // 任课教师同样出现在班级教师列表中；已有关联时保持原样，撤销任课时也不会解除
+    let linked = sqlx::query(
+        "INSERT INTO teacher_class (teacher_id, class_id, is_main_teacher, via_assignment)
+         VALUES ($1, $2, false, true)
+         ON CONFLICT (teacher_id, class_id) DO NOTHING",
+    )
+    .bind(payload.teacher_id)
+    .bind(payload.class_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected()
+        > 0;
+    tx.commit().await?;
+
+    if linked {
+        manager.add_class_permissions_for_subject_teacher(payload.teacher_id, payload.class_id).await
+            .map_err(|e| AppError::InternalWithMessage(format!("植入权限失败: {}", e)))?;
+    }
+
+    let assignment = get_assignment(&pool, id).await?;
+    Ok(Json(assignment))
+}
+
+// 撤销任课安排
+pub async fn delete_assignment(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "subject.assign").await?;
+
+    let mut tx = pool.begin().await?;
+    let (teacher_id, class_id, subject_id, academic_year): (Uuid, Uuid, Uuid, String) = sqlx::query_as(
+        "DELETE FROM teaching_assignments WHERE id = $1
+         RETURNING teacher_id, class_id, subject_id, academic_year",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    // 课程表中对应的课节保留，但不再指定该教师
+    sqlx::query(
+        "UPDATE timetable_entries SET teacher_id = NULL
+         WHERE teacher_id = $1 AND class_id = $2 AND subject_id = $3 AND academic_year = $4",
+    )
+    .bind(teacher_id)
+    .bind(class_id)
+    .bind(subject_id)
+    .bind(&academic_year)
+    .execute(&mut *tx)
+    .await?;
+
+    let released = release_class(&mut tx, teacher_id, class_id).await?;
+    tx.commit().await?;
+    if released {
+        revoke_class_permissions(&manager, teacher_id, class_id).await?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_assignment(pool: &sqlx::PgPool, id: Uuid) -> Result<TeachingAssignment, AppError> {
+    sqlx::query_as::<_, TeachingAssignment>(&format!("{} WHERE ta.id = $1", ASSIGNMENT_SELECT))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+/// 教师在班级已无任课安排、不是班主任且班级关联由任课安排创建时，解除班级关联；
+/// 返回是否解除，解除后由调用方收回任课权限
+async fn release_class(conn: &mut PgConnection, teacher_id: Uuid, class_id: Uuid) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        "DELETE FROM teacher_class
+         WHERE teacher_id = $1 AND class_id = $2 AND via_assignment AND NOT COALESCE(is_main_teacher, false)
+           AND NOT EXISTS(SELECT 1 FROM teaching_assignments WHERE teacher_id = $1 AND class_id = $2)",
+    )
+    .bind(teacher_id)
+    .bind(class_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+async fn revoke_class_permissions(manager: &PermissionManager, teacher_id: Uuid, class_id: Uuid) -> Result<(), AppError> {
+    manager.remove_class_permissions_for_subject_teacher(teacher_id, class_id).await
+        .map_err(|e| AppError::InternalWithMessage(format!("移除权限失败: {}", e)))
+}
+
+fn duplicate_subject(e: sqlx::Error) -> AppError {
+    match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::InvalidInput("学科名称或代码已存在".to_string())
+        }
+        e => AppError::Database(e),
+    }
+}
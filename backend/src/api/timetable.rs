@@ -0,0 +1,484 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::api::routes::AppState;
+use crate::core::auth::Claims;
+use crate::core::error::AppError;
+use crate::core::permission::PermissionManager;
+use crate::models::timetable::{
+    Period, PeriodCreate, PeriodUpdate, TimetableEntry, TimetableEntryCreate, TimetableEntryUpdate,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct TimetableQuery {
+    // 不填时：班级视图使用班级当前学年，教师视图返回所有学年
+    pub academic_year: Option<String>,
+}
+
+const ENTRY_SELECT: &str = "SELECT e.id, e.class_id, c.name AS class_name, e.academic_year,
+            e.day_of_week, e.period_id, pd.period_no, pd.name AS period_name,
+            pd.start_time, pd.end_time,
+            e.subject_id, s.name AS subject_name,
+            e.teacher_id, p.name AS teacher_name, e.room
+     FROM timetable_entries e
+     JOIN classes c ON c.id = e.class_id
+     JOIN periods pd ON pd.id = e.period_id
+     JOIN subjects s ON s.id = e.subject_id
+     LEFT JOIN persons p ON p.id = e.teacher_id";
+
+// ========== 作息节次 ==========
+
+pub async fn list_periods(State(state): State<AppState>) -> Result<Json<Vec<Period>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let periods = sqlx::query_as::<_, Period>("SELECT * FROM periods ORDER BY period_no")
+        .fetch_all(&pool)
+        .await?;
+    Ok(Json(periods))
+}
+
+pub async fn create_period(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<PeriodCreate>,
+) -> Result<Json<Period>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "timetable.manage").await?;
+
+    let period = save_period(&pool, None, payload.period_no, &payload.name, payload.start_time, payload.end_time).await?;
+    Ok(Json(period))
+}
+
+pub async fn update_period(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<PeriodUpdate>,
+) -> Result<Json<Period>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "timetable.manage").await?;
+
+    let existing = sqlx::query_as::<_, Period>("SELECT * FROM periods WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let period = save_period(
+        &pool,
+        Some(id),
+        payload.period_no.unwrap_or(existing.period_no),
+        payload.name.as_deref().unwrap_or(&existing.name),
+        payload.start_time.unwrap_or(existing.start_time),
+        payload.end_time.unwrap_or(existing.end_time),
+    )
+    .await?;
+    Ok(Json(period))
+}
+
+// 删除节次，该节次的所有排课一并删除
+pub async fn delete_period(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "timetable.manage").await?;
+
+    let result = sqlx::query("DELETE FROM periods WHERE id = $1")
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_foreign_key_violation() => {
+                AppError::InvalidInput("该节次已有考勤记录，不能删除".to_string())
+            }
+            e => AppError::Database(e),
+        })?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ========== 课程表 ==========
+
+// 班级课程表
+pub async fn class_timetable(
+    State(state): State<AppState>,
+    Path(class_id): Path<Uuid>,
+    Query(query): Query<TimetableQuery>,
+) -> Result<Json<Vec<TimetableEntry>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let class_year: Option<Option<String>> =
+        sqlx::query_scalar("SELECT academic_year FROM classes WHERE id = $1")
+            .bind(class_id)
+            .fetch_optional(&pool)
+            .await?;
+    let class_year = class_year.ok_or(AppError::NotFound)?;
+    let academic_year = query.academic_year.filter(|y| !y.is_empty()).or(class_year);
+
+    let entries = sqlx::query_as::<_, TimetableEntry>(&format!(
+        "{} WHERE e.class_id = $1 AND ($2::text IS NULL OR e.academic_year = $2)
+         ORDER BY e.academic_year, e.day_of_week, pd.period_no",
+        ENTRY_SELECT
+    ))
+    .bind(class_id)
+    .bind(academic_year)
+    .fetch_all(&pool)
+    .await?;
+    Ok(Json(entries))
+}
+
+// 教师课程表
+pub async fn teacher_timetable(
+    State(state): State<AppState>,
+    Path(teacher_id): Path<Uuid>,
+    Query(query): Query<TimetableQuery>,
+) -> Result<Json<Vec<TimetableEntry>>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let entries = sqlx::query_as::<_, TimetableEntry>(&format!(
+        "{} WHERE e.teacher_id = $1 AND ($2::text IS NULL OR e.academic_year = $2)
+         ORDER BY e.academic_year, e.day_of_week, pd.period_no",
+        ENTRY_SELECT
+    ))
+    .bind(teacher_id)
+    .bind(query.academic_year.filter(|y| !y.is_empty()))
+    .fetch_all(&pool)
+    .await?;
+    Ok(Json(entries))
+}
+
+pub async fn create_entry(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<TimetableEntryCreate>,
+) -> Result<Json<TimetableEntry>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "timetable.manage").await?;
+
+    let class_year: Option<Option<String>> = sqlx::query_scalar(
+        "SELECT academic_year FROM classes WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(payload.class_id)
+    .fetch_optional(&pool)
+    .await?;
+    let class_year = class_year.ok_or_else(|| AppError::InvalidInput("班级不存在".to_string()))?;
+    let academic_year = payload
+        .academic_year
+        .filter(|y| !y.is_empty())
+        .or(class_year)
+        .ok_or_else(|| AppError::InvalidInput("班级未设置学年，请指定 academic_year".to_string()))?;
+
+    let mut slot = Slot {
+        class_id: payload.class_id,
+        academic_year,
+        day_of_week: payload.day_of_week,
+        period_id: payload.period_id,
+        subject_id: payload.subject_id,
+        teacher_id: payload.teacher_id,
+        room: payload.room.filter(|r| !r.trim().is_empty()),
+    };
+    validate_slot(&pool, &mut slot, None).await?;
+
+    let id: Uuid = sqlx::query_scalar(
+        "INSERT INTO timetable_entries (class_id, academic_year, day_of_week, period_id, subject_id, teacher_id, room)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING id",
+    )
+    .bind(slot.class_id)
+    .bind(&slot.academic_year)
+    .bind(slot.day_of_week)
+    .bind(slot.period_id)
+    .bind(slot.subject_id)
+    .bind(slot.teacher_id)
+    .bind(&slot.room)
+    .fetch_one(&pool)
+    .await
+    .map_err(slot_conflict)?;
+
+    let entry = get_entry(&pool, id).await?;
+    Ok(Json(entry))
+}
+
+pub async fn update_entry(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<TimetableEntryUpdate>,
+) -> Result<Json<TimetableEntry>, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "timetable.manage").await?;
+
+    let existing = get_entry(&pool, id).await?;
+    // 更换学科而未指定教师时，重新按任课安排确定教师
+    let teacher_id = match (payload.teacher_id, payload.subject_id) {
+        (Some(teacher_id), _) => Some(teacher_id),
+        (None, Some(subject_id)) if subject_id != existing.subject_id => None,
+        (None, _) => existing.teacher_id,
+    };
+    let mut slot = Slot {
+        class_id: existing.class_id,
+        academic_year: existing.academic_year,
+        day_of_week: payload.day_of_week.unwrap_or(existing.day_of_week),
+        period_id: payload.period_id.unwrap_or(existing.period_id),
+        subject_id: payload.subject_id.unwrap_or(existing.subject_id),
+        teacher_id,
+        room: match payload.room {
+            Some(r) if r.trim().is_empty() => None,
+            Some(r) => Some(r),
+            None => existing.room,
+        },
+    };
+    validate_slot(&pool, &mut slot, Some(id)).await?;
+
+    sqlx::query(
+        "UPDATE timetable_entries
+         SET day_of_week = $2, period_id = $3, subject_id = $4, teacher_id = $5, room = $6
+         WHERE id = $1",
+    )
+    .bind(id)
+    .bind(slot.day_of_week)
+    .bind(slot.period_id)
+    .bind(slot.subject_id)
+    .bind(slot.teacher_id)
+    .bind(&slot.room)
+    .execute(&pool)
+    .await
+    .map_err(slot_conflict)?;
+
+    let entry = get_entry(&pool, id).await?;
+    Ok(Json(entry))
+}
+
+pub async fn delete_entry(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let pool = state.pool.ok_or_else(|| AppError::Internal)?;
+
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+    let manager = PermissionManager::new(pool.clone());
+    manager.require_permission(user_id, "timetable.manage").await?;
+
+    let result = sqlx::query("DELETE FROM timetable_entries WHERE id = $1")
+        .bind(id)
+        .execute(&pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn save_period(
+    pool: &sqlx::PgPool,
+    id: Option<Uuid>,
+    period_no: i16,
+    name: &str,
+    start_time: chrono::NaiveTime,
+    end_time: chrono::NaiveTime,
+) -> Result<Period, AppError> {
+    if end_time <= start_time {
+        return Err(AppError::InvalidInput("结束时间必须晚于开始时间".to_string()));
+    }
+    // 节次时间段不能与其他节次重叠
+    let overlap: Option<String> = sqlx::query_scalar(
+        "SELECT name FROM periods
+         WHERE ($1::uuid IS NULL OR id <> $1) AND start_time < $3 AND end_time > $2
+         LIMIT 1",
+    )
+    .bind(id)
+    .bind(start_time)
+    .bind(end_time)
+    .fetch_optional(pool)
+    .await?;
+    if let Some(other) = overlap {
+        return Err(AppError::InvalidInput(format!("时间段与 {} 重叠", other)));
+    }
+
+    let result = match id {
+        Some(id) => {
+            sqlx::query_as::<_, Period>(
+                "UPDATE periods SET period_no = $2, name = $3, start_time = $4, end_time = $5
+                 WHERE id = $1 RETURNING *",
+            )
+            .bind(id)
+            .bind(period_no)
+            .bind(name)
+            .bind(start_time)
+            .bind(end_time)
+            .fetch_one(pool)
+            .await
+        }
+        None => {
+            sqlx::query_as::<_, Period>(
+                "INSERT INTO periods (period_no, name, start_time, end_time)
+                 VALUES ($1, $2, $3, $4) RETURNING *",
+            )
+            .bind(period_no)
+            .bind(name)
+            .bind(start_time)
+            .bind(end_time)
+            .fetch_one(pool)
+            .await
+        }
+    };
+    result.map_err(|e| match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::InvalidInput(format!("第 {} 节已存在", period_no))
+        }
+        e => AppError::Database(e),
+    })
+}
+
+async fn get_entry(pool: &sqlx::PgPool, id: Uuid) -> Result<TimetableEntry, AppError> {
+    sqlx::query_as::<_, TimetableEntry>(&format!("{} WHERE e.id = $1", ENTRY_SELECT))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+// 待写入的排课时段
+struct Slot {
+    class_id: Uuid,
+    academic_year: String,
+    day_of_week: i16,
+    period_id: Uuid,
+    subject_id: Uuid,
+    teacher_id: Option<Uuid>,
+    room: Option<String>,
+}
+
+/// 校验排课：节次、学科有效，教师须有对应任课安排，且班级、教师、教室在该时段均无冲突
+async fn validate_slot(pool: &sqlx::PgPool, slot: &mut Slot, exclude: Option<Uuid>) -> Result<(), AppError> {
+    if !(1..=7).contains(&slot.day_of_week) {
+        return Err(AppError::InvalidInput("day_of_week 必须在 1~7 之间".to_string()));
+    }
+    let (period_ok, subject_ok): (bool, bool) = sqlx::query_as(
+        "SELECT EXISTS(SELECT 1 FROM periods WHERE id = $1), EXISTS(SELECT 1 FROM subjects WHERE id = $2)",
+    )
+    .bind(slot.period_id)
+    .bind(slot.subject_id)
+    .fetch_one(pool)
+    .await?;
+    if !period_ok {
+        return Err(AppError::InvalidInput("节次不存在".to_string()));
+    }
+    if !subject_ok {
+        return Err(AppError::InvalidInput("学科不存在".to_string()));
+    }
+
+    // 该班级该学年该学科的任课教师
+    let assigned: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT teacher_id FROM teaching_assignments
+         WHERE class_id = $1 AND subject_id = $2 AND academic_year = $3",
+    )
+    .bind(slot.class_id)
+    .bind(slot.subject_id)
+    .bind(&slot.academic_year)
+    .fetch_all(pool)
+    .await?;
+    match slot.teacher_id {
+        Some(teacher_id) if !assigned.contains(&teacher_id) => {
+            return Err(AppError::InvalidInput("该教师未被安排教授此班级的该学科".to_string()));
+        }
+        Some(_) => {}
+        None if assigned.len() > 1 => {
+            return Err(AppError::InvalidInput("该学科有多名任课教师，请指定 teacher_id".to_string()));
+        }
+        None => slot.teacher_id = assigned.first().copied(),
+    }
+
+    let mut conflicts = Vec::new();
+    let class_taken: Option<String> = sqlx::query_scalar(
+        "SELECT s.name FROM timetable_entries e JOIN subjects s ON s.id = e.subject_id
+         WHERE e.class_id = $1 AND e.academic_year = $2 AND e.day_of_week = $3 AND e.period_id = $4
+           AND ($5::uuid IS NULL OR e.id <> $5)",
+    )
+    .bind(slot.class_id)
+    .bind(&slot.academic_year)
+    .bind(slot.day_of_week)
+    .bind(slot.period_id)
+    .bind(exclude)
+    .fetch_optional(pool)
+    .await?;
+    if let Some(subject) = class_taken {
+        conflicts.push(format!("该班级此时段已安排 {}", subject));
+    }
+
+    if let Some(teacher_id) = slot.teacher_id {
+        let teacher_taken: Option<String> = sqlx::query_scalar(
+            "SELECT c.name FROM timetable_entries e JOIN classes c ON c.id = e.class_id
+             WHERE e.teacher_id = $1 AND e.academic_year = $2 AND e.day_of_week = $3 AND e.period_id = $4
+               AND ($5::uuid IS NULL OR e.id <> $5)",
+        )
+        .bind(teacher_id)
+        .bind(&slot.academic_year)
+        .bind(slot.day_of_week)
+        .bind(slot.period_id)
+        .bind(exclude)
+        .fetch_optional(pool)
+        .await?;
+        if let Some(class_name) = teacher_taken {
+            conflicts.push(format!("教师此时段已在 {} 上课", class_name));
+        }
+    }
+
+    if let Some(room) = &slot.room {
+        let room_taken: Option<String> = sqlx::query_scalar(
+            "SELECT c.name FROM timetable_entries e JOIN classes c ON c.id = e.class_id
+             WHERE e.room = $1 AND e.academic_year = $2 AND e.day_of_week = $3 AND e.period_id = $4
+               AND ($5::uuid IS NULL OR e.id <> $5)",
+        )
+        .bind(room)
+        .bind(&slot.academic_year)
+        .bind(slot.day_of_week)
+        .bind(slot.period_id)
+        .bind(exclude)
+        .fetch_optional(pool)
+        .await?;
+        if let Some(class_name) = room_taken {
+            conflicts.push(format!("教室 {} 此时段已被 {} 占用", room, class_name));
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(AppError::InvalidInput(format!("排课冲突：{}", conflicts.join("；"))));
+    }
+    Ok(())
+}
+
+// 并发写入时由唯一索引兜底
+fn slot_conflict(e: sqlx::Error) -> AppError {
+    match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::InvalidInput("排课冲突：该时段已被占用".to_string())
+        }
+        e => AppError::Database(e),
+    }
+}
@@ -0,0 +1,320 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::notice::{NoticeResponse, NoticeRow};
+use crate::core::config::{load_config, Config};
+use crate::core::error::AppError;
+use crate::models::attendance::AttendanceAlertRule;
+use crate::ws::hub::Hub;
+
+/// 站外投递的提醒（短信、公众号等）
+#[derive(Debug, Clone)]
+pub struct OutboundAlert {
+    pub recipient_id: Uuid,
+    pub recipient_name: String,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub title: String,
+    pub content: String,
+}
+
+/// 站外投递通道；站内通知和 WebSocket 推送总是会发送
+#[async_trait]
+pub trait AlertChannel: Send + Sync {
+    async fn send(&self, alert: &OutboundAlert) -> Result<(), AppError>;
+}
+
+/// 本地通道：只写日志，开发和测试环境使用
+pub struct LogChannel;
+
+#[async_trait]
+impl AlertChannel for LogChannel {
+    async fn send(&self, alert: &OutboundAlert) -> Result<(), AppError> {
+        tracing::info!(
+            "考勤提醒 -> {}（{} / {}）: {} {}",
+            alert.recipient_name,
+            alert.phone.as_deref().unwrap_or("-"),
+            alert.email.as_deref().unwrap_or("-"),
+            alert.title,
+            alert.content
+        );
+        Ok(())
+    }
+}
+
+/// 按配置创建站外投递通道（短信等通道后续通过新的实现接入）
+pub fn channel_from_config(config: &Config) -> Result<Arc<dyn AlertChannel>, AppError> {
+    match config.alert_channel.as_str() {
+        "log" => Ok(Arc::new(LogChannel)),
+        other => Err(AppError::InternalWithMessage(format!("不支持的提醒通道: {}", other))),
+    }
+}
+
+fn status_label(status: &str) -> &'static str {
+    match status {
+        "late" => "迟到",
+        _ => "缺勤",
+    }
+}
+
+/// 规则是否触发：窗口内次数达到阈值，且本窗口内尚未提醒过
+pub fn should_alert(rule: &AttendanceAlertRule, occurrences: i64, already_alerted: bool) -> bool {
+    rule.is_active && occurrences >= rule.threshold as i64 && !already_alerted
+}
+
+/// 提醒的标题和内容
+pub fn alert_message(
+    rule: &AttendanceAlertRule,
+    student_name: &str,
+    class_name: Option<&str>,
+    date: NaiveDate,
+    occurrences: i64,
+) -> (String, String) {
+    let label = status_label(&rule.status);
+    let who = match class_name {
+        Some(class_name) => format!("{}（{}）", student_name, class_name),
+        None => student_name.to_string(),
+    };
+    if rule.threshold <= 1 {
+        (
+            format!("【{}提醒】{} {}{}", label, student_name, date.format("%m月%d日"), label),
+            format!("{} {} 的考勤记录为{}。", who, date, label),
+        )
+    } else {
+        (
+            format!("【多次{}】{} 近 {} 天{} {} 次", label, student_name, rule.window_days, label, occurrences),
+            format!(
+                "{} 近 {} 天内已{} {} 次，最近一次为 {}，请关注。",
+                who, rule.window_days, label, occurrences, date
+            ),
+        )
+    }
+}
+
+/// 考勤记录写入后检查提醒规则并投递；失败只记录日志，不影响考勤本身
+pub async fn notify_attendance(pool: &PgPool, hub: &Hub, attendance_ids: &[Uuid], actor_id: Uuid) {
+    for attendance_id in attendance_ids {
+        if let Err(e) = check_rules(pool, hub, *attendance_id, actor_id).await {
+            tracing::warn!("考勤提醒发送失败（考勤记录 {}）: {:?}", attendance_id, e);
+        }
+    }
+}
+
+// (学生, 日期, 状态, 姓名, 班级ID, 班级名, 年级)
+type AlertSubject = (Uuid, NaiveDate, String, String, Option<Uuid>, Option<String>, Option<i16>);
+
+async fn check_rules(pool: &PgPool, hub: &Hub, attendance_id: Uuid, actor_id: Uuid) -> Result<(), AppError> {
+    let record: Option<AlertSubject> = sqlx::query_as(
+        "SELECT a.person_id, a.date, a.status, p.name, c.id, c.name, c.grade
+         FROM attendances a
+         JOIN persons p ON p.id = a.person_id
+         JOIN students s ON s.person_id = a.person_id
+         LEFT JOIN classes c ON c.id = s.class_id
+         WHERE a.id = $1",
+    )
+    .bind(attendance_id)
+    .fetch_optional(pool)
+    .await?;
+    let Some((student_id, date, status, student_name, class_id, class_name, grade)) = record else {
+        return Ok(());
+    };
+
+    let rules = sqlx::query_as::<_, AttendanceAlertRule>(
+        "SELECT id, name, status, threshold, window_days, notify_parents, notify_head_teacher,
+                notify_grade_leader, is_active
+         FROM attendance_alert_rules WHERE status = $1 AND is_active
+         ORDER BY threshold",
+    )
+    .bind(&status)
+    .fetch_all(pool)
+    .await?;
+
+    for rule in rules {
+        let window_start = date - Duration::days(rule.window_days as i64 - 1);
+        // 同一学生同一规则的判断和登记串行执行，避免并发写入考勤时重复提醒
+        let mut tx = pool.begin().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1::text), hashtext($2::text))")
+            .bind(rule.id)
+            .bind(student_id)
+            .execute(&mut *tx)
+            .await?;
+        // 同一天按节次记录的多条只算一次
+        let occurrences: i64 = sqlx::query_scalar(
+            "SELECT COUNT(DISTINCT date) FROM attendances
+             WHERE person_id = $1 AND status = $2 AND date BETWEEN $3 AND $4",
+        )
+        .bind(student_id)
+        .bind(&rule.status)
+        .bind(window_start)
+        .bind(date)
+        .fetch_one(&mut *tx)
+        .await?;
+        let already_alerted: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM attendance_alerts
+                           WHERE rule_id = $1 AND student_id = $2 AND date BETWEEN $3 AND $4)",
+        )
+        .bind(rule.id)
+        .bind(student_id)
+        .bind(window_start)
+        .bind(date)
+        .fetch_one(&mut *tx)
+        .await?;
+        if !should_alert(&rule, occurrences, already_alerted) {
+            continue;
+        }
+
+        let recipients = recipients_for(pool, &rule, student_id, class_id, grade).await?;
+        if recipients.is_empty() {
+            continue;
+        }
+        let recipient_ids: Vec<Uuid> = recipients.iter().map(|r| r.recipient_id).collect();
+        let inserted = sqlx::query(
+            "INSERT INTO attendance_alerts (rule_id, student_id, attendance_id, date, occurrences, recipients)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (rule_id, student_id, date) DO NOTHING",
+        )
+        .bind(rule.id)
+        .bind(student_id)
+        .bind(attendance_id)
+        .bind(date)
+        .bind(occurrences as i32)
+        .bind(&recipient_ids)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        tx.commit().await?;
+        if inserted == 0 {
+            continue;
+        }
+
+        let (title, content) = alert_message(&rule, &student_name, class_name.as_deref(), date, occurrences);
+        deliver(pool, hub, recipients, &title, &content, rule.threshold > 1, actor_id).await?;
+    }
+    Ok(())
+}
+
+// 规则对应的接收人：家长、班主任（teacher_class 中的班主任和班级负责教师）、年级组长
+async fn recipients_for(
+    pool: &PgPool,
+    rule: &AttendanceAlertRule,
+    student_id: Uuid,
+    class_id: Option<Uuid>,
+    grade: Option<i16>,
+) -> Result<Vec<OutboundAlert>, AppError> {
+    let rows: Vec<(Uuid, String, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT DISTINCT p.id, p.name, p.phone, p.email FROM persons p
+         WHERE p.deleted_at IS NULL AND p.id IN (
+             SELECT parent_id FROM student_parent WHERE $1 AND student_id = $4
+             UNION
+             SELECT teacher_id FROM teacher_class WHERE $2 AND class_id = $5 AND is_main_teacher
+             UNION
+             SELECT teacher_id FROM classes WHERE $2 AND id = $5 AND teacher_id IS NOT NULL
+             UNION
+             SELECT teacher_id FROM grade_leaders WHERE $3 AND grade = $6
+         )
+         ORDER BY p.name",
+    )
+    .bind(rule.notify_parents)
+    .bind(rule.notify_head_teacher)
+    .bind(rule.notify_grade_leader)
+    .bind(student_id)
+    .bind(class_id)
+    .bind(grade)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(recipient_id, recipient_name, phone, email)| OutboundAlert {
+            recipient_id,
+            recipient_name,
+            phone,
+            email,
+            title: String::new(),
+            content: String::new(),
+        })
+        .collect())
+}
+
+// 每个接收人一条个人通知，在线时通过 WebSocket 推送，并交给站外通道
+async fn deliver(
+    pool: &PgPool,
+    hub: &Hub,
+    recipients: Vec<OutboundAlert>,
+    title: &str,
+    content: &str,
+    is_important: bool,
+    actor_id: Uuid,
+) -> Result<(), AppError> {
+    let config = load_config().map_err(|_| AppError::Internal)?;
+    let channel = channel_from_config(&config)?;
+
+    for mut recipient in recipients {
+        let notice: NoticeResponse = sqlx::query_as::<_, NoticeRow>(
+            "INSERT INTO notices (title, content, author_id, target_type, target_id, is_important)
+             VALUES ($1, $2, $3, 'person', $4, $5)
+             RETURNING id, title, content, author_id, (SELECT name FROM persons WHERE id = $3) as author_name,
+             target_type, target_id, is_important, created_at",
+        )
+        .bind(title)
+        .bind(content)
+        .bind(actor_id)
+        .bind(recipient.recipient_id)
+        .bind(is_important)
+        .fetch_one(pool)
+        .await?
+        .into();
+
+        let message = serde_json::json!({ "type": "notice", "notice": notice }).to_string();
+        hub.push(recipient.recipient_id, &message);
+
+        recipient.title = title.to_string();
+        recipient.content = content.to_string();
+        if let Err(e) = channel.send(&recipient).await {
+            tracing::warn!("考勤提醒站外投递失败（{}）: {:?}", recipient.recipient_name, e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(status: &str, threshold: i32, window_days: i32) -> AttendanceAlertRule {
+        AttendanceAlertRule {
+            id: Uuid::nil(),
+            name: String::new(),
+            status: status.to_string(),
+            threshold,
+            window_days,
+            notify_parents: true,
+            notify_head_teacher: true,
+            notify_grade_leader: false,
+            is_active: true,
+        }
+    }
+
+    #[test]
+    fn test_alert_rules() {
+        let first = rule("absent", 1, 1);
+        assert!(should_alert(&first, 1, false));
+        // 同一天已经提醒过不再重复
+        assert!(!should_alert(&first, 1, true));
+
+        let repeated = rule("absent", 3, 30);
+        assert!(!should_alert(&repeated, 2, false));
+        assert!(should_alert(&repeated, 3, false));
+
+        let date = NaiveDate::from_ymd_opt(2025, 3, 12).unwrap();
+        let (title, content) = alert_message(&rule("late", 1, 1), "张三", Some("一班"), date, 1);
+        assert_eq!(title, "【迟到提醒】张三 03月12日迟到");
+        assert_eq!(content, "张三（一班） 2025-03-12 的考勤记录为迟到。");
+        let (title, _) = alert_message(&repeated, "张三", None, date, 3);
+        assert_eq!(title, "【多次缺勤】张三 近 30 天缺勤 3 次");
+    }
+}
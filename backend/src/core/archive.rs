@@ -0,0 +1,121 @@
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::core::error::AppError;
+
+/// 支持软删除（归档）的实体
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveTarget {
+    Person,
+    Class,
+    Department,
+}
+
+impl ArchiveTarget {
+    /// 对应的数据表名（固定值，可安全拼接到SQL中）
+    fn table(&self) -> &'static str {
+        match self {
+            ArchiveTarget::Person => "persons",
+            ArchiveTarget::Class => "classes",
+            ArchiveTarget::Department => "departments",
+        }
+    }
+
+    /// 中文名称，用于错误提示
+    fn label(&self) -> &'static str {
+        match self {
+            ArchiveTarget::Person => "人员",
+            ArchiveTarget::Class => "班级",
+            ArchiveTarget::Department => "部门",
+        }
+    }
+}
+
+/// 软删除：只标记 deleted_at，不触发级联删除
+pub async fn soft_delete(
+    pool: &PgPool,
+    target: ArchiveTarget,
+    id: Uuid,
+    deleted_by: Uuid,
+) -> Result<(), AppError> {
+    let mut conn = pool.acquire().await?;
+    mark_deleted(&mut conn, target, id, deleted_by).await
+}
+
+/// 在调用方的事务中软删除，便于与删除前的检查、移交一起提交
+pub async fn mark_deleted(
+    conn: &mut PgConnection,
+    target: ArchiveTarget,
+    id: Uuid,
+    deleted_by: Uuid,
+) -> Result<(), AppError> {
+    let sql = format!(
+        "UPDATE {} SET deleted_at = NOW(), deleted_by = $2 WHERE id = $1 AND deleted_at IS NULL",
+        target.table()
+    );
+    let result = sqlx::query(&sql)
+        .bind(id)
+        .bind(deleted_by)
+        .execute(&mut *conn)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(())
+}
+
+/// 恢复已归档的记录
+pub async fn restore(pool: &PgPool, target: ArchiveTarget, id: Uuid) -> Result<(), AppError> {
+    let sql = format!(
+        "UPDATE {} SET deleted_at = NULL, deleted_by = NULL WHERE id = $1 AND deleted_at IS NOT NULL",
+        target.table()
+    );
+    let result = sqlx::query(&sql).bind(id).execute(pool).await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(())
+}
+
+/// 彻底删除：仅允许删除归档时间超过保留期的记录
+/// 彻底删除会通过外键级联清除考勤、积分、小组成员等关联数据
+pub async fn purge(
+    pool: &PgPool,
+    target: ArchiveTarget,
+    id: Uuid,
+    retention_days: i64,
+) -> Result<(), AppError> {
+    let sql = format!("SELECT deleted_at FROM {} WHERE id = $1", target.table());
+    let deleted_at: Option<Option<chrono::DateTime<chrono::Utc>>> = sqlx::query_scalar(&sql)
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    let deleted_at = match deleted_at {
+        None => return Err(AppError::NotFound),
+        Some(None) => {
+            return Err(AppError::InvalidInput(format!(
+                "{}尚未归档，不能彻底删除",
+                target.label()
+            )))
+        }
+        Some(Some(deleted_at)) => deleted_at,
+    };
+
+    if chrono::Utc::now() - deleted_at < chrono::Duration::days(retention_days) {
+        return Err(AppError::InvalidInput(format!(
+            "{}归档未满 {} 天，暂不能彻底删除",
+            target.label(),
+            retention_days
+        )));
+    }
+
+    let sql = format!("DELETE FROM {} WHERE id = $1 AND deleted_at IS NOT NULL", target.table());
+    sqlx::query(&sql).bind(id).execute(pool).await?;
+
+    Ok(())
+}
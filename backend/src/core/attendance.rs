@@ -0,0 +1,366 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime};
+use rust_xlsxwriter::{Format, Workbook};
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::core::error::AppError;
+use crate::models::attendance::{AttendanceReport, StatusCounts};
+
+// 考勤状态，与数据库约束一致
+pub const STATUSES: &[&str] = &["present", "absent", "late", "early_leave", "excused"];
+
+/// 签到时间窗口
+#[derive(Debug, Clone, Copy)]
+pub struct CheckInWindow {
+    pub on_time_until: NaiveTime,
+    pub late_until: NaiveTime,
+}
+
+/// 按签到时间判断状态：不晚于 on_time_until 为出勤，不晚于 late_until 为迟到，之后为缺勤
+pub fn derive_status(time: NaiveTime, window: &CheckInWindow) -> &'static str {
+    if time <= window.on_time_until {
+        "present"
+    } else if time <= window.late_until {
+        "late"
+    } else {
+        "absent"
+    }
+}
+
+/// 确定考勤状态：指定了状态则校验后使用，否则按签到时间推断
+pub fn resolve_status(
+    status: Option<&str>,
+    time: Option<NaiveTime>,
+    window: Option<&CheckInWindow>,
+) -> Result<String, AppError> {
+    match (status, time) {
+        (Some(status), _) => {
+            if !STATUSES.contains(&status) {
+                return Err(AppError::InvalidInput(format!(
+                    "无效的考勤状态: {}，可选 {}",
+                    status,
+                    STATUSES.join("、")
+                )));
+            }
+            Ok(status.to_string())
+        }
+        (None, Some(time)) => match window {
+            Some(window) => Ok(derive_status(time, window).to_string()),
+            None => Err(AppError::InvalidInput("未配置签到时间窗口，请指定考勤状态".to_string())),
+        },
+        (None, None) => Err(AppError::InvalidInput("请指定考勤状态或签到时间".to_string())),
+    }
+}
+
+/// 时段的签到窗口：优先取单独配置；节次没有配置时按上课时间为准点、下课时间为迟到截止
+pub async fn window_for(pool: &PgPool, period_id: Option<Uuid>) -> Result<Option<CheckInWindow>, AppError> {
+    let configured: Option<(NaiveTime, NaiveTime)> = sqlx::query_as(
+        "SELECT on_time_until, late_until FROM attendance_windows WHERE period_id IS NOT DISTINCT FROM $1",
+    )
+    .bind(period_id)
+    .fetch_optional(pool)
+    .await?;
+    if let Some((on_time_until, late_until)) = configured {
+        return Ok(Some(CheckInWindow { on_time_until, late_until }));
+    }
+
+    let Some(period_id) = period_id else {
+        return Ok(None);
+    };
+    let period: Option<(NaiveTime, NaiveTime)> =
+        sqlx::query_as("SELECT start_time, end_time FROM periods WHERE id = $1")
+            .bind(period_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(period.map(|(on_time_until, late_until)| CheckInWindow { on_time_until, late_until }))
+}
+
+/// 节次必须存在；为空表示全天考勤
+pub async fn require_period(pool: &PgPool, period_id: Option<Uuid>) -> Result<(), AppError> {
+    if let Some(period_id) = period_id {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM periods WHERE id = $1)")
+            .bind(period_id)
+            .fetch_one(pool)
+            .await?;
+        if !exists {
+            return Err(AppError::InvalidInput("节次不存在".to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// 新建单条考勤时确定状态：校验节次，再按该时段的签到窗口校验或推断状态
+pub async fn status_for(
+    pool: &PgPool,
+    period_id: Option<Uuid>,
+    status: Option<&str>,
+    time: Option<NaiveTime>,
+) -> Result<String, AppError> {
+    require_period(pool, period_id).await?;
+    let window = window_for(pool, period_id).await?;
+    resolve_status(status, time, window.as_ref())
+}
+
+/// 考勤记录的一组可更正的值：状态、签到时间、备注
+pub type AttendanceValues = (String, Option<NaiveTime>, Option<String>);
+
+/// 记录一次更正；值没有变化时不记录
+pub async fn record_correction(
+    conn: &mut PgConnection,
+    attendance_id: Uuid,
+    old: &AttendanceValues,
+    new: &AttendanceValues,
+    reason: Option<&str>,
+    corrected_by: Uuid,
+) -> Result<(), AppError> {
+    if old == new {
+        return Ok(());
+    }
+    sqlx::query(
+        "INSERT INTO attendance_corrections
+             (attendance_id, person_id, date, period_id, old_status, new_status, old_time, new_time,
+              old_remark, new_remark, reason, corrected_by)
+         SELECT id, person_id, date, period_id, $2, $3, $4, $5, $6, $7, $8, $9
+         FROM attendances WHERE id = $1",
+    )
+    .bind(attendance_id)
+    .bind(&old.0)
+    .bind(&new.0)
+    .bind(old.1)
+    .bind(new.1)
+    .bind(&old.2)
+    .bind(&new.2)
+    .bind(reason)
+    .bind(corrected_by)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// 删除考勤记录：先记一条新值为空的更正，已有的更正记录保留
+pub async fn delete_attendance(
+    conn: &mut PgConnection,
+    attendance_id: Uuid,
+    reason: Option<&str>,
+    deleted_by: Uuid,
+) -> Result<(), AppError> {
+    let recorded = sqlx::query(
+        "INSERT INTO attendance_corrections
+             (attendance_id, person_id, date, period_id, old_status, old_time, old_remark, reason, corrected_by)
+         SELECT id, person_id, date, period_id, status, time, remark, $2, $3
+         FROM attendances WHERE id = $1",
+    )
+    .bind(attendance_id)
+    .bind(reason.unwrap_or("删除考勤记录"))
+    .bind(deleted_by)
+    .execute(&mut *conn)
+    .await?;
+    if recorded.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    sqlx::query("DELETE FROM attendances WHERE id = $1")
+        .bind(attendance_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+// 唯一约束冲突时的提示
+pub const DUPLICATE_MESSAGE: &str = "该学生在这一天（这一节）已有考勤记录，请修改原记录";
+
+// ========== 统计 ==========
+
+pub fn tally(counts: &mut StatusCounts, status: &str) {
+    match status {
+        "present" => counts.present += 1,
+        "absent" => counts.absent += 1,
+        "late" => counts.late += 1,
+        "early_leave" => counts.early_leave += 1,
+        "excused" => counts.excused += 1,
+        _ => {}
+    }
+}
+
+pub fn add_counts(total: &mut StatusCounts, counts: &StatusCounts) {
+    total.present += counts.present;
+    total.absent += counts.absent;
+    total.late += counts.late;
+    total.early_leave += counts.early_leave;
+    total.excused += counts.excused;
+}
+
+/// 出勤率：到校（出勤、迟到、早退）的记录占全部记录的比例，保留四位小数
+pub fn attendance_rate(counts: &StatusCounts) -> Option<f64> {
+    let attended = counts.present + counts.late + counts.early_leave;
+    let records = attended + counts.absent + counts.excused;
+    if records == 0 {
+        return None;
+    }
+    Some((attended as f64 / records as f64 * 10000.0).round() / 10000.0)
+}
+
+/// 连续缺勤天数：(最长, 截至最后一个上学日)。
+/// 按上学日顺序计算，其他状态中断连续；没有记录的日子既不中断也不累计
+pub fn absence_streaks(school_days: &[NaiveDate], statuses: &HashMap<NaiveDate, String>) -> (i64, i64) {
+    let mut longest = 0;
+    let mut current = 0;
+    for date in school_days {
+        match statuses.get(date).map(String::as_str) {
+            Some("absent") => {
+                current += 1;
+                longest = longest.max(current);
+            }
+            Some(_) => current = 0,
+            None => {}
+        }
+    }
+    (longest, current)
+}
+
+/// 趋势统计中 date 所在区间的第一天：day、week（星期一开始）或 month
+pub fn interval_start(date: NaiveDate, interval: &str) -> NaiveDate {
+    match interval {
+        "week" => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+        "month" => date.with_day(1).unwrap_or(date),
+        _ => date,
+    }
+}
+
+// ========== 月度考勤表导出 ==========
+
+// 考勤表中的状态符号
+pub fn status_mark(status: &str) -> &'static str {
+    match status {
+        "present" => "√",
+        "late" => "迟",
+        "early_leave" => "早",
+        "absent" => "缺",
+        "excused" => "假",
+        _ => "?",
+    }
+}
+
+const SUMMARY_HEADERS: [&str; 6] = ["出勤", "迟到", "早退", "缺勤", "请假", "出勤率"];
+
+fn report_header(report: &AttendanceReport) -> Vec<String> {
+    let mut header = vec!["学号".to_string(), "姓名".to_string()];
+    header.extend(report.days.iter().map(|d| d.format("%m-%d").to_string()));
+    header.extend(SUMMARY_HEADERS.iter().map(|h| h.to_string()));
+    header
+}
+
+fn format_rate(rate: Option<f64>) -> String {
+    rate.map(|r| format!("{:.1}%", r * 100.0)).unwrap_or_default()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// CSV 格式的月度考勤表，带 BOM 以便 Excel 正确识别 UTF-8
+pub fn render_report_csv(report: &AttendanceReport) -> String {
+    let mut lines = vec![report_header(report).iter().map(|h| csv_field(h)).collect::<Vec<_>>().join(",")];
+    for student in &report.students {
+        let mut fields = vec![csv_field(&student.student_no), csv_field(&student.name)];
+        fields.extend(student.statuses.iter().map(|s| s.as_deref().map(status_mark).unwrap_or("").to_string()));
+        let c = &student.counts;
+        fields.extend([c.present, c.late, c.early_leave, c.absent, c.excused].iter().map(|n| n.to_string()));
+        fields.push(format_rate(student.attendance_rate));
+        lines.push(fields.join(","));
+    }
+    format!("\u{feff}{}\r\n", lines.join("\r\n"))
+}
+
+/// Excel 格式的月度考勤表
+pub fn render_report_xlsx(report: &AttendanceReport) -> Result<Vec<u8>, AppError> {
+    let xlsx = |e: rust_xlsxwriter::XlsxError| AppError::InternalWithMessage(format!("生成考勤表失败: {}", e));
+
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.set_name(format!("{}考勤", report.month)).map_err(xlsx)?;
+    let bold = Format::new().set_bold();
+    let percent = Format::new().set_num_format("0.0%");
+
+    let title = format!("{} {} 考勤表", report.class_name, report.month);
+    sheet.write_string_with_format(0, 0, &title, &bold).map_err(xlsx)?;
+    for (col, header) in report_header(report).iter().enumerate() {
+        sheet.write_string_with_format(1, col as u16, header, &bold).map_err(xlsx)?;
+    }
+
+    let day_cols = report.days.len() as u16;
+    for (index, student) in report.students.iter().enumerate() {
+        let row = index as u32 + 2;
+        sheet.write_string(row, 0, &student.student_no).map_err(xlsx)?;
+        sheet.write_string(row, 1, &student.name).map_err(xlsx)?;
+        for (offset, status) in student.statuses.iter().enumerate() {
+            if let Some(status) = status {
+                sheet.write_string(row, 2 + offset as u16, status_mark(status)).map_err(xlsx)?;
+            }
+        }
+        let c = &student.counts;
+        for (offset, n) in [c.present, c.late, c.early_leave, c.absent, c.excused].iter().enumerate() {
+            sheet.write_number(row, 2 + day_cols + offset as u16, *n as f64).map_err(xlsx)?;
+        }
+        if let Some(rate) = student.attendance_rate {
+            sheet.write_number_with_format(row, 7 + day_cols, rate, &percent).map_err(xlsx)?;
+        }
+    }
+
+    sheet.set_column_width(0, 12).map_err(xlsx)?;
+    sheet.set_column_width(1, 10).map_err(xlsx)?;
+    for col in 0..day_cols {
+        sheet.set_column_width(2 + col, 6).map_err(xlsx)?;
+    }
+    sheet.set_freeze_panes(2, 2).map_err(xlsx)?;
+
+    workbook.save_to_buffer().map_err(xlsx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_status_from_time() {
+        let t = |s: &str| NaiveTime::parse_from_str(s, "%H:%M").unwrap();
+        let window = CheckInWindow { on_time_until: t("07:50"), late_until: t("08:30") };
+        assert_eq!(derive_status(t("07:30"), &window), "present");
+        assert_eq!(derive_status(t("07:50"), &window), "present");
+        assert_eq!(derive_status(t("08:05"), &window), "late");
+        assert_eq!(derive_status(t("08:31"), &window), "absent");
+
+        assert_eq!(resolve_status(Some("excused"), Some(t("09:00")), Some(&window)).unwrap(), "excused");
+        assert!(resolve_status(Some("sick"), None, Some(&window)).is_err());
+        assert!(resolve_status(None, Some(t("08:00")), None).is_err());
+    }
+
+    #[test]
+    fn test_absence_streaks() {
+        let d = |day: u32| NaiveDate::from_ymd_opt(2025, 3, day).unwrap();
+        // 3 月 3 日至 14 日的上学日（跳过周末）
+        let school_days: Vec<NaiveDate> = (3..=14).map(d).filter(|date| date.weekday().number_from_monday() <= 5).collect();
+        let statuses: HashMap<NaiveDate, String> = [
+            (3, "absent"), (4, "absent"), (5, "present"),
+            // 6 日没有记录，不中断连续
+            (7, "absent"), (10, "absent"), (11, "absent"),
+            (12, "late"), (13, "absent"), (14, "absent"),
+        ]
+        .into_iter()
+        .map(|(day, status)| (d(day), status.to_string()))
+        .collect();
+        assert_eq!(absence_streaks(&school_days, &statuses), (3, 2));
+
+        let mut counts = StatusCounts::default();
+        for status in statuses.values() {
+            tally(&mut counts, status);
+        }
+        assert_eq!(counts.absent, 7);
+        assert_eq!(attendance_rate(&counts), Some(0.2222));
+        assert_eq!(interval_start(d(13), "week"), d(10));
+    }
+}
@@ -39,4 +39,11 @@ pub fn verify_token(token: &str, secret: &str) -> Result<Claims, anyhow::Error>
     
     let token_data = decode::<Claims>(token, &secret, &validation)?;
     Ok(token_data.claims)
-}
\ No newline at end of file
+}
+
+/// 从可选的 Bearer 令牌中解析用户ID（公开路由使用，令牌无效时视为未登录）
+pub fn optional_user_id(token: Option<&str>) -> Option<uuid::Uuid> {
+    let config = crate::core::config::load_config().ok()?;
+    let claims = verify_token(token?, &config.jwt_secret).ok()?;
+    uuid::Uuid::parse_str(&claims.sub).ok()
+}
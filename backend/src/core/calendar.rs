@@ -0,0 +1,371 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use sqlx::PgPool;
+
+use crate::core::error::AppError;
+use crate::models::calendar::{CalendarDay, DayInfo, Term};
+
+// 单次查询的最大天数
+pub const MAX_RANGE_DAYS: i64 = 366;
+
+/// 星期几，1 表示星期一
+pub fn day_of_week(date: NaiveDate) -> i16 {
+    date.weekday().number_from_monday() as i16
+}
+
+/// 学期第几周：开学日所在周为第 1 周，以星期一为每周第一天
+pub fn week_of_term(term_start: NaiveDate, date: NaiveDate) -> i32 {
+    let first_monday = term_start - Duration::days(term_start.weekday().num_days_from_monday() as i64);
+    ((date - first_monday).num_days()).div_euclid(7) as i32 + 1
+}
+
+/// 学期共有几周
+pub fn term_weeks(term: &Term) -> i32 {
+    week_of_term(term.start_date, term.end_date)
+}
+
+/// 日期加减天数，超出可表示范围时报错
+pub fn add_days(date: NaiveDate, days: i64) -> Result<NaiveDate, AppError> {
+    date.checked_add_signed(Duration::days(days))
+        .ok_or_else(|| AppError::InvalidInput("日期超出范围".to_string()))
+}
+
+/// 学期第 week 周星期 dow 的日期，周次必须在学期范围内
+pub fn date_of_week(term: &Term, week: i32, dow: i16) -> Result<NaiveDate, AppError> {
+    let weeks = term_weeks(term);
+    if week < 1 || week > weeks {
+        return Err(AppError::InvalidInput(format!("{} 只有第 1 ~ {} 周", term.name, weeks)));
+    }
+    let first_monday = add_days(term.start_date, -(term.start_date.weekday().num_days_from_monday() as i64))?;
+    add_days(first_monday, (week as i64 - 1) * 7 + (dow as i64 - 1))
+}
+
+/// 判断某天是否上学日。
+/// 学期内：周一至周五上课，holiday 放假，workday 调休上课；学期外为假期。
+/// 尚未配置任何学期时（`configured` 为 false）不区分学期内外，只按星期和特殊日期判断
+pub fn classify(
+    date: NaiveDate,
+    term: Option<&Term>,
+    special: Option<&CalendarDay>,
+    configured: bool,
+) -> DayInfo {
+    let dow = day_of_week(date);
+    let (kind, name) = match (special, term.is_some() || !configured) {
+        (_, false) => ("vacation", None),
+        (Some(day), true) if day.kind == "holiday" => ("holiday", Some(day.name.clone())),
+        (Some(day), true) if day.kind == "workday" => ("workday", Some(day.name.clone())),
+        _ if dow >= 6 => ("weekend", None),
+        _ => ("school", None),
+    };
+    let is_school_day = kind == "school" || kind == "workday";
+    let schedule_day_of_week = match kind {
+        "school" => Some(dow),
+        "workday" => Some(special.and_then(|d| d.follows_day_of_week).unwrap_or(dow)),
+        _ => None,
+    };
+
+    DayInfo {
+        date,
+        day_of_week: dow,
+        is_school_day,
+        kind: kind.to_string(),
+        name,
+        term_id: term.map(|t| t.id),
+        term_name: term.map(|t| t.name.clone()),
+        week: term.map(|t| week_of_term(t.start_date, date)),
+        schedule_day_of_week,
+    }
+}
+
+// 是否已配置学期（校历）
+pub async fn is_configured(pool: &PgPool) -> Result<bool, AppError> {
+    let configured: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM terms)")
+        .fetch_one(pool)
+        .await?;
+    Ok(configured)
+}
+
+/// 当前学期；放假期间取下一个学期，没有则取最近结束的学期
+pub async fn current_term(pool: &PgPool, today: NaiveDate) -> Result<Option<Term>, AppError> {
+    let term = sqlx::query_as::<_, Term>(
+        "SELECT id, name, academic_year, start_date, end_date FROM terms
+         ORDER BY (end_date < $1), CASE WHEN end_date >= $1 THEN start_date END, end_date DESC
+         LIMIT 1",
+    )
+    .bind(today)
+    .fetch_optional(pool)
+    .await?;
+    Ok(term)
+}
+
+/// [from, to] 区间内每一天的校历情况
+pub async fn days_between(pool: &PgPool, from: NaiveDate, to: NaiveDate) -> Result<Vec<DayInfo>, AppError> {
+    if to < from {
+        return Err(AppError::InvalidInput("结束日期不能早于开始日期".to_string()));
+    }
+    if (to - from).num_days() >= MAX_RANGE_DAYS {
+        return Err(AppError::InvalidInput(format!("查询范围不能超过 {} 天", MAX_RANGE_DAYS)));
+    }
+
+    let configured = is_configured(pool).await?;
+    let terms = sqlx::query_as::<_, Term>(
+        "SELECT id, name, academic_year, start_date, end_date FROM terms
+         WHERE start_date <= $2 AND end_date >= $1",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+    let specials = sqlx::query_as::<_, CalendarDay>(
+        "SELECT date, kind, name, follows_day_of_week FROM calendar_days WHERE date BETWEEN $1 AND $2",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+
+    let days = from
+        .iter_days()
+        .take((to - from).num_days() as usize + 1)
+        .map(|date| {
+            let term = terms.iter().find(|t| t.start_date <= date && t.end_date >= date);
+            let special = specials.iter().find(|d| d.date == date);
+            classify(date, term, special, configured)
+        })
+        .collect();
+    Ok(days)
+}
+
+pub async fn day_info(pool: &PgPool, date: NaiveDate) -> Result<DayInfo, AppError> {
+    let mut days = days_between(pool, date, date).await?;
+    Ok(days.remove(0))
+}
+
+/// [from, to] 区间内的上学日，用于按上学日统计
+pub async fn school_days_between(pool: &PgPool, from: NaiveDate, to: NaiveDate) -> Result<Vec<NaiveDate>, AppError> {
+    Ok(days_between(pool, from, to)
+        .await?
+        .into_iter()
+        .filter(|d| d.is_school_day)
+        .map(|d| d.date)
+        .collect())
+}
+
+/// 考勤等按天记录的数据只能落在上学日；尚未配置学期时不做限制
+pub async fn require_school_day(pool: &PgPool, date: NaiveDate) -> Result<(), AppError> {
+    if !is_configured(pool).await? {
+        return Ok(());
+    }
+    let info = day_info(pool, date).await?;
+    if info.is_school_day {
+        return Ok(());
+    }
+    let reason = match info.kind.as_str() {
+        "holiday" => info.name.unwrap_or_else(|| "节假日".to_string()),
+        "weekend" => "周末".to_string(),
+        _ => "假期".to_string(),
+    };
+    Err(AppError::InvalidInput(format!("{} 不是上学日（{}）", date, reason)))
+}
+
+// ========== 日期表达式 ==========
+
+/// 解析数字或中文数字（一 ~ 九十九）
+fn parse_number(s: &str) -> Option<i32> {
+    if let Ok(n) = s.parse::<i32>() {
+        return Some(n);
+    }
+    let digit = |c: char| "零一二三四五六七八九".chars().position(|d| d == c).map(|p| p as i32);
+    let chars: Vec<char> = s.chars().collect();
+    match chars.as_slice() {
+        ['十'] => Some(10),
+        [c] => digit(*c),
+        ['十', u] => digit(*u).map(|u| 10 + u),
+        [t, '十'] => digit(*t).map(|t| t * 10),
+        [t, '十', u] => Some(digit(*t)? * 10 + digit(*u)?),
+        _ => None,
+    }
+}
+
+/// 解析“周三”“星期日”“礼拜五”等，返回星期几（1 表示星期一）
+fn parse_weekday(s: &str) -> Option<i16> {
+    let rest = ["星期", "礼拜", "周"].iter().find_map(|p| s.strip_prefix(p))?;
+    match rest {
+        "一" | "1" => Some(1),
+        "二" | "2" => Some(2),
+        "三" | "3" => Some(3),
+        "四" | "4" => Some(4),
+        "五" | "5" => Some(5),
+        "六" | "6" => Some(6),
+        "日" | "天" | "七" | "7" => Some(7),
+        _ => None,
+    }
+}
+
+/// 不依赖校历的相对日期：今天、明天、前天、周三、下周一、上星期五等
+pub fn parse_relative(s: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let s = s.trim();
+    let offset = match s {
+        "今天" | "今日" | "today" | "now" => Some(0),
+        "明天" | "明日" | "tomorrow" => Some(1),
+        "后天" => Some(2),
+        "昨天" | "昨日" | "yesterday" => Some(-1),
+        "前天" => Some(-2),
+        _ => None,
+    };
+    if let Some(days) = offset {
+        return today.checked_add_signed(Duration::days(days));
+    }
+    if let Ok(date) = s.parse::<NaiveDate>() {
+        return Some(date);
+    }
+
+    let (weeks, rest) = if let Some(rest) = s.strip_prefix("下") {
+        (1, rest)
+    } else if let Some(rest) = s.strip_prefix("上") {
+        (-1, rest)
+    } else if let Some(rest) = s.strip_prefix("本").or_else(|| s.strip_prefix("这")) {
+        (0, rest)
+    } else {
+        (0, s)
+    };
+    let dow = parse_weekday(rest)?;
+    let monday = today.checked_sub_signed(Duration::days(today.weekday().num_days_from_monday() as i64))?;
+    monday.checked_add_signed(Duration::days(weeks * 7 + dow as i64 - 1))
+}
+
+/// 解析日期表达式，在 `parse_relative` 基础上支持依赖校历的说法：
+/// 第3周周二、第十二周星期五（当前学期）、开学第一天、下个上学日、上个上学日
+pub async fn resolve_date(pool: &PgPool, s: &str, today: NaiveDate) -> Result<Option<NaiveDate>, AppError> {
+    if let Some(date) = parse_relative(s, today) {
+        return Ok(Some(date));
+    }
+    let s = s.trim();
+
+    match s {
+        "开学第一天" | "开学日" | "开学" => {
+            return Ok(current_term(pool, today).await?.map(|t| t.start_date));
+        }
+        "下个上学日" | "下一个上学日" | "下个教学日" => {
+            let days = school_days_between(pool, add_days(today, 1)?, add_days(today, MAX_RANGE_DAYS - 1)?).await?;
+            return Ok(days.first().copied());
+        }
+        "上个上学日" | "上一个上学日" | "上个教学日" => {
+            let days = school_days_between(pool, add_days(today, 1 - MAX_RANGE_DAYS)?, add_days(today, -1)?).await?;
+            return Ok(days.last().copied());
+        }
+        _ => {}
+    }
+
+    // 第N周周X
+    if let Some((week, weekday)) = s.strip_prefix('第').and_then(|r| r.split_once('周')) {
+        let dow = parse_weekday(weekday).or_else(|| parse_weekday(&format!("周{}", weekday)));
+        let (Some(week), Some(dow)) = (parse_number(week), dow) else {
+            return Ok(None);
+        };
+        let Some(term) = current_term(pool, today).await? else {
+            return Ok(None);
+        };
+        let date = date_of_week(&term, week, dow)?;
+        if date > term.end_date {
+            return Err(AppError::InvalidInput(format!("{} 没有第{}周", term.name, week)));
+        }
+        return Ok(Some(date));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn d(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn term() -> Term {
+        // 2024-09-02 为星期一，2025-01-17 为第 20 周星期五
+        Term {
+            id: Uuid::nil(),
+            name: "第一学期".to_string(),
+            academic_year: "2024-2025".to_string(),
+            start_date: d("2024-09-02"),
+            end_date: d("2025-01-17"),
+        }
+    }
+
+    #[test]
+    fn test_week_of_term() {
+        let term = term();
+        assert_eq!(week_of_term(term.start_date, d("2024-09-08")), 1);
+        assert_eq!(week_of_term(term.start_date, d("2024-09-09")), 2);
+        assert_eq!(term_weeks(&term), 20);
+    }
+
+    #[test]
+    fn test_date_of_week() {
+        let term = term();
+        assert_eq!(date_of_week(&term, 3, 2).unwrap(), d("2024-09-17"));
+        assert_eq!(date_of_week(&term, 20, 5).unwrap(), d("2025-01-17"));
+    }
+
+    #[test]
+    fn test_date_of_week_out_of_range() {
+        let term = term();
+        for week in [0, -1, 21, i32::MAX, i32::MIN] {
+            assert!(matches!(date_of_week(&term, week, 1), Err(AppError::InvalidInput(_))));
+        }
+    }
+
+    #[test]
+    fn test_add_days_out_of_range() {
+        assert!(add_days(NaiveDate::MAX, 6).is_err());
+        assert!(add_days(NaiveDate::MIN, -1).is_err());
+        assert_eq!(add_days(d("2024-12-31"), 1).unwrap(), d("2025-01-01"));
+    }
+
+    #[test]
+    fn test_classify_holiday_and_workday() {
+        let term = term();
+        let holiday = CalendarDay { date: d("2024-10-01"), kind: "holiday".to_string(), name: "国庆节".to_string(), follows_day_of_week: None };
+        let workday = CalendarDay { date: d("2024-09-29"), kind: "workday".to_string(), name: "国庆调休".to_string(), follows_day_of_week: Some(1) };
+        assert!(!classify(d("2024-10-01"), Some(&term), Some(&holiday), true).is_school_day);
+        let info = classify(d("2024-09-29"), Some(&term), Some(&workday), true);
+        assert!(info.is_school_day);
+        assert_eq!(info.schedule_day_of_week, Some(1));
+        assert!(!classify(d("2024-09-07"), Some(&term), None, true).is_school_day);
+    }
+
+    #[test]
+    fn test_classify_outside_term() {
+        assert_eq!(classify(d("2024-08-20"), None, None, true).kind, "vacation");
+        // 尚未配置学期时只按星期判断
+        assert!(classify(d("2024-08-20"), None, None, false).is_school_day);
+    }
+
+    #[test]
+    fn test_parse_relative() {
+        // 2024-09-11 为星期三
+        let today = d("2024-09-11");
+        assert_eq!(parse_relative("明天", today), Some(d("2024-09-12")));
+        assert_eq!(parse_relative("下周一", today), Some(d("2024-09-16")));
+        assert_eq!(parse_relative("周五", today), Some(d("2024-09-13")));
+        assert_eq!(parse_relative("上星期日", today), Some(d("2024-09-08")));
+    }
+
+    #[test]
+    fn test_parse_relative_out_of_range() {
+        assert_eq!(parse_relative("明天", NaiveDate::MAX), None);
+        assert_eq!(parse_relative("下周一", NaiveDate::MAX), None);
+        assert_eq!(parse_relative("上周一", NaiveDate::MIN), None);
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("十二"), Some(12));
+        assert_eq!(parse_number("二十"), Some(20));
+        assert_eq!(parse_number("3"), Some(3));
+        assert_eq!(parse_number("周"), None);
+    }
+}
@@ -11,6 +11,13 @@ pub struct Config {
     pub server_port: u16,
     pub ws_path: String,
     pub plugin_dir: String,
+    pub archive_retention_days: i64,
+    pub storage_backend: String,
+    pub storage_dir: String,
+    pub upload_max_bytes: usize,
+    pub upload_allowed_types: Vec<String>,
+    pub kiosk_token_ttl_secs: u64,
+    pub alert_channel: String,
 }
 
 pub fn load_config() -> Result<Config, anyhow::Error> {
@@ -26,6 +33,24 @@ pub fn load_config() -> Result<Config, anyhow::Error> {
             .parse()?,
         ws_path: env::var("WS_PATH").unwrap_or_else(|_| "/ws".to_string()),
         plugin_dir: env::var("PLUGIN_DIR").unwrap_or_else(|_| "plugins".to_string()),
+        archive_retention_days: env::var("ARCHIVE_RETENTION_DAYS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse()?,
+        storage_backend: env::var("STORAGE_BACKEND").unwrap_or_else(|_| "local".to_string()),
+        storage_dir: env::var("STORAGE_DIR").unwrap_or_else(|_| "uploads".to_string()),
+        upload_max_bytes: env::var("UPLOAD_MAX_BYTES")
+            .unwrap_or_else(|_| "10485760".to_string())
+            .parse()?,
+        upload_allowed_types: env::var("UPLOAD_ALLOWED_TYPES")
+            .unwrap_or_else(|_| "image/jpeg,image/png,image/gif,image/webp,application/pdf".to_string())
+            .split(',')
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect(),
+        kiosk_token_ttl_secs: env::var("KIOSK_TOKEN_TTL_SECS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse()?,
+        alert_channel: env::var("ALERT_CHANNEL").unwrap_or_else(|_| "log".to_string()),
     };
 
     Ok(config)
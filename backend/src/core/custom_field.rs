@@ -0,0 +1,184 @@
+use std::collections::HashSet;
+
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::core::error::AppError;
+use crate::core::permission::PermissionManager;
+use crate::models::custom_field::{CustomFieldDefinition, CustomFields};
+
+pub const FIELD_TYPES: &[&str] = &["text", "number", "boolean", "date", "enum"];
+
+/// 加载某实体的字段定义；person_type 不为空时只返回适用于该类人员的定义
+pub async fn load_definitions(
+    pool: &PgPool,
+    entity: &str,
+    person_type: Option<&str>,
+) -> Result<Vec<CustomFieldDefinition>, AppError> {
+    let defs = sqlx::query_as::<_, CustomFieldDefinition>(
+        "SELECT * FROM custom_field_definitions
+         WHERE entity = $1 AND ($2::text IS NULL OR person_type IS NULL OR person_type = $2)
+         ORDER BY sort_order, created_at",
+    )
+    .bind(entity)
+    .bind(person_type)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(defs)
+}
+
+/// 按定义校验取值：不允许未定义的键，检查类型、枚举选项和必填项
+pub fn validate(defs: &[CustomFieldDefinition], values: &CustomFields) -> Result<(), AppError> {
+    for key in values.keys() {
+        if !defs.iter().any(|d| &d.key == key) {
+            return Err(AppError::InvalidInput(format!("未定义的自定义字段: {}", key)));
+        }
+    }
+
+    for def in defs {
+        let value = match values.get(&def.key) {
+            Some(Value::Null) | None => {
+                if def.required {
+                    return Err(AppError::InvalidInput(format!("{}为必填项", def.label)));
+                }
+                continue;
+            }
+            Some(value) => value,
+        };
+
+        let valid = match def.field_type.as_str() {
+            "text" => value.is_string(),
+            "number" => value.is_number(),
+            "boolean" => value.is_boolean(),
+            "date" => value
+                .as_str()
+                .map(|s| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok())
+                .unwrap_or(false),
+            "enum" => match (value.as_str(), def.options.as_array()) {
+                (Some(s), Some(options)) => options.iter().any(|o| o.as_str() == Some(s)),
+                _ => false,
+            },
+            _ => false,
+        };
+
+        if !valid {
+            return Err(AppError::InvalidInput(format!(
+                "{}的值不符合字段类型 {}",
+                def.label, def.field_type
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// 将更新内容合并到已有取值中，值为 null 表示删除该字段
+pub fn merge(mut existing: CustomFields, patch: CustomFields) -> CustomFields {
+    for (key, value) in patch {
+        if value.is_null() {
+            existing.remove(&key);
+        } else {
+            existing.insert(key, value);
+        }
+    }
+    existing
+}
+
+/// 当前用户无权查看的字段键；未登录时所有设置了可见性权限的字段都不可见
+pub async fn hidden_keys(
+    pool: &PgPool,
+    entity: &str,
+    user_id: Option<Uuid>,
+) -> Result<HashSet<String>, AppError> {
+    let defs: Vec<CustomFieldDefinition> = load_definitions(pool, entity, None)
+        .await?
+        .into_iter()
+        .filter(|d| d.visibility_permission.is_some())
+        .collect();
+
+    // 用户权限只加载一次，再逐个字段判断
+    let permissions: Vec<&str> = defs.iter().filter_map(|d| d.visibility_permission.as_deref()).collect();
+    let allowed = match user_id {
+        Some(user_id) if !permissions.is_empty() => {
+            PermissionManager::new(pool.clone()).check_permissions(user_id, &permissions).await
+        }
+        _ => vec![false; permissions.len()],
+    };
+
+    Ok(defs
+        .into_iter()
+        .zip(allowed)
+        .filter(|(_, allowed)| !allowed)
+        .map(|(def, _)| def.key)
+        .collect())
+}
+
+/// 解析列表查询中的自定义字段过滤条件（JSON 对象），并拒绝按不可见字段过滤
+pub fn parse_filter(raw: &str, hidden: &HashSet<String>) -> Result<Value, AppError> {
+    let filter: Value = serde_json::from_str(raw)
+        .map_err(|_| AppError::InvalidInput("custom_fields 过滤条件必须是JSON对象".to_string()))?;
+    let object = filter
+        .as_object()
+        .ok_or_else(|| AppError::InvalidInput("custom_fields 过滤条件必须是JSON对象".to_string()))?;
+    if let Some(key) = object.keys().find(|k| hidden.contains(*k)) {
+        return Err(AppError::Auth(format!("无权按字段 {} 过滤", key)));
+    }
+    Ok(filter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn def(key: &str, field_type: &str, required: bool, options: Value) -> CustomFieldDefinition {
+        CustomFieldDefinition {
+            id: Uuid::new_v4(),
+            entity: "person".to_string(),
+            key: key.to_string(),
+            label: key.to_string(),
+            field_type: field_type.to_string(),
+            required,
+            options,
+            person_type: None,
+            visibility_permission: None,
+            sort_order: 0,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_validate_custom_fields() {
+        let defs = vec![
+            def("dorm", "text", true, json!([])),
+            def("bus_route", "enum", false, json!(["A", "B"])),
+            def("allergy", "boolean", false, json!([])),
+        ];
+
+        let ok = json!({"dorm": "301", "bus_route": "A"});
+        assert!(validate(&defs, ok.as_object().unwrap()).is_ok());
+
+        // 缺少必填项
+        let missing = json!({"bus_route": "B"});
+        assert!(validate(&defs, missing.as_object().unwrap()).is_err());
+
+        // 枚举值不在选项中
+        let bad_enum = json!({"dorm": "301", "bus_route": "C"});
+        assert!(validate(&defs, bad_enum.as_object().unwrap()).is_err());
+
+        // 类型不匹配
+        let bad_type = json!({"dorm": "301", "allergy": "yes"});
+        assert!(validate(&defs, bad_type.as_object().unwrap()).is_err());
+
+        // 未定义的字段
+        let unknown = json!({"dorm": "301", "id_card": "x"});
+        assert!(validate(&defs, unknown.as_object().unwrap()).is_err());
+
+        // 合并时 null 删除字段
+        let merged = merge(ok.as_object().unwrap().clone(), json!({"bus_route": null}).as_object().unwrap().clone());
+        assert!(!merged.contains_key("bus_route"));
+        assert_eq!(merged.get("dorm"), Some(&json!("301")));
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use sqlx::{PgConnection, PgPool, Row};
+use uuid::Uuid;
+
+use crate::core::error::AppError;
+use crate::models::history::FieldChange;
+
+/// 人员某一时刻的字段快照，字段值统一转为文本便于比较和存储
+pub type PersonSnapshot = BTreeMap<String, Option<String>>;
+
+// 纳入版本记录的字段
+const TRACKED_FIELDS: &[&str] = &[
+    "name",
+    "gender",
+    "birthday",
+    "phone",
+    "email",
+    "role",
+    "is_active",
+    "student_no",
+    "class_id",
+    "status",
+    "enrollment_date",
+    "employee_no",
+    "department_id",
+    "title",
+    "hire_date",
+    "wechat_openid",
+    "occupation",
+    "teacher_classes",
+    "custom_fields",
+];
+
+/// 读取人员当前快照；人员不存在时返回空快照
+pub async fn snapshot(conn: &mut PgConnection, person_id: Uuid) -> Result<PersonSnapshot, AppError> {
+    let row = sqlx::query(
+        "SELECT p.name, p.gender::text AS gender, p.birthday::text AS birthday, p.phone, p.email,
+                p.role, p.is_active::text AS is_active,
+                s.student_no, s.class_id::text AS class_id, s.status, s.enrollment_date::text AS enrollment_date,
+                t.employee_no, t.department_id::text AS department_id, t.title, t.hire_date::text AS hire_date,
+                pa.wechat_openid, pa.occupation,
+                (SELECT string_agg(tc.class_id::text || CASE WHEN tc.is_main_teacher THEN '(班主任)' ELSE '' END, ',' ORDER BY tc.class_id)
+                 FROM teacher_class tc WHERE tc.teacher_id = p.id) AS teacher_classes,
+                p.custom_fields::text AS custom_fields
+         FROM persons p
+         LEFT JOIN students s ON p.id = s.person_id
+         LEFT JOIN teachers t ON p.id = t.person_id
+         LEFT JOIN parents pa ON p.id = pa.person_id
+         WHERE p.id = $1",
+    )
+    .bind(person_id)
+    .fetch_optional(conn)
+    .await?;
+
+    let mut snapshot = PersonSnapshot::new();
+    if let Some(row) = row {
+        for field in TRACKED_FIELDS {
+            snapshot.insert(field.to_string(), row.get::<Option<String>, _>(*field));
+        }
+    }
+    Ok(snapshot)
+}
+
+/// 比较两个快照，返回发生变化的字段
+pub fn diff(before: &PersonSnapshot, after: &PersonSnapshot) -> Vec<FieldChange> {
+    TRACKED_FIELDS
+        .iter()
+        .filter_map(|field| {
+            let old_value = before.get(*field).cloned().flatten();
+            let new_value = after.get(*field).cloned().flatten();
+            if old_value == new_value {
+                None
+            } else {
+                Some(FieldChange {
+                    field: field.to_string(),
+                    old_value,
+                    new_value,
+                })
+            }
+        })
+        .collect()
+}
+
+/// 将前后快照的差异写入 person_history，同一次变更共用一个 change_id
+pub async fn record(
+    conn: &mut PgConnection,
+    person_id: Uuid,
+    before: &PersonSnapshot,
+    after: &PersonSnapshot,
+    changed_by: Uuid,
+) -> Result<(), AppError> {
+    let changes = diff(before, after);
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let change_id = Uuid::new_v4();
+    for change in changes {
+        sqlx::query(
+            "INSERT INTO person_history (person_id, change_id, field, old_value, new_value, changed_by)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(person_id)
+        .bind(change_id)
+        .bind(&change.field)
+        .bind(&change.old_value)
+        .bind(&change.new_value)
+        .bind(changed_by)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// 查询学生在指定日期（当天结束时）所在的班级
+/// 优先取该日期之前最后一次班级变更后的值；若之后才有变更，则取第一次变更前的值；都没有则为当前班级
+pub async fn class_on_date(pool: &PgPool, student_id: Uuid, date: NaiveDate) -> Result<Option<Uuid>, AppError> {
+    let before: Option<Option<String>> = sqlx::query_scalar(
+        "SELECT new_value FROM person_history
+         WHERE person_id = $1 AND field = 'class_id' AND changed_at < ($2::date + 1)
+         ORDER BY changed_at DESC LIMIT 1",
+    )
+    .bind(student_id)
+    .bind(date)
+    .fetch_optional(pool)
+    .await?;
+
+    let value = match before {
+        Some(value) => value,
+        None => {
+            let after: Option<Option<String>> = sqlx::query_scalar(
+                "SELECT old_value FROM person_history
+                 WHERE person_id = $1 AND field = 'class_id' AND changed_at >= ($2::date + 1)
+                 ORDER BY changed_at ASC LIMIT 1",
+            )
+            .bind(student_id)
+            .bind(date)
+            .fetch_optional(pool)
+            .await?;
+
+            match after {
+                Some(value) => value,
+                None => {
+                    let current: Option<Option<Uuid>> =
+                        sqlx::query_scalar("SELECT class_id FROM students WHERE person_id = $1")
+                            .bind(student_id)
+                            .fetch_optional(pool)
+                            .await?;
+                    return Ok(current.flatten());
+                }
+            }
+        }
+    };
+
+    Ok(value.and_then(|v| Uuid::parse_str(&v).ok()))
+}
+
+/// 查询指定日期（当天结束时）在该班的学生，规则与 class_on_date 相同，一次查询完成；
+/// 候选为当前在班的学生以及历史上转入、转出过该班的学生
+pub async fn class_members_on_date(pool: &PgPool, class_id: Uuid, date: NaiveDate) -> Result<Vec<Uuid>, AppError> {
+    let members = sqlx::query_scalar(
+        "WITH candidates AS (
+             SELECT person_id FROM students WHERE class_id = $1
+             UNION
+             SELECT person_id FROM person_history
+             WHERE field = 'class_id' AND (old_value = $1::text OR new_value = $1::text)
+         )
+         SELECT c.person_id FROM candidates c
+         LEFT JOIN LATERAL (
+             SELECT true AS found, new_value FROM person_history
+             WHERE person_id = c.person_id AND field = 'class_id' AND changed_at < ($2::date + 1)
+             ORDER BY changed_at DESC LIMIT 1
+         ) before ON true
+         LEFT JOIN LATERAL (
+             SELECT true AS found, old_value FROM person_history
+             WHERE person_id = c.person_id AND field = 'class_id' AND changed_at >= ($2::date + 1)
+             ORDER BY changed_at ASC LIMIT 1
+         ) after ON true
+         LEFT JOIN students s ON s.person_id = c.person_id
+         WHERE $1::text = CASE
+             WHEN before.found THEN before.new_value
+             WHEN after.found THEN after.old_value
+             ELSE s.class_id::text
+         END",
+    )
+    .bind(class_id)
+    .bind(date)
+    .fetch_all(pool)
+    .await?;
+    Ok(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_only_reports_changed_fields() {
+        let mut before = PersonSnapshot::new();
+        before.insert("name".to_string(), Some("张三".to_string()));
+        before.insert("phone".to_string(), None);
+        before.insert("class_id".to_string(), Some("a".to_string()));
+
+        let mut after = before.clone();
+        after.insert("name".to_string(), Some("张三丰".to_string()));
+        after.insert("phone".to_string(), Some("13800000000".to_string()));
+
+        let changes = diff(&before, &after);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].field, "name");
+        assert_eq!(changes[0].old_value.as_deref(), Some("张三"));
+        assert_eq!(changes[0].new_value.as_deref(), Some("张三丰"));
+        assert_eq!(changes[1].field, "phone");
+        assert_eq!(changes[1].old_value, None);
+
+        // 新建人员：空快照到完整快照
+        let created = diff(&PersonSnapshot::new(), &after);
+        assert_eq!(created.len(), 3);
+    }
+}
@@ -0,0 +1,97 @@
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::core::error::AppError;
+
+/// 签到码内容：签发设备、班级和一次性随机数
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KioskClaims {
+    pub sub: String,      // 设备ID
+    pub class_id: String, // 班级ID
+    pub nonce: String,    // 每个签到码不同，用于防止重复使用
+    pub exp: u64,         // 过期时间
+}
+
+// 签到码使用单独的密钥，不能当作登录令牌使用
+fn signing_key(jwt_secret: &str) -> String {
+    format!("kiosk:{}", jwt_secret)
+}
+
+/// 为设备签发签到码，返回 (签到码, 过期时间戳)
+pub fn sign_token(device_id: Uuid, class_id: Uuid, jwt_secret: &str, ttl_secs: u64) -> Result<(String, u64), AppError> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_err(|_| AppError::Internal)?;
+    let claims = KioskClaims {
+        sub: device_id.to_string(),
+        class_id: class_id.to_string(),
+        nonce: Uuid::new_v4().to_string(),
+        exp: now.as_secs() + ttl_secs,
+    };
+    let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(signing_key(jwt_secret).as_ref()))
+        .map_err(|_| AppError::Internal)?;
+    Ok((token, claims.exp))
+}
+
+/// 校验签到码的签名和有效期（不留宽限时间）
+pub fn verify_token(token: &str, jwt_secret: &str) -> Result<KioskClaims, AppError> {
+    let mut validation = Validation::default();
+    validation.leeway = 0;
+    decode::<KioskClaims>(token, &DecodingKey::from_secret(signing_key(jwt_secret).as_ref()), &validation)
+        .map(|data| data.claims)
+        .map_err(|e| match e.kind() {
+            jsonwebtoken::errors::ErrorKind::ExpiredSignature => {
+                AppError::InvalidInput("签到码已过期，请扫描屏幕上最新的二维码".to_string())
+            }
+            _ => AppError::InvalidInput("无效的签到码".to_string()),
+        })
+}
+
+/// 设备密钥：两个随机 UUID 拼接的 64 位十六进制字符串
+pub fn generate_secret() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// 两个经纬度之间的距离（米）
+pub fn distance_m(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
+    const EARTH_RADIUS_M: f64 = 6_371_000.0;
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_phi = (lat2 - lat1).to_radians();
+    let d_lambda = (lng2 - lng1).to_radians();
+    let a = (d_phi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * a.sqrt().asin()
+}
+
+pub fn validate_location(latitude: Option<f64>, longitude: Option<f64>) -> Result<(), AppError> {
+    match (latitude, longitude) {
+        (None, None) => Ok(()),
+        (Some(lat), Some(lng)) if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lng) => Ok(()),
+        (Some(_), Some(_)) => Err(AppError::InvalidInput("无效的经纬度".to_string())),
+        _ => Err(AppError::InvalidInput("经度和纬度需同时提供".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_and_distance() {
+        let device = Uuid::new_v4();
+        let class = Uuid::new_v4();
+        let (token, _) = sign_token(device, class, "secret", 30).unwrap();
+        let claims = verify_token(&token, "secret").unwrap();
+        assert_eq!(claims.sub, device.to_string());
+        assert_eq!(claims.class_id, class.to_string());
+        // 其他密钥签发的令牌（如登录令牌）不能作为签到码
+        assert!(verify_token(&token, "other").is_err());
+        let (expired, _) = sign_token(device, class, "secret", 0).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+        assert!(verify_token(&expired, "secret").is_err());
+
+        // 纬度相差 0.001 度约 111 米
+        let d = distance_m(39.9, 116.4, 39.901, 116.4);
+        assert!((d - 111.2).abs() < 1.0);
+        assert_eq!(generate_secret().len(), 64);
+    }
+}
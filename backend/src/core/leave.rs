@@ -0,0 +1,177 @@
+use chrono::NaiveDate;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::core::attendance as rules;
+use crate::core::error::AppError;
+
+pub const LEAVE_TYPES: &[&str] = &["sick", "personal", "other"];
+// 超过这么多个上学日的请假，班主任同意后还需复核
+pub const LONG_LEAVE_SCHOOL_DAYS: i32 = 3;
+
+/// 同意后的状态。
+/// 待班主任审批：短假直接批准，长假转为待复核（审批人本身有复核权限时直接批准）；
+/// 待复核：只有复核人可以批准
+pub fn after_approval(status: &str, school_days: i32, can_review: bool) -> Result<&'static str, AppError> {
+    match status {
+        "pending" if school_days > LONG_LEAVE_SCHOOL_DAYS && !can_review => Ok("escalated"),
+        "pending" => Ok("approved"),
+        "escalated" if can_review => Ok("approved"),
+        "escalated" => Err(AppError::Auth("该请假超过规定天数，需要有复核权限的人员审批".to_string())),
+        _ => Err(AppError::InvalidInput("该请假申请已处理".to_string())),
+    }
+}
+
+/// 审批步骤：待班主任审批为第 1 步，待复核为第 2 步，撤销已批准的请假为第 3 步
+pub fn approval_step(status: &str) -> i16 {
+    match status {
+        "escalated" => 2,
+        "approved" => 3,
+        _ => 1,
+    }
+}
+
+/// 批准后把请假期间的上学日记为请假（全天考勤）；已有记录的改为请假并保留更正记录。
+/// 改动记入 leave_attendances，撤销请假时据此恢复
+pub async fn record_excused(
+    conn: &mut PgConnection,
+    leave_request_id: Uuid,
+    student_id: Uuid,
+    days: &[NaiveDate],
+    remark: &str,
+    approver_id: Uuid,
+) -> Result<(), AppError> {
+    for date in days {
+        let existing: Option<(Uuid, String, Option<chrono::NaiveTime>, Option<String>)> = sqlx::query_as(
+            "SELECT id, status, time, remark FROM attendances
+             WHERE person_id = $1 AND date = $2 AND period_id IS NULL
+             FOR UPDATE",
+        )
+        .bind(student_id)
+        .bind(date)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        match existing {
+            Some((id, status, time, old_remark)) => {
+                let old = (status, time, old_remark);
+                let new = ("excused".to_string(), old.1, Some(remark.to_string()));
+                rules::record_correction(&mut *conn, id, &old, &new, Some("请假审批通过"), approver_id).await?;
+                sqlx::query("UPDATE attendances SET status = $2, remark = $3, updated_at = NOW() WHERE id = $1")
+                    .bind(id)
+                    .bind(&new.0)
+                    .bind(&new.2)
+                    .execute(&mut *conn)
+                    .await?;
+                sqlx::query(
+                    "INSERT INTO leave_attendances (leave_request_id, attendance_id, created, old_status, old_time, old_remark)
+                     VALUES ($1, $2, false, $3, $4, $5)",
+                )
+                .bind(leave_request_id)
+                .bind(id)
+                .bind(&old.0)
+                .bind(old.1)
+                .bind(&old.2)
+                .execute(&mut *conn)
+                .await?;
+            }
+            None => {
+                let id: Uuid = sqlx::query_scalar(
+                    "INSERT INTO attendances (person_id, date, status, remark, created_by)
+                     VALUES ($1, $2, 'excused', $3, $4)
+                     RETURNING id",
+                )
+                .bind(student_id)
+                .bind(date)
+                .bind(remark)
+                .bind(approver_id)
+                .fetch_one(&mut *conn)
+                .await?;
+                sqlx::query(
+                    "INSERT INTO leave_attendances (leave_request_id, attendance_id, created) VALUES ($1, $2, true)",
+                )
+                .bind(leave_request_id)
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 批准请假时改动的一条考勤：created 为 true 表示由请假新建，否则 old_* 为改动前的值
+#[derive(sqlx::FromRow)]
+struct LeaveAttendance {
+    attendance_id: Uuid,
+    created: bool,
+    old_status: Option<String>,
+    old_time: Option<chrono::NaiveTime>,
+    old_remark: Option<String>,
+}
+
+/// 撤销请假：恢复批准时改动的考勤。之后又被改成其他状态的记录保持不变；
+/// 批准时新建的记录删除（保留更正记录），原有记录恢复原值并记为更正。返回恢复的记录数
+pub async fn restore_attendances(
+    conn: &mut PgConnection,
+    leave_request_id: Uuid,
+    operator_id: Uuid,
+) -> Result<u64, AppError> {
+    let changes = sqlx::query_as::<_, LeaveAttendance>(
+        "SELECT attendance_id, created, old_status, old_time, old_remark FROM leave_attendances
+         WHERE leave_request_id = $1",
+    )
+    .bind(leave_request_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut restored = 0;
+    for change in changes {
+        let attendance_id = change.attendance_id;
+        let current: Option<(String, Option<chrono::NaiveTime>, Option<String>)> = sqlx::query_as(
+            "SELECT status, time, remark FROM attendances WHERE id = $1 FOR UPDATE",
+        )
+        .bind(attendance_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+        let Some(current) = current.filter(|c| c.0 == "excused") else {
+            continue;
+        };
+
+        if change.created {
+            rules::delete_attendance(&mut *conn, attendance_id, Some("撤销请假"), operator_id).await?;
+        } else {
+            let old = (change.old_status.unwrap_or_else(|| "absent".to_string()), change.old_time, change.old_remark);
+            rules::record_correction(&mut *conn, attendance_id, &current, &old, Some("撤销请假"), operator_id).await?;
+            sqlx::query("UPDATE attendances SET status = $2, time = $3, remark = $4, updated_at = NOW() WHERE id = $1")
+                .bind(attendance_id)
+                .bind(&old.0)
+                .bind(old.1)
+                .bind(&old.2)
+                .execute(&mut *conn)
+                .await?;
+        }
+        restored += 1;
+    }
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_approval_chain() {
+        // 短假：班主任同意即批准
+        assert_eq!(after_approval("pending", 2, false).unwrap(), "approved");
+        // 长假：班主任同意后待复核，复核人同意后批准
+        assert_eq!(after_approval("pending", 5, false).unwrap(), "escalated");
+        assert_eq!(approval_step("escalated"), 2);
+        assert!(after_approval("escalated", 5, false).is_err());
+        assert_eq!(after_approval("escalated", 5, true).unwrap(), "approved");
+        // 有复核权限的班主任直接批准
+        assert_eq!(after_approval("pending", 5, true).unwrap(), "approved");
+        assert!(after_approval("approved", 1, true).is_err());
+        assert_eq!(approval_step("approved"), 3);
+    }
+}
@@ -1,8 +1,23 @@
+pub mod alert;
+pub mod archive;
+pub mod attendance;
 pub mod auth;
+pub mod calendar;
 pub mod config;
+pub mod custom_field;
 pub mod db;
 pub mod error;
+pub mod history;
+pub mod kiosk;
+pub mod leave;
 pub mod middleware;
 pub mod password;
 pub mod permission;
+pub mod pinyin;
 pub mod plugin;
+pub mod roster;
+pub mod score;
+pub mod score_appeal;
+pub mod score_ledger;
+pub mod search;
+pub mod storage;
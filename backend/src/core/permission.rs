@@ -4,6 +4,10 @@ use sqlx::{PgPool, Row};
 use uuid::Uuid;
 use serde::{Deserialize, Serialize};
 
+/// 部门负责人的部门通用权限（department.{dept_suffix}）覆盖的操作；
+/// 删除部门、指定负责人、删除人员等仍需全局权限或单独授予的部门权限
+pub const DEPARTMENT_HEAD_PERMISSIONS: &[&str] = &["department.update", "person.create", "person.update"];
+
 /// 权限管理器
 pub struct PermissionManager {
     pool: PgPool,
@@ -46,6 +50,22 @@ impl PermissionManager {
         self.evaluate_permission(&user_permissions, permission)
     }
 
+    /// 一次加载用户权限后批量检查多个权限，结果与传入顺序一致
+    pub async fn check_permissions(&self, user_id: Uuid, permissions: &[&str]) -> Vec<bool> {
+        let role = match self.get_user_role(user_id).await {
+            Ok(role) => role,
+            Err(_) => return vec![false; permissions.len()],
+        };
+        let user_permissions = self.get_user_effective_permissions(user_id, &role).await;
+
+        permissions
+            .iter()
+            .map(|permission| {
+                matches!(self.evaluate_permission(&user_permissions, permission), PermissionResult::Allowed)
+            })
+            .collect()
+    }
+
     /// 获取用户角色
     async fn get_user_role(&self, user_id: Uuid) -> Result<String, sqlx::Error> {
         let row = sqlx::query("SELECT role FROM persons WHERE id = $1")
@@ -322,6 +342,83 @@ impl PermissionManager {
         }
     }
 
+    /// 获取部门及其所有上级部门的ID（从自身开始向上）
+    async fn get_department_chain(&self, department_id: Uuid) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar(
+            "WITH RECURSIVE chain AS (
+                 SELECT id, parent_id, 0 AS depth FROM departments WHERE id = $1
+                 UNION ALL
+                 SELECT d.id, d.parent_id, chain.depth + 1
+                 FROM departments d
+                 JOIN chain ON d.id = chain.parent_id
+                 WHERE chain.depth < 64
+             )
+             SELECT id FROM chain ORDER BY depth",
+        )
+        .bind(department_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// 检查用户是否拥有特定部门（及其下级部门）的权限
+    /// 权限格式: person.update.{dept_suffix} 或 department.{dept_suffix}（部门通用权限，授予部门负责人）
+    /// 上级部门的部门权限同样覆盖所有下级部门；部门通用权限只覆盖 DEPARTMENT_HEAD_PERMISSIONS 中的操作
+    pub async fn check_department_permission(&self, user_id: Uuid, permission: &str, department_id: Uuid) -> PermissionResult {
+        let role = match self.get_user_role(user_id).await {
+            Ok(role) => role,
+            Err(_) => return PermissionResult::NotSet,
+        };
+        let user_permissions = self.get_user_effective_permissions(user_id, &role).await;
+
+        // 首先检查通用权限
+        if self.evaluate_permission(&user_permissions, permission) == PermissionResult::Allowed {
+            return PermissionResult::Allowed;
+        }
+
+        let chain = match self.get_department_chain(department_id).await {
+            Ok(chain) => chain,
+            Err(_) => return PermissionResult::Denied,
+        };
+        for dept_id in chain {
+            let suffix = Self::get_class_suffix(dept_id);
+            let general = format!("department.{}", suffix);
+            let specific = format!("{}.{}", permission, suffix);
+            if (DEPARTMENT_HEAD_PERMISSIONS.contains(&permission)
+                && self.evaluate_permission(&user_permissions, &general) == PermissionResult::Allowed)
+                || self.evaluate_permission(&user_permissions, &specific) == PermissionResult::Allowed
+            {
+                return PermissionResult::Allowed;
+            }
+        }
+
+        PermissionResult::Denied
+    }
+
+    /// 检查部门权限，如果拒绝则返回AppError
+    pub async fn require_department_permission(&self, user_id: Uuid, permission: &str, department_id: Uuid) -> Result<(), crate::core::error::AppError> {
+        match self.check_department_permission(user_id, permission, department_id).await {
+            PermissionResult::Allowed => Ok(()),
+            PermissionResult::Denied => Err(crate::core::error::AppError::Auth(
+                format!("没有权限执行此操作: {} (部门ID: {})", permission, department_id)
+            )),
+            PermissionResult::NotSet => Err(crate::core::error::AppError::Auth(
+                format!("权限未设置: {} (部门ID: {})", permission, department_id)
+            )),
+        }
+    }
+
+    /// 为部门负责人添加部门通用权限（覆盖整棵子树）
+    pub async fn add_department_permissions_for_head(&self, head_id: Uuid, department_id: Uuid) -> Result<(), sqlx::Error> {
+        let permission = format!("department.{}", Self::get_class_suffix(department_id));
+        self.add_user_permission(head_id, &permission, true, 20).await
+    }
+
+    /// 移除部门负责人的部门通用权限
+    pub async fn remove_department_permissions_for_head(&self, head_id: Uuid, department_id: Uuid) -> Result<(), sqlx::Error> {
+        let permission = format!("department.{}", Self::get_class_suffix(department_id));
+        self.remove_user_permission(head_id, &permission).await
+    }
+
     /// 为用户添加班级特定权限
     pub async fn add_class_permissions_for_teacher(&self, teacher_id: Uuid, class_id: Uuid) -> Result<(), sqlx::Error> {
         let class_suffix = Self::get_class_suffix(class_id);
@@ -345,6 +442,39 @@ impl PermissionManager {
         Ok(())
     }
 
+    /// 为任课教师添加班级特定权限（查看小组、调整小组积分）
+    pub async fn add_class_permissions_for_subject_teacher(&self, teacher_id: Uuid, class_id: Uuid) -> Result<(), sqlx::Error> {
+        let class_suffix = Self::get_class_suffix(class_id);
+        let priority = 20;
+
+        let permissions = vec![
+            format!("group.view.{}", class_suffix),
+            format!("group.update.score.{}", class_suffix),
+        ];
+
+        for permission in permissions {
+            self.add_user_permission(teacher_id, &permission, true, priority).await?;
+        }
+
+        Ok(())
+    }
+
+    /// 移除任课教师的班级特定权限
+    pub async fn remove_class_permissions_for_subject_teacher(&self, teacher_id: Uuid, class_id: Uuid) -> Result<(), sqlx::Error> {
+        let class_suffix = Self::get_class_suffix(class_id);
+
+        let permissions = vec![
+            format!("group.view.{}", class_suffix),
+            format!("group.update.score.{}", class_suffix),
+        ];
+
+        for permission in permissions {
+            self.remove_user_permission(teacher_id, &permission).await?;
+        }
+
+        Ok(())
+    }
+
     /// 移除用户的班级特定权限
     pub async fn remove_class_permissions_for_teacher(&self, teacher_id: Uuid, class_id: Uuid) -> Result<(), sqlx::Error> {
         let class_suffix = Self::get_class_suffix(class_id);
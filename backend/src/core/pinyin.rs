@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+// 拼音字表，每行“拼音 汉字序列”，覆盖常用 CJK 统一汉字
+const TABLE: &str = include_str!("pinyin.txt");
+
+// 多音字作姓氏时的读音，只对姓名首字生效
+const SURNAMES: &[(char, &str)] = &[
+    ('单', "shan"),
+    ('曾', "zeng"),
+    ('仇', "qiu"),
+    ('区', "ou"),
+    ('查', "zha"),
+    ('朴', "piao"),
+    ('解', "xie"),
+    ('缪', "miao"),
+    ('盖', "ge"),
+    ('乐', "yue"),
+    ('覃', "qin"),
+    ('秘', "bi"),
+    ('召', "shao"),
+    ('尉', "yu"),
+];
+
+fn table() -> &'static HashMap<char, &'static str> {
+    static MAP: OnceLock<HashMap<char, &'static str>> = OnceLock::new();
+    MAP.get_or_init(|| {
+        let mut map = HashMap::new();
+        for line in TABLE.lines().filter(|l| !l.starts_with('#')) {
+            if let Some((syllable, chars)) = line.split_once(' ') {
+                for c in chars.chars() {
+                    map.insert(c, syllable);
+                }
+            }
+        }
+        map
+    })
+}
+
+/// 按字切分为拼音音节；字母数字串原样保留（转小写），其他符号忽略。
+/// 第二个值标记该片段是否来自汉字
+fn syllables(name: &str) -> Vec<(String, bool)> {
+    let map = table();
+    let mut result = Vec::new();
+    let mut word = String::new();
+    for (i, c) in name.trim().chars().enumerate() {
+        if c.is_ascii_alphanumeric() {
+            word.push(c.to_ascii_lowercase());
+            continue;
+        }
+        if !word.is_empty() {
+            result.push((std::mem::take(&mut word), false));
+        }
+        let surname = if i == 0 {
+            SURNAMES.iter().find(|(s, _)| *s == c).map(|(_, p)| *p)
+        } else {
+            None
+        };
+        if let Some(p) = surname.or_else(|| map.get(&c).copied()) {
+            result.push((p.to_string(), true));
+        }
+    }
+    if !word.is_empty() {
+        result.push((word, false));
+    }
+    result
+}
+
+/// 全拼，如“张三” → “zhangsan”
+pub fn full(name: &str) -> String {
+    syllables(name).into_iter().map(|(s, _)| s).collect()
+}
+
+/// 拼音首字母，如“张三” → “zs”；字母数字串整体保留，如“三年级2班” → “snj2b”
+pub fn initials(name: &str) -> String {
+    syllables(name)
+        .into_iter()
+        .map(|(s, han)| if han { s[..1].to_string() } else { s })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_to_pinyin() {
+        assert_eq!(full("张三"), "zhangsan");
+        assert_eq!(initials("张三"), "zs");
+        assert_eq!(full("单田芳"), "shantianfang");
+        assert_eq!(full("吕 小明"), "lvxiaoming");
+        assert_eq!(initials("三年级2班"), "snj2b");
+    }
+}
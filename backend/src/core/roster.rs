@@ -0,0 +1,234 @@
+use uuid::Uuid;
+
+use crate::models::roster::{Roster, SeatPlacement, SeatingChart};
+
+// 座位表最大行列数，与数据库约束一致
+pub const MAX_SEATING_SIZE: i16 = 20;
+// 没有座位表且未指定行列数时每排的人数
+pub const DEFAULT_COLS: i16 = 6;
+
+/// 参与自动排座的学生
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub person_id: Uuid,
+    pub gender: i16,
+    // 所在小组，没有小组的排在最后
+    pub group: Option<String>,
+}
+
+/// 自动排座，从第 1 排开始逐排从左到右入座。
+/// group：按小组连续就座，组内保持传入顺序；gender：男女交错（棋盘格）；random：按传入顺序（调用方已打乱）。
+/// 学生数不能超过座位数，由调用方检查
+pub fn arrange(mut candidates: Vec<Candidate>, cols: i16, mode: &str) -> Vec<SeatPlacement> {
+    let seat = |index: usize| (index as i16 / cols + 1, index as i16 % cols + 1);
+
+    match mode {
+        "gender" => {
+            let mut male: Vec<Candidate> = Vec::new();
+            let mut female: Vec<Candidate> = Vec::new();
+            let mut unknown: Vec<Candidate> = Vec::new();
+            for c in candidates {
+                match c.gender {
+                    1 => male.push(c),
+                    2 => female.push(c),
+                    _ => unknown.push(c),
+                }
+            }
+            // 倒序后从尾部取，保持各自的原始顺序
+            male.reverse();
+            female.reverse();
+            unknown.reverse();
+
+            let total = male.len() + female.len() + unknown.len();
+            let mut placements = Vec::with_capacity(total);
+            for index in 0..total {
+                let (row, col) = seat(index);
+                let (preferred, other) = if (row + col) % 2 == 0 {
+                    (&mut male, &mut female)
+                } else {
+                    (&mut female, &mut male)
+                };
+                let next = preferred.pop().or_else(|| unknown.pop()).or_else(|| other.pop());
+                if let Some(c) = next {
+                    placements.push(SeatPlacement { row, col, person_id: c.person_id });
+                }
+            }
+            placements
+        }
+        _ => {
+            if mode == "group" {
+                // 稳定排序，组内保持传入顺序
+                candidates.sort_by(|a, b| match (&a.group, &b.group) {
+                    (Some(x), Some(y)) => x.cmp(y),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                });
+            }
+            candidates
+                .into_iter()
+                .enumerate()
+                .map(|(index, c)| {
+                    let (row, col) = seat(index);
+                    SeatPlacement { row, col, person_id: c.person_id }
+                })
+                .collect()
+        }
+    }
+}
+
+// ========== 打印页面 ==========
+
+pub fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn gender_label(gender: i16) -> &'static str {
+    match gender {
+        1 => "男",
+        2 => "女",
+        _ => "",
+    }
+}
+
+// 打印样式：A4，浏览器“打印 → 另存为 PDF”即可得到 PDF
+fn page(title: &str, landscape: bool, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="zh-CN">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+@page {{ size: A4 {orientation}; margin: 12mm; }}
+body {{ font-family: "PingFang SC", "Microsoft YaHei", sans-serif; color: #000; }}
+h1 {{ font-size: 20px; text-align: center; margin: 0 0 4px; }}
+.meta {{ text-align: center; font-size: 12px; margin-bottom: 12px; }}
+table {{ width: 100%; border-collapse: collapse; }}
+th, td {{ border: 1px solid #333; padding: 4px 6px; font-size: 13px; text-align: center; }}
+.podium {{ width: 40%; margin: 0 auto 12px; border: 1px solid #333; text-align: center; padding: 4px; }}
+.seat {{ height: 48px; }}
+.seat .no {{ font-size: 10px; color: #555; }}
+.empty {{ background: #f3f3f3; }}
+@media print {{ .empty {{ background: none; }} }}
+</style>
+</head>
+<body>
+{body}
+</body>
+</html>
+"#,
+        title = escape_html(title),
+        orientation = if landscape { "landscape" } else { "portrait" },
+        body = body,
+    )
+}
+
+/// 可打印的班级花名册
+pub fn render_roster_html(roster: &Roster) -> String {
+    let mut rows = String::new();
+    for s in &roster.students {
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td></td></tr>\n",
+            s.seq,
+            escape_html(&s.student_no),
+            escape_html(&s.name),
+            gender_label(s.gender),
+            escape_html(&s.groups.join("、")),
+        ));
+    }
+
+    let title = format!("{} 花名册", roster.class_name);
+    let body = format!(
+        "<h1>{}</h1>\n<div class=\"meta\">{}{}　日期：{}　人数：{}</div>\n\
+         <table>\n<tr><th>序号</th><th>学号</th><th>姓名</th><th>性别</th><th>小组</th><th>备注</th></tr>\n{}</table>",
+        escape_html(&title),
+        roster.academic_year.as_deref().map(|y| format!("{}学年　", escape_html(y))).unwrap_or_default(),
+        roster.teacher_name.as_deref().map(|t| format!("班主任：{}", escape_html(t))).unwrap_or_default(),
+        roster.date,
+        roster.students.len(),
+        rows,
+    );
+    page(&title, false, &body)
+}
+
+/// 可打印的座位表，讲台在上方，第 1 列在讲台视角的左侧
+pub fn render_seating_html(chart: &SeatingChart) -> String {
+    let mut grid = String::new();
+    for row in 1..=chart.rows {
+        grid.push_str(&format!("<tr><th>第{}排</th>", row));
+        for col in 1..=chart.cols {
+            match chart.seats.iter().find(|s| s.row == row && s.col == col) {
+                Some(seat) => grid.push_str(&format!(
+                    "<td class=\"seat\">{}<div class=\"no\">{}</div></td>",
+                    escape_html(&seat.name),
+                    escape_html(&seat.student_no),
+                )),
+                None => grid.push_str("<td class=\"seat empty\"></td>"),
+            }
+        }
+        grid.push_str("</tr>\n");
+    }
+
+    let unseated = if chart.unseated.is_empty() {
+        String::new()
+    } else {
+        let names: Vec<String> = chart.unseated.iter().map(|s| escape_html(&s.name)).collect();
+        format!("<p>未安排座位：{}</p>", names.join("、"))
+    };
+
+    let title = format!("{} 座位表", chart.class_name);
+    let body = format!(
+        "<h1>{}</h1>\n<div class=\"meta\">{} 排 × {} 列　已入座 {} 人</div>\n<div class=\"podium\">讲　台</div>\n\
+         <table>\n{}</table>\n{}",
+        escape_html(&title),
+        chart.rows,
+        chart.cols,
+        chart.seats.len(),
+        grid,
+        unseated,
+    );
+    page(&title, chart.cols > 6, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(n: u128, gender: i16, group: Option<&str>) -> Candidate {
+        Candidate { person_id: Uuid::from_u128(n), gender, group: group.map(str::to_string) }
+    }
+
+    #[test]
+    fn test_arrange_modes() {
+        // 男女交错：相邻座位性别不同
+        let students = vec![
+            candidate(1, 1, None),
+            candidate(2, 1, None),
+            candidate(3, 2, None),
+            candidate(4, 2, None),
+        ];
+        let seats = arrange(students, 2, "gender");
+        let gender = |p: &SeatPlacement| if p.person_id.as_u128() <= 2 { 1 } else { 2 };
+        assert_eq!(seats.len(), 4);
+        assert_ne!(gender(&seats[0]), gender(&seats[1]));
+        assert_ne!(gender(&seats[0]), gender(&seats[2]));
+        assert_eq!((seats[3].row, seats[3].col), (2, 2));
+
+        // 按小组：同组连续，没有小组的在最后
+        let students = vec![
+            candidate(1, 1, None),
+            candidate(2, 1, Some("B组")),
+            candidate(3, 2, Some("A组")),
+            candidate(4, 2, Some("B组")),
+        ];
+        let order: Vec<u128> = arrange(students, 3, "group").iter().map(|p| p.person_id.as_u128()).collect();
+        assert_eq!(order, vec![3, 2, 4, 1]);
+
+        assert_eq!(escape_html("<a&b>"), "&lt;a&amp;b&gt;");
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::core::error::AppError;
+use crate::models::score::ScoreReason;
+
+// 积分对象类型
+pub const SCORE_TYPES: &[&str] = &["personal", "group"];
+pub const LEADERBOARD_SCOPES: &[&str] = &["student", "group", "class"];
+// 未指定起始日期时统计最近这么多天
+pub const DEFAULT_PERIOD_DAYS: i64 = 30;
+// 单次统计的最大天数
+pub const MAX_RANGE_DAYS: i64 = 366;
+
+/// 统计周期和紧邻的上一周期（长度相同）：(from, to, previous_from, previous_to)
+pub fn periods(
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    today: NaiveDate,
+) -> Result<(NaiveDate, NaiveDate, NaiveDate, NaiveDate), AppError> {
+    let to = to.unwrap_or(today);
+    let from = from.unwrap_or(to - Duration::days(DEFAULT_PERIOD_DAYS - 1));
+    if from > to {
+        return Err(AppError::InvalidInput("开始日期不能晚于结束日期".to_string()));
+    }
+    let days = (to - from).num_days() + 1;
+    if days > MAX_RANGE_DAYS {
+        return Err(AppError::InvalidInput(format!("统计范围不能超过 {} 天", MAX_RANGE_DAYS)));
+    }
+    let previous_to = from - Duration::days(1);
+    let previous_from = previous_to - Duration::days(days - 1);
+    Ok((from, to, previous_from, previous_to))
+}
+
+/// 按总分从高到低排名，同分同名次（1, 2, 2, 4）
+pub fn competition_ranks(totals: &[(Uuid, i64)]) -> HashMap<Uuid, i64> {
+    let mut sorted: Vec<&(Uuid, i64)> = totals.iter().collect();
+    sorted.sort_by_key(|&&(_, total)| std::cmp::Reverse(total));
+
+    let mut ranks = HashMap::new();
+    let mut previous_total = None;
+    let mut rank = 0;
+    for (i, (id, total)) in sorted.into_iter().enumerate() {
+        if previous_total != Some(*total) {
+            rank = i as i64 + 1;
+            previous_total = Some(*total);
+        }
+        ranks.insert(*id, rank);
+    }
+    ranks
+}
+
+/// 名次变化：正数表示上升，上一周期没有名次时为空
+pub fn rank_change(rank: i64, previous_rank: Option<i64>) -> Option<i64> {
+    previous_rank.map(|previous| previous - rank)
+}
+
+/// 在积分目录中查找原因：名称或同义说法相同即为同一原因（忽略首尾空白和大小写）
+pub fn match_reason<'a>(catalog: &'a [ScoreReason], text: &str) -> Option<&'a ScoreReason> {
+    let text = text.trim().to_lowercase();
+    catalog.iter().filter(|r| r.is_active).find(|r| {
+        r.name.trim().to_lowercase() == text || r.aliases.iter().any(|alias| alias.trim().to_lowercase() == text)
+    })
+}
+
+/// 检查分值：不能为 0，加减方向与预设分值一致，且不超过角色的单次上限
+pub fn check_value(value: i32, default_value: i32, max_points: Option<i32>) -> Result<(), AppError> {
+    if value == 0 {
+        return Err(AppError::InvalidInput("分值不能为 0".to_string()));
+    }
+    if default_value != 0 && (value > 0) != (default_value > 0) {
+        let kind = if default_value > 0 { "加分" } else { "扣分" };
+        return Err(AppError::InvalidInput(format!("该原因只能{}", kind)));
+    }
+    if let Some(max_points) = max_points {
+        if value.abs() > max_points {
+            return Err(AppError::InvalidInput(format!("单次加减分不能超过 {} 分", max_points)));
+        }
+    }
+    Ok(())
+}
+
+/// 按目录规范化后的积分录入
+#[derive(Debug)]
+pub struct ResolvedScore {
+    pub category_id: Uuid,
+    pub reason_id: Uuid,
+    pub reason: String,
+    pub value: i32,
+}
+
+/// 按积分目录校验一次积分录入：原因必须是目录中的预设原因（可用 ID、名称或同义说法），
+/// 未给分值时使用预设分值，并按录入人角色检查单次上限
+pub async fn resolve_entry(
+    pool: &PgPool,
+    user_id: Uuid,
+    score_type: &str,
+    reason_id: Option<Uuid>,
+    reason: Option<&str>,
+    value: Option<i32>,
+) -> Result<ResolvedScore, AppError> {
+    if !SCORE_TYPES.contains(&score_type) {
+        return Err(AppError::InvalidInput(format!("积分类型只能是 {}", SCORE_TYPES.join("、"))));
+    }
+
+    let catalog = sqlx::query_as::<_, ScoreReason>(
+        "SELECT r.id, r.category_id, r.name, r.aliases, r.default_value, r.sort_order, r.is_active
+         FROM score_reasons r JOIN score_categories c ON c.id = r.category_id
+         WHERE r.is_active AND c.is_active AND ($1::uuid IS NULL OR r.id = $1)",
+    )
+    .bind(reason_id)
+    .fetch_all(pool)
+    .await?;
+
+    let found = match (reason_id, reason) {
+        (Some(_), _) => catalog
+            .first()
+            .ok_or_else(|| AppError::InvalidInput("积分原因不存在或已停用".to_string()))?,
+        (None, Some(text)) if !text.trim().is_empty() => match_reason(&catalog, text).ok_or_else(|| {
+            AppError::InvalidInput(format!("原因“{}”不在积分目录中，请选择预设原因", text.trim()))
+        })?,
+        _ => return Err(AppError::InvalidInput("请选择积分原因".to_string())),
+    };
+
+    let value = value.unwrap_or(found.default_value);
+    check_entry_value(pool, user_id, value, found.default_value).await?;
+
+    Ok(ResolvedScore {
+        category_id: found.category_id,
+        reason_id: found.id,
+        reason: found.name.clone(),
+        value,
+    })
+}
+
+/// 按录入人角色的单次上限检查分值；default_value 为 0 时不限制加减方向
+pub async fn check_entry_value(pool: &PgPool, user_id: Uuid, value: i32, default_value: i32) -> Result<(), AppError> {
+    let max_points: Option<i32> = sqlx::query_scalar(
+        "SELECT l.max_points FROM persons p JOIN score_limits l ON l.role = p.role WHERE p.id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    check_value(value, default_value, max_points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_periods_previous_has_same_length() {
+        let today = date(2025, 3, 31);
+        let (from, to, previous_from, previous_to) = periods(Some(date(2025, 3, 1)), None, today).unwrap();
+        assert_eq!((from, to), (date(2025, 3, 1), today));
+        assert_eq!((previous_from, previous_to), (date(2025, 1, 29), date(2025, 2, 28)));
+    }
+
+    #[test]
+    fn test_periods_default_range() {
+        let today = date(2025, 3, 31);
+        let (from, to, _, _) = periods(None, None, today).unwrap();
+        assert_eq!((from, to), (date(2025, 3, 2), today));
+    }
+
+    #[test]
+    fn test_periods_invalid_range() {
+        let today = date(2025, 3, 31);
+        assert!(periods(Some(today), Some(date(2025, 3, 1)), today).is_err());
+        assert!(periods(Some(date(2024, 1, 1)), Some(today), today).is_err());
+    }
+
+    #[test]
+    fn test_competition_ranks_ties() {
+        let ids: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+        let ranks = competition_ranks(&[(ids[0], 5), (ids[1], 12), (ids[2], 5), (ids[3], -3)]);
+        assert_eq!(ranks[&ids[1]], 1);
+        assert_eq!(ranks[&ids[0]], 2);
+        assert_eq!(ranks[&ids[2]], 2);
+        assert_eq!(ranks[&ids[3]], 4);
+    }
+
+    #[test]
+    fn test_rank_change() {
+        assert_eq!(rank_change(2, Some(4)), Some(2));
+        assert_eq!(rank_change(3, Some(1)), Some(-2));
+        assert_eq!(rank_change(1, None), None);
+    }
+
+    fn reason(name: &str, aliases: &[&str], default_value: i32) -> ScoreReason {
+        ScoreReason {
+            id: Uuid::new_v4(),
+            category_id: Uuid::nil(),
+            name: name.to_string(),
+            aliases: aliases.iter().map(|a| a.to_string()).collect(),
+            default_value,
+            sort_order: 0,
+            is_active: true,
+        }
+    }
+
+    #[test]
+    fn test_match_reason_by_name_or_alias() {
+        let catalog = vec![reason("上课说话", &["课堂讲话", "Talking"], -1), reason("课堂回答问题", &[], 1)];
+        assert_eq!(match_reason(&catalog, "课堂回答问题").unwrap().name, "课堂回答问题");
+        assert_eq!(match_reason(&catalog, " talking ").unwrap().name, "上课说话");
+        assert_eq!(match_reason(&catalog, "课堂讲话").unwrap().name, "上课说话");
+        assert!(match_reason(&catalog, "打架").is_none());
+    }
+
+    #[test]
+    fn test_match_reason_skips_inactive() {
+        let mut inactive = reason("迟到", &["晚到"], -1);
+        inactive.is_active = false;
+        let catalog = vec![inactive];
+        assert!(match_reason(&catalog, "迟到").is_none());
+        assert!(match_reason(&catalog, "晚到").is_none());
+    }
+
+    #[test]
+    fn test_check_value_rejects_zero() {
+        assert!(check_value(0, 1, None).is_err());
+        assert!(check_value(0, 0, None).is_err());
+    }
+
+    #[test]
+    fn test_check_value_direction() {
+        assert!(check_value(-2, -1, None).is_ok());
+        assert!(check_value(2, -1, None).is_err());
+        assert!(check_value(-2, 1, None).is_err());
+        assert!(check_value(-2, 0, None).is_ok());
+        assert!(check_value(2, 0, None).is_ok());
+    }
+
+    #[test]
+    fn test_check_value_max_points() {
+        assert!(check_value(-5, -1, Some(5)).is_ok());
+        assert!(check_value(-6, -1, Some(5)).is_err());
+        assert!(check_value(-6, -1, None).is_ok());
+    }
+}
@@ -0,0 +1,106 @@
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::core::error::AppError;
+
+/// 申诉处理后的最终分值需在原扣分和 0 之间（含 0，表示撤销），返回需要补回的分值
+pub fn compensation(original_value: i32, adjusted_value: Option<i32>) -> Result<i32, AppError> {
+    if original_value >= 0 {
+        return Err(AppError::InvalidInput("只能对扣分记录提出申诉".to_string()));
+    }
+    let adjusted_value = adjusted_value.unwrap_or(0);
+    if adjusted_value > 0 || adjusted_value < original_value {
+        return Err(AppError::InvalidInput(format!(
+            "调整后的分值需在 {} 到 0 之间",
+            original_value
+        )));
+    }
+    if adjusted_value == original_value {
+        return Err(AppError::InvalidInput("分值没有变化，如不调整请驳回申诉".to_string()));
+    }
+    Ok(adjusted_value - original_value)
+}
+
+/// 同一条扣分记录只能更正一次：已有同意的申诉（即已生成更正记录）时不能再申诉或同意
+pub async fn ensure_not_compensated(
+    conn: &mut PgConnection,
+    score_id: Option<Uuid>,
+    group_score_record_id: Option<Uuid>,
+    appeal_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    let compensated: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM score_appeals
+                       WHERE status = 'accepted' AND (score_id = $1 OR group_score_record_id = $2)
+                         AND id IS DISTINCT FROM $3)",
+    )
+    .bind(score_id)
+    .bind(group_score_record_id)
+    .bind(appeal_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    if compensated {
+        return Err(AppError::InvalidInput("该记录已通过申诉更正，不能重复申诉".to_string()));
+    }
+    Ok(())
+}
+
+/// 生成申诉更正记录：个人积分补一条 scores，小组积分补一条 group_score_records（小组总分由触发器更新）。
+/// 返回 (个人更正记录, 小组更正记录)
+pub async fn record_compensation(
+    conn: &mut PgConnection,
+    score_id: Option<Uuid>,
+    group_score_record_id: Option<Uuid>,
+    value: i32,
+    reviewer_id: Uuid,
+) -> Result<(Option<Uuid>, Option<Uuid>), AppError> {
+    if let Some(score_id) = score_id {
+        let id: Uuid = sqlx::query_scalar(
+            "INSERT INTO scores (person_id, group_id, score_type, value, reason, created_by, category_id, reason_id)
+             SELECT person_id, group_id, score_type, $2, '申诉更正：' || reason, $3, category_id, reason_id
+             FROM scores s WHERE id = $1 AND NOT EXISTS (SELECT 1 FROM scores r WHERE r.reverses_id = s.id)
+             RETURNING id",
+        )
+        .bind(score_id)
+        .bind(value)
+        .bind(reviewer_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::InvalidInput("原积分记录已被冲销或不存在".to_string()))?;
+        return Ok((Some(id), None));
+    }
+
+    let group_score_record_id =
+        group_score_record_id.ok_or_else(|| AppError::InvalidInput("原积分记录已不存在".to_string()))?;
+    let id: Uuid = sqlx::query_scalar(
+        "INSERT INTO group_score_records (group_id, score_change, reason, created_by)
+         SELECT group_id, $2, '申诉更正：' || reason, $3
+         FROM group_score_records WHERE id = $1
+         RETURNING id",
+    )
+    .bind(group_score_record_id)
+    .bind(value)
+    .bind(reviewer_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::InvalidInput("原积分记录已不存在".to_string()))?;
+    Ok((None, Some(id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_appeal_compensation() {
+        // 撤销：补回全部扣分
+        assert_eq!(compensation(-3, None).unwrap(), 3);
+        // 调整为 -1：补回 2 分
+        assert_eq!(compensation(-3, Some(-1)).unwrap(), 2);
+        assert_eq!(compensation(-3, Some(0)).unwrap(), 3);
+        assert!(compensation(-3, Some(-3)).is_err());
+        assert!(compensation(-3, Some(-4)).is_err());
+        assert!(compensation(-3, Some(1)).is_err());
+        // 加分不能申诉
+        assert!(compensation(2, None).is_err());
+    }
+}
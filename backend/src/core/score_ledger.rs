@@ -0,0 +1,139 @@
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::core::error::AppError;
+use crate::models::score::{DailyTotalDrift, GroupScoreDrift, ScoreReconciliation};
+
+// 有效积分记录：不是冲销记录，也没有被冲销
+const ACTIVE_SCORES: &str = "SELECT s.* FROM scores s
+    WHERE s.reverses_id IS NULL AND NOT EXISTS (SELECT 1 FROM scores r WHERE r.reverses_id = s.id)";
+
+/// 冲销记录本身和已被冲销的记录不能再冲销
+pub fn check_reversible(reverses_id: Option<Uuid>, reversed_by: Option<Uuid>) -> Result<(), AppError> {
+    if reverses_id.is_some() {
+        return Err(AppError::InvalidInput("冲销记录不能修改、删除或申诉".to_string()));
+    }
+    if reversed_by.is_some() {
+        return Err(AppError::InvalidInput("该积分记录已被冲销".to_string()));
+    }
+    Ok(())
+}
+
+/// 追加一条冲销记录抵消原积分，返回冲销记录 ID
+pub async fn reverse(conn: &mut PgConnection, score_id: Uuid, operator_id: Uuid) -> Result<Uuid, AppError> {
+    let (reverses_id, reversed_by): (Option<Uuid>, Option<Uuid>) = sqlx::query_as(
+        "SELECT reverses_id, (SELECT r.id FROM scores r WHERE r.reverses_id = s.id)
+         FROM scores s WHERE s.id = $1 FOR UPDATE",
+    )
+    .bind(score_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(AppError::NotFound)?;
+    check_reversible(reverses_id, reversed_by)?;
+
+    let id = sqlx::query_scalar(
+        "INSERT INTO scores (person_id, group_id, score_type, value, reason, created_by, category_id, reason_id, reverses_id)
+         SELECT person_id, group_id, score_type, -value, '冲销：' || reason, $2, category_id, reason_id, id
+         FROM scores WHERE id = $1
+         RETURNING id",
+    )
+    .bind(score_id)
+    .bind(operator_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(id)
+}
+
+/// 对比小组总分、按天汇总与积分流水；fix 为 true 时按流水重算有差异的汇总
+pub async fn reconcile(conn: &mut PgConnection, fix: bool) -> Result<ScoreReconciliation, AppError> {
+    if fix {
+        // 对账期间暂停写入积分，避免重算与新记录交错
+        sqlx::query("LOCK TABLE scores, group_score_records IN SHARE MODE")
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    let group_scores = sqlx::query_as::<_, GroupScoreDrift>(
+        "SELECT g.id AS group_id, g.name AS group_name, g.class_id,
+                COALESCE(g.score, 0)::BIGINT AS recorded_score,
+                COALESCE(SUM(r.score_change), 0)::BIGINT AS ledger_score
+         FROM class_groups g
+         LEFT JOIN group_score_records r ON r.group_id = g.id
+         GROUP BY g.id
+         HAVING COALESCE(g.score, 0) <> COALESCE(SUM(r.score_change), 0)
+         ORDER BY g.name",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let sql = format!(
+        "WITH ledger AS (
+             SELECT created_at::date AS score_date, person_id,
+                    COALESCE(group_id, '00000000-0000-0000-0000-000000000000') AS group_key, score_type,
+                    SUM(value)::BIGINT AS total, COUNT(*)::BIGINT AS record_count,
+                    COUNT(*) FILTER (WHERE value > 0)::BIGINT AS positive_count,
+                    COUNT(*) FILTER (WHERE value < 0)::BIGINT AS negative_count
+             FROM ({}) s
+             GROUP BY 1, 2, 3, 4
+         )
+         SELECT score_date, person_id,
+                NULLIF(group_key, '00000000-0000-0000-0000-000000000000') AS group_id, score_type,
+                COALESCE(t.total, 0) AS recorded_total, COALESCE(l.total, 0) AS ledger_total,
+                COALESCE(t.record_count, 0)::BIGINT AS recorded_count, COALESCE(l.record_count, 0) AS ledger_count
+         FROM ledger l
+         FULL JOIN score_daily_totals t USING (score_date, person_id, group_key, score_type)
+         WHERE t.total IS DISTINCT FROM l.total
+            OR t.record_count IS DISTINCT FROM l.record_count
+            OR t.positive_count IS DISTINCT FROM l.positive_count
+            OR t.negative_count IS DISTINCT FROM l.negative_count
+         ORDER BY score_date, person_id",
+        ACTIVE_SCORES
+    );
+    let daily_totals = sqlx::query_as::<_, DailyTotalDrift>(&sql).fetch_all(&mut *conn).await?;
+
+    if fix && !group_scores.is_empty() {
+        sqlx::query(
+            "UPDATE class_groups g SET score = l.total, updated_at = CURRENT_TIMESTAMP
+             FROM (SELECT g.id, COALESCE(SUM(r.score_change), 0) AS total
+                   FROM class_groups g LEFT JOIN group_score_records r ON r.group_id = g.id
+                   GROUP BY g.id) l
+             WHERE g.id = l.id AND COALESCE(g.score, 0) <> l.total",
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    if fix && !daily_totals.is_empty() {
+        sqlx::query("DELETE FROM score_daily_totals").execute(&mut *conn).await?;
+        let sql = format!(
+            "INSERT INTO score_daily_totals
+                 (score_date, person_id, group_key, score_type, total, record_count, positive_count, negative_count)
+             SELECT created_at::date, person_id, COALESCE(group_id, '00000000-0000-0000-0000-000000000000'), score_type,
+                    SUM(value), COUNT(*), COUNT(*) FILTER (WHERE value > 0), COUNT(*) FILTER (WHERE value < 0)
+             FROM ({}) s
+             GROUP BY 1, 2, 3, 4",
+            ACTIVE_SCORES
+        );
+        sqlx::query(&sql).execute(&mut *conn).await?;
+    }
+
+    Ok(ScoreReconciliation {
+        group_scores,
+        daily_totals,
+        fixed: fix,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_reversible() {
+        let id = Uuid::new_v4();
+        assert!(check_reversible(None, None).is_ok());
+        // 已被冲销
+        assert!(check_reversible(None, Some(id)).is_err());
+        // 冲销记录本身
+        assert!(check_reversible(Some(id), None).is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::core::error::AppError;
+use crate::core::pinyin;
+
+// 建立了拼音索引的表
+const INDEXED_TABLES: &[&str] = &["persons", "classes", "class_groups"];
+
+/// 检索词：原文用于匹配汉字名称，拼音形式用于匹配拼音 / 首字母列
+pub struct SearchTerm {
+    pub raw: String,
+    pub pinyin: String,
+}
+
+impl SearchTerm {
+    pub fn new(q: &str) -> Option<Self> {
+        let raw = q.trim();
+        if raw.is_empty() {
+            return None;
+        }
+        Some(Self {
+            raw: raw.to_string(),
+            pinyin: pinyin::full(raw),
+        })
+    }
+}
+
+/// WHERE 片段：名称包含检索词、拼音或首字母前缀匹配，或 trigram 相似。
+/// `raw` / `py` 为 SearchTerm 两个值的参数序号
+pub fn condition(alias: &str, raw: usize, py: usize) -> String {
+    format!(
+        "({a}.name ILIKE '%' || ${r} || '%' OR {a}.name % ${r}
+          OR (${p} <> '' AND ({a}.name_pinyin LIKE ${p} || '%' OR {a}.name_initials LIKE ${p} || '%'
+                             OR {a}.name_pinyin % ${p})))",
+        a = alias,
+        r = raw,
+        p = py
+    )
+}
+
+/// 相关度表达式（0~1）：名称完全相同最高，其次拼音/首字母完全相同、前缀，再按 trigram 相似度
+pub fn score(alias: &str, raw: usize, py: usize) -> String {
+    format!(
+        "(CASE WHEN {a}.name = ${r} THEN 1.0
+               WHEN ${p} <> '' AND ({a}.name_pinyin = ${p} OR {a}.name_initials = ${p}) THEN 0.95
+               WHEN {a}.name ILIKE ${r} || '%' THEN 0.9
+               WHEN ${p} <> '' AND ({a}.name_pinyin LIKE ${p} || '%' OR {a}.name_initials LIKE ${p} || '%') THEN 0.8
+               WHEN {a}.name ILIKE '%' || ${r} || '%' THEN 0.7
+               ELSE GREATEST(similarity({a}.name, ${r}), similarity({a}.name_pinyin, ${p})) * 0.7
+          END)::float8",
+        a = alias,
+        r = raw,
+        p = py
+    )
+}
+
+/// 更新单条记录的拼音索引列，名称变更时在同一事务中调用
+pub async fn index_name<'e, E>(executor: E, table: &str, id: Uuid, name: &str) -> Result<(), AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query(&format!(
+        "UPDATE {} SET name_pinyin = $1, name_initials = $2 WHERE id = $3",
+        table
+    ))
+    .bind(pinyin::full(name))
+    .bind(pinyin::initials(name))
+    .bind(id)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// 为尚未建立拼音索引的记录补齐索引列（迁移前的存量数据、直接写库的数据）
+pub async fn backfill(pool: &PgPool) -> Result<u64, AppError> {
+    let mut total = 0;
+    for table in INDEXED_TABLES {
+        let rows: Vec<(Uuid, String)> = sqlx::query_as(&format!(
+            "SELECT id, name FROM {} WHERE name_pinyin IS NULL",
+            table
+        ))
+        .fetch_all(pool)
+        .await?;
+        for (id, name) in rows {
+            index_name(pool, table, id, &name).await?;
+            total += 1;
+        }
+    }
+    Ok(total)
+}
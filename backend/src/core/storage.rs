@@ -0,0 +1,98 @@
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::core::config::Config;
+use crate::core::error::AppError;
+
+/// 文件存储后端；key 为形如 `persons/{id}/{file}` 的相对路径
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn put(&self, key: &str, data: &[u8]) -> Result<(), AppError>;
+    async fn get(&self, key: &str) -> Result<Vec<u8>, AppError>;
+    async fn delete(&self, key: &str) -> Result<(), AppError>;
+}
+
+/// 本地文件系统存储
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    // key 只允许普通路径片段，防止跳出存储目录
+    fn path_for(&self, key: &str) -> Result<PathBuf, AppError> {
+        let relative = Path::new(key);
+        if key.is_empty() || !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(AppError::InvalidInput(format!("非法的存储路径: {}", key)));
+        }
+        Ok(self.root.join(relative))
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, data: &[u8]) -> Result<(), AppError> {
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| AppError::InternalWithMessage(format!("创建存储目录失败: {}", e)))?;
+        }
+        tokio::fs::write(&path, data)
+            .await
+            .map_err(|e| AppError::InternalWithMessage(format!("写入文件失败: {}", e)))
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, AppError> {
+        let path = self.path_for(key)?;
+        match tokio::fs::read(&path).await {
+            Ok(data) => Ok(data),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(AppError::NotFound),
+            Err(e) => Err(AppError::InternalWithMessage(format!("读取文件失败: {}", e))),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AppError> {
+        let path = self.path_for(key)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            // 文件已不存在视为删除成功
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(AppError::InternalWithMessage(format!("删除文件失败: {}", e))),
+        }
+    }
+}
+
+/// 按配置创建存储后端（S3 兼容存储后续通过新的实现接入）
+pub fn from_config(config: &Config) -> Result<Arc<dyn Storage>, AppError> {
+    match config.storage_backend.as_str() {
+        "local" => Ok(Arc::new(LocalStorage::new(&config.storage_dir))),
+        other => Err(AppError::InternalWithMessage(format!("不支持的存储后端: {}", other))),
+    }
+}
+
+// 解码缩略图时的尺寸和内存上限，防止小文件解压出超大图片
+const THUMBNAIL_MAX_DIMENSION: u32 = 10_000;
+const THUMBNAIL_MAX_ALLOC: u64 = 128 * 1024 * 1024;
+
+/// 生成图片缩略图（PNG，最长边不超过 size），非图片、超出解码上限或解码失败时返回 None
+pub fn make_thumbnail(data: &[u8], size: u32) -> Option<Vec<u8>> {
+    let mut reader = image::io::Reader::new(std::io::Cursor::new(data))
+        .with_guessed_format()
+        .ok()?;
+    let mut limits = image::io::Limits::default();
+    limits.max_image_width = Some(THUMBNAIL_MAX_DIMENSION);
+    limits.max_image_height = Some(THUMBNAIL_MAX_DIMENSION);
+    limits.max_alloc = Some(THUMBNAIL_MAX_ALLOC);
+    reader.limits(limits);
+    let image = reader.decode().ok()?;
+    let thumbnail = image.thumbnail(size, size);
+    let mut output = std::io::Cursor::new(Vec::new());
+    thumbnail.write_to(&mut output, image::ImageOutputFormat::Png).ok()?;
+    Some(output.into_inner())
+}
@@ -34,6 +34,15 @@ async fn main() {
         }
     };
 
+    // 补齐存量数据的拼音检索索引
+    if let Some(pool) = &pool {
+        match core::search::backfill(pool).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Indexed pinyin for {} records", count),
+            Err(e) => tracing::warn!("Failed to backfill pinyin index: {:?}", e),
+        }
+    }
+
     // 初始化插件管理器
     let plugin_manager = core::plugin::PluginManager::new();
 
@@ -0,0 +1,21 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Attachment {
+    pub id: Uuid,
+    pub person_id: Uuid,
+    pub kind: String, // 'avatar', 'document'
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    // 存储路径不对外暴露，统一通过下载接口访问
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    #[serde(skip_serializing)]
+    pub thumbnail_key: Option<String>,
+    pub has_thumbnail: bool,
+    pub uploaded_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
@@ -46,3 +46,220 @@ impl From<Attendance> for AttendanceResponse {
         }
     }
 }
+
+/// 签到时间窗口；period_id 为空表示全天（到校）考勤
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AttendanceWindow {
+    pub id: Uuid,
+    pub period_id: Option<Uuid>,
+    pub period_name: Option<String>,
+    pub on_time_until: chrono::NaiveTime,
+    pub late_until: chrono::NaiveTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttendanceWindowSet {
+    pub period_id: Option<Uuid>,
+    pub on_time_until: chrono::NaiveTime,
+    pub late_until: chrono::NaiveTime,
+}
+
+/// 考勤更正记录，保留修改前的值
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AttendanceCorrection {
+    pub id: Uuid,
+    pub attendance_id: Uuid,
+    // 考勤记录删除后仍可据此查到所属人员和日期
+    pub person_id: Option<Uuid>,
+    pub date: Option<chrono::NaiveDate>,
+    pub period_id: Option<Uuid>,
+    // 考勤记录被删除时新值为空
+    pub old_status: Option<String>,
+    pub new_status: Option<String>,
+    pub old_time: Option<chrono::NaiveTime>,
+    pub new_time: Option<chrono::NaiveTime>,
+    pub old_remark: Option<String>,
+    pub new_remark: Option<String>,
+    pub reason: Option<String>,
+    pub corrected_by: Option<Uuid>,
+    pub corrected_by_name: Option<String>,
+    pub corrected_at: chrono::DateTime<chrono::Utc>,
+}
+
+// ========== 考勤统计 ==========
+
+#[derive(Debug, Deserialize)]
+pub struct AttendanceStatsQuery {
+    pub from: chrono::NaiveDate,
+    pub to: chrono::NaiveDate,
+    // student（默认）、class、grade 或 department（按部门统计教职工）
+    pub group_by: Option<String>,
+    pub class_id: Option<Uuid>,
+    pub grade: Option<i16>,
+    // 部门，包含下级部门的教职工
+    pub department_id: Option<Uuid>,
+    // 节次，不填统计全天考勤
+    pub period_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttendanceTrendQuery {
+    pub from: chrono::NaiveDate,
+    pub to: chrono::NaiveDate,
+    // day（默认）、week 或 month
+    pub interval: Option<String>,
+    pub class_id: Option<Uuid>,
+    pub grade: Option<i16>,
+    pub department_id: Option<Uuid>,
+    pub person_id: Option<Uuid>,
+    pub period_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttendanceReportQuery {
+    // 月份，格式 YYYY-MM，不填为本月
+    pub month: Option<String>,
+    // json（默认）、csv 或 xlsx
+    pub format: Option<String>,
+}
+
+/// 各状态的记录数
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StatusCounts {
+    pub present: i64,
+    pub absent: i64,
+    pub late: i64,
+    pub early_leave: i64,
+    pub excused: i64,
+}
+
+/// 一名学生或一个班级 / 年级 / 部门的统计
+#[derive(Debug, Serialize)]
+pub struct AttendanceStatRow {
+    // 学生、班级或部门的 ID；按年级统计时为空
+    pub id: Option<Uuid>,
+    pub name: String,
+    pub grade: Option<i16>,
+    pub people: i64,
+    pub records: i64,
+    // 应记录的次数：人数 × 上学日天数；unrecorded 为其中没有考勤记录的次数
+    pub expected: i64,
+    pub unrecorded: i64,
+    pub counts: StatusCounts,
+    // 出勤率：(出勤 + 迟到 + 早退) / 记录数，没有记录时为空
+    pub attendance_rate: Option<f64>,
+    // 连续缺勤天数（按上学日连续计算），分组统计时取组内最大值
+    pub longest_absence_streak: i64,
+    pub current_absence_streak: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AttendanceStats {
+    pub from: chrono::NaiveDate,
+    pub to: chrono::NaiveDate,
+    pub group_by: String,
+    pub school_days: usize,
+    // 是否按校历统计：已配置学期时只统计上学日的记录
+    pub calendar_aware: bool,
+    pub rows: Vec<AttendanceStatRow>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AttendanceTrendPoint {
+    pub start: chrono::NaiveDate,
+    pub end: chrono::NaiveDate,
+    pub school_days: usize,
+    pub records: i64,
+    pub counts: StatusCounts,
+    pub attendance_rate: Option<f64>,
+}
+
+/// 班级月度考勤表中的一名学生
+#[derive(Debug, Serialize)]
+pub struct AttendanceReportRow {
+    pub person_id: Uuid,
+    pub student_no: String,
+    pub name: String,
+    // 与 days 一一对应，没有记录为空
+    pub statuses: Vec<Option<String>>,
+    pub counts: StatusCounts,
+    pub attendance_rate: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AttendanceReport {
+    pub class_id: Uuid,
+    pub class_name: String,
+    pub month: String,
+    // 当月的上学日
+    pub days: Vec<chrono::NaiveDate>,
+    pub students: Vec<AttendanceReportRow>,
+}
+
+// ========== 缺勤提醒 ==========
+
+/// 提醒规则：window_days 天内出现 threshold 次 status 时通知所选的接收人
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AttendanceAlertRule {
+    pub id: Uuid,
+    pub name: String,
+    // absent 或 late
+    pub status: String,
+    pub threshold: i32,
+    pub window_days: i32,
+    pub notify_parents: bool,
+    pub notify_head_teacher: bool,
+    pub notify_grade_leader: bool,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttendanceAlertRuleSave {
+    pub name: String,
+    pub status: String,
+    pub threshold: Option<i32>,
+    pub window_days: Option<i32>,
+    #[serde(default)]
+    pub notify_parents: bool,
+    #[serde(default)]
+    pub notify_head_teacher: bool,
+    #[serde(default)]
+    pub notify_grade_leader: bool,
+    pub is_active: Option<bool>,
+}
+
+/// 已发出的提醒
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AttendanceAlert {
+    pub id: Uuid,
+    pub rule_id: Uuid,
+    pub rule_name: String,
+    pub student_id: Uuid,
+    pub student_name: String,
+    pub attendance_id: Option<Uuid>,
+    pub date: chrono::NaiveDate,
+    pub occurrences: i32,
+    pub recipients: Vec<Uuid>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttendanceAlertQuery {
+    pub student_id: Option<Uuid>,
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct GradeLeader {
+    pub grade: i16,
+    pub teacher_id: Uuid,
+    pub teacher_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GradeLeadersSave {
+    pub teacher_ids: Vec<Uuid>,
+}
@@ -0,0 +1,65 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 学期，周次从开学日所在周的星期一开始计算
+#[derive(Debug, Clone, Deserialize, Serialize, sqlx::FromRow)]
+pub struct Term {
+    pub id: Uuid,
+    pub name: String,
+    pub academic_year: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TermCreate {
+    pub name: String,
+    pub academic_year: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TermUpdate {
+    pub name: Option<String>,
+    pub academic_year: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+/// 特殊日期：holiday（放假）或 workday（调休上课）
+#[derive(Debug, Clone, Deserialize, Serialize, sqlx::FromRow)]
+pub struct CalendarDay {
+    pub date: NaiveDate,
+    pub kind: String,
+    pub name: String,
+    pub follows_day_of_week: Option<i16>,
+}
+
+/// 批量设置特殊日期，end_date 不填表示只设置一天
+#[derive(Debug, Deserialize)]
+pub struct CalendarDaySet {
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub kind: String,
+    pub name: String,
+    pub follows_day_of_week: Option<i16>,
+}
+
+/// 某一天在校历中的情况
+#[derive(Debug, Serialize)]
+pub struct DayInfo {
+    pub date: NaiveDate,
+    pub day_of_week: i16,
+    pub is_school_day: bool,
+    // school（正常上课）、workday（调休上课）、weekend、holiday、vacation（学期外）
+    pub kind: String,
+    // 节假日或调休名称
+    pub name: Option<String>,
+    pub term_id: Option<Uuid>,
+    pub term_name: Option<String>,
+    pub week: Option<i32>,
+    // 当天按星期几的课表上课，非上学日为空
+    pub schedule_day_of_week: Option<i16>,
+}
@@ -2,6 +2,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::models::custom_field::CustomFields;
+
 #[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct Class {
     pub id: Uuid,
@@ -18,6 +20,7 @@ pub struct ClassCreate {
     pub grade: i32,
     pub teacher_id: Option<String>,
     pub academic_year: String,
+    pub custom_fields: Option<CustomFields>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -26,6 +29,8 @@ pub struct ClassUpdate {
     pub grade: Option<i32>,
     pub teacher_id: Option<String>,
     pub academic_year: Option<String>,
+    // 与已有取值合并，值为 null 表示删除该字段
+    pub custom_fields: Option<CustomFields>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -37,6 +42,8 @@ pub struct ClassResponse {
     pub teacher_name: Option<String>, // 班主任姓名
     pub academic_year: String,
     pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub custom_fields: CustomFields,
 }
 
 impl From<Class> for ClassResponse {
@@ -49,6 +56,52 @@ impl From<Class> for ClassResponse {
             teacher_name: None, // 需要额外查询
             academic_year: class.academic_year,
             created_at: class.created_at,
+            custom_fields: CustomFields::new(),
         }
     }
 }
+
+// 合并班级：source_ids 中的班级并入路径中的目标班级
+#[derive(Debug, Deserialize)]
+pub struct ClassMergeRequest {
+    pub source_ids: Vec<Uuid>,
+    // 小组处理方式：move（默认，小组整体迁入目标班级）或 rebuild（在目标班级新建同名小组，原小组及积分留在原班级）
+    pub group_mode: Option<String>,
+    // 合并后归档原班级，默认 true；不归档时原班级保留老师、任课安排和课程表
+    pub archive_sources: Option<bool>,
+}
+
+// 拆分班级：把路径中班级的部分学生分到其他班级
+#[derive(Debug, Deserialize)]
+pub struct ClassSplitRequest {
+    pub targets: Vec<ClassSplitTarget>,
+    // 小组处理方式：rebuild（默认，在新班级重建同名小组）或 dissolve（移出的学生退出原小组）
+    pub group_mode: Option<String>,
+    // 是否把原班级本学年的任课安排复制到新班级，默认 true
+    pub copy_assignments: Option<bool>,
+    // 拆分后归档原班级，此时所有学生都必须分配去向，默认 false
+    pub archive_source: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClassSplitTarget {
+    // 分到已有班级；不填时按 name 新建班级（年级、学年与原班级相同）
+    pub class_id: Option<Uuid>,
+    pub name: Option<String>,
+    // 新建班级的班主任，只能在新建班级时指定
+    pub teacher_id: Option<Uuid>,
+    pub student_ids: Vec<Uuid>,
+}
+
+// 合并 / 拆分结果
+#[derive(Debug, Serialize)]
+pub struct ClassReorganizeResult {
+    // 接收学生的班级（合并时为目标班级，拆分时为各去向班级，含新建班级）
+    pub class_ids: Vec<Uuid>,
+    pub archived: Vec<Uuid>,
+    pub students: u64,
+    pub groups_moved: u64,
+    pub groups_created: u64,
+    pub teachers: u64,
+    pub assignments: u64,
+}
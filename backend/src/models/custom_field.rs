@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 自定义字段的取值（存储为 JSONB 对象）
+pub type CustomFields = serde_json::Map<String, serde_json::Value>;
+
+// 自定义字段定义
+#[derive(Debug, Clone, Deserialize, Serialize, sqlx::FromRow)]
+pub struct CustomFieldDefinition {
+    pub id: Uuid,
+    pub entity: String,              // 'person', 'class'
+    pub key: String,                 // 存储在 custom_fields JSONB 中的键
+    pub label: String,               // 显示名称，如 "宿舍号"
+    pub field_type: String,          // 'text', 'number', 'boolean', 'date', 'enum'
+    pub required: bool,
+    pub options: serde_json::Value,  // enum 类型的可选值（字符串数组）
+    pub person_type: Option<String>, // 仅适用于某类人员，为空表示全部
+    pub visibility_permission: Option<String>, // 查看该字段所需权限，为空表示所有人可见
+    pub sort_order: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CustomFieldCreate {
+    pub entity: String,
+    pub key: String,
+    pub label: String,
+    pub field_type: String,
+    pub required: Option<bool>,
+    pub options: Option<Vec<String>>,
+    pub person_type: Option<String>,
+    pub visibility_permission: Option<String>,
+    pub sort_order: Option<i32>,
+}
+
+// 字段键、实体和类型创建后不可修改，避免已有数据失效
+#[derive(Debug, Deserialize)]
+pub struct CustomFieldUpdate {
+    pub label: Option<String>,
+    pub required: Option<bool>,
+    pub options: Option<Vec<String>>,
+    pub visibility_permission: Option<String>,
+    pub sort_order: Option<i32>,
+}
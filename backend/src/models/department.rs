@@ -28,6 +28,8 @@ pub struct DepartmentResponse {
     pub name: String,
     pub parent_id: Option<Uuid>,
     pub parent_name: Option<String>, // 上级部门名称
+    pub head_id: Option<Uuid>,
+    pub head_name: Option<String>, // 部门负责人姓名
     pub created_at: DateTime<Utc>,
 }
 
@@ -38,7 +40,42 @@ impl From<Department> for DepartmentResponse {
             name: department.name,
             parent_id: department.parent_id,
             parent_name: None, // 需要额外查询
+            head_id: None,
+            head_name: None,
             created_at: department.created_at,
         }
     }
 }
+
+/// 部门树节点
+#[derive(Debug, Serialize)]
+pub struct DepartmentTreeNode {
+    pub id: Uuid,
+    pub name: String,
+    pub parent_id: Option<Uuid>,
+    pub head_id: Option<Uuid>,
+    pub head_name: Option<String>,
+    pub teacher_count: i64,
+    pub children: Vec<DepartmentTreeNode>,
+}
+
+/// 祖先 / 后代查询结果，depth 为相对起点部门的层级距离
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct DepartmentPathItem {
+    pub id: Uuid,
+    pub name: String,
+    pub parent_id: Option<Uuid>,
+    pub depth: i32,
+}
+
+/// 移动部门（连同整棵子树），parent_id 为空表示移动到顶层
+#[derive(Debug, Deserialize)]
+pub struct DepartmentMove {
+    pub parent_id: Option<Uuid>,
+}
+
+/// 设置部门负责人，head_id 为空表示取消负责人
+#[derive(Debug, Deserialize)]
+pub struct DepartmentHeadUpdate {
+    pub head_id: Option<Uuid>,
+}
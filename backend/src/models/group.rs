@@ -56,6 +56,8 @@ pub struct GroupMemberAdd {
 pub struct GroupScoreRecord {
     pub id: Uuid,
     pub group_id: Uuid,
+    // 记录时小组所属的班级
+    pub class_id: Option<Uuid>,
     pub score_change: i32,
     pub reason: String,
     pub created_by: Uuid,
@@ -64,8 +66,11 @@ pub struct GroupScoreRecord {
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct GroupScoreChange {
-    pub score_change: i32,
-    pub reason: String,
+    // 不填时使用积分目录中的预设分值
+    pub score_change: Option<i32>,
+    // 积分目录中的原因，可用 reason_id 或原因名称（同义说法）指定
+    pub reason_id: Option<Uuid>,
+    pub reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
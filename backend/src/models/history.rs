@@ -0,0 +1,30 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// 单个字段的变更
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+// 一次变更（同一次保存产生的所有字段变更）
+#[derive(Debug, Serialize)]
+pub struct PersonHistoryEntry {
+    pub change_id: Uuid,
+    pub changed_by: Option<Uuid>,
+    pub changed_by_name: Option<String>,
+    pub changed_at: DateTime<Utc>,
+    pub changes: Vec<FieldChange>,
+}
+
+// 某一天学生所在班级
+#[derive(Debug, Serialize)]
+pub struct ClassAtDate {
+    pub student_id: Uuid,
+    pub date: NaiveDate,
+    pub class_id: Option<Uuid>,
+    pub class_name: Option<String>,
+}
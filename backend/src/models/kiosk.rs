@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 登记的一体机设备
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct KioskDevice {
+    pub id: Uuid,
+    pub name: String,
+    pub class_id: Uuid,
+    pub class_name: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub radius_m: i32,
+    pub is_active: bool,
+    pub last_seen_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KioskDeviceCreate {
+    pub name: String,
+    pub class_id: Uuid,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub radius_m: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KioskDeviceUpdate {
+    pub name: Option<String>,
+    pub class_id: Option<Uuid>,
+    // 经纬度需同时提供
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub radius_m: Option<i32>,
+    pub is_active: Option<bool>,
+    // 为 true 时清除设备位置，签到不再校验定位
+    pub clear_location: Option<bool>,
+}
+
+/// 设备凭据，secret 只在登记和重置时返回一次
+#[derive(Debug, Serialize)]
+pub struct KioskDeviceCredentials {
+    #[serde(flatten)]
+    pub device: KioskDevice,
+    pub secret: String,
+}
+
+/// 一体机展示的签到码
+#[derive(Debug, Serialize)]
+pub struct KioskToken {
+    pub token: String,
+    pub class_id: Uuid,
+    pub class_name: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KioskCheckIn {
+    pub token: String,
+    // 设备配置了位置时必填
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KioskCheckInResult {
+    pub attendance_id: Uuid,
+    pub class_id: Uuid,
+    pub date: chrono::NaiveDate,
+    pub time: chrono::NaiveTime,
+    pub status: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct KioskEvent {
+    pub id: Uuid,
+    pub device_id: Option<Uuid>,
+    pub device_name: Option<String>,
+    // auth_failed、token_issued、check_in、check_in_rejected
+    pub event: String,
+    pub person_id: Option<Uuid>,
+    pub person_name: Option<String>,
+    pub detail: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KioskEventQuery {
+    pub device_id: Option<Uuid>,
+    pub event: Option<String>,
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+}
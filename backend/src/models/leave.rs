@@ -0,0 +1,71 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 请假申请
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LeaveRequest {
+    pub id: Uuid,
+    pub student_id: Uuid,
+    pub student_name: String,
+    pub class_id: Option<Uuid>,
+    pub class_name: Option<String>,
+    // sick：病假；personal：事假；other：其他
+    pub leave_type: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub school_days: i32,
+    pub reason: String,
+    pub attachment_id: Option<Uuid>,
+    // pending、escalated、approved、rejected、cancelled 或 revoked（批准后撤销）
+    pub status: String,
+    pub submitted_by: Option<Uuid>,
+    pub submitted_by_name: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// 审批记录
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LeaveApproval {
+    pub id: Uuid,
+    pub step: i16,
+    pub decision: String,
+    pub approver_id: Option<Uuid>,
+    pub approver_name: Option<String>,
+    pub comment: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LeaveRequestDetail {
+    #[serde(flatten)]
+    pub request: LeaveRequest,
+    pub approvals: Vec<LeaveApproval>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeaveRequestCreate {
+    // 学生本人提交时可不填；家长提交时为孩子的 ID
+    pub student_id: Option<Uuid>,
+    pub leave_type: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub reason: String,
+    // 证明材料（如病历），需先上传为该学生的附件（学生本人或家长可上传）
+    pub attachment_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeaveDecision {
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeaveQuery {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    pub status: Option<String>,
+    pub class_id: Option<Uuid>,
+    pub student_id: Option<Uuid>,
+}
@@ -0,0 +1,69 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::models::group::GroupScoreRecord;
+
+// 个人视图的分页与日期范围
+#[derive(Debug, Deserialize)]
+pub struct MeQuery {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+/// 学生所在班级
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct MyClass {
+    pub id: Uuid,
+    pub name: String,
+    pub grade: i16,
+    pub academic_year: Option<String>,
+    pub teacher_id: Option<Uuid>,
+    pub teacher_name: Option<String>,
+    pub student_count: i64,
+    #[sqlx(skip)]
+    pub teachers: Vec<MyClassTeacher>,
+}
+
+/// 班级的老师及其本学年任教学科
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct MyClassTeacher {
+    pub teacher_id: Uuid,
+    pub name: String,
+    pub is_main_teacher: bool,
+    pub subjects: Vec<String>,
+}
+
+/// 学生所在小组，rank 为积分在班级内的名次
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct MyGroup {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub score: i32,
+    pub class_id: Uuid,
+    pub class_name: Option<String>,
+    pub rank: i64,
+    pub group_count: i64,
+    #[sqlx(skip)]
+    pub members: Vec<MyGroupMember>,
+    // 最近的积分变动
+    #[sqlx(skip)]
+    pub recent_records: Vec<GroupScoreRecord>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct MyGroupMember {
+    pub person_id: Uuid,
+    pub name: String,
+}
+
+/// 考勤汇总：按状态计数；指定起止日期时附带该区间的上学日天数
+#[derive(Debug, Serialize)]
+pub struct AttendanceSummary {
+    pub by_status: BTreeMap<String, i64>,
+    pub school_days: Option<usize>,
+}
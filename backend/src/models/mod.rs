@@ -1,8 +1,20 @@
+pub mod attachment;
 pub mod attendance;
+pub mod calendar;
 pub mod class;
+pub mod custom_field;
 pub mod department;
 pub mod group;
+pub mod history;
+pub mod kiosk;
+pub mod leave;
+pub mod me;
 pub mod notice;
 pub mod person;
+pub mod roster;
 pub mod score;
+pub mod score_appeal;
+pub mod search;
+pub mod subject;
+pub mod timetable;
 pub mod user;
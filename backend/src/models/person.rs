@@ -2,6 +2,8 @@ use chrono::{NaiveDate, DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::models::custom_field::CustomFields;
+
 #[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct Person {
     pub id: Uuid,
@@ -38,6 +40,8 @@ pub struct PersonCreate {
     pub occupation: Option<String>,
     // 老师关联的多个班级
     pub classes: Option<Vec<TeacherClassCreate>>,
+    // 自定义字段取值，按 custom_field_definitions 校验
+    pub custom_fields: Option<CustomFields>,
 }
 
 // 老师班级关联创建结构
@@ -68,6 +72,8 @@ pub struct PersonUpdate {
     pub occupation: Option<String>,
     // 老师关联的多个班级
     pub classes: Option<Vec<TeacherClassCreate>>,
+    // 自定义字段取值，按 custom_field_definitions 校验
+    pub custom_fields: Option<CustomFields>,
 }
 
 // 子类型结构体（用于查询连接结果）
@@ -129,6 +135,21 @@ pub struct StudentResponse {
     pub class_name: Option<String>,
     pub enrollment_date: Option<NaiveDate>,
     pub status: String,
+    // 学生的家长/监护人
+    #[serde(default)]
+    pub guardians: Vec<GuardianInfo>,
+    #[serde(default)]
+    pub custom_fields: CustomFields,
+}
+
+// 学生详情中的家长信息
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct GuardianInfo {
+    pub parent_id: Uuid,
+    pub name: String,
+    pub phone: Option<String>,
+    pub relationship: Option<String>,
+    pub is_primary_contact: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -136,6 +157,9 @@ pub struct TeacherClassInfo {
     pub class_id: Uuid,
     pub class_name: String,
     pub is_main_teacher: bool,
+    // 在该班级当前学年所教的学科
+    #[serde(default)]
+    pub subjects: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -152,6 +176,8 @@ pub struct TeacherResponse {
     pub classes: Vec<TeacherClassInfo>,
     pub title: Option<String>,
     pub hire_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub custom_fields: CustomFields,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -164,6 +190,19 @@ pub struct ParentResponse {
     pub email: Option<String>,
     pub wechat_openid: Option<String>,
     pub occupation: Option<String>,
+    #[serde(default)]
+    pub custom_fields: CustomFields,
+}
+
+impl PersonResponse {
+    /// 自定义字段（用于按可见性权限隐藏字段）
+    pub fn custom_fields_mut(&mut self) -> &mut CustomFields {
+        match self {
+            PersonResponse::Student(student) => &mut student.custom_fields,
+            PersonResponse::Teacher(teacher) => &mut teacher.custom_fields,
+            PersonResponse::Parent(parent) => &mut parent.custom_fields,
+        }
+    }
 }
 
 // 转换实现
@@ -183,7 +222,9 @@ impl From<(Person, Option<Student>)> for PersonResponse {
                     class_id: student.class_id,
                     class_name: None, // 需要额外查询
                     enrollment_date: student.enrollment_date,
-                    status: student.status
+                    status: student.status,
+                    guardians: Vec::new(),
+                    custom_fields: CustomFields::new(),
                 })
             }
             "teacher" => {
@@ -200,7 +241,8 @@ impl From<(Person, Option<Student>)> for PersonResponse {
                     department_name: None,
                     classes: Vec::new(),
                     title: None,
-                    hire_date: None
+                    hire_date: None,
+                    custom_fields: CustomFields::new(),
                 })
             }
             "parent" => {
@@ -212,7 +254,8 @@ impl From<(Person, Option<Student>)> for PersonResponse {
                     phone: person.phone,
                     email: person.email,
                     wechat_openid: None,
-                    occupation: None
+                    occupation: None,
+                    custom_fields: CustomFields::new(),
                 })
             }
             _ => {
@@ -229,9 +272,71 @@ impl From<(Person, Option<Student>)> for PersonResponse {
                     department_name: None,
                     classes: Vec::new(),
                     title: None,
-                    hire_date: None
+                    hire_date: None,
+                    custom_fields: CustomFields::new(),
                 })
             }
         }
     }
 }
+
+// 疑似重复人员（成对返回）
+#[derive(Debug, Serialize)]
+pub struct DuplicateCandidate {
+    pub person_a: DuplicatePersonInfo,
+    pub person_b: DuplicatePersonInfo,
+    // 综合得分 0~1，越高越可能是同一人
+    pub score: f64,
+    // 命中的判断依据，如 "姓名相似"、"手机号相同"
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicatePersonInfo {
+    pub id: Uuid,
+    pub name: String,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub birthday: Option<NaiveDate>,
+    pub created_at: DateTime<Utc>,
+}
+
+// 合并请求：source 的关联数据迁移到路径中的目标人员，source 随后被归档
+#[derive(Debug, Deserialize)]
+pub struct PersonMergeRequest {
+    pub source_id: Uuid,
+}
+
+// 合并结果，记录各类关联数据的迁移数量
+#[derive(Debug, Serialize)]
+pub struct PersonMergeResult {
+    pub target_id: Uuid,
+    pub source_id: Uuid,
+    pub student_parent: u64,
+    pub attendances: u64,
+    pub scores: u64,
+    pub group_members: u64,
+    pub permissions: u64,
+    pub teacher_classes: u64,
+}
+
+// 关联家长与学生的请求
+#[derive(Debug, Deserialize)]
+pub struct GuardianLink {
+    pub parent_id: Uuid,
+    // father / mother / guardian
+    pub relationship: String,
+    pub is_primary_contact: Option<bool>,
+}
+
+// 家长查看自己的孩子
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ChildInfo {
+    pub student_id: Uuid,
+    pub name: String,
+    pub student_no: String,
+    pub class_id: Option<Uuid>,
+    pub class_name: Option<String>,
+    pub relationship: Option<String>,
+    pub is_primary_contact: bool,
+}
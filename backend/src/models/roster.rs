@@ -0,0 +1,90 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct RosterQuery {
+    // 花名册日期，不填为当前名单；指定日期时按人员变更历史还原当天的班级成员
+    pub date: Option<NaiveDate>,
+    // json（默认）或 html（可打印，浏览器中打印为 PDF）
+    pub format: Option<String>,
+}
+
+/// 花名册中的学生
+#[derive(Debug, Clone, Serialize)]
+pub struct RosterStudent {
+    // 序号，从 1 开始
+    pub seq: usize,
+    pub person_id: Uuid,
+    pub name: String,
+    pub gender: i16,
+    pub student_no: String,
+    pub status: String,
+    // 所在小组（当前）
+    pub groups: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Roster {
+    pub class_id: Uuid,
+    pub class_name: String,
+    pub grade: i16,
+    pub academic_year: Option<String>,
+    pub teacher_name: Option<String>,
+    pub date: NaiveDate,
+    pub students: Vec<RosterStudent>,
+}
+
+/// 座位，第 1 排靠近讲台
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SeatPlacement {
+    pub row: i16,
+    pub col: i16,
+    pub person_id: Uuid,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Seat {
+    pub row: i16,
+    pub col: i16,
+    pub person_id: Uuid,
+    pub name: String,
+    pub gender: i16,
+    pub student_no: String,
+    pub groups: Vec<String>,
+}
+
+/// 班级座位表；unseated 为尚未安排座位的学生
+#[derive(Debug, Serialize)]
+pub struct SeatingChart {
+    pub class_id: Uuid,
+    pub class_name: String,
+    pub rows: i16,
+    pub cols: i16,
+    pub seats: Vec<Seat>,
+    pub unseated: Vec<RosterStudent>,
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+// 手动保存座位表，整体替换原有安排
+#[derive(Debug, Deserialize)]
+pub struct SeatingChartSave {
+    pub rows: i16,
+    pub cols: i16,
+    #[serde(default)]
+    pub seats: Vec<SeatPlacement>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SeatingArrange {
+    // group：同组同学坐在一起；gender：男女交错；random：随机
+    pub mode: String,
+    // 不填时沿用现有座位表的行列数，没有座位表时按每排 6 人计算
+    pub rows: Option<i16>,
+    pub cols: Option<i16>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SeatingQuery {
+    pub format: Option<String>,
+}
@@ -51,10 +51,145 @@ impl From<Score> for ScoreResponse {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct ScoreSummary {
     pub person_id: Uuid,
     pub total_score: i32,
     pub positive_count: i32,
     pub negative_count: i32,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct ScoreSummaryQuery {
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+    pub score_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardQuery {
+    // student / group / class
+    pub scope: Option<String>,
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+    // 小组排行按小组积分记录统计，不区分积分类型
+    pub score_type: Option<String>,
+    pub class_id: Option<Uuid>,
+    pub grade: Option<i16>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LeaderboardEntry {
+    pub id: Uuid,
+    pub name: String,
+    pub class_id: Option<Uuid>,
+    pub class_name: Option<String>,
+    pub total_score: i64,
+    pub positive_count: i64,
+    pub negative_count: i64,
+    pub rank: i64,
+    // 上一周期的名次，上一周期没有积分时为空
+    pub previous_rank: Option<i64>,
+    // 正数表示名次上升
+    pub rank_change: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Leaderboard {
+    pub scope: String,
+    pub from: chrono::NaiveDate,
+    pub to: chrono::NaiveDate,
+    pub previous_from: chrono::NaiveDate,
+    pub previous_to: chrono::NaiveDate,
+    pub items: Vec<LeaderboardEntry>,
+}
+
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct ScoreCategory {
+    pub id: Uuid,
+    pub code: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub sort_order: i32,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScoreCategorySave {
+    pub code: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub sort_order: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, sqlx::FromRow)]
+pub struct ScoreReason {
+    pub id: Uuid,
+    pub category_id: Uuid,
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub default_value: i32,
+    pub sort_order: i32,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScoreReasonSave {
+    pub name: String,
+    pub aliases: Option<Vec<String>>,
+    pub default_value: i32,
+    pub sort_order: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+// 类别及其预设原因
+#[derive(Debug, Serialize)]
+pub struct ScoreCategoryDetail {
+    #[serde(flatten)]
+    pub category: ScoreCategory,
+    pub reasons: Vec<ScoreReason>,
+}
+
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct ScoreLimit {
+    pub role: String,
+    pub max_points: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScoreLimitsSave {
+    pub limits: Vec<ScoreLimit>,
+}
+
+// 小组总分与小组积分记录合计不一致
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct GroupScoreDrift {
+    pub group_id: Uuid,
+    pub group_name: String,
+    pub class_id: Option<Uuid>,
+    pub recorded_score: i64,
+    pub ledger_score: i64,
+}
+
+// 按天汇总与积分流水不一致（某一侧缺失时对应字段为 0）
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct DailyTotalDrift {
+    pub score_date: chrono::NaiveDate,
+    pub person_id: Uuid,
+    pub group_id: Option<Uuid>,
+    pub score_type: String,
+    pub recorded_total: i64,
+    pub ledger_total: i64,
+    pub recorded_count: i64,
+    pub ledger_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScoreReconciliation {
+    pub group_scores: Vec<GroupScoreDrift>,
+    pub daily_totals: Vec<DailyTotalDrift>,
+    // 是否已按流水修复
+    pub fixed: bool,
+}
@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 扣分申诉
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ScoreAppeal {
+    pub id: Uuid,
+    // score：个人积分；group_score：小组积分
+    pub target_type: String,
+    pub score_id: Option<Uuid>,
+    pub group_score_record_id: Option<Uuid>,
+    pub group_id: Option<Uuid>,
+    pub group_name: Option<String>,
+    pub student_id: Uuid,
+    pub student_name: String,
+    pub class_id: Option<Uuid>,
+    pub class_name: Option<String>,
+    pub original_value: i32,
+    pub original_reason: String,
+    // 原扣分的录入人
+    pub scored_by: Option<Uuid>,
+    pub evidence: String,
+    pub attachment_id: Option<Uuid>,
+    pub requested_value: Option<i32>,
+    // pending、accepted、rejected 或 withdrawn
+    pub status: String,
+    pub reviewer_id: Option<Uuid>,
+    pub reviewer_name: Option<String>,
+    pub submitted_by: Option<Uuid>,
+    pub submitted_by_name: Option<String>,
+    pub adjusted_value: Option<i32>,
+    pub compensation_score_id: Option<Uuid>,
+    pub compensation_group_record_id: Option<Uuid>,
+    pub resolved_by: Option<Uuid>,
+    pub resolved_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// 申诉过程中的一条记录
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ScoreAppealMessage {
+    pub id: Uuid,
+    // comment、assigned、accepted、rejected 或 withdrawn
+    pub kind: String,
+    pub author_id: Option<Uuid>,
+    pub author_name: Option<String>,
+    pub content: String,
+    pub attachment_id: Option<Uuid>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScoreAppealDetail {
+    #[serde(flatten)]
+    pub appeal: ScoreAppeal,
+    pub messages: Vec<ScoreAppealMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScoreAppealCreate {
+    // 二选一：个人积分记录或小组积分记录
+    pub score_id: Option<Uuid>,
+    pub group_score_record_id: Option<Uuid>,
+    // 家长为孩子申诉小组扣分时填写孩子的 ID；其他情况可不填
+    pub student_id: Option<Uuid>,
+    pub evidence: String,
+    // 证明材料，需先上传为该学生的附件（学生本人或家长可上传）
+    pub attachment_id: Option<Uuid>,
+    pub requested_value: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScoreAppealMessageCreate {
+    pub content: String,
+    pub attachment_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScoreAppealAssign {
+    pub reviewer_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScoreAppealDecision {
+    pub comment: Option<String>,
+    // 同意时的最终分值，不填表示撤销原扣分
+    pub adjusted_value: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScoreAppealQuery {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    pub status: Option<String>,
+    pub student_id: Option<Uuid>,
+    // 只看指派给自己的申诉
+    pub assigned_to_me: Option<bool>,
+}
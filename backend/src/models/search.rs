@@ -0,0 +1,14 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+/// 统一检索结果，`type` 为 person / class / group
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SearchResult {
+    #[serde(rename = "type")]
+    #[sqlx(rename = "type")]
+    pub type_: String,
+    pub id: Uuid,
+    pub name: String,
+    pub subtitle: Option<String>,
+    pub score: f64,
+}
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct Subject {
+    pub id: Uuid,
+    pub name: String,
+    pub code: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubjectCreate {
+    pub name: String,
+    pub code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubjectUpdate {
+    pub name: Option<String>,
+    pub code: Option<String>,
+}
+
+/// 任课安排：某学年某教师在某班级教授某学科
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TeachingAssignment {
+    pub id: Uuid,
+    pub teacher_id: Uuid,
+    pub teacher_name: String,
+    pub subject_id: Uuid,
+    pub subject_name: String,
+    pub class_id: Uuid,
+    pub class_name: String,
+    pub academic_year: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TeachingAssignmentCreate {
+    pub teacher_id: Uuid,
+    pub subject_id: Uuid,
+    pub class_id: Uuid,
+    // 不填时使用班级当前学年
+    pub academic_year: Option<String>,
+}
@@ -0,0 +1,72 @@
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 作息节次，如第1节 08:00-08:45
+#[derive(Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct Period {
+    pub id: Uuid,
+    pub period_no: i16,
+    pub name: String,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PeriodCreate {
+    pub period_no: i16,
+    pub name: String,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PeriodUpdate {
+    pub period_no: Option<i16>,
+    pub name: Option<String>,
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
+}
+
+/// 课程表条目（含班级、节次、学科、教师名称）
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TimetableEntry {
+    pub id: Uuid,
+    pub class_id: Uuid,
+    pub class_name: String,
+    pub academic_year: String,
+    pub day_of_week: i16,
+    pub period_id: Uuid,
+    pub period_no: i16,
+    pub period_name: String,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub subject_id: Uuid,
+    pub subject_name: String,
+    pub teacher_id: Option<Uuid>,
+    pub teacher_name: Option<String>,
+    pub room: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimetableEntryCreate {
+    pub class_id: Uuid,
+    // 不填时使用班级当前学年
+    pub academic_year: Option<String>,
+    pub day_of_week: i16,
+    pub period_id: Uuid,
+    pub subject_id: Uuid,
+    // 不填时使用该班级该学科唯一的任课教师
+    pub teacher_id: Option<Uuid>,
+    pub room: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimetableEntryUpdate {
+    pub day_of_week: Option<i16>,
+    pub period_id: Option<Uuid>,
+    pub subject_id: Option<Uuid>,
+    pub teacher_id: Option<Uuid>,
+    // 传空字符串表示清除教室
+    pub room: Option<String>,
+}
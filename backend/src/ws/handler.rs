@@ -1,20 +1,54 @@
 use axum::{
-    extract::ws::{WebSocket, WebSocketUpgrade},
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    extract::{Query, State},
     response::IntoResponse,
 };
+use serde::Deserialize;
+use uuid::Uuid;
 
-pub async fn ws_handler(ws: WebSocketUpgrade) -> impl IntoResponse {
-    ws.on_upgrade(handle_connection)
+use crate::api::routes::AppState;
+use crate::core::auth::verify_token;
+use crate::core::config::load_config;
+use crate::core::error::AppError;
+use crate::ws::hub::Hub;
+
+#[derive(Debug, Deserialize)]
+pub struct WsQuery {
+    // 浏览器的 WebSocket 不能设置请求头，登录令牌通过查询参数传递
+    pub token: String,
+}
+
+pub async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(query): Query<WsQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let config = load_config().map_err(|_| AppError::Internal)?;
+    let claims = verify_token(&query.token, &config.jwt_secret).map_err(|_| AppError::Auth("无效的令牌".to_string()))?;
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+
+    let hub = state.hub.clone();
+    Ok(ws.on_upgrade(move |socket| handle_connection(socket, hub, user_id)))
 }
 
-async fn handle_connection(mut socket: WebSocket) {
-    // 这里可以实现 WebSocket 连接的处理逻辑
-    // 例如：分数变化推送、通知推送等
-    while let Some(Ok(message)) = socket.recv().await {
-        // 处理接收到的消息
-        if let axum::extract::ws::Message::Text(text) = message {
-            // 这里可以处理客户端发送的消息
-            println!("Received message: {}", text);
+// 把推送给该用户的消息（如通知、考勤提醒）转发到连接上
+async fn handle_connection(mut socket: WebSocket, hub: Hub, user_id: Uuid) {
+    let mut outbox = hub.subscribe(user_id);
+    loop {
+        tokio::select! {
+            pushed = outbox.recv() => match pushed {
+                Some(text) => {
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                None => break,
+            },
+            received = socket.recv() => match received {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // 客户端消息目前只用于保持连接
+                Some(Ok(_)) => {}
+            },
         }
     }
 }
@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use uuid::Uuid;
+
+/// 在线用户的 WebSocket 连接，一个用户可以有多个连接（多个设备或标签页）
+#[derive(Clone, Default)]
+pub struct Hub {
+    clients: Arc<Mutex<HashMap<Uuid, Vec<UnboundedSender<String>>>>>,
+}
+
+impl Hub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记一个连接，返回接收推送消息的通道；连接断开后通道随之关闭
+    pub fn subscribe(&self, person_id: Uuid) -> UnboundedReceiver<String> {
+        let (tx, rx) = unbounded_channel();
+        if let Ok(mut clients) = self.clients.lock() {
+            clients.entry(person_id).or_default().push(tx);
+        }
+        rx
+    }
+
+    /// 推送给某个用户的全部连接，返回送达的连接数；顺带清理已断开的连接
+    pub fn push(&self, person_id: Uuid, message: &str) -> usize {
+        let Ok(mut clients) = self.clients.lock() else {
+            return 0;
+        };
+        let Some(senders) = clients.get_mut(&person_id) else {
+            return 0;
+        };
+        senders.retain(|tx| tx.send(message.to_string()).is_ok());
+        let delivered = senders.len();
+        if delivered == 0 {
+            clients.remove(&person_id);
+        }
+        delivered
+    }
+}